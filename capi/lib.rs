@@ -0,0 +1,9 @@
+//! The C library for QuantumSafe Finance
+//!
+//! The main crate is an `rlib` so that `no_std` consumers can link it. This
+//! crate links the C bindings into `libquantumsafe_finance.so`, declared in
+//! `include/quantumsafe.h`:
+//!
+//! `cargo build --release --manifest-path capi/Cargo.toml`
+
+pub use quantumsafe_finance::bindings::c::*;
@@ -0,0 +1,69 @@
+/*
+ * Sign on one side, verify with only the public key on the other.
+ *
+ * Build from the repository root:
+ *   cargo build --release --manifest-path capi/Cargo.toml
+ *   cc -std=c11 -Iinclude examples/c/example.c -Lcapi/target/release \
+ *      -Wl,-rpath,capi/target/release -lquantumsafe_finance -o example
+ */
+#include <stdio.h>
+#include <string.h>
+
+#include "quantumsafe.h"
+
+#define TRY(call)                                                            \
+    do {                                                                     \
+        QsfStatus status_ = (call);                                          \
+        if (status_ != QSF_OK) {                                             \
+            fprintf(stderr, "%s: %s\n", #call, qsf_status_message(status_)); \
+            goto done;                                                       \
+        }                                                                    \
+    } while (0)
+
+int main(void) {
+    const char *message = "authorise 42.00 EUR";
+    size_t message_len = strlen(message);
+    int exit_code = 1;
+
+    QsfKeyPair *key_pair = NULL;
+    QsfPublicKey *exported = NULL;
+    QsfPublicKey *terminal_key = NULL;
+    QsfVerifier *verifier = NULL;
+    QsfBytes signature = {0};
+    QsfBytes encoded = {0};
+
+    /* Signer: generate a Falcon-512 key pair and sign */
+    TRY(qsf_key_pair_generate(QSF_ALGORITHM_FALCON512, &key_pair));
+    TRY(qsf_sign(key_pair, (const uint8_t *)message, message_len, &signature));
+    printf("Signature length: %zu bytes\n", signature.len);
+
+    /* Signer: export the public key for distribution */
+    TRY(qsf_public_key_from_key_pair(key_pair, &exported));
+    TRY(qsf_public_key_to_bytes(exported, &encoded));
+    printf("Encoded public key length: %zu bytes\n", encoded.len);
+
+    /* Terminal: import the public key once and verify many messages */
+    TRY(qsf_public_key_from_bytes(encoded.data, encoded.len, &terminal_key));
+    TRY(qsf_verifier_new(terminal_key, &verifier));
+
+    QsfStatus status = qsf_verifier_verify(verifier, (const uint8_t *)message, message_len,
+                                           signature.data, signature.len);
+    printf("Genuine message: %s\n", qsf_status_message(status));
+
+    const char *tampered = "authorise 4200.00 EUR";
+    QsfStatus rejected = qsf_verifier_verify(verifier, (const uint8_t *)tampered,
+                                             strlen(tampered), signature.data, signature.len);
+    printf("Tampered message: %s\n", qsf_status_message(rejected));
+
+    if (status == QSF_OK && rejected == QSF_ERR_BAD_SIGNATURE)
+        exit_code = 0;
+
+done:
+    qsf_verifier_free(&verifier);
+    qsf_public_key_free(&terminal_key);
+    qsf_public_key_free(&exported);
+    qsf_bytes_free(&encoded);
+    qsf_bytes_free(&signature);
+    qsf_key_pair_free(&key_pair);
+    return exit_code;
+}
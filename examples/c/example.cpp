@@ -0,0 +1,87 @@
+/*
+ * The C API from C++, with handles and buffers owned by RAII wrappers.
+ *
+ * Build from the repository root:
+ *   cargo build --release --manifest-path capi/Cargo.toml
+ *   c++ -std=c++17 -Iinclude examples/c/example.cpp -Lcapi/target/release \
+ *       -Wl,-rpath,capi/target/release -lquantumsafe_finance -o example_cpp
+ */
+#include <cstdio>
+#include <memory>
+#include <stdexcept>
+#include <string>
+
+#include "quantumsafe.h"
+
+namespace qsf {
+
+struct KeyPairDeleter {
+    void operator()(QsfKeyPair *key_pair) const { qsf_key_pair_free(&key_pair); }
+};
+struct PublicKeyDeleter {
+    void operator()(QsfPublicKey *public_key) const { qsf_public_key_free(&public_key); }
+};
+struct VerifierDeleter {
+    void operator()(QsfVerifier *verifier) const { qsf_verifier_free(&verifier); }
+};
+
+using KeyPair = std::unique_ptr<QsfKeyPair, KeyPairDeleter>;
+using PublicKey = std::unique_ptr<QsfPublicKey, PublicKeyDeleter>;
+using Verifier = std::unique_ptr<QsfVerifier, VerifierDeleter>;
+
+// Buffer returned by the library, freed on destruction
+class Bytes {
+  public:
+    Bytes() = default;
+    Bytes(const Bytes &) = delete;
+    Bytes &operator=(const Bytes &) = delete;
+    ~Bytes() { qsf_bytes_free(&bytes_); }
+
+    QsfBytes *out() { return &bytes_; }
+    const uint8_t *data() const { return bytes_.data; }
+    size_t size() const { return bytes_.len; }
+
+  private:
+    QsfBytes bytes_{};
+};
+
+void check(QsfStatus status, const char *what) {
+    if (status != QSF_OK)
+        throw std::runtime_error(std::string(what) + ": " + qsf_status_message(status));
+}
+
+const uint8_t *bytes(const std::string &text) {
+    return reinterpret_cast<const uint8_t *>(text.data());
+}
+
+}  // namespace qsf
+
+int main() {
+    try {
+        const std::string message = "authorise 42.00 EUR";
+
+        QsfKeyPair *raw_key_pair = nullptr;
+        qsf::check(qsf_key_pair_generate(QSF_ALGORITHM_FALCON1024, &raw_key_pair), "generate");
+        qsf::KeyPair key_pair(raw_key_pair);
+
+        qsf::Bytes signature;
+        qsf::check(qsf_sign(key_pair.get(), qsf::bytes(message), message.size(), signature.out()),
+                   "sign");
+
+        QsfPublicKey *raw_public_key = nullptr;
+        qsf::check(qsf_public_key_from_key_pair(key_pair.get(), &raw_public_key), "export");
+        qsf::PublicKey public_key(raw_public_key);
+
+        QsfVerifier *raw_verifier = nullptr;
+        qsf::check(qsf_verifier_new(public_key.get(), &raw_verifier), "verifier");
+        qsf::Verifier verifier(raw_verifier);
+
+        QsfStatus status = qsf_verifier_verify(verifier.get(), qsf::bytes(message), message.size(),
+                                               signature.data(), signature.size());
+        std::printf("Signature (%zu bytes): %s\n", signature.size(), qsf_status_message(status));
+        return status == QSF_OK ? 0 : 1;
+    } catch (const std::exception &e) {
+        std::fprintf(stderr, "%s\n", e.what());
+        return 1;
+    }
+}
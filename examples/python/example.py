@@ -1,42 +1,56 @@
-from quantumsafe_finance import KeyPairWrapper, sign_message, verify_signature
+from quantumsafe_finance import (
+    KeyPairWrapper,
+    PublicKey,
+    QuantumSafeError,
+    Signature,
+    sign_message,
+    verify_signature,
+)
 import time
 
 def main():
     # Example 1: Key Generation
     print("Generating key pair...")
-    key_pair = KeyPairWrapper.generate()
+    key_pair = KeyPairWrapper.generate("falcon-512")
+    print(f"Algorithm: {key_pair.algorithm}, key id: {key_pair.key_id}")
     print(f"Public key length: {len(key_pair.public_key)} bytes")
     print(f"Private key length: {len(key_pair.private_key)} bytes")
 
     # Example 2: Signing and Verification
-    message = "Hello, quantum world!"
+    message = b"Hello, quantum world!"
     print("\nSigning message...")
-    signature = sign_message(message, key_pair.private_key)
-    
-    print("\nVerifying signature...")
-    is_valid = verify_signature(
-        message,
-        signature.signature,
-        key_pair.public_key
-    )
-    print(f"Signature verification: {is_valid}")
+    signature = key_pair.sign(message)
+
+    print("\nVerifying signature with the public key...")
+    public_key = PublicKey.from_bytes(PublicKey.from_key_pair(key_pair).to_bytes())
+    received = Signature.from_bytes(signature.to_bytes())
+    print(f"Signature verification: {public_key.verify(message, received)}")
 
     # Example 3: Key Pair Serialization
     print("\nSerializing key pair...")
     key_pair_json = key_pair.to_json()
-    print(f"Serialized key pair: {key_pair_json}")
 
     print("\nDeserializing key pair...")
     deserialized_key_pair = KeyPairWrapper.from_json(key_pair_json)
     print(f"Deserialized key pair: {deserialized_key_pair}")
 
-    # Example 4: Performance Benchmark
+    try:
+        KeyPairWrapper.from_json("{}")
+    except QuantumSafeError as e:
+        print(f"Rejected key pair: {type(e).__name__}: {e}")
+
+    # Example 4: HMAC signatures
+    hmac_key = KeyPairWrapper.generate()
+    tag = sign_message("Hello, quantum world!", hmac_key.private_key)
+    print(f"\nHMAC verification: {verify_signature('Hello, quantum world!', tag, hmac_key.private_key)}")
+
+    # Example 5: Performance Benchmark
     print("\nRunning performance benchmark...")
     start = time.time()
     for _ in range(1000):
-        sign_message(message, key_pair.private_key)
+        key_pair.sign(message)
     duration = time.time() - start
-    
+
     print(f"\nPerformance results:")
     print(f"Signatures per second: {1000 / duration:.2f}")
 
@@ -1,45 +1,48 @@
-import { KeyPairWrapper, signMessage, verifySignature } from 'quantumsafe-finance';
+import { KeyPairWrapper, PublicKey, QuantumSafeError } from 'quantumsafe-finance-wasm';
 import { performance } from 'perf_hooks';
 
-async function main() {
+function main() {
     // Example 1: Key Generation
     console.log('Generating key pair...');
-    const keyPair = await KeyPairWrapper.generate();
+    const keyPair = KeyPairWrapper.generate('falcon-512');
+    console.log(`Key id: ${keyPair.keyId}`);
     console.log(`Public key length: ${keyPair.publicKey.length} bytes`);
     console.log(`Private key length: ${keyPair.privateKey.length} bytes`);
 
     // Example 2: Signing and Verification
     const message = "Hello, quantum world!";
     console.log('\nSigning message...');
-    const signature = await signMessage(message, keyPair.privateKey);
-    
-    console.log('\nVerifying signature...');
-    const is_valid = await verifySignature(
-        message,
-        signature,
-        keyPair.publicKey
-    );
-    console.log(`Signature verification: ${is_valid}`);
+    const signature = keyPair.sign(message);
+
+    console.log('\nVerifying signature with the public key...');
+    const publicKey = PublicKey.fromBytes(PublicKey.fromKeyPair(keyPair).toBytes());
+    console.log(`Signature verification: ${publicKey.verify(message, signature)}`);
 
     // Example 3: Key Pair Serialization
     console.log('\nSerializing key pair...');
-    const keyPairJson = await KeyPairWrapper.toJson(keyPair);
-    console.log(`Serialized key pair: ${keyPairJson}`);
+    const keyPairJson = keyPair.toJson();
 
     console.log('\nDeserializing key pair...');
-    const deserializedKeyPair = await KeyPairWrapper.fromJson(keyPairJson);
-    console.log(`Deserialized key pair: ${deserializedKeyPair}`);
+    const deserializedKeyPair = KeyPairWrapper.fromJson(keyPairJson);
+    console.log(`Deserialized key id: ${deserializedKeyPair.keyId}`);
+
+    // Example 4: Typed errors
+    try {
+        KeyPairWrapper.fromJson('{}');
+    } catch (error) {
+        console.log(`\nRejected key pair: ${(error as QuantumSafeError).name}`);
+    }
 
-    // Example 4: Performance Benchmark
+    // Example 5: Performance Benchmark
     console.log('\nRunning performance benchmark...');
     const start = performance.now();
     for (let i = 0; i < 1000; i++) {
-        await signMessage(message, keyPair.privateKey);
+        keyPair.sign(message).free();
     }
     const duration = performance.now() - start;
-    
+
     console.log('\nPerformance results:');
     console.log(`Signatures per second: ${(1000 / (duration / 1000)).toFixed(2)}`);
 }
 
-main().catch(console.error);
+main();
@@ -0,0 +1,370 @@
+#ifndef QUANTUMSAFE_H
+#define QUANTUMSAFE_H
+
+/* Generated by cbindgen from src/bindings/c.rs. Do not edit. */
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Opaque key pair handle
+ */
+typedef struct QsfKeyPair QsfKeyPair;
+
+/**
+ * Opaque public key handle; holds no secret material
+ */
+typedef struct QsfPublicKey QsfPublicKey;
+
+/**
+ * Opaque verification context with the public key already decoded
+ *
+ * Verifying does not modify the context, so threads may share one.
+ */
+typedef struct QsfVerifier QsfVerifier;
+
+/**
+ * Result of a C API call: `QSF_OK` or a negative error code
+ */
+typedef int QsfStatus;
+
+/**
+ * Signature algorithm: one of the `QSF_ALGORITHM_*` values
+ */
+typedef uint32_t QsfAlgorithm;
+
+/**
+ * Byte buffer owned by the library, released with `qsf_bytes_free`
+ */
+typedef struct QsfBytes {
+  uint8_t *data;
+  size_t len;
+} QsfBytes;
+
+/**
+ * The call succeeded
+ */
+#define QSF_OK 0
+
+/**
+ * A required pointer was null
+ */
+#define QSF_ERR_NULL_POINTER -1
+
+/**
+ * A length is too large or wrong for the value
+ */
+#define QSF_ERR_INVALID_LENGTH -2
+
+/**
+ * The algorithm identifier is not one of the `QSF_ALGORITHM_*` values
+ */
+#define QSF_ERR_INVALID_ALGORITHM -3
+
+/**
+ * The key is malformed or its halves do not match
+ */
+#define QSF_ERR_INVALID_KEY -4
+
+/**
+ * Input is not valid UTF-8 or JSON
+ */
+#define QSF_ERR_INVALID_ENCODING -5
+
+/**
+ * The signature does not verify
+ */
+#define QSF_ERR_BAD_SIGNATURE -6
+
+/**
+ * An unexpected internal failure; the call had no effect
+ */
+#define QSF_ERR_INTERNAL -7
+
+/**
+ * HMAC-SHA3-256; verification needs the private key
+ */
+#define QSF_ALGORITHM_HMAC_SHA3_256 0
+
+/**
+ * Falcon-512 (FN-DSA)
+ */
+#define QSF_ALGORITHM_FALCON512 1
+
+/**
+ * Falcon-1024 (FN-DSA)
+ */
+#define QSF_ALGORITHM_FALCON1024 2
+
+/**
+ * ML-DSA-44 (FIPS 204)
+ */
+#define QSF_ALGORITHM_ML_DSA_44 3
+
+/**
+ * ML-DSA-65 (FIPS 204)
+ */
+#define QSF_ALGORITHM_ML_DSA_65 4
+
+/**
+ * ML-DSA-87 (FIPS 204)
+ */
+#define QSF_ALGORITHM_ML_DSA_87 5
+
+/**
+ * SLH-DSA-SHAKE-128s (FIPS 205)
+ */
+#define QSF_ALGORITHM_SLH_DSA_SHAKE_128S 6
+
+/**
+ * SLH-DSA-SHAKE-128f (FIPS 205)
+ */
+#define QSF_ALGORITHM_SLH_DSA_SHAKE_128F 7
+
+/**
+ * SLH-DSA-SHAKE-192s (FIPS 205)
+ */
+#define QSF_ALGORITHM_SLH_DSA_SHAKE_192S 8
+
+/**
+ * SLH-DSA-SHAKE-192f (FIPS 205)
+ */
+#define QSF_ALGORITHM_SLH_DSA_SHAKE_192F 9
+
+/**
+ * SLH-DSA-SHAKE-256s (FIPS 205)
+ */
+#define QSF_ALGORITHM_SLH_DSA_SHAKE_256S 10
+
+/**
+ * SLH-DSA-SHAKE-256f (FIPS 205)
+ */
+#define QSF_ALGORITHM_SLH_DSA_SHAKE_256F 11
+
+/**
+ * Composite ML-DSA-44 + Ed25519 (draft-ietf-lamps-pq-composite-sigs)
+ */
+#define QSF_ALGORITHM_ML_DSA_44_ED25519 12
+
+/**
+ * Composite ML-DSA-65 + Ed25519 (draft-ietf-lamps-pq-composite-sigs)
+ */
+#define QSF_ALGORITHM_ML_DSA_65_ED25519 13
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Static description of a status code; never null
+ */
+const char *qsf_status_message(QsfStatus status);
+
+/**
+ * Generate a key pair for `algorithm`
+ *
+ * # Safety
+ *
+ * `out_key_pair` must be null or writable.
+ */
+QsfStatus qsf_key_pair_generate(QsfAlgorithm algorithm, struct QsfKeyPair **out_key_pair);
+
+/**
+ * Import a key pair from the JSON written by `qsf_key_pair_to_json`
+ *
+ * # Safety
+ *
+ * `json` must be null or readable for `json_len` bytes, and `out_key_pair`
+ * must be null or writable.
+ */
+QsfStatus qsf_key_pair_from_json(const uint8_t *json,
+                                 size_t json_len,
+                                 struct QsfKeyPair **out_key_pair);
+
+/**
+ * Export a key pair, including its private key, as JSON
+ *
+ * # Safety
+ *
+ * `key_pair` must be null or live, and `out_json` null or writable.
+ */
+QsfStatus qsf_key_pair_to_json(const struct QsfKeyPair *key_pair, struct QsfBytes *out_json);
+
+/**
+ * Algorithm of a key pair
+ *
+ * # Safety
+ *
+ * `key_pair` must be null or live, and `out_algorithm` null or writable.
+ */
+QsfStatus qsf_key_pair_algorithm(const struct QsfKeyPair *key_pair, QsfAlgorithm *out_algorithm);
+
+/**
+ * Copy of a key pair's encoded public key
+ *
+ * # Safety
+ *
+ * `key_pair` must be null or live, and `out_public_key` null or writable.
+ */
+QsfStatus qsf_key_pair_public_key(const struct QsfKeyPair *key_pair,
+                                  struct QsfBytes *out_public_key);
+
+/**
+ * Free a key pair and null the handle; a null handle is ignored
+ *
+ * # Safety
+ *
+ * `key_pair` must be null or point to a null or live key pair handle, which
+ * must not be used afterwards.
+ */
+void qsf_key_pair_free(struct QsfKeyPair **key_pair);
+
+/**
+ * Sign `message` with a key pair
+ *
+ * # Safety
+ *
+ * `key_pair` must be null or live, `message` null or readable for
+ * `message_len` bytes, and `out_signature` null or writable.
+ */
+QsfStatus qsf_sign(const struct QsfKeyPair *key_pair,
+                   const uint8_t *message,
+                   size_t message_len,
+                   struct QsfBytes *out_signature);
+
+/**
+ * Verify a signature made with a key pair: `QSF_OK` or `QSF_ERR_BAD_SIGNATURE`
+ *
+ * # Safety
+ *
+ * `key_pair` must be null or live, and `message` and `signature` null or
+ * readable for `message_len` and `signature_len` bytes.
+ */
+QsfStatus qsf_verify(const struct QsfKeyPair *key_pair,
+                     const uint8_t *message,
+                     size_t message_len,
+                     const uint8_t *signature,
+                     size_t signature_len);
+
+/**
+ * Import a public key from its algorithm-tagged encoding
+ *
+ * The encoding is the one written by `qsf_public_key_to_bytes`. HMAC keys
+ * are refused with `QSF_ERR_INVALID_ALGORITHM` as they cannot verify alone.
+ *
+ * # Safety
+ *
+ * `encoded` must be null or readable for `encoded_len` bytes, and
+ * `out_public_key` null or writable.
+ */
+QsfStatus qsf_public_key_from_bytes(const uint8_t *encoded,
+                                    size_t encoded_len,
+                                    struct QsfPublicKey **out_public_key);
+
+/**
+ * Public key of a key pair, e.g. for export to verifying devices
+ *
+ * # Safety
+ *
+ * `key_pair` must be null or live, and `out_public_key` null or writable.
+ */
+QsfStatus qsf_public_key_from_key_pair(const struct QsfKeyPair *key_pair,
+                                       struct QsfPublicKey **out_public_key);
+
+/**
+ * Algorithm-tagged encoding of a public key
+ *
+ * # Safety
+ *
+ * `public_key` must be null or live, and `out_encoded` null or writable.
+ */
+QsfStatus qsf_public_key_to_bytes(const struct QsfPublicKey *public_key,
+                                  struct QsfBytes *out_encoded);
+
+/**
+ * Algorithm of a public key
+ *
+ * # Safety
+ *
+ * `public_key` must be null or live, and `out_algorithm` null or writable.
+ */
+QsfStatus qsf_public_key_algorithm(const struct QsfPublicKey *public_key,
+                                   QsfAlgorithm *out_algorithm);
+
+/**
+ * Verify a signature with only a public key: `QSF_OK` or `QSF_ERR_BAD_SIGNATURE`
+ *
+ * Decodes the key on every call; use a `QsfVerifier` for repeated checks.
+ *
+ * # Safety
+ *
+ * `public_key` must be null or live, and `message` and `signature` null or
+ * readable for `message_len` and `signature_len` bytes.
+ */
+QsfStatus qsf_public_key_verify(const struct QsfPublicKey *public_key,
+                                const uint8_t *message,
+                                size_t message_len,
+                                const uint8_t *signature,
+                                size_t signature_len);
+
+/**
+ * Free a public key and null the handle; a null handle is ignored
+ *
+ * # Safety
+ *
+ * `public_key` must be null or point to a null or live public key handle,
+ * which must not be used afterwards.
+ */
+void qsf_public_key_free(struct QsfPublicKey **public_key);
+
+/**
+ * Decode a public key once into a reusable verification context
+ *
+ * The context does not borrow the public key handle.
+ *
+ * # Safety
+ *
+ * `public_key` must be null or live, and `out_verifier` null or writable.
+ */
+QsfStatus qsf_verifier_new(const struct QsfPublicKey *public_key,
+                           struct QsfVerifier **out_verifier);
+
+/**
+ * Verify a signature with a context: `QSF_OK` or `QSF_ERR_BAD_SIGNATURE`
+ *
+ * # Safety
+ *
+ * `verifier` must be null or live, and `message` and `signature` null or
+ * readable for `message_len` and `signature_len` bytes.
+ */
+QsfStatus qsf_verifier_verify(const struct QsfVerifier *verifier,
+                              const uint8_t *message,
+                              size_t message_len,
+                              const uint8_t *signature,
+                              size_t signature_len);
+
+/**
+ * Free a verification context and null the handle; a null handle is ignored
+ *
+ * # Safety
+ *
+ * `verifier` must be null or point to a null or live verifier handle, which
+ * must not be used afterwards.
+ */
+void qsf_verifier_free(struct QsfVerifier **verifier);
+
+/**
+ * Free a buffer returned by the library and clear it; an empty buffer is ignored
+ *
+ * # Safety
+ *
+ * `bytes` must be null or point to an empty buffer or one returned by this
+ * library and not yet freed.
+ */
+void qsf_bytes_free(struct QsfBytes *bytes);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* QUANTUMSAFE_H */
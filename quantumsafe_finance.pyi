@@ -0,0 +1,102 @@
+"""Type stubs for the quantumsafe_finance extension module."""
+
+from asyncio import Future
+from typing import Any, Iterable, List, Literal, Tuple, Union
+
+AlgorithmTag = Literal[
+    "hmac-sha3-256",
+    "falcon-512",
+    "falcon-1024",
+    "ml-dsa-44",
+    "ml-dsa-65",
+    "ml-dsa-87",
+    "slh-dsa-shake-128s",
+    "slh-dsa-shake-128f",
+    "slh-dsa-shake-192s",
+    "slh-dsa-shake-192f",
+    "slh-dsa-shake-256s",
+    "slh-dsa-shake-256f",
+    "ml-dsa-44-ed25519",
+    "ml-dsa-65-ed25519",
+]
+Buffer = Union[bytes, bytearray, memoryview, "Signature"]
+Message = Union[str, Buffer]
+# Lists, tuples, NumPy arrays (one buffer per row) or any other iterable
+Buffers = Iterable[Any]
+
+ALGORITHMS: Tuple[AlgorithmTag, ...]
+
+class QuantumSafeError(Exception):
+    """Base class of all QuantumSafe Finance errors"""
+
+class InvalidKeyError(QuantumSafeError):
+    """A key is malformed or does not match its algorithm"""
+
+class InvalidAlgorithmError(QuantumSafeError):
+    """An unknown algorithm, or one that cannot perform the operation"""
+
+class EncodingError(QuantumSafeError):
+    """Serialized key material cannot be decoded"""
+
+class Signature:
+    def __init__(self, signature: Buffer) -> None: ...
+    @classmethod
+    def from_bytes(cls, data: Buffer) -> Signature: ...
+    def to_bytes(self) -> bytes: ...
+    @property
+    def signature(self) -> bytes: ...
+    def __bytes__(self) -> bytes: ...
+    def __len__(self) -> int: ...
+    def __eq__(self, other: object) -> bool: ...
+    def __ne__(self, other: object) -> bool: ...
+
+class KeyPairWrapper:
+    def __init__(self, algorithm: AlgorithmTag = "hmac-sha3-256") -> None: ...
+    @classmethod
+    def generate(cls, algorithm: AlgorithmTag = "hmac-sha3-256") -> KeyPairWrapper: ...
+    @classmethod
+    def generate_async(
+        cls, algorithm: AlgorithmTag = "hmac-sha3-256"
+    ) -> Future[KeyPairWrapper]: ...
+    @property
+    def algorithm(self) -> AlgorithmTag: ...
+    @property
+    def public_key(self) -> bytes: ...
+    @property
+    def private_key(self) -> bytes: ...
+    @property
+    def key_id(self) -> str: ...
+    def sign(self, message: Message) -> Signature:
+        """Raises InvalidKeyError if the private key is malformed"""
+    def sign_async(self, message: Message) -> Future[Signature]: ...
+    def verify(self, message: Message, signature: Buffer) -> bool: ...
+    def verify_async(self, message: Message, signature: Buffer) -> Future[bool]: ...
+    def verify_many(self, messages: Buffers, signatures: Buffers) -> List[bool]:
+        """Raises ValueError unless there is one signature per message"""
+    def verify_many_async(self, messages: Buffers, signatures: Buffers) -> Future[List[bool]]: ...
+    @classmethod
+    def from_json(cls, json: str) -> KeyPairWrapper:
+        """Raises EncodingError for malformed JSON, InvalidKeyError for bad keys"""
+    def to_json(self) -> str: ...
+
+class PublicKey:
+    @classmethod
+    def from_bytes(cls, data: Buffer) -> PublicKey:
+        """Raises EncodingError, InvalidAlgorithmError or InvalidKeyError"""
+    @classmethod
+    def from_key_pair(cls, key_pair: KeyPairWrapper) -> PublicKey:
+        """Raises InvalidAlgorithmError for hmac-sha3-256 key pairs"""
+    def to_bytes(self) -> bytes: ...
+    @property
+    def algorithm(self) -> AlgorithmTag: ...
+    def verify(self, message: Message, signature: Buffer) -> bool: ...
+    def verify_async(self, message: Message, signature: Buffer) -> Future[bool]: ...
+    def verify_many(self, messages: Buffers, signatures: Buffers) -> List[bool]:
+        """Raises ValueError unless there is one signature per message"""
+    def verify_many_async(self, messages: Buffers, signatures: Buffers) -> Future[List[bool]]: ...
+
+def sign_message(message: Message, private_key: Buffer) -> Signature:
+    """HMAC-SHA3-256 signature"""
+
+def verify_signature(message: Message, signature: Buffer, private_key: Buffer) -> bool:
+    """HMAC-SHA3-256 verification, which needs the private key"""
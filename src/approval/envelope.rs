@@ -0,0 +1,401 @@
+//! M-of-N approval envelope
+//!
+//! An envelope carries a document, the policy that must be met before the
+//! document is acted upon, and the approvals collected so far. Each approval
+//! signs the document and the policy together, so neither can be swapped
+//! after signing. The embedded policy is whatever the envelope's author wrote,
+//! so verifiers check it against the policy they expect from their own
+//! configuration.
+
+use crate::backend::{BackendError, KeyBackend, KeyHandle};
+use crate::crypto::KeyId;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::{Digest, Sha3_256};
+use std::collections::HashSet;
+use std::fmt;
+
+const DOMAIN: &[u8] = b"quantumsafe-finance/approval/v1";
+
+/// Approval envelope error
+#[derive(Debug)]
+pub enum ApprovalError {
+    InvalidPolicy(&'static str),
+    PolicyMismatch,
+    NotAnApprover(KeyId),
+    AlreadyApproved(KeyId),
+    Backend(BackendError),
+}
+
+impl fmt::Display for ApprovalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalError::InvalidPolicy(reason) => write!(f, "invalid policy: {}", reason),
+            ApprovalError::PolicyMismatch => {
+                write!(f, "envelope policy differs from the expected policy")
+            }
+            ApprovalError::NotAnApprover(id) => write!(f, "key {} is not an approver", id),
+            ApprovalError::AlreadyApproved(id) => write!(f, "key {} already approved", id),
+            ApprovalError::Backend(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ApprovalError {}
+
+impl From<BackendError> for ApprovalError {
+    fn from(e: BackendError) -> Self {
+        ApprovalError::Backend(e)
+    }
+}
+
+/// Named holder of an approval key
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Approver {
+    pub name: String,
+    pub key_id: KeyId,
+}
+
+/// Policy requiring `threshold` approvals out of `approvers`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalPolicy {
+    pub threshold: usize,
+    pub approvers: Vec<Approver>,
+}
+
+impl ApprovalPolicy {
+    /// Create a policy, checking the threshold and that key ids are distinct
+    pub fn new(threshold: usize, approvers: Vec<Approver>) -> Result<Self, ApprovalError> {
+        let policy = Self {
+            threshold,
+            approvers,
+        };
+        policy.validate()?;
+        Ok(policy)
+    }
+
+    fn validate(&self) -> Result<(), ApprovalError> {
+        if self.threshold == 0 || self.threshold > self.approvers.len() {
+            return Err(ApprovalError::InvalidPolicy(
+                "threshold must be between 1 and the number of approvers",
+            ));
+        }
+        let distinct: HashSet<_> = self.approvers.iter().map(|a| a.key_id).collect();
+        if distinct.len() != self.approvers.len() {
+            return Err(ApprovalError::InvalidPolicy(
+                "approver keys must be distinct",
+            ));
+        }
+        Ok(())
+    }
+
+    fn approver(&self, key_id: &KeyId) -> Option<&Approver> {
+        self.approvers.iter().find(|a| a.key_id == *key_id)
+    }
+}
+
+/// Signature of one approver
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Approval {
+    pub key_id: KeyId,
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    pub signature: Vec<u8>,
+}
+
+/// Document with an embedded approval policy and collected approvals
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    #[serde(serialize_with = "to_base64", deserialize_with = "from_base64")]
+    pub document: Vec<u8>,
+    pub policy: ApprovalPolicy,
+    pub approvals: Vec<Approval>,
+}
+
+/// Outcome of verifying an envelope
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalReport {
+    /// Approvers with a valid signature
+    pub approved_by: Vec<Approver>,
+    /// Approvers who have not (validly) signed
+    pub missing: Vec<Approver>,
+    /// Approvals that are invalid, duplicated or from keys outside the policy
+    pub rejected: Vec<KeyId>,
+    /// Whether the number of valid approvals meets the threshold
+    pub policy_met: bool,
+}
+
+impl Envelope {
+    /// Wrap a document with the policy it must satisfy
+    pub fn new(document: &[u8], policy: ApprovalPolicy) -> Result<Self, ApprovalError> {
+        policy.validate()?;
+        Ok(Self {
+            document: document.to_vec(),
+            policy,
+            approvals: Vec::new(),
+        })
+    }
+
+    /// Bytes each approver signs: the document bound to the policy
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let policy = serde_json::to_vec(&self.policy).expect("Failed to serialize policy");
+        let mut hasher = Sha3_256::new();
+        hasher.update(DOMAIN);
+        hasher.update((self.document.len() as u64).to_be_bytes());
+        hasher.update(&self.document);
+        hasher.update(policy);
+        hasher.finalize().to_vec()
+    }
+
+    /// Add the approval of `signer`, who must be listed in the policy
+    pub fn approve(&mut self, signer: &dyn KeyHandle) -> Result<(), ApprovalError> {
+        let key_id = signer.key_id();
+        if self.policy.approver(&key_id).is_none() {
+            return Err(ApprovalError::NotAnApprover(key_id));
+        }
+        if self.approvals.iter().any(|a| a.key_id == key_id) {
+            return Err(ApprovalError::AlreadyApproved(key_id));
+        }
+        let signature = signer.sign(&self.signing_payload())?;
+        self.approvals.push(Approval { key_id, signature });
+        Ok(())
+    }
+
+    /// Check every approval against `backend` and evaluate the policy, which
+    /// must equal `expected`
+    pub fn verify(
+        &self,
+        expected: &ApprovalPolicy,
+        backend: &dyn KeyBackend,
+    ) -> Result<ApprovalReport, ApprovalError> {
+        expected.validate()?;
+        if self.policy != *expected {
+            return Err(ApprovalError::PolicyMismatch);
+        }
+        let payload = self.signing_payload();
+        let mut seen = HashSet::new();
+        let mut approved_by = Vec::new();
+        let mut rejected = Vec::new();
+
+        for approval in &self.approvals {
+            let approver = match self.policy.approver(&approval.key_id) {
+                Some(approver) if seen.insert(approval.key_id) => approver,
+                _ => {
+                    rejected.push(approval.key_id);
+                    continue;
+                }
+            };
+            let valid = match backend.verify(&approval.key_id, &payload, &approval.signature) {
+                Ok(valid) => valid,
+                Err(BackendError::KeyNotFound(_)) => false,
+                Err(e) => return Err(e.into()),
+            };
+            if valid {
+                approved_by.push(approver.clone());
+            } else {
+                rejected.push(approval.key_id);
+            }
+        }
+
+        let missing = self
+            .policy
+            .approvers
+            .iter()
+            .filter(|a| !approved_by.contains(a))
+            .cloned()
+            .collect();
+        let policy_met = approved_by.len() >= self.policy.threshold;
+        Ok(ApprovalReport {
+            approved_by,
+            missing,
+            rejected,
+            policy_met,
+        })
+    }
+
+    /// Convert envelope to JSON string
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("Failed to serialize envelope")
+    }
+
+    /// Convert JSON string to envelope
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+}
+
+fn to_base64<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&STANDARD.encode(bytes))
+}
+
+fn from_base64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    STANDARD
+        .decode(s)
+        .map_err(|e| serde::de::Error::custom(format!("Invalid base64: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::MemoryBackend;
+    use crate::crypto::{generate_key_pair, KeyPair};
+
+    struct Treasury {
+        cfo: KeyPair,
+        treasurer: KeyPair,
+        controller: KeyPair,
+        backend: MemoryBackend,
+    }
+
+    fn treasury() -> (Treasury, ApprovalPolicy) {
+        let cfo = generate_key_pair();
+        let treasurer = generate_key_pair();
+        let controller = generate_key_pair();
+        let policy = ApprovalPolicy::new(
+            2,
+            vec![
+                Approver {
+                    name: "CFO".to_string(),
+                    key_id: cfo.key_id(),
+                },
+                Approver {
+                    name: "treasurer".to_string(),
+                    key_id: treasurer.key_id(),
+                },
+                Approver {
+                    name: "controller".to_string(),
+                    key_id: controller.key_id(),
+                },
+            ],
+        )
+        .unwrap();
+
+        let mut backend = MemoryBackend::new();
+        for key_pair in [&cfo, &treasurer, &controller] {
+            backend.insert(KeyPair {
+                public_key: key_pair.public_key.clone(),
+                private_key: key_pair.private_key.clone(),
+                algorithm: key_pair.algorithm,
+            });
+        }
+        let treasury = Treasury {
+            cfo,
+            treasurer,
+            controller,
+            backend,
+        };
+        (treasury, policy)
+    }
+
+    #[test]
+    fn test_policy_met() {
+        let (t, policy) = treasury();
+        let mut envelope = Envelope::new(b"wire 1,000,000 EUR", policy.clone()).unwrap();
+
+        envelope.approve(&t.cfo).unwrap();
+        let report = envelope.verify(&policy, &t.backend).unwrap();
+        assert!(!report.policy_met);
+        assert_eq!(report.approved_by[0].name, "CFO");
+        assert_eq!(report.missing.len(), 2);
+
+        envelope.approve(&t.treasurer).unwrap();
+        let report = envelope.verify(&policy, &t.backend).unwrap();
+        assert!(report.policy_met);
+        assert_eq!(report.missing[0].key_id, t.controller.key_id());
+        assert!(report.rejected.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_outsiders_and_duplicates() {
+        let (t, policy) = treasury();
+        let mut envelope = Envelope::new(b"wire", policy.clone()).unwrap();
+
+        assert!(matches!(
+            envelope.approve(&generate_key_pair()),
+            Err(ApprovalError::NotAnApprover(_))
+        ));
+        envelope.approve(&t.cfo).unwrap();
+        assert!(matches!(
+            envelope.approve(&t.cfo),
+            Err(ApprovalError::AlreadyApproved(_))
+        ));
+
+        let duplicate = envelope.approvals[0].clone();
+        envelope.approvals.push(duplicate);
+        let report = envelope.verify(&policy, &t.backend).unwrap();
+        assert_eq!(report.approved_by.len(), 1);
+        assert_eq!(report.rejected, vec![t.cfo.key_id()]);
+        assert!(!report.policy_met);
+    }
+
+    #[test]
+    fn test_tampering_invalidates_approvals() {
+        let (t, policy) = treasury();
+        let mut envelope = Envelope::new(b"wire 100 EUR", policy.clone()).unwrap();
+        envelope.approve(&t.cfo).unwrap();
+        envelope.approve(&t.treasurer).unwrap();
+
+        let mut json = Envelope::from_json(&envelope.to_json()).unwrap();
+        assert_eq!(json, envelope);
+        assert!(json.verify(&policy, &t.backend).unwrap().policy_met);
+
+        json.document = b"wire 900 EUR".to_vec();
+        assert!(!json.verify(&policy, &t.backend).unwrap().policy_met);
+
+        let mut lowered = envelope.clone();
+        lowered.policy.threshold = 1;
+        assert!(matches!(
+            lowered.verify(&policy, &t.backend),
+            Err(ApprovalError::PolicyMismatch)
+        ));
+        // Approvals over the embedded policy fail even if the verifier
+        // expects the lowered one
+        assert_eq!(
+            lowered
+                .verify(&lowered.policy, &t.backend)
+                .unwrap()
+                .rejected
+                .len(),
+            2
+        );
+        lowered.policy.threshold = 0;
+        assert!(matches!(
+            lowered.verify(&lowered.policy, &t.backend),
+            Err(ApprovalError::InvalidPolicy(_))
+        ));
+    }
+
+    #[test]
+    fn test_forged_policy_rejected() {
+        let (t, policy) = treasury();
+        // The controller writes a 1-of-1 policy naming only themselves
+        let forged = ApprovalPolicy::new(
+            1,
+            vec![Approver {
+                name: "controller".to_string(),
+                key_id: t.controller.key_id(),
+            }],
+        )
+        .unwrap();
+        let mut envelope = Envelope::new(b"wire 1,000,000 EUR", forged.clone()).unwrap();
+        envelope.approve(&t.controller).unwrap();
+
+        assert!(envelope.verify(&forged, &t.backend).unwrap().policy_met);
+        assert!(matches!(
+            envelope.verify(&policy, &t.backend),
+            Err(ApprovalError::PolicyMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_policy() {
+        assert!(ApprovalPolicy::new(1, vec![]).is_err());
+        let key_id = generate_key_pair().key_id();
+        let approver = Approver {
+            name: "CFO".to_string(),
+            key_id,
+        };
+        assert!(ApprovalPolicy::new(1, vec![approver.clone(), approver]).is_err());
+    }
+}
@@ -0,0 +1,5 @@
+//! Multi-signature approval envelopes
+
+pub use self::envelope::*;
+
+mod envelope;
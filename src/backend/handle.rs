@@ -0,0 +1,147 @@
+//! Key handle abstraction
+
+use crate::crypto::{CryptoError, KeyId, KeyPair, SHARED_SECRET_LEN};
+use std::fmt;
+
+/// Error returned by a key backend
+#[derive(Debug)]
+pub enum BackendError {
+    /// No key with the given id is held by the backend
+    KeyNotFound(KeyId),
+    /// The backend refused or does not support the operation
+    Unsupported(&'static str),
+    /// Failure reported by the underlying token, store or service
+    Backend(String),
+}
+
+impl fmt::Display for BackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackendError::KeyNotFound(id) => write!(f, "key {} not found", id),
+            BackendError::Unsupported(op) => write!(f, "operation not supported: {}", op),
+            BackendError::Backend(e) => write!(f, "backend error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BackendError {}
+
+impl From<CryptoError> for BackendError {
+    fn from(e: CryptoError) -> Self {
+        BackendError::Backend(e.to_string())
+    }
+}
+
+/// Reference to a key, wherever its private material lives
+///
+/// Implementations for hardware tokens perform `sign`, `verify` and
+/// `decapsulate` inside the token; the private key never enters process
+/// memory.
+pub trait KeyHandle {
+    /// Identifier of the referenced key
+    fn key_id(&self) -> KeyId;
+
+    /// Sign a message with the referenced key
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, BackendError>;
+
+    /// Verify a signature produced by `sign`
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, BackendError>;
+
+    /// Recover the shared secret of an ML-KEM-768 ciphertext
+    ///
+    /// Signing keys return `BackendError::Unsupported`.
+    fn decapsulate(&self, ciphertext: &[u8]) -> Result<[u8; SHARED_SECRET_LEN], BackendError> {
+        let _ = ciphertext;
+        Err(BackendError::Unsupported("decapsulate"))
+    }
+}
+
+/// Store of keys addressed by id
+///
+/// Signatures are opaque to the caller and must be verified through the
+/// backend that produced them.
+pub trait KeyBackend {
+    /// Sign a message with the key `key_id`
+    fn sign(&self, key_id: &KeyId, message: &[u8]) -> Result<Vec<u8>, BackendError>;
+
+    /// Verify a signature produced by `sign` with the key `key_id`
+    fn verify(
+        &self,
+        key_id: &KeyId,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, BackendError>;
+
+    /// Decapsulate an ML-KEM-768 ciphertext with the key `key_id`
+    fn decapsulate(
+        &self,
+        key_id: &KeyId,
+        ciphertext: &[u8],
+    ) -> Result<[u8; SHARED_SECRET_LEN], BackendError> {
+        let _ = (key_id, ciphertext);
+        Err(BackendError::Unsupported("decapsulate"))
+    }
+}
+
+/// Handle to one key of a `KeyBackend`
+pub struct BackendKey<'a> {
+    backend: &'a dyn KeyBackend,
+    key_id: KeyId,
+}
+
+impl<'a> BackendKey<'a> {
+    /// Refer to the key `key_id` held by `backend`
+    pub fn new(backend: &'a dyn KeyBackend, key_id: KeyId) -> Self {
+        Self { backend, key_id }
+    }
+}
+
+impl KeyHandle for BackendKey<'_> {
+    fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, BackendError> {
+        self.backend.sign(&self.key_id, message)
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, BackendError> {
+        self.backend.verify(&self.key_id, message, signature)
+    }
+
+    fn decapsulate(&self, ciphertext: &[u8]) -> Result<[u8; SHARED_SECRET_LEN], BackendError> {
+        self.backend.decapsulate(&self.key_id, ciphertext)
+    }
+}
+
+impl KeyHandle for KeyPair {
+    fn key_id(&self) -> KeyId {
+        KeyPair::key_id(self)
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, BackendError> {
+        Ok(KeyPair::sign(self, message)?)
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, BackendError> {
+        Ok(KeyPair::verify(self, message, signature))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair, verify_signature};
+
+    #[test]
+    fn test_key_pair_handle() {
+        let key_pair = generate_key_pair();
+        let handle: &dyn KeyHandle = &key_pair;
+        let message = b"Test message";
+
+        let signature = handle.sign(message).unwrap();
+        assert!(handle.verify(message, &signature).unwrap());
+        assert!(verify_signature(message, &signature, &key_pair.private_key));
+        assert_eq!(handle.key_id(), key_pair.key_id());
+    }
+}
@@ -0,0 +1,167 @@
+//! In-process key backends
+
+use super::handle::{BackendError, KeyBackend};
+use crate::crypto::{
+    generate_key_pair, tagged_fingerprint, Ciphertext, Kem, KeyId, KeyPair, MlKem768, ParameterSet,
+    PublicKey, SecretKey, SHARED_SECRET_LEN,
+};
+use crate::keystore::{KeyStore, KeyStoreError};
+use rand::thread_rng;
+use std::collections::HashMap;
+
+/// Backend holding key pairs in process memory
+#[derive(Default)]
+pub struct MemoryBackend {
+    keys: HashMap<KeyId, KeyPair>,
+    kem_keys: HashMap<KeyId, SecretKey<MlKem768>>,
+}
+
+impl MemoryBackend {
+    /// Create an empty backend
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate and store a new key pair
+    pub fn generate(&mut self) -> KeyId {
+        self.insert(generate_key_pair())
+    }
+
+    /// Store an existing key pair
+    pub fn insert(&mut self, key_pair: KeyPair) -> KeyId {
+        let key_id = key_pair.key_id();
+        self.keys.insert(key_id, key_pair);
+        key_id
+    }
+
+    /// Generate and store a new ML-KEM-768 key, returning its public key
+    pub fn generate_kem(&mut self) -> (KeyId, PublicKey<MlKem768>) {
+        let (public_key, secret_key) = MlKem768::generate(&mut thread_rng());
+        let key_id = kem_key_id(&public_key);
+        self.kem_keys.insert(key_id, secret_key);
+        (key_id, public_key)
+    }
+
+    fn key(&self, key_id: &KeyId) -> Result<&KeyPair, BackendError> {
+        self.keys
+            .get(key_id)
+            .ok_or(BackendError::KeyNotFound(*key_id))
+    }
+}
+
+impl KeyBackend for MemoryBackend {
+    fn sign(&self, key_id: &KeyId, message: &[u8]) -> Result<Vec<u8>, BackendError> {
+        Ok(self.key(key_id)?.sign(message)?)
+    }
+
+    fn verify(
+        &self,
+        key_id: &KeyId,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, BackendError> {
+        Ok(self.key(key_id)?.verify(message, signature))
+    }
+
+    fn decapsulate(
+        &self,
+        key_id: &KeyId,
+        ciphertext: &[u8],
+    ) -> Result<[u8; SHARED_SECRET_LEN], BackendError> {
+        let secret_key = match self.kem_keys.get(key_id) {
+            Some(secret_key) => secret_key,
+            None if self.keys.contains_key(key_id) => {
+                return Err(BackendError::Unsupported("decapsulate"))
+            }
+            None => return Err(BackendError::KeyNotFound(*key_id)),
+        };
+        let ciphertext = Ciphertext::<MlKem768>::from_bytes(ciphertext)?;
+        Ok(secret_key.decapsulate(&ciphertext))
+    }
+}
+
+/// Identifier of an ML-KEM-768 public key
+pub fn kem_key_id(public_key: &PublicKey<MlKem768>) -> KeyId {
+    tagged_fingerprint(MlKem768::NAME, public_key.as_bytes()).key_id()
+}
+
+impl From<KeyStoreError> for BackendError {
+    fn from(e: KeyStoreError) -> Self {
+        match e {
+            KeyStoreError::UnknownKey(key_id) => BackendError::KeyNotFound(key_id),
+            e => BackendError::Backend(e.to_string()),
+        }
+    }
+}
+
+/// Signatures carry the key id and are subject to the key's lifecycle state
+impl KeyBackend for KeyStore {
+    fn sign(&self, key_id: &KeyId, message: &[u8]) -> Result<Vec<u8>, BackendError> {
+        Ok(KeyStore::sign(self, key_id, message)?)
+    }
+
+    fn verify(
+        &self,
+        key_id: &KeyId,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, BackendError> {
+        if crate::crypto::signature_key_id(signature) != Some(*key_id) {
+            return Ok(false);
+        }
+        Ok(KeyStore::verify(self, message, signature)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendKey, KeyHandle};
+
+    fn round_trip(backend: &dyn KeyBackend, key_id: &KeyId) {
+        let message = b"Test message";
+        let signature = backend.sign(key_id, message).unwrap();
+        assert!(backend.verify(key_id, message, &signature).unwrap());
+        assert!(!backend.verify(key_id, b"Other", &signature).unwrap());
+    }
+
+    #[test]
+    fn test_memory_backend() {
+        let mut backend = MemoryBackend::new();
+        let key_id = backend.generate();
+        round_trip(&backend, &key_id);
+        assert!(matches!(
+            backend.decapsulate(&key_id, &[0; 1088]),
+            Err(BackendError::Unsupported(_))
+        ));
+
+        let unknown = generate_key_pair().key_id();
+        assert!(matches!(
+            backend.sign(&unknown, b"Test"),
+            Err(BackendError::KeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_memory_backend_decapsulate() {
+        let mut backend = MemoryBackend::new();
+        let (key_id, public_key) = backend.generate_kem();
+        assert_eq!(key_id, kem_key_id(&public_key));
+
+        let (ciphertext, secret) = public_key.encapsulate(&mut thread_rng());
+        let handle = BackendKey::new(&backend, key_id);
+        assert_eq!(handle.decapsulate(ciphertext.as_bytes()).unwrap(), secret);
+        assert!(handle.sign(b"Test").is_err());
+        assert!(handle.decapsulate(&[0; 16]).is_err());
+    }
+
+    #[test]
+    fn test_key_store_backend() {
+        let mut store = KeyStore::in_memory();
+        let key_id = store.generate().unwrap();
+        assert!(KeyBackend::sign(&store, &key_id, b"Test").is_err());
+
+        store.activate(&key_id).unwrap();
+        round_trip(&store, &key_id);
+    }
+}
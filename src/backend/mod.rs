@@ -0,0 +1,15 @@
+//! Key handles and backends for keys that may live outside the process
+
+pub use self::handle::*;
+pub use self::local::*;
+
+mod handle;
+mod local;
+
+/// HTTP key management service backend
+#[cfg(feature = "kms")]
+pub mod kms;
+
+/// PKCS#11 token backend
+#[cfg(feature = "pkcs11")]
+pub mod pkcs11;
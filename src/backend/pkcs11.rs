@@ -0,0 +1,895 @@
+//! Keys held in a PKCS#11 token (HSM, SoftHSMv2)
+//!
+//! Signing keys are generic secrets generated inside the token as sensitive,
+//! non-extractable objects. A secret key has no public key, so the token MACs
+//! a fixed label to give each key a public value, the counterpart of the
+//! SHA3 digest published by software HMAC keys. The key id is the
+//! fingerprint of that value and is stored in the object's `CKA_ID`.
+//!
+//! `cryptoki` does not expose the ML-KEM mechanisms of PKCS#11 3.2 yet, so an
+//! ML-KEM-768 decapsulation key is stored on the token sealed with
+//! AES-256-GCM under a non-extractable token key with the same `CKA_ID`. The
+//! token unseals it for one decapsulation at a time, and the plaintext key is
+//! wiped as soon as the shared secret is computed.
+
+use super::handle::{BackendError, KeyBackend, KeyHandle};
+use super::local::kem_key_id;
+use crate::crypto::{
+    tagged_fingerprint, Ciphertext, Kem, KeyId, MlKem768, PublicKey, SecretKey, SHARED_SECRET_LEN,
+};
+use cryptoki::context::{CInitializeArgs, Pkcs11};
+use cryptoki::error::{Error, RvError};
+use cryptoki::mechanism::aead::GcmParams;
+use cryptoki::mechanism::Mechanism;
+use cryptoki::object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle};
+use cryptoki::session::{Session, UserType};
+use cryptoki::types::AuthPin;
+use rand::{thread_rng, Rng};
+use std::path::Path;
+use zeroize::Zeroize;
+
+/// Label whose MAC is a token key's public value
+const PUBLIC_VALUE_LABEL: &[u8] = b"quantumsafe-finance pkcs11 key id";
+
+/// `CKA_APPLICATION` of the data objects holding sealed ML-KEM keys
+const SEALED_KEM_APPLICATION: &[u8] = b"quantumsafe-finance ml-kem-768";
+
+const GCM_IV_LEN: usize = 12;
+const GCM_TAG_BITS: u64 = 128;
+
+/// MAC mechanism used inside the token
+///
+/// Few tokens implement `CKM_SHA3_256_HMAC`, so token keys use a SHA-2 HMAC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pkcs11Mechanism {
+    Sha256Hmac,
+    Sha384Hmac,
+    Sha512Hmac,
+}
+
+impl Pkcs11Mechanism {
+    fn mechanism(&self) -> Mechanism<'static> {
+        match self {
+            Pkcs11Mechanism::Sha256Hmac => Mechanism::Sha256Hmac,
+            Pkcs11Mechanism::Sha384Hmac => Mechanism::Sha384Hmac,
+            Pkcs11Mechanism::Sha512Hmac => Mechanism::Sha512Hmac,
+        }
+    }
+
+    /// Tag bound into the key ids of keys using this mechanism
+    fn tag(&self) -> &'static str {
+        match self {
+            Pkcs11Mechanism::Sha256Hmac => "pkcs11-hmac-sha256",
+            Pkcs11Mechanism::Sha384Hmac => "pkcs11-hmac-sha384",
+            Pkcs11Mechanism::Sha512Hmac => "pkcs11-hmac-sha512",
+        }
+    }
+
+    fn key_len(&self) -> u64 {
+        match self {
+            Pkcs11Mechanism::Sha256Hmac => 32,
+            Pkcs11Mechanism::Sha384Hmac => 48,
+            Pkcs11Mechanism::Sha512Hmac => 64,
+        }
+    }
+}
+
+impl From<Error> for BackendError {
+    fn from(e: Error) -> Self {
+        BackendError::Backend(e.to_string())
+    }
+}
+
+/// Token operations the backend relies on
+///
+/// Implemented by `cryptoki`'s `Session`; other implementations stand in for
+/// a token in tests.
+pub trait TokenSession {
+    /// Handle to an object on the token
+    type Object: Copy;
+
+    fn generate_key(
+        &self,
+        mechanism: &Mechanism<'_>,
+        template: &[Attribute],
+    ) -> Result<Self::Object, Error>;
+
+    fn create_object(&self, template: &[Attribute]) -> Result<Self::Object, Error>;
+
+    fn find_objects(&self, template: &[Attribute]) -> Result<Vec<Self::Object>, Error>;
+
+    /// `CKA_VALUE` of a non-sensitive object
+    fn value(&self, object: Self::Object) -> Result<Vec<u8>, Error>;
+
+    fn update_attributes(&self, object: Self::Object, template: &[Attribute]) -> Result<(), Error>;
+
+    fn destroy_object(&self, object: Self::Object) -> Result<(), Error>;
+
+    fn sign(
+        &self,
+        mechanism: &Mechanism<'_>,
+        key: Self::Object,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error>;
+
+    fn verify(
+        &self,
+        mechanism: &Mechanism<'_>,
+        key: Self::Object,
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error>;
+
+    fn encrypt(
+        &self,
+        mechanism: &Mechanism<'_>,
+        key: Self::Object,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error>;
+
+    fn decrypt(
+        &self,
+        mechanism: &Mechanism<'_>,
+        key: Self::Object,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error>;
+}
+
+impl TokenSession for Session {
+    type Object = ObjectHandle;
+
+    fn generate_key(
+        &self,
+        mechanism: &Mechanism<'_>,
+        template: &[Attribute],
+    ) -> Result<ObjectHandle, Error> {
+        Session::generate_key(self, mechanism, template)
+    }
+
+    fn create_object(&self, template: &[Attribute]) -> Result<ObjectHandle, Error> {
+        Session::create_object(self, template)
+    }
+
+    fn find_objects(&self, template: &[Attribute]) -> Result<Vec<ObjectHandle>, Error> {
+        Session::find_objects(self, template)
+    }
+
+    fn value(&self, object: ObjectHandle) -> Result<Vec<u8>, Error> {
+        match self
+            .get_attributes(object, &[AttributeType::Value])?
+            .into_iter()
+            .next()
+        {
+            Some(Attribute::Value(value)) => Ok(value),
+            _ => Err(Error::InvalidValue),
+        }
+    }
+
+    fn update_attributes(&self, object: ObjectHandle, template: &[Attribute]) -> Result<(), Error> {
+        Session::update_attributes(self, object, template)
+    }
+
+    fn destroy_object(&self, object: ObjectHandle) -> Result<(), Error> {
+        Session::destroy_object(self, object)
+    }
+
+    fn sign(
+        &self,
+        mechanism: &Mechanism<'_>,
+        key: ObjectHandle,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        Session::sign(self, mechanism, key, data)
+    }
+
+    fn verify(
+        &self,
+        mechanism: &Mechanism<'_>,
+        key: ObjectHandle,
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<(), Error> {
+        Session::verify(self, mechanism, key, data, signature)
+    }
+
+    fn encrypt(
+        &self,
+        mechanism: &Mechanism<'_>,
+        key: ObjectHandle,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        Session::encrypt(self, mechanism, key, data)
+    }
+
+    fn decrypt(
+        &self,
+        mechanism: &Mechanism<'_>,
+        key: ObjectHandle,
+        data: &[u8],
+    ) -> Result<Vec<u8>, Error> {
+        Session::decrypt(self, mechanism, key, data)
+    }
+}
+
+/// Logged-in session on a PKCS#11 token
+pub struct Pkcs11Token<S: TokenSession = Session> {
+    session: S,
+    mechanism: Pkcs11Mechanism,
+}
+
+impl Pkcs11Token {
+    /// Load `module`, find the token labelled `token_label` and log in as user
+    pub fn open<P: AsRef<Path>>(
+        module: P,
+        token_label: &str,
+        pin: &str,
+    ) -> Result<Self, BackendError> {
+        let pkcs11 = Pkcs11::new(module.as_ref())?;
+        match pkcs11.initialize(CInitializeArgs::OsThreads) {
+            Err(Error::Pkcs11(RvError::CryptokiAlreadyInitialized, _)) | Ok(()) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut slot = None;
+        for candidate in pkcs11.get_slots_with_initialized_token()? {
+            if pkcs11.get_token_info(candidate)?.label() == token_label {
+                slot = Some(candidate);
+                break;
+            }
+        }
+        let slot = slot
+            .ok_or_else(|| BackendError::Backend(format!("no token labelled {:?}", token_label)))?;
+
+        let session = pkcs11.open_rw_session(slot)?;
+        match session.login(UserType::User, Some(&AuthPin::new(pin.to_string()))) {
+            Err(Error::Pkcs11(RvError::UserAlreadyLoggedIn, _)) | Ok(()) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        Ok(Self::from_session(session))
+    }
+}
+
+impl<S: TokenSession> Pkcs11Token<S> {
+    /// Use a session that is already logged in
+    pub fn from_session(session: S) -> Self {
+        Self {
+            session,
+            mechanism: Pkcs11Mechanism::Sha256Hmac,
+        }
+    }
+
+    /// Use `mechanism` for keys generated or found from now on
+    pub fn with_mechanism(mut self, mechanism: Pkcs11Mechanism) -> Self {
+        self.mechanism = mechanism;
+        self
+    }
+
+    /// Generate a new signing key inside the token
+    pub fn generate_key(&self, label: &str) -> Result<Pkcs11Key<'_, S>, BackendError> {
+        let template = [
+            Attribute::Class(ObjectClass::SECRET_KEY),
+            Attribute::KeyType(KeyType::GENERIC_SECRET),
+            Attribute::ValueLen(self.mechanism.key_len().into()),
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Sensitive(true),
+            Attribute::Extractable(false),
+            Attribute::Sign(true),
+            Attribute::Verify(true),
+            Attribute::Label(label.as_bytes().to_vec()),
+        ];
+        let object = self
+            .session
+            .generate_key(&Mechanism::GenericSecretKeyGen, &template)?;
+        let key_id = self.key_id_of(object).and_then(|key_id| {
+            let id = Attribute::Id(key_id.0.to_vec());
+            self.session.update_attributes(object, &[id])?;
+            Ok(key_id)
+        });
+        match key_id {
+            Ok(key_id) => Ok(Pkcs11Key {
+                token: self,
+                object,
+                key_id,
+                sealed: None,
+            }),
+            Err(e) => {
+                let _ = self.session.destroy_object(object);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Generate an ML-KEM-768 key and seal it on the token, returning the
+    /// handle and the public key to publish
+    pub fn generate_kem_key(
+        &self,
+        label: &str,
+    ) -> Result<(Pkcs11Key<'_, S>, PublicKey<MlKem768>), BackendError> {
+        let (public_key, secret_key) = MlKem768::generate(&mut thread_rng());
+        let key_id = kem_key_id(&public_key);
+        let template = [
+            Attribute::Class(ObjectClass::SECRET_KEY),
+            Attribute::KeyType(KeyType::AES),
+            Attribute::ValueLen(32.into()),
+            Attribute::Token(true),
+            Attribute::Private(true),
+            Attribute::Sensitive(true),
+            Attribute::Extractable(false),
+            Attribute::Encrypt(true),
+            Attribute::Decrypt(true),
+            Attribute::Id(key_id.0.to_vec()),
+            Attribute::Label(label.as_bytes().to_vec()),
+        ];
+        let object = self
+            .session
+            .generate_key(&Mechanism::AesKeyGen, &template)?;
+        let sealed = self
+            .seal(object, &key_id, secret_key.as_bytes())
+            .and_then(|blob| {
+                self.session.create_object(&[
+                    Attribute::Class(ObjectClass::DATA),
+                    Attribute::Token(true),
+                    Attribute::Private(true),
+                    Attribute::Application(SEALED_KEM_APPLICATION.to_vec()),
+                    Attribute::Label(key_id.to_string().into_bytes()),
+                    Attribute::Value(blob),
+                ])
+            });
+        match sealed {
+            Ok(sealed) => Ok((
+                Pkcs11Key {
+                    token: self,
+                    object,
+                    key_id,
+                    sealed: Some(sealed),
+                },
+                public_key,
+            )),
+            Err(e) => {
+                let _ = self.session.destroy_object(object);
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Find a key previously generated with `generate_key` or
+    /// `generate_kem_key`
+    pub fn find_key(&self, key_id: &KeyId) -> Result<Pkcs11Key<'_, S>, BackendError> {
+        let find = |key_type| {
+            let template = [
+                Attribute::Class(ObjectClass::SECRET_KEY),
+                Attribute::KeyType(key_type),
+                Attribute::Id(key_id.0.to_vec()),
+            ];
+            Ok::<_, Error>(self.session.find_objects(&template)?.into_iter().next())
+        };
+        let key = |object, sealed| Pkcs11Key {
+            token: self,
+            object,
+            key_id: *key_id,
+            sealed,
+        };
+        if let Some(object) = find(KeyType::GENERIC_SECRET)? {
+            return Ok(key(object, None));
+        }
+        let object = find(KeyType::AES)?.ok_or(BackendError::KeyNotFound(*key_id))?;
+        let template = [
+            Attribute::Class(ObjectClass::DATA),
+            Attribute::Application(SEALED_KEM_APPLICATION.to_vec()),
+            Attribute::Label(key_id.to_string().into_bytes()),
+        ];
+        let sealed = self
+            .session
+            .find_objects(&template)?
+            .into_iter()
+            .next()
+            .ok_or(BackendError::KeyNotFound(*key_id))?;
+        Ok(key(object, Some(sealed)))
+    }
+
+    /// Permanently delete a key from the token
+    pub fn destroy_key(&self, key: Pkcs11Key<'_, S>) -> Result<(), BackendError> {
+        if let Some(sealed) = key.sealed {
+            self.session.destroy_object(sealed)?;
+        }
+        self.session.destroy_object(key.object)?;
+        Ok(())
+    }
+
+    /// Key id derived from the key's public value
+    fn key_id_of(&self, object: S::Object) -> Result<KeyId, Error> {
+        let public_value =
+            self.session
+                .sign(&self.mechanism.mechanism(), object, PUBLIC_VALUE_LABEL)?;
+        Ok(tagged_fingerprint(self.mechanism.tag(), &public_value).key_id())
+    }
+
+    /// Encrypt `secret` under `key`, bound to `key_id`; returns `iv || ciphertext`
+    fn seal(&self, key: S::Object, key_id: &KeyId, secret: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut iv = [0u8; GCM_IV_LEN];
+        thread_rng().fill(&mut iv);
+        let mut blob = iv.to_vec();
+        let params = GcmParams::new(&mut iv, &key_id.0, GCM_TAG_BITS.into())?;
+        blob.extend(
+            self.session
+                .encrypt(&Mechanism::AesGcm(params), key, secret)?,
+        );
+        Ok(blob)
+    }
+
+    /// Decrypt a `seal` output inside the token
+    fn unseal(&self, key: S::Object, key_id: &KeyId, blob: &[u8]) -> Result<Vec<u8>, Error> {
+        if blob.len() < GCM_IV_LEN {
+            return Err(Error::InvalidValue);
+        }
+        let (iv, ciphertext) = blob.split_at(GCM_IV_LEN);
+        let mut iv: [u8; GCM_IV_LEN] = iv.try_into().unwrap();
+        let params = GcmParams::new(&mut iv, &key_id.0, GCM_TAG_BITS.into())?;
+        self.session
+            .decrypt(&Mechanism::AesGcm(params), key, ciphertext)
+    }
+}
+
+impl<S: TokenSession> KeyBackend for Pkcs11Token<S> {
+    fn sign(&self, key_id: &KeyId, message: &[u8]) -> Result<Vec<u8>, BackendError> {
+        self.find_key(key_id)?.sign(message)
+    }
+
+    fn verify(
+        &self,
+        key_id: &KeyId,
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<bool, BackendError> {
+        self.find_key(key_id)?.verify(message, signature)
+    }
+
+    fn decapsulate(
+        &self,
+        key_id: &KeyId,
+        ciphertext: &[u8],
+    ) -> Result<[u8; SHARED_SECRET_LEN], BackendError> {
+        self.find_key(key_id)?.decapsulate(ciphertext)
+    }
+}
+
+/// Handle to a key inside a PKCS#11 token
+pub struct Pkcs11Key<'a, S: TokenSession = Session> {
+    token: &'a Pkcs11Token<S>,
+    object: S::Object,
+    key_id: KeyId,
+    /// Data object holding the sealed decapsulation key of an ML-KEM key
+    sealed: Option<S::Object>,
+}
+
+impl<S: TokenSession> KeyHandle for Pkcs11Key<'_, S> {
+    fn key_id(&self) -> KeyId {
+        self.key_id
+    }
+
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, BackendError> {
+        if self.sealed.is_some() {
+            return Err(BackendError::Unsupported("sign with an ML-KEM key"));
+        }
+        Ok(self
+            .token
+            .session
+            .sign(&self.token.mechanism.mechanism(), self.object, message)?)
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, BackendError> {
+        if self.sealed.is_some() {
+            return Err(BackendError::Unsupported("verify with an ML-KEM key"));
+        }
+        match self.token.session.verify(
+            &self.token.mechanism.mechanism(),
+            self.object,
+            message,
+            signature,
+        ) {
+            Ok(()) => Ok(true),
+            Err(Error::Pkcs11(RvError::SignatureInvalid, _))
+            | Err(Error::Pkcs11(RvError::SignatureLenRange, _)) => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn decapsulate(&self, ciphertext: &[u8]) -> Result<[u8; SHARED_SECRET_LEN], BackendError> {
+        let sealed = self
+            .sealed
+            .ok_or(BackendError::Unsupported("decapsulate with a signing key"))?;
+        let ciphertext = Ciphertext::<MlKem768>::from_bytes(ciphertext)?;
+        let blob = self.token.session.value(sealed)?;
+        let mut plaintext = self.token.unseal(self.object, &self.key_id, &blob)?;
+        let secret_key = SecretKey::<MlKem768>::from_bytes(&plaintext);
+        plaintext.zeroize();
+        Ok(secret_key?.decapsulate(&ciphertext))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{sign_message, verify_signature};
+    use cryptoki::context::Function;
+    use std::cell::{Cell, RefCell};
+    use std::fs;
+    use std::path::PathBuf;
+
+    const SO_PIN: &str = "12345678";
+    const USER_PIN: &str = "87654321";
+    const LABEL: &str = "qsf-test";
+
+    /// In-memory token: MACs are HMAC-SHA3-256 and "encryption" is a keyed
+    /// XOR with a MAC tag, enough to exercise the backend's logic
+    #[derive(Default)]
+    struct MockSession {
+        objects: RefCell<Vec<Option<Vec<Attribute>>>>,
+        /// Error returned once `passes` more operations have succeeded
+        fail: Cell<Option<RvError>>,
+        passes: Cell<usize>,
+    }
+
+    impl MockSession {
+        fn check(&self, function: Function) -> Result<(), Error> {
+            let Some(rv) = self.fail.take() else {
+                return Ok(());
+            };
+            match self.passes.get() {
+                0 => Err(Error::Pkcs11(rv, function)),
+                passes => {
+                    self.passes.set(passes - 1);
+                    self.fail.set(Some(rv));
+                    Ok(())
+                }
+            }
+        }
+
+        fn live_objects(&self) -> usize {
+            self.objects.borrow().iter().flatten().count()
+        }
+
+        fn secret(&self, object: usize) -> Result<Vec<u8>, Error> {
+            self.objects.borrow()[object]
+                .as_ref()
+                .and_then(|attributes| {
+                    attributes.iter().find_map(|attribute| match attribute {
+                        Attribute::Value(value) => Some(value.clone()),
+                        _ => None,
+                    })
+                })
+                .ok_or(Error::Pkcs11(RvError::KeyHandleInvalid, Function::Sign))
+        }
+
+        fn keystream(key: &[u8], len: usize) -> Vec<u8> {
+            (0..len).map(|i| key[i % key.len()] ^ i as u8).collect()
+        }
+    }
+
+    impl TokenSession for MockSession {
+        type Object = usize;
+
+        fn generate_key(
+            &self,
+            _mechanism: &Mechanism<'_>,
+            template: &[Attribute],
+        ) -> Result<usize, Error> {
+            self.check(Function::GenerateKey)?;
+            let mut secret = [0u8; 32];
+            thread_rng().fill(&mut secret);
+            let mut attributes = template.to_vec();
+            attributes.push(Attribute::Value(secret.to_vec()));
+            let mut objects = self.objects.borrow_mut();
+            objects.push(Some(attributes));
+            Ok(objects.len() - 1)
+        }
+
+        fn create_object(&self, template: &[Attribute]) -> Result<usize, Error> {
+            self.check(Function::CreateObject)?;
+            let mut objects = self.objects.borrow_mut();
+            objects.push(Some(template.to_vec()));
+            Ok(objects.len() - 1)
+        }
+
+        fn find_objects(&self, template: &[Attribute]) -> Result<Vec<usize>, Error> {
+            self.check(Function::FindObjects)?;
+            let objects = self.objects.borrow();
+            Ok((0..objects.len())
+                .filter(|&i| {
+                    objects[i]
+                        .as_ref()
+                        .is_some_and(|attributes| template.iter().all(|a| attributes.contains(a)))
+                })
+                .collect())
+        }
+
+        fn value(&self, object: usize) -> Result<Vec<u8>, Error> {
+            self.check(Function::GetAttributeValue)?;
+            self.secret(object)
+        }
+
+        fn update_attributes(&self, object: usize, template: &[Attribute]) -> Result<(), Error> {
+            self.check(Function::SetAttributeValue)?;
+            let mut objects = self.objects.borrow_mut();
+            let attributes = objects[object].as_mut().unwrap();
+            attributes.extend_from_slice(template);
+            Ok(())
+        }
+
+        fn destroy_object(&self, object: usize) -> Result<(), Error> {
+            self.check(Function::DestroyObject)?;
+            self.objects.borrow_mut()[object] = None;
+            Ok(())
+        }
+
+        fn sign(
+            &self,
+            _mechanism: &Mechanism<'_>,
+            key: usize,
+            data: &[u8],
+        ) -> Result<Vec<u8>, Error> {
+            self.check(Function::Sign)?;
+            Ok(sign_message(data, &self.secret(key)?))
+        }
+
+        fn verify(
+            &self,
+            _mechanism: &Mechanism<'_>,
+            key: usize,
+            data: &[u8],
+            signature: &[u8],
+        ) -> Result<(), Error> {
+            self.check(Function::Verify)?;
+            match verify_signature(data, signature, &self.secret(key)?) {
+                true => Ok(()),
+                false => Err(Error::Pkcs11(RvError::SignatureInvalid, Function::Verify)),
+            }
+        }
+
+        fn encrypt(
+            &self,
+            _mechanism: &Mechanism<'_>,
+            key: usize,
+            data: &[u8],
+        ) -> Result<Vec<u8>, Error> {
+            self.check(Function::Encrypt)?;
+            let key = self.secret(key)?;
+            let mut ciphertext: Vec<u8> = data
+                .iter()
+                .zip(Self::keystream(&key, data.len()))
+                .map(|(a, b)| a ^ b)
+                .collect();
+            let tag = sign_message(&ciphertext, &key);
+            ciphertext.extend_from_slice(&tag[..16]);
+            Ok(ciphertext)
+        }
+
+        fn decrypt(
+            &self,
+            _mechanism: &Mechanism<'_>,
+            key: usize,
+            data: &[u8],
+        ) -> Result<Vec<u8>, Error> {
+            self.check(Function::Decrypt)?;
+            let key = self.secret(key)?;
+            let invalid = Error::Pkcs11(RvError::EncryptedDataInvalid, Function::Decrypt);
+            let (ciphertext, tag) = data.split_at(data.len().checked_sub(16).ok_or(invalid)?);
+            if sign_message(ciphertext, &key)[..16] != *tag {
+                return Err(Error::Pkcs11(
+                    RvError::EncryptedDataInvalid,
+                    Function::Decrypt,
+                ));
+            }
+            Ok(ciphertext
+                .iter()
+                .zip(Self::keystream(&key, ciphertext.len()))
+                .map(|(a, b)| a ^ b)
+                .collect())
+        }
+    }
+
+    fn mock_token() -> Pkcs11Token<MockSession> {
+        Pkcs11Token::from_session(MockSession::default())
+    }
+
+    #[test]
+    fn test_backend_sign_verify_mapping() {
+        let token = mock_token();
+        let key_id = token.generate_key("signing").unwrap().key_id();
+        let message = b"Test message";
+
+        let signature = KeyBackend::sign(&token, &key_id, message).unwrap();
+        assert!(KeyBackend::verify(&token, &key_id, message, &signature).unwrap());
+        // CKR_SIGNATURE_INVALID and CKR_SIGNATURE_LEN_RANGE mean a bad signature
+        assert!(!KeyBackend::verify(&token, &key_id, b"Other", &signature).unwrap());
+        token.session.fail.set(Some(RvError::SignatureLenRange));
+        // The failure is consumed by the key lookup, not the verification
+        assert!(KeyBackend::verify(&token, &key_id, message, &signature).is_err());
+        let key = token.find_key(&key_id).unwrap();
+        token.session.fail.set(Some(RvError::SignatureLenRange));
+        assert!(!key.verify(message, &signature).unwrap());
+
+        // Other token failures surface as backend errors
+        token.session.fail.set(Some(RvError::DeviceError));
+        assert!(matches!(
+            key.sign(message),
+            Err(BackendError::Backend(e)) if e.contains("CKR_DEVICE_ERROR")
+        ));
+        token.session.fail.set(Some(RvError::DeviceError));
+        assert!(matches!(
+            key.verify(message, &signature),
+            Err(BackendError::Backend(_))
+        ));
+        assert!(matches!(
+            key.decapsulate(&[0; 1088]),
+            Err(BackendError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_backend_unknown_key() {
+        let token = mock_token();
+        let unknown = KeyId([7; 16]);
+        assert!(matches!(
+            KeyBackend::sign(&token, &unknown, b"Test"),
+            Err(BackendError::KeyNotFound(id)) if id == unknown
+        ));
+        assert!(matches!(
+            KeyBackend::verify(&token, &unknown, b"Test", &[0; 32]),
+            Err(BackendError::KeyNotFound(_))
+        ));
+        assert!(matches!(
+            KeyBackend::decapsulate(&token, &unknown, &[0; 1088]),
+            Err(BackendError::KeyNotFound(_))
+        ));
+
+        let key = token.generate_key("signing").unwrap();
+        let key_id = key.key_id();
+        token.destroy_key(key).unwrap();
+        assert!(matches!(
+            token.find_key(&key_id),
+            Err(BackendError::KeyNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_id_from_public_value() {
+        let token = mock_token();
+        let key = token.generate_key("signing").unwrap();
+        let public_value = sign_message(
+            PUBLIC_VALUE_LABEL,
+            &token.session.secret(key.object).unwrap(),
+        );
+        assert_eq!(
+            key.key_id(),
+            tagged_fingerprint("pkcs11-hmac-sha256", &public_value).key_id()
+        );
+
+        // A failure to set CKA_ID leaves no key behind
+        let before = token.session.live_objects();
+        token.session.passes.set(2);
+        token.session.fail.set(Some(RvError::GeneralError));
+        assert!(token.generate_key("signing").is_err());
+        assert_eq!(token.session.live_objects(), before);
+
+        // As does a failure to store the sealed ML-KEM key
+        token.session.passes.set(2);
+        token.session.fail.set(Some(RvError::DeviceMemory));
+        assert!(token.generate_kem_key("kem").is_err());
+        assert_eq!(token.session.live_objects(), before);
+    }
+
+    #[test]
+    fn test_backend_decapsulate() {
+        let token = mock_token();
+        let (key, public_key) = token.generate_kem_key("kem").unwrap();
+        let key_id = key.key_id();
+        assert_eq!(key_id, kem_key_id(&public_key));
+
+        let (ciphertext, secret) = public_key.encapsulate(&mut thread_rng());
+        assert_eq!(
+            KeyBackend::decapsulate(&token, &key_id, ciphertext.as_bytes()).unwrap(),
+            secret
+        );
+        assert!(matches!(
+            KeyBackend::sign(&token, &key_id, b"Test"),
+            Err(BackendError::Unsupported(_))
+        ));
+        assert!(matches!(
+            KeyBackend::decapsulate(&token, &key_id, &[0; 16]),
+            Err(BackendError::Backend(_))
+        ));
+
+        // A tampered sealed key is rejected by the token
+        let sealed = key.sealed.unwrap();
+        let mut objects = token.session.objects.borrow_mut();
+        for attribute in objects[sealed].as_mut().unwrap() {
+            if let Attribute::Value(value) = attribute {
+                value[GCM_IV_LEN] ^= 1;
+            }
+        }
+        drop(objects);
+        assert!(matches!(
+            KeyBackend::decapsulate(&token, &key_id, ciphertext.as_bytes()),
+            Err(BackendError::Backend(e)) if e.contains("invalid ciphertext")
+        ));
+
+        token.destroy_key(key).unwrap();
+        assert!(matches!(
+            token.find_key(&key_id),
+            Err(BackendError::KeyNotFound(_))
+        ));
+    }
+
+    fn softhsm_module() -> PathBuf {
+        std::env::var_os("SOFTHSM2_MODULE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from("/usr/lib/softhsm/libsofthsm2.so"))
+    }
+
+    /// Point SoftHSM at a fresh token directory and initialise one token
+    fn init_softhsm() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qsf-softhsm-{}", std::process::id()));
+        let tokens = dir.join("tokens");
+        fs::create_dir_all(&tokens).unwrap();
+        let conf = dir.join("softhsm2.conf");
+        fs::write(
+            &conf,
+            format!(
+                "directories.tokendir = {}\nobjectstore.backend = file\n",
+                tokens.display()
+            ),
+        )
+        .unwrap();
+        std::env::set_var("SOFTHSM2_CONF", &conf);
+
+        let pkcs11 = Pkcs11::new(softhsm_module()).unwrap();
+        pkcs11.initialize(CInitializeArgs::OsThreads).unwrap();
+        let slot = pkcs11.get_slots_with_token().unwrap()[0];
+        let so_pin = AuthPin::new(SO_PIN.to_string());
+        pkcs11.init_token(slot, &so_pin, LABEL).unwrap();
+        let session = pkcs11.open_rw_session(slot).unwrap();
+        session.login(UserType::So, Some(&so_pin)).unwrap();
+        session
+            .init_pin(&AuthPin::new(USER_PIN.to_string()))
+            .unwrap();
+        dir
+    }
+
+    /// Runs against a local SoftHSMv2 install with
+    /// `cargo test --features pkcs11 -- --ignored`; the token is created in a
+    /// temporary directory, so no existing SoftHSM configuration is touched.
+    #[test]
+    #[ignore = "requires SoftHSMv2"]
+    fn test_softhsm_sign_verify() {
+        let dir = init_softhsm();
+        let token = Pkcs11Token::open(softhsm_module(), LABEL, USER_PIN).unwrap();
+        let message = b"Test message";
+
+        let key = token.generate_key("signing").unwrap();
+        let signature = key.sign(message).unwrap();
+        assert!(key.verify(message, &signature).unwrap());
+        assert!(!key.verify(b"Other message", &signature).unwrap());
+        assert_eq!(token.key_id_of(key.object).unwrap(), key.key_id());
+
+        let found = token.find_key(&key.key_id()).unwrap();
+        assert!(found.verify(message, &signature).unwrap());
+        assert!(KeyBackend::verify(&token, &key.key_id(), message, &signature).unwrap());
+
+        token.destroy_key(found).unwrap();
+        assert!(matches!(
+            token.find_key(&key.key_id()),
+            Err(BackendError::KeyNotFound(_))
+        ));
+
+        let (kem_key, public_key) = token.generate_kem_key("kem").unwrap();
+        let (ciphertext, secret) = public_key.encapsulate(&mut thread_rng());
+        let found = token.find_key(&kem_key.key_id()).unwrap();
+        assert_eq!(found.decapsulate(ciphertext.as_bytes()).unwrap(), secret);
+        token.destroy_key(found).unwrap();
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,52 @@
+//! Logging and startup shared by the service binaries
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::env;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+/// Writes every enabled record to stderr, prefixed with the program name
+struct StderrLogger {
+    program: &'static str,
+}
+
+impl Log for StderrLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}: {}: {}", self.program, record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the stderr logger at the `QSF_LOG` level, then run `serve` on the
+/// configuration file named on the command line
+pub fn run<E: Display>(
+    program: &'static str,
+    serve: impl FnOnce(&Path) -> Result<(), E>,
+) -> ExitCode {
+    let level = env::var("QSF_LOG")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(LevelFilter::Info);
+    log::set_logger(Box::leak(Box::new(StderrLogger { program }))).expect("logger already set");
+    log::set_max_level(level);
+
+    let Some(path) = env::args_os().nth(1).map(PathBuf::from) else {
+        eprintln!("usage: {} <config.toml>", program);
+        return ExitCode::from(2);
+    };
+    match serve(&path) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            log::error!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
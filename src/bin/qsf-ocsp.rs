@@ -0,0 +1,20 @@
+//! OCSP responder answering for one CA from its latest CRL
+//!
+//! Usage: `qsf-ocsp <config.toml>`; see `x509::responder` for the format.
+//! Logs go to stderr; `QSF_LOG` sets the level (`error` to `trace`, default
+//! `info`).
+
+mod common;
+
+use quantumsafe_finance::x509::responder::{OcspServer, ResponderConfig};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    common::run("qsf-ocsp", |path| {
+        let server = OcspServer::bind(ResponderConfig::load(path)?)?;
+        if let Some(addr) = server.local_addr() {
+            log::info!("listening on {}", addr);
+        }
+        server.run()
+    })
+}
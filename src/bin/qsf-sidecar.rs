@@ -0,0 +1,18 @@
+//! TLS-terminating sidecar: hybrid post-quantum TLS in, plaintext or TLS out
+//!
+//! Usage: `qsf-sidecar <config.toml>`; see `tls::proxy` for the format. Logs
+//! go to stderr; `QSF_LOG` sets the level (`error` to `trace`, default
+//! `info`).
+
+mod common;
+
+use quantumsafe_finance::tls::proxy::{Proxy, ProxyConfig};
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    common::run("qsf-sidecar", |path| {
+        let proxy = Proxy::bind(ProxyConfig::load(path)?)?;
+        log::info!("listening on {}", proxy.local_addr()?);
+        proxy.run()
+    })
+}
@@ -1,144 +1,818 @@
-#![allow(clippy::not_unsafe_ptr_arg_deref)]
-
 //! C bindings for QuantumSafe Finance
+//!
+//! Every function returns a `QsfStatus` and writes results through out
+//! pointers, which are cleared before any check so they never hold garbage
+//! on failure. A null input pointer is accepted only with a length of zero.
+//! Objects are opaque handles released with their `_free` function, which
+//! takes the address of the handle and nulls it, so a repeated free is a
+//! no-op. Byte buffers returned in a `QsfBytes` are released with
+//! `qsf_bytes_free`. The header is generated with cbindgen into
+//! `include/quantumsafe.h`.
+//!
+//! Functions taking pointers are `unsafe`: a non-null pointer must be valid
+//! for the access described in the function's safety section, and a handle
+//! is live from the call that returns it until it is freed.
 
-use crate::crypto;
-use std::os::raw::c_int;
+use crate::crypto::{self, Algorithm, KeyPair, PublicKeyVerifier};
+use std::os::raw::{c_char, c_int};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
 use std::slice;
 
-/// Quantum-safe key pair
+/// Result of a C API call: `QSF_OK` or a negative error code
+pub type QsfStatus = c_int;
+
+/// The call succeeded
+pub const QSF_OK: QsfStatus = 0;
+/// A required pointer was null
+pub const QSF_ERR_NULL_POINTER: QsfStatus = -1;
+/// A length is too large or wrong for the value
+pub const QSF_ERR_INVALID_LENGTH: QsfStatus = -2;
+/// The algorithm identifier is not one of the `QSF_ALGORITHM_*` values
+pub const QSF_ERR_INVALID_ALGORITHM: QsfStatus = -3;
+/// The key is malformed or its halves do not match
+pub const QSF_ERR_INVALID_KEY: QsfStatus = -4;
+/// Input is not valid UTF-8 or JSON
+pub const QSF_ERR_INVALID_ENCODING: QsfStatus = -5;
+/// The signature does not verify
+pub const QSF_ERR_BAD_SIGNATURE: QsfStatus = -6;
+/// An unexpected internal failure; the call had no effect
+pub const QSF_ERR_INTERNAL: QsfStatus = -7;
+
+/// Signature algorithm: one of the `QSF_ALGORITHM_*` values
+pub type QsfAlgorithm = u32;
+
+/// HMAC-SHA3-256; verification needs the private key
+pub const QSF_ALGORITHM_HMAC_SHA3_256: QsfAlgorithm = 0;
+/// Falcon-512 (FN-DSA)
+pub const QSF_ALGORITHM_FALCON512: QsfAlgorithm = 1;
+/// Falcon-1024 (FN-DSA)
+pub const QSF_ALGORITHM_FALCON1024: QsfAlgorithm = 2;
+/// ML-DSA-44 (FIPS 204)
+pub const QSF_ALGORITHM_ML_DSA_44: QsfAlgorithm = 3;
+/// ML-DSA-65 (FIPS 204)
+pub const QSF_ALGORITHM_ML_DSA_65: QsfAlgorithm = 4;
+/// ML-DSA-87 (FIPS 204)
+pub const QSF_ALGORITHM_ML_DSA_87: QsfAlgorithm = 5;
+/// SLH-DSA-SHAKE-128s (FIPS 205)
+pub const QSF_ALGORITHM_SLH_DSA_SHAKE_128S: QsfAlgorithm = 6;
+/// SLH-DSA-SHAKE-128f (FIPS 205)
+pub const QSF_ALGORITHM_SLH_DSA_SHAKE_128F: QsfAlgorithm = 7;
+/// SLH-DSA-SHAKE-192s (FIPS 205)
+pub const QSF_ALGORITHM_SLH_DSA_SHAKE_192S: QsfAlgorithm = 8;
+/// SLH-DSA-SHAKE-192f (FIPS 205)
+pub const QSF_ALGORITHM_SLH_DSA_SHAKE_192F: QsfAlgorithm = 9;
+/// SLH-DSA-SHAKE-256s (FIPS 205)
+pub const QSF_ALGORITHM_SLH_DSA_SHAKE_256S: QsfAlgorithm = 10;
+/// SLH-DSA-SHAKE-256f (FIPS 205)
+pub const QSF_ALGORITHM_SLH_DSA_SHAKE_256F: QsfAlgorithm = 11;
+/// Composite ML-DSA-44 + Ed25519 (draft-ietf-lamps-pq-composite-sigs)
+pub const QSF_ALGORITHM_ML_DSA_44_ED25519: QsfAlgorithm = 12;
+/// Composite ML-DSA-65 + Ed25519 (draft-ietf-lamps-pq-composite-sigs)
+pub const QSF_ALGORITHM_ML_DSA_65_ED25519: QsfAlgorithm = 13;
+
+/// Opaque key pair handle
+pub struct QsfKeyPair(KeyPair);
+
+/// Opaque public key handle; holds no secret material
+pub struct QsfPublicKey {
+    algorithm: Algorithm,
+    public_key: Vec<u8>,
+}
+
+/// Opaque verification context with the public key already decoded
+///
+/// Verifying does not modify the context, so threads may share one.
+pub struct QsfVerifier(PublicKeyVerifier);
+
+/// Byte buffer owned by the library, released with `qsf_bytes_free`
 #[repr(C)]
-pub struct KeyPairWrapper {
-    pub public_key_len: usize,
-    pub private_key_len: usize,
-    pub public_key: *mut u8,
-    pub private_key: *mut u8,
+#[derive(Debug)]
+pub struct QsfBytes {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl QsfBytes {
+    const EMPTY: QsfBytes = QsfBytes {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+
+    fn new(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+        QsfBytes { data, len }
+    }
+}
+
+fn algorithm_from_c(algorithm: QsfAlgorithm) -> Result<Algorithm, QsfStatus> {
+    match algorithm {
+        QSF_ALGORITHM_HMAC_SHA3_256 => Ok(Algorithm::HmacSha3_256),
+        QSF_ALGORITHM_FALCON512 => Ok(Algorithm::Falcon512),
+        QSF_ALGORITHM_FALCON1024 => Ok(Algorithm::Falcon1024),
+        QSF_ALGORITHM_ML_DSA_44 => Ok(Algorithm::MlDsa44),
+        QSF_ALGORITHM_ML_DSA_65 => Ok(Algorithm::MlDsa65),
+        QSF_ALGORITHM_ML_DSA_87 => Ok(Algorithm::MlDsa87),
+        QSF_ALGORITHM_SLH_DSA_SHAKE_128S => Ok(Algorithm::SlhDsaShake128s),
+        QSF_ALGORITHM_SLH_DSA_SHAKE_128F => Ok(Algorithm::SlhDsaShake128f),
+        QSF_ALGORITHM_SLH_DSA_SHAKE_192S => Ok(Algorithm::SlhDsaShake192s),
+        QSF_ALGORITHM_SLH_DSA_SHAKE_192F => Ok(Algorithm::SlhDsaShake192f),
+        QSF_ALGORITHM_SLH_DSA_SHAKE_256S => Ok(Algorithm::SlhDsaShake256s),
+        QSF_ALGORITHM_SLH_DSA_SHAKE_256F => Ok(Algorithm::SlhDsaShake256f),
+        QSF_ALGORITHM_ML_DSA_44_ED25519 => Ok(Algorithm::MlDsa44Ed25519),
+        QSF_ALGORITHM_ML_DSA_65_ED25519 => Ok(Algorithm::MlDsa65Ed25519),
+        _ => Err(QSF_ERR_INVALID_ALGORITHM),
+    }
+}
+
+fn algorithm_to_c(algorithm: Algorithm) -> QsfAlgorithm {
+    match algorithm {
+        Algorithm::HmacSha3_256 => QSF_ALGORITHM_HMAC_SHA3_256,
+        Algorithm::Falcon512 => QSF_ALGORITHM_FALCON512,
+        Algorithm::Falcon1024 => QSF_ALGORITHM_FALCON1024,
+        Algorithm::MlDsa44 => QSF_ALGORITHM_ML_DSA_44,
+        Algorithm::MlDsa65 => QSF_ALGORITHM_ML_DSA_65,
+        Algorithm::MlDsa87 => QSF_ALGORITHM_ML_DSA_87,
+        Algorithm::SlhDsaShake128s => QSF_ALGORITHM_SLH_DSA_SHAKE_128S,
+        Algorithm::SlhDsaShake128f => QSF_ALGORITHM_SLH_DSA_SHAKE_128F,
+        Algorithm::SlhDsaShake192s => QSF_ALGORITHM_SLH_DSA_SHAKE_192S,
+        Algorithm::SlhDsaShake192f => QSF_ALGORITHM_SLH_DSA_SHAKE_192F,
+        Algorithm::SlhDsaShake256s => QSF_ALGORITHM_SLH_DSA_SHAKE_256S,
+        Algorithm::SlhDsaShake256f => QSF_ALGORITHM_SLH_DSA_SHAKE_256F,
+        Algorithm::MlDsa44Ed25519 => QSF_ALGORITHM_ML_DSA_44_ED25519,
+        Algorithm::MlDsa65Ed25519 => QSF_ALGORITHM_ML_DSA_65_ED25519,
+    }
+}
+
+/// Run `body`, turning a panic into `QSF_ERR_INTERNAL` instead of unwinding into C
+fn guard(body: impl FnOnce() -> Result<(), QsfStatus>) -> QsfStatus {
+    match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => QSF_OK,
+        Ok(Err(status)) => status,
+        Err(_) => QSF_ERR_INTERNAL,
+    }
+}
+
+/// Borrow `len` bytes at `data`; null is only valid for an empty input
+///
+/// # Safety
+///
+/// A non-null `data` must be readable for `len` bytes during `'a`.
+unsafe fn input<'a>(data: *const u8, len: usize) -> Result<&'a [u8], QsfStatus> {
+    if len > isize::MAX as usize {
+        return Err(QSF_ERR_INVALID_LENGTH);
+    }
+    if data.is_null() {
+        return if len == 0 {
+            Ok(&[])
+        } else {
+            Err(QSF_ERR_NULL_POINTER)
+        };
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+/// Borrow the object behind a handle
+///
+/// # Safety
+///
+/// A non-null `handle` must be live during `'a`.
+unsafe fn handle<'a, T>(handle: *const T) -> Result<&'a T, QsfStatus> {
+    handle.as_ref().ok_or(QSF_ERR_NULL_POINTER)
+}
+
+/// Clear an out pointer, failing if it is null
+///
+/// # Safety
+///
+/// A non-null `out` must be writable and unaliased during `'a`.
+unsafe fn output<'a, T>(out: *mut T, empty: T) -> Result<&'a mut T, QsfStatus> {
+    let out = out.as_mut().ok_or(QSF_ERR_NULL_POINTER)?;
+    *out = empty;
+    Ok(out)
+}
+
+/// Free the object behind a handle and null the handle
+///
+/// # Safety
+///
+/// A non-null `handle` must point to a null or live handle boxed as `T`.
+unsafe fn free_handle<T>(handle: *mut *mut T) {
+    if let Some(slot) = handle.as_mut() {
+        let object = std::mem::replace(slot, ptr::null_mut());
+        if !object.is_null() {
+            drop(Box::from_raw(object));
+        }
+    }
 }
 
-/// Create a new quantum-safe key pair
+/// Static description of a status code; never null
 #[no_mangle]
-pub extern "C" fn create_key_pair() -> *mut KeyPairWrapper {
-    let rust_key_pair = crypto::generate_key_pair();
-    let key_pair = Box::new(KeyPairWrapper {
-        public_key_len: rust_key_pair.public_key.len(),
-        private_key_len: rust_key_pair.private_key.len(),
-        public_key: Box::into_raw(rust_key_pair.public_key.into_boxed_slice()) as *mut u8,
-        private_key: Box::into_raw(rust_key_pair.private_key.into_boxed_slice()) as *mut u8,
-    });
+pub extern "C" fn qsf_status_message(status: QsfStatus) -> *const c_char {
+    let message: &'static [u8] = match status {
+        QSF_OK => b"success\0",
+        QSF_ERR_NULL_POINTER => b"null pointer\0",
+        QSF_ERR_INVALID_LENGTH => b"invalid length\0",
+        QSF_ERR_INVALID_ALGORITHM => b"invalid algorithm\0",
+        QSF_ERR_INVALID_KEY => b"invalid key\0",
+        QSF_ERR_INVALID_ENCODING => b"invalid encoding\0",
+        QSF_ERR_BAD_SIGNATURE => b"signature verification failed\0",
+        QSF_ERR_INTERNAL => b"internal error\0",
+        _ => b"unknown status\0",
+    };
+    message.as_ptr() as *const c_char
+}
 
-    Box::into_raw(key_pair)
+/// Generate a key pair for `algorithm`
+///
+/// # Safety
+///
+/// `out_key_pair` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_key_pair_generate(
+    algorithm: QsfAlgorithm,
+    out_key_pair: *mut *mut QsfKeyPair,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_key_pair, ptr::null_mut())?;
+        let key_pair = crypto::generate_key_pair_for(algorithm_from_c(algorithm)?);
+        *out = Box::into_raw(Box::new(QsfKeyPair(key_pair)));
+        Ok(())
+    })
 }
 
-/// Free a quantum-safe key pair
+/// Import a key pair from the JSON written by `qsf_key_pair_to_json`
+///
+/// # Safety
+///
+/// `json` must be null or readable for `json_len` bytes, and `out_key_pair`
+/// must be null or writable.
 #[no_mangle]
-pub extern "C" fn free_key_pair(key_pair: *mut KeyPairWrapper) {
-    if !key_pair.is_null() {
-        let key_pair = unsafe { Box::from_raw(key_pair) };
-        // Free public and private keys
-        unsafe {
-            drop(Box::from_raw(key_pair.public_key));
-            drop(Box::from_raw(key_pair.private_key));
+pub unsafe extern "C" fn qsf_key_pair_from_json(
+    json: *const u8,
+    json_len: usize,
+    out_key_pair: *mut *mut QsfKeyPair,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_key_pair, ptr::null_mut())?;
+        let json =
+            std::str::from_utf8(input(json, json_len)?).map_err(|_| QSF_ERR_INVALID_ENCODING)?;
+        let key_pair = crypto::key_pair_from_json(json).map_err(|e| {
+            if e.is_data() {
+                QSF_ERR_INVALID_KEY
+            } else {
+                QSF_ERR_INVALID_ENCODING
+            }
+        })?;
+        *out = Box::into_raw(Box::new(QsfKeyPair(key_pair)));
+        Ok(())
+    })
+}
+
+/// Export a key pair, including its private key, as JSON
+///
+/// # Safety
+///
+/// `key_pair` must be null or live, and `out_json` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_key_pair_to_json(
+    key_pair: *const QsfKeyPair,
+    out_json: *mut QsfBytes,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_json, QsfBytes::EMPTY)?;
+        let key_pair = handle(key_pair)?;
+        *out = QsfBytes::new(crypto::key_pair_to_json(&key_pair.0).into_bytes());
+        Ok(())
+    })
+}
+
+/// Algorithm of a key pair
+///
+/// # Safety
+///
+/// `key_pair` must be null or live, and `out_algorithm` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_key_pair_algorithm(
+    key_pair: *const QsfKeyPair,
+    out_algorithm: *mut QsfAlgorithm,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_algorithm, 0)?;
+        *out = algorithm_to_c(handle(key_pair)?.0.algorithm);
+        Ok(())
+    })
+}
+
+/// Copy of a key pair's encoded public key
+///
+/// # Safety
+///
+/// `key_pair` must be null or live, and `out_public_key` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_key_pair_public_key(
+    key_pair: *const QsfKeyPair,
+    out_public_key: *mut QsfBytes,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_public_key, QsfBytes::EMPTY)?;
+        *out = QsfBytes::new(handle(key_pair)?.0.public_key.clone());
+        Ok(())
+    })
+}
+
+/// Free a key pair and null the handle; a null handle is ignored
+///
+/// # Safety
+///
+/// `key_pair` must be null or point to a null or live key pair handle, which
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_key_pair_free(key_pair: *mut *mut QsfKeyPair) {
+    free_handle(key_pair)
+}
+
+/// Sign `message` with a key pair
+///
+/// # Safety
+///
+/// `key_pair` must be null or live, `message` null or readable for
+/// `message_len` bytes, and `out_signature` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_sign(
+    key_pair: *const QsfKeyPair,
+    message: *const u8,
+    message_len: usize,
+    out_signature: *mut QsfBytes,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_signature, QsfBytes::EMPTY)?;
+        let key_pair = handle(key_pair)?;
+        let message = input(message, message_len)?;
+        let signature = key_pair.0.sign(message).map_err(|_| QSF_ERR_INVALID_KEY)?;
+        *out = QsfBytes::new(signature);
+        Ok(())
+    })
+}
+
+/// Verify a signature made with a key pair: `QSF_OK` or `QSF_ERR_BAD_SIGNATURE`
+///
+/// # Safety
+///
+/// `key_pair` must be null or live, and `message` and `signature` null or
+/// readable for `message_len` and `signature_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_verify(
+    key_pair: *const QsfKeyPair,
+    message: *const u8,
+    message_len: usize,
+    signature: *const u8,
+    signature_len: usize,
+) -> QsfStatus {
+    guard(|| {
+        let key_pair = handle(key_pair)?;
+        let message = input(message, message_len)?;
+        let signature = input(signature, signature_len)?;
+        if key_pair.0.verify(message, signature) {
+            Ok(())
+        } else {
+            Err(QSF_ERR_BAD_SIGNATURE)
         }
-        drop(key_pair);
-    }
+    })
+}
+
+/// Import a public key from its algorithm-tagged encoding
+///
+/// The encoding is the one written by `qsf_public_key_to_bytes`. HMAC keys
+/// are refused with `QSF_ERR_INVALID_ALGORITHM` as they cannot verify alone.
+///
+/// # Safety
+///
+/// `encoded` must be null or readable for `encoded_len` bytes, and
+/// `out_public_key` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_public_key_from_bytes(
+    encoded: *const u8,
+    encoded_len: usize,
+    out_public_key: *mut *mut QsfPublicKey,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_public_key, ptr::null_mut())?;
+        let (algorithm, public_key) = crypto::decode_public_key(input(encoded, encoded_len)?)
+            .ok_or(QSF_ERR_INVALID_ENCODING)?;
+        if algorithm == Algorithm::HmacSha3_256 {
+            return Err(QSF_ERR_INVALID_ALGORITHM);
+        }
+        PublicKeyVerifier::new(algorithm, public_key).map_err(|_| QSF_ERR_INVALID_KEY)?;
+        *out = Box::into_raw(Box::new(QsfPublicKey {
+            algorithm,
+            public_key: public_key.to_vec(),
+        }));
+        Ok(())
+    })
 }
 
-/// Sign a message using quantum-safe signature
+/// Public key of a key pair, e.g. for export to verifying devices
+///
+/// # Safety
+///
+/// `key_pair` must be null or live, and `out_public_key` null or writable.
 #[no_mangle]
-pub extern "C" fn sign_message(
+pub unsafe extern "C" fn qsf_public_key_from_key_pair(
+    key_pair: *const QsfKeyPair,
+    out_public_key: *mut *mut QsfPublicKey,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_public_key, ptr::null_mut())?;
+        let key_pair = &handle(key_pair)?.0;
+        if key_pair.algorithm == Algorithm::HmacSha3_256 {
+            return Err(QSF_ERR_INVALID_ALGORITHM);
+        }
+        *out = Box::into_raw(Box::new(QsfPublicKey {
+            algorithm: key_pair.algorithm,
+            public_key: key_pair.public_key.clone(),
+        }));
+        Ok(())
+    })
+}
+
+/// Algorithm-tagged encoding of a public key
+///
+/// # Safety
+///
+/// `public_key` must be null or live, and `out_encoded` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_public_key_to_bytes(
+    public_key: *const QsfPublicKey,
+    out_encoded: *mut QsfBytes,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_encoded, QsfBytes::EMPTY)?;
+        let public_key = handle(public_key)?;
+        let encoded = crypto::encode_public_key(public_key.algorithm, &public_key.public_key);
+        *out = QsfBytes::new(encoded);
+        Ok(())
+    })
+}
+
+/// Algorithm of a public key
+///
+/// # Safety
+///
+/// `public_key` must be null or live, and `out_algorithm` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_public_key_algorithm(
+    public_key: *const QsfPublicKey,
+    out_algorithm: *mut QsfAlgorithm,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_algorithm, 0)?;
+        *out = algorithm_to_c(handle(public_key)?.algorithm);
+        Ok(())
+    })
+}
+
+/// Verify a signature with only a public key: `QSF_OK` or `QSF_ERR_BAD_SIGNATURE`
+///
+/// Decodes the key on every call; use a `QsfVerifier` for repeated checks.
+///
+/// # Safety
+///
+/// `public_key` must be null or live, and `message` and `signature` null or
+/// readable for `message_len` and `signature_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_public_key_verify(
+    public_key: *const QsfPublicKey,
     message: *const u8,
     message_len: usize,
-    private_key: *const u8,
-    private_key_len: usize,
-    signature: *mut *mut u8,
-    signature_len: *mut usize,
-) -> c_int {
-    let message = unsafe { std::slice::from_raw_parts(message, message_len) };
-    let private_key = unsafe { std::slice::from_raw_parts(private_key, private_key_len) };
-    let signature_result = crypto::sign_message(message, private_key);
-    let result_len = signature_result.len();
-    let signature_ptr = Box::into_raw(signature_result.into_boxed_slice()) as *mut u8;
-    unsafe {
-        *signature = signature_ptr;
-        *signature_len = result_len;
-    }
-    0
+    signature: *const u8,
+    signature_len: usize,
+) -> QsfStatus {
+    guard(|| {
+        let public_key = handle(public_key)?;
+        let message = input(message, message_len)?;
+        let signature = input(signature, signature_len)?;
+        let valid = crypto::verify_with_public_key(
+            public_key.algorithm,
+            &public_key.public_key,
+            message,
+            signature,
+        );
+        valid.then_some(()).ok_or(QSF_ERR_BAD_SIGNATURE)
+    })
 }
 
-/// Free a signature
+/// Free a public key and null the handle; a null handle is ignored
+///
+/// # Safety
+///
+/// `public_key` must be null or point to a null or live public key handle,
+/// which must not be used afterwards.
 #[no_mangle]
-pub extern "C" fn free_signature(signature: *mut u8) {
-    if signature.is_null() {
-        return;
-    }
-    unsafe {
-        drop(Box::from_raw(signature));
-    }
+pub unsafe extern "C" fn qsf_public_key_free(public_key: *mut *mut QsfPublicKey) {
+    free_handle(public_key)
+}
+
+/// Decode a public key once into a reusable verification context
+///
+/// The context does not borrow the public key handle.
+///
+/// # Safety
+///
+/// `public_key` must be null or live, and `out_verifier` null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_verifier_new(
+    public_key: *const QsfPublicKey,
+    out_verifier: *mut *mut QsfVerifier,
+) -> QsfStatus {
+    guard(|| {
+        let out = output(out_verifier, ptr::null_mut())?;
+        let public_key = handle(public_key)?;
+        let verifier = PublicKeyVerifier::new(public_key.algorithm, &public_key.public_key)
+            .map_err(|_| QSF_ERR_INVALID_KEY)?;
+        *out = Box::into_raw(Box::new(QsfVerifier(verifier)));
+        Ok(())
+    })
 }
 
-/// Verify a quantum-safe signature
+/// Verify a signature with a context: `QSF_OK` or `QSF_ERR_BAD_SIGNATURE`
+///
+/// # Safety
+///
+/// `verifier` must be null or live, and `message` and `signature` null or
+/// readable for `message_len` and `signature_len` bytes.
 #[no_mangle]
-pub extern "C" fn verify_signature(
+pub unsafe extern "C" fn qsf_verifier_verify(
+    verifier: *const QsfVerifier,
     message: *const u8,
     message_len: usize,
     signature: *const u8,
     signature_len: usize,
-    private_key: *const u8,
-    private_key_len: usize,
-) -> c_int {
-    let message_slice = unsafe { slice::from_raw_parts(message, message_len) };
-    let signature_slice = unsafe { slice::from_raw_parts(signature, signature_len) };
-    let private_key_slice = unsafe { slice::from_raw_parts(private_key, private_key_len) };
-
-    if crypto::verify_signature(message_slice, signature_slice, private_key_slice) {
-        1
-    } else {
-        0
+) -> QsfStatus {
+    guard(|| {
+        let verifier = handle(verifier)?;
+        let message = input(message, message_len)?;
+        let signature = input(signature, signature_len)?;
+        let valid = verifier.0.verify(message, signature);
+        valid.then_some(()).ok_or(QSF_ERR_BAD_SIGNATURE)
+    })
+}
+
+/// Free a verification context and null the handle; a null handle is ignored
+///
+/// # Safety
+///
+/// `verifier` must be null or point to a null or live verifier handle, which
+/// must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_verifier_free(verifier: *mut *mut QsfVerifier) {
+    free_handle(verifier)
+}
+
+/// Free a buffer returned by the library and clear it; an empty buffer is ignored
+///
+/// # Safety
+///
+/// `bytes` must be null or point to an empty buffer or one returned by this
+/// library and not yet freed.
+#[no_mangle]
+pub unsafe extern "C" fn qsf_bytes_free(bytes: *mut QsfBytes) {
+    if let Some(bytes) = bytes.as_mut() {
+        let QsfBytes { data, len } = std::mem::replace(bytes, QsfBytes::EMPTY);
+        if !data.is_null() {
+            drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::ffi::CStr;
+
+    fn generate(algorithm: QsfAlgorithm) -> *mut QsfKeyPair {
+        unsafe {
+            let mut key_pair = ptr::null_mut();
+            assert_eq!(qsf_key_pair_generate(algorithm, &mut key_pair), QSF_OK);
+            assert!(!key_pair.is_null());
+            key_pair
+        }
+    }
+
+    fn sign(key_pair: *const QsfKeyPair, message: &[u8]) -> QsfBytes {
+        unsafe {
+            let mut signature = QsfBytes::EMPTY;
+            let status = qsf_sign(key_pair, message.as_ptr(), message.len(), &mut signature);
+            assert_eq!(status, QSF_OK);
+            signature
+        }
+    }
 
     #[test]
     fn test_c_bindings() {
-        // Test key pair generation
-        let key_pair = create_key_pair();
-        assert!(!key_pair.is_null());
-
-        // Test signing
-        let message = b"Test message";
-        let mut signature: *mut u8 = std::ptr::null_mut();
-        let mut signature_len: usize = 0;
-        let sign_result = unsafe {
-            sign_message(
-                message.as_ptr(),
-                message.len(),
-                (*key_pair).private_key,
-                (*key_pair).private_key_len,
-                &mut signature,
-                &mut signature_len,
-            )
-        };
-        assert_eq!(sign_result, 0);
-        assert!(!signature.is_null());
-
-        // Test verification
-        let result = unsafe {
-            verify_signature(
-                message.as_ptr(),
-                message.len(),
-                signature,
-                signature_len,
-                (*key_pair).private_key,
-                (*key_pair).private_key_len,
-            )
-        };
-        assert_eq!(result, 1);
+        unsafe {
+            for algorithm in [QSF_ALGORITHM_HMAC_SHA3_256, QSF_ALGORITHM_FALCON512] {
+                let mut key_pair = generate(algorithm);
+                let message = b"Test message";
+                let mut signature = sign(key_pair, message);
+                assert!(!signature.data.is_null());
+
+                let verify = |message: &[u8], signature: &QsfBytes| {
+                    qsf_verify(
+                        key_pair,
+                        message.as_ptr(),
+                        message.len(),
+                        signature.data,
+                        signature.len,
+                    )
+                };
+                assert_eq!(verify(message, &signature), QSF_OK);
+                assert_eq!(verify(b"Other message", &signature), QSF_ERR_BAD_SIGNATURE);
+
+                let mut found = u32::MAX;
+                assert_eq!(qsf_key_pair_algorithm(key_pair, &mut found), QSF_OK);
+                assert_eq!(found, algorithm);
 
-        // Free allocated memory
-        free_signature(signature);
-        free_key_pair(key_pair);
+                let mut json = QsfBytes::EMPTY;
+                assert_eq!(qsf_key_pair_to_json(key_pair, &mut json), QSF_OK);
+                let mut imported = ptr::null_mut();
+                let status = qsf_key_pair_from_json(json.data, json.len, &mut imported);
+                assert_eq!(status, QSF_OK);
+                let (mut ours, mut theirs) = (QsfBytes::EMPTY, QsfBytes::EMPTY);
+                assert_eq!(qsf_key_pair_public_key(key_pair, &mut ours), QSF_OK);
+                assert_eq!(qsf_key_pair_public_key(imported, &mut theirs), QSF_OK);
+                assert_eq!(input(ours.data, ours.len), input(theirs.data, theirs.len));
+
+                for bytes in [&mut signature, &mut json, &mut ours, &mut theirs] {
+                    qsf_bytes_free(bytes);
+                    assert!(bytes.data.is_null() && bytes.len == 0);
+                    qsf_bytes_free(bytes);
+                }
+                qsf_key_pair_free(&mut imported);
+                qsf_key_pair_free(&mut key_pair);
+                assert!(key_pair.is_null());
+                qsf_key_pair_free(&mut key_pair);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_arguments() {
+        unsafe {
+            let mut key_pair = generate(QSF_ALGORITHM_FALCON512);
+            let mut out = ptr::null_mut();
+            assert_eq!(
+                qsf_key_pair_generate(14, &mut out),
+                QSF_ERR_INVALID_ALGORITHM
+            );
+            assert!(out.is_null());
+            assert_eq!(
+                qsf_key_pair_generate(QSF_ALGORITHM_FALCON512, ptr::null_mut()),
+                QSF_ERR_NULL_POINTER
+            );
+
+            let mut signature = QsfBytes {
+                data: ptr::dangling_mut(),
+                len: 1,
+            };
+            let status = qsf_sign(ptr::null(), b"m".as_ptr(), 1, &mut signature);
+            assert_eq!(status, QSF_ERR_NULL_POINTER);
+            assert!(signature.data.is_null() && signature.len == 0);
+            let status = qsf_sign(key_pair, ptr::null(), 1, &mut signature);
+            assert_eq!(status, QSF_ERR_NULL_POINTER);
+            let status = qsf_sign(key_pair, b"m".as_ptr(), usize::MAX, &mut signature);
+            assert_eq!(status, QSF_ERR_INVALID_LENGTH);
+
+            // An empty message may be passed as a null pointer
+            let mut signature = sign(key_pair, b"");
+            let status = qsf_verify(key_pair, ptr::null(), 0, signature.data, signature.len);
+            assert_eq!(status, QSF_OK);
+            let status = qsf_verify(key_pair, ptr::null(), 0, ptr::null(), 0);
+            assert_eq!(status, QSF_ERR_BAD_SIGNATURE);
+            qsf_bytes_free(&mut signature);
+
+            let json = b"{\"public_key\":[1],\"private_key\":[2],\"algorithm\":\"Falcon512\"}";
+            let status = qsf_key_pair_from_json(json.as_ptr(), json.len(), &mut out);
+            assert_eq!(status, QSF_ERR_INVALID_KEY);
+            let status = qsf_key_pair_from_json(b"\xff".as_ptr(), 1, &mut out);
+            assert_eq!(status, QSF_ERR_INVALID_ENCODING);
+            let status = qsf_key_pair_from_json(b"{".as_ptr(), 1, &mut out);
+            assert_eq!(status, QSF_ERR_INVALID_ENCODING);
+            assert!(out.is_null());
+            qsf_key_pair_free(&mut key_pair);
+            qsf_key_pair_free(ptr::null_mut());
+            qsf_bytes_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_public_key_verification() {
+        unsafe {
+            let mut key_pair = generate(QSF_ALGORITHM_FALCON1024);
+            let message = b"Card-present authorisation";
+            let mut signature = sign(key_pair, message);
+
+            // The signer exports the encoded public key; the terminal imports it
+            let mut exported = ptr::null_mut();
+            assert_eq!(
+                qsf_public_key_from_key_pair(key_pair, &mut exported),
+                QSF_OK
+            );
+            let mut encoded = QsfBytes::EMPTY;
+            assert_eq!(qsf_public_key_to_bytes(exported, &mut encoded), QSF_OK);
+            qsf_public_key_free(&mut exported);
+            qsf_key_pair_free(&mut key_pair);
+
+            let mut public_key = ptr::null_mut();
+            let status = qsf_public_key_from_bytes(encoded.data, encoded.len, &mut public_key);
+            assert_eq!(status, QSF_OK);
+            let mut algorithm = 0;
+            assert_eq!(qsf_public_key_algorithm(public_key, &mut algorithm), QSF_OK);
+            assert_eq!(algorithm, QSF_ALGORITHM_FALCON1024);
+
+            let mut verifier = ptr::null_mut();
+            assert_eq!(qsf_verifier_new(public_key, &mut verifier), QSF_OK);
+            let (data, len) = (signature.data, signature.len);
+            for other in [&b"Other message"[..], &message[..]] {
+                let expected = if other == message {
+                    QSF_OK
+                } else {
+                    QSF_ERR_BAD_SIGNATURE
+                };
+                let one_shot =
+                    qsf_public_key_verify(public_key, other.as_ptr(), other.len(), data, len);
+                assert_eq!(one_shot, expected);
+                let status = qsf_verifier_verify(verifier, other.as_ptr(), other.len(), data, len);
+                assert_eq!(status, expected);
+            }
+
+            qsf_public_key_free(&mut public_key);
+            assert_eq!(
+                qsf_verifier_verify(verifier, message.as_ptr(), message.len(), data, len),
+                QSF_OK
+            );
+            qsf_verifier_free(&mut verifier);
+            assert!(verifier.is_null());
+            qsf_verifier_free(&mut verifier);
+            qsf_bytes_free(&mut encoded);
+            qsf_bytes_free(&mut signature);
+        }
+    }
+
+    #[test]
+    fn test_rejects_invalid_public_keys() {
+        unsafe {
+            let mut out = ptr::null_mut();
+            let hmac = crypto::generate_key_pair();
+            let mut key_pair = generate(QSF_ALGORITHM_HMAC_SHA3_256);
+            assert_eq!(
+                qsf_public_key_from_key_pair(key_pair, &mut out),
+                QSF_ERR_INVALID_ALGORITHM
+            );
+            qsf_key_pair_free(&mut key_pair);
+
+            let falcon = crypto::generate_key_pair_for(Algorithm::Falcon512);
+            let truncated = &falcon.public_key[..falcon.public_key.len() - 1];
+            for (encoded, expected) in [
+                (
+                    crypto::encode_public_key(Algorithm::HmacSha3_256, &hmac.public_key),
+                    QSF_ERR_INVALID_ALGORITHM,
+                ),
+                (
+                    crypto::encode_public_key(Algorithm::Falcon512, truncated),
+                    QSF_ERR_INVALID_KEY,
+                ),
+                (
+                    crypto::encode_public_key(Algorithm::Falcon1024, &falcon.public_key),
+                    QSF_ERR_INVALID_KEY,
+                ),
+                (falcon.public_key.clone(), QSF_ERR_INVALID_ENCODING),
+            ] {
+                let status = qsf_public_key_from_bytes(encoded.as_ptr(), encoded.len(), &mut out);
+                assert_eq!(status, expected);
+                assert!(out.is_null());
+            }
+
+            let mut verifier = ptr::null_mut();
+            assert_eq!(
+                qsf_verifier_new(ptr::null(), &mut verifier),
+                QSF_ERR_NULL_POINTER
+            );
+            assert_eq!(
+                qsf_verifier_verify(ptr::null(), ptr::null(), 0, ptr::null(), 0),
+                QSF_ERR_NULL_POINTER
+            );
+            qsf_public_key_free(ptr::null_mut());
+            qsf_verifier_free(ptr::null_mut());
+        }
+    }
+
+    #[test]
+    fn test_status_messages() {
+        for status in [QSF_OK, QSF_ERR_BAD_SIGNATURE, 42] {
+            let message = unsafe { CStr::from_ptr(qsf_status_message(status)) };
+            assert!(!message.to_str().unwrap().is_empty());
+        }
     }
 }
@@ -1,22 +1,23 @@
 //! Language bindings for QuantumSafe Finance
 
 /// C bindings
+#[cfg(feature = "c-api")]
 pub mod c;
 
 /// Python bindings
+#[cfg(feature = "python")]
 pub mod python;
 
 /// TypeScript bindings
+#[cfg(feature = "wasm")]
 pub mod typescript;
 
-pub use c::{
-    sign_message as c_sign_message, verify_signature as c_verify_signature,
-    KeyPairWrapper as CKeyPairWrapper,
-};
+#[cfg(feature = "python")]
 pub use python::{
     sign_message as python_sign_message, verify_signature as python_verify_signature,
     KeyPairWrapper as PythonKeyPairWrapper, Signature as PythonSignature,
 };
+#[cfg(feature = "wasm")]
 pub use typescript::{
     sign_message as typescript_sign_message, verify_signature as typescript_verify_signature,
     KeyPairWrapper as TypeScriptKeyPairWrapper, Signature as TypeScriptSignature,
@@ -1,88 +1,537 @@
 #![allow(clippy::uninlined_format_args)]
 #![allow(non_local_definitions)]
+#![allow(unexpected_cfgs)]
 
 //! Python bindings for QuantumSafe Finance
+//!
+//! Byte arguments accept `bytes`, `bytearray`, `memoryview` and other
+//! buffer-protocol objects; messages may also be `str`, signed as UTF-8.
+//! Failures raise subclasses of `QuantumSafeError`. `quantumsafe_finance.pyi`
+//! at the crate root holds the type stubs.
+//!
+//! Key generation, signing and verification run with the GIL released. The
+//! `*_async` variants return asyncio futures completed from a pool of worker
+//! threads, so they must be called with an event loop running. Large
+//! `verify_many` batches are split between the calling thread and that pool.
 
-use crate::crypto;
-use base64::{engine::general_purpose::STANDARD, Engine};
-use pyo3::exceptions::PyValueError;
+use crate::crypto::{self, Algorithm, CryptoError, KeyPair, PublicKeyVerifier};
+use pyo3::basic::CompareOp;
+use pyo3::buffer::PyBuffer;
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
-use pyo3::types::PyType;
-use serde::ser::SerializeMap;
+use pyo3::types::{PyBytes, PyString, PyTuple, PyType};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex, OnceLock};
+use std::thread;
+
+create_exception!(
+    quantumsafe_finance,
+    QuantumSafeError,
+    PyException,
+    "Base class of all QuantumSafe Finance errors"
+);
+create_exception!(
+    quantumsafe_finance,
+    InvalidKeyError,
+    QuantumSafeError,
+    "A key is malformed or does not match its algorithm"
+);
+create_exception!(
+    quantumsafe_finance,
+    InvalidAlgorithmError,
+    QuantumSafeError,
+    "An unknown algorithm, or one that cannot perform the operation"
+);
+create_exception!(
+    quantumsafe_finance,
+    EncodingError,
+    QuantumSafeError,
+    "Serialized key material cannot be decoded"
+);
+
+/// Algorithms whose tags are exported as `ALGORITHMS`
+const ALGORITHMS: [Algorithm; 14] = Algorithm::ALL;
+
+fn algorithm_from_tag(tag: &str) -> PyResult<Algorithm> {
+    Algorithm::from_tag(tag)
+        .ok_or_else(|| InvalidAlgorithmError::new_err(format!("unknown algorithm {:?}", tag)))
+}
+
+fn crypto_error(e: CryptoError) -> PyErr {
+    InvalidKeyError::new_err(e.to_string())
+}
+
+/// Byte string argument taken from any bytes-like object, `str` or `Signature`
+pub struct BytesLike(Vec<u8>);
+
+impl<'source> FromPyObject<'source> for BytesLike {
+    fn extract(obj: &'source PyAny) -> PyResult<Self> {
+        if let Ok(text) = obj.downcast::<PyString>() {
+            return Ok(BytesLike(text.to_str()?.as_bytes().to_vec()));
+        }
+        if let Ok(signature) = obj.extract::<PyRef<Signature>>() {
+            return Ok(BytesLike(signature.signature.clone()));
+        }
+        let buffer = PyBuffer::<u8>::get(obj).map_err(|_| {
+            let name = obj.get_type().name().unwrap_or("object");
+            PyTypeError::new_err(format!("expected a bytes-like object, not {}", name))
+        })?;
+        Ok(BytesLike(buffer.to_vec(obj.py())?))
+    }
+}
+
+/// Smallest share of a `verify_many` batch worth a worker of its own; smaller
+/// batches are verified on the calling thread
+const BATCH_CHUNK: usize = 64;
+
+fn parallelism() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Buffers from a list, tuple, NumPy array or other iterable of bytes-like objects
+///
+/// A two-dimensional `uint8` array contributes one buffer per row.
+fn buffers(obj: &PyAny) -> PyResult<Vec<Vec<u8>>> {
+    if obj.is_instance_of::<PyBytes>() || obj.is_instance_of::<PyString>() {
+        return Err(PyTypeError::new_err(
+            "expected a sequence of bytes-like objects",
+        ));
+    }
+    obj.iter()?
+        .map(|item| Ok(item?.extract::<BytesLike>()?.0))
+        .collect()
+}
+
+/// Messages and the signatures over them, index for index
+type Batch = (Vec<Vec<u8>>, Vec<Vec<u8>>);
+
+/// Messages and signatures for `verify_many`, which must pair up
+fn batch(messages: &PyAny, signatures: &PyAny) -> PyResult<Batch> {
+    let messages = buffers(messages)?;
+    let signatures = buffers(signatures)?;
+    if messages.len() != signatures.len() {
+        return Err(PyValueError::new_err(format!(
+            "{} messages but {} signatures",
+            messages.len(),
+            signatures.len()
+        )));
+    }
+    Ok((messages, signatures))
+}
+
+/// Verify each message against the signature at the same index, sharing
+/// large batches with the worker pool
+fn verify_batch<F>(messages: Vec<Vec<u8>>, signatures: Vec<Vec<u8>>, verify: F) -> Vec<bool>
+where
+    F: Fn(&[u8], &[u8]) -> bool + Send + Sync + 'static,
+{
+    if messages.len() <= BATCH_CHUNK {
+        return messages
+            .iter()
+            .zip(&signatures)
+            .map(|(message, signature)| verify(message, signature))
+            .collect();
+    }
+    let chunk = messages.len().div_ceil(parallelism()).max(BATCH_CHUNK);
+    let chunks = messages.len().div_ceil(chunk);
+    let batch = Arc::new(SharedBatch {
+        messages,
+        signatures,
+        verify,
+        chunk,
+        next: AtomicUsize::new(0),
+    });
+    let (sender, receiver) = mpsc::channel();
+    for _ in 1..chunks {
+        let batch = batch.clone();
+        let sender = sender.clone();
+        WorkerPool::get().execute(Box::new(move || batch.run(&sender)));
+    }
+    // The calling thread works through the batch too, so the batch finishes
+    // even when every worker is busy, including with the caller itself
+    batch.run(&sender);
+
+    let mut results = vec![Vec::new(); chunks];
+    for _ in 0..chunks {
+        let (index, verified) = receiver.recv().expect("batch sender dropped");
+        results[index] = verified.unwrap_or_else(|e| panic::resume_unwind(e));
+    }
+    results.concat()
+}
+
+/// `verify_many` batch whose chunks are claimed in turn by the calling thread
+/// and pool workers
+struct SharedBatch<F> {
+    messages: Vec<Vec<u8>>,
+    signatures: Vec<Vec<u8>>,
+    verify: F,
+    chunk: usize,
+    next: AtomicUsize,
+}
+
+/// Index of a chunk and its results
+type ChunkResult = (usize, thread::Result<Vec<bool>>);
+
+impl<F: Fn(&[u8], &[u8]) -> bool> SharedBatch<F> {
+    /// Verify unclaimed chunks until none are left
+    fn run(&self, results: &mpsc::Sender<ChunkResult>) {
+        loop {
+            let index = self.next.fetch_add(1, Ordering::Relaxed);
+            let start = index * self.chunk;
+            if start >= self.messages.len() {
+                return;
+            }
+            let end = (start + self.chunk).min(self.messages.len());
+            let verified = panic::catch_unwind(AssertUnwindSafe(|| {
+                (start..end)
+                    .map(|i| (self.verify)(&self.messages[i], &self.signatures[i]))
+                    .collect()
+            }));
+            let _ = results.send((index, verified));
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Threads that run the `*_async` methods off the event loop
+struct WorkerPool {
+    sender: Mutex<mpsc::Sender<Job>>,
+}
+
+impl WorkerPool {
+    fn get() -> &'static WorkerPool {
+        static POOL: OnceLock<WorkerPool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<Job>();
+            let receiver = Arc::new(Mutex::new(receiver));
+            for _ in 0..parallelism() {
+                let receiver = receiver.clone();
+                thread::Builder::new()
+                    .name("qsf-python-worker".into())
+                    .spawn(move || loop {
+                        let job = receiver.lock().expect("worker pool poisoned").recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => return,
+                        }
+                    })
+                    .expect("failed to spawn worker thread");
+            }
+            WorkerPool {
+                sender: Mutex::new(sender),
+            }
+        })
+    }
+
+    fn execute(&self, job: Job) {
+        self.sender
+            .lock()
+            .expect("worker pool poisoned")
+            .send(job)
+            .expect("worker threads exited");
+    }
+}
+
+/// Callback scheduled on the event loop to complete a future with a job's result
+#[pyclass]
+struct Resolve {
+    future: PyObject,
+    result: Option<PyResult<PyObject>>,
+}
+
+#[pymethods]
+impl Resolve {
+    fn __call__(&mut self, py: Python) -> PyResult<()> {
+        let future = self.future.as_ref(py);
+        // A cancelled future no longer wants the result
+        if future.call_method0("done")?.is_true()? {
+            return Ok(());
+        }
+        match self.result.take() {
+            Some(Ok(value)) => future.call_method1("set_result", (value,))?,
+            Some(Err(e)) => future.call_method1("set_exception", (e.into_value(py),))?,
+            None => return Ok(()),
+        };
+        Ok(())
+    }
+}
+
+/// Run `job` on the worker pool and return a future of the running event loop
+/// that completes with its result
+fn spawn<T, F>(py: Python, job: F) -> PyResult<PyObject>
+where
+    F: FnOnce() -> PyResult<T> + Send + 'static,
+    T: IntoPy<PyObject> + Send + 'static,
+{
+    let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+    let future = event_loop.call_method0("create_future")?;
+    let event_loop: PyObject = event_loop.into();
+    let pending: PyObject = future.into();
+    WorkerPool::get().execute(Box::new(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(job))
+            .unwrap_or_else(|_| Err(PyRuntimeError::new_err("worker thread panicked")));
+        Python::with_gil(|py| {
+            let callback = Resolve {
+                future: pending,
+                result: Some(result.map(|value| value.into_py(py))),
+            };
+            // Fails only once the loop is closed, when nobody awaits the future
+            let _ = Py::new(py, callback).and_then(|callback| {
+                event_loop.call_method1(py, "call_soon_threadsafe", (callback,))
+            });
+        });
+    }));
+    Ok(future.into())
+}
 
 /// Quantum-safe key pair
 #[pyclass]
 pub struct KeyPairWrapper {
-    #[pyo3(get)]
-    pub public_key: Vec<u8>,
-    #[pyo3(get)]
-    pub private_key: Vec<u8>,
+    key_pair: Arc<KeyPair>,
 }
 
 #[pymethods]
 impl KeyPairWrapper {
     #[new]
-    fn new() -> PyResult<Self> {
-        let key_pair = crypto::generate_key_pair();
+    #[pyo3(signature = (algorithm = "hmac-sha3-256"))]
+    fn new(py: Python, algorithm: &str) -> PyResult<Self> {
+        let algorithm = algorithm_from_tag(algorithm)?;
+        let key_pair = py.allow_threads(|| crypto::generate_key_pair_for(algorithm));
         Ok(Self {
-            public_key: key_pair.public_key,
-            private_key: key_pair.private_key,
+            key_pair: Arc::new(key_pair),
+        })
+    }
+
+    /// Generate a key pair for `algorithm`
+    #[classmethod]
+    #[pyo3(signature = (algorithm = "hmac-sha3-256"))]
+    pub fn generate(_cls: &PyType, py: Python, algorithm: &str) -> PyResult<Self> {
+        Self::new(py, algorithm)
+    }
+
+    #[classmethod]
+    #[pyo3(signature = (algorithm = "hmac-sha3-256"))]
+    pub fn generate_async(_cls: &PyType, py: Python, algorithm: &str) -> PyResult<PyObject> {
+        let algorithm = algorithm_from_tag(algorithm)?;
+        spawn(py, move || {
+            Ok(Self {
+                key_pair: Arc::new(crypto::generate_key_pair_for(algorithm)),
+            })
+        })
+    }
+
+    #[getter]
+    fn algorithm(&self) -> &'static str {
+        self.key_pair.algorithm.tag()
+    }
+
+    #[getter]
+    fn public_key<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.key_pair.public_key)
+    }
+
+    #[getter]
+    fn private_key<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.key_pair.private_key)
+    }
+
+    #[getter]
+    fn key_id(&self) -> String {
+        self.key_pair.key_id().to_string()
+    }
+
+    /// Sign `message` with this key pair's algorithm
+    pub fn sign(&self, py: Python, message: BytesLike) -> PyResult<Signature> {
+        let signature = py
+            .allow_threads(|| self.key_pair.sign(&message.0))
+            .map_err(crypto_error)?;
+        Ok(Signature { signature })
+    }
+
+    pub fn sign_async(&self, py: Python, message: BytesLike) -> PyResult<PyObject> {
+        let key_pair = self.key_pair.clone();
+        spawn(py, move || {
+            let signature = key_pair.sign(&message.0).map_err(crypto_error)?;
+            Ok(Signature { signature })
+        })
+    }
+
+    /// Verify a signature produced by `sign`
+    pub fn verify(&self, py: Python, message: BytesLike, signature: BytesLike) -> bool {
+        py.allow_threads(|| self.key_pair.verify(&message.0, &signature.0))
+    }
+
+    pub fn verify_async(
+        &self,
+        py: Python,
+        message: BytesLike,
+        signature: BytesLike,
+    ) -> PyResult<PyObject> {
+        let key_pair = self.key_pair.clone();
+        spawn(py, move || Ok(key_pair.verify(&message.0, &signature.0)))
+    }
+
+    /// Verify `signatures[i]` over `messages[i]` for every index
+    pub fn verify_many(
+        &self,
+        py: Python,
+        messages: &PyAny,
+        signatures: &PyAny,
+    ) -> PyResult<Vec<bool>> {
+        let (messages, signatures) = batch(messages, signatures)?;
+        let key_pair = self.key_pair.clone();
+        Ok(py.allow_threads(|| {
+            verify_batch(messages, signatures, move |m, s| key_pair.verify(m, s))
+        }))
+    }
+
+    pub fn verify_many_async(
+        &self,
+        py: Python,
+        messages: &PyAny,
+        signatures: &PyAny,
+    ) -> PyResult<PyObject> {
+        let (messages, signatures) = batch(messages, signatures)?;
+        let key_pair = self.key_pair.clone();
+        spawn(py, move || {
+            Ok(verify_batch(messages, signatures, move |m, s| {
+                key_pair.verify(m, s)
+            }))
         })
     }
 
     fn __repr__(&self) -> String {
         format!(
-            "KeyPair(public_key_len={}, private_key_len={}",
-            self.public_key.len(),
-            self.private_key.len()
+            "KeyPair(algorithm={}, public_key_len={}, private_key_len={})",
+            self.key_pair.algorithm.tag(),
+            self.key_pair.public_key.len(),
+            self.key_pair.private_key.len()
         )
     }
 
+    /// Read a key pair written by `to_json` or any other binding
     #[classmethod]
-    pub fn from_json(_cls: &PyType, json: &str) -> PyResult<Self> {
-        let data: serde_json::Value = serde_json::from_str(json)
-            .map_err(|e| PyValueError::new_err(format!("Invalid JSON: {}", e)))?;
-        let public_key = data["public_key"]
-            .as_str()
-            .ok_or_else(|| PyValueError::new_err("Missing public_key in JSON"))?
-            .as_bytes()
-            .to_vec();
-        let private_key = data["private_key"]
-            .as_str()
-            .ok_or_else(|| PyValueError::new_err("Missing private_key in JSON"))?
-            .as_bytes()
-            .to_vec();
+    pub fn from_json(_cls: &PyType, py: Python, json: &str) -> PyResult<Self> {
+        let key_pair = py
+            .allow_threads(|| crypto::key_pair_from_json(json))
+            .map_err(|e| {
+                if e.is_data() {
+                    InvalidKeyError::new_err(e.to_string())
+                } else {
+                    EncodingError::new_err(format!("invalid JSON: {}", e))
+                }
+            })?;
         Ok(Self {
-            public_key,
-            private_key,
+            key_pair: Arc::new(key_pair),
         })
     }
 
-    #[classmethod]
-    pub fn to_json(_cls: &PyType, obj: &PyAny) -> PyResult<String> {
-        let _py = obj.py();
-        let public_key = obj.getattr("public_key")?.extract::<Vec<u8>>()?;
-        let private_key = obj.getattr("private_key")?.extract::<Vec<u8>>()?;
-        let json = serde_json::json!({
-            "public_key": STANDARD.encode(&public_key),
-            "private_key": STANDARD.encode(&private_key),
-        });
-        Ok(json.to_string())
+    pub fn to_json(&self) -> String {
+        crypto::key_pair_to_json(&self.key_pair)
     }
 }
 
-impl serde::Serialize for KeyPairWrapper {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let public_key_base64 = base64::engine::general_purpose::STANDARD.encode(&self.public_key);
-        let private_key_base64 =
-            base64::engine::general_purpose::STANDARD.encode(&self.private_key);
-        let mut map = serializer.serialize_map(Some(2))?;
-        map.serialize_entry("public_key", &public_key_base64)?;
-        map.serialize_entry("private_key", &private_key_base64)?;
-        map.end()
+/// Public half of an asymmetric key pair, decoded once for verifying many signatures
+#[pyclass]
+pub struct PublicKey {
+    public_key: Vec<u8>,
+    verifier: Arc<PublicKeyVerifier>,
+}
+
+impl PublicKey {
+    fn new(algorithm: Algorithm, public_key: &[u8]) -> PyResult<Self> {
+        if algorithm == Algorithm::HmacSha3_256 {
+            return Err(InvalidAlgorithmError::new_err(
+                "hmac-sha3-256 cannot verify without the private key",
+            ));
+        }
+        let verifier = PublicKeyVerifier::new(algorithm, public_key)
+            .map_err(|e| InvalidKeyError::new_err(e.to_string()))?;
+        Ok(Self {
+            public_key: public_key.to_vec(),
+            verifier: Arc::new(verifier),
+        })
+    }
+}
+
+#[pymethods]
+impl PublicKey {
+    /// Decode a public key written by `to_bytes`
+    #[classmethod]
+    pub fn from_bytes(_cls: &PyType, data: BytesLike) -> PyResult<Self> {
+        let (algorithm, public_key) = crypto::decode_public_key(&data.0)
+            .ok_or_else(|| EncodingError::new_err("malformed public key encoding"))?;
+        Self::new(algorithm, public_key)
+    }
+
+    #[classmethod]
+    pub fn from_key_pair(_cls: &PyType, key_pair: &KeyPairWrapper) -> PyResult<Self> {
+        Self::new(key_pair.key_pair.algorithm, &key_pair.key_pair.public_key)
+    }
+
+    /// Algorithm tag followed by the raw key, as `encode_public_key` writes it
+    pub fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        let encoded = crypto::encode_public_key(self.verifier.algorithm(), &self.public_key);
+        PyBytes::new(py, &encoded)
+    }
+
+    #[getter]
+    fn algorithm(&self) -> &'static str {
+        self.verifier.algorithm().tag()
+    }
+
+    pub fn verify(&self, py: Python, message: BytesLike, signature: BytesLike) -> bool {
+        py.allow_threads(|| self.verifier.verify(&message.0, &signature.0))
+    }
+
+    pub fn verify_async(
+        &self,
+        py: Python,
+        message: BytesLike,
+        signature: BytesLike,
+    ) -> PyResult<PyObject> {
+        let verifier = self.verifier.clone();
+        spawn(py, move || Ok(verifier.verify(&message.0, &signature.0)))
+    }
+
+    /// Verify `signatures[i]` over `messages[i]` for every index
+    pub fn verify_many(
+        &self,
+        py: Python,
+        messages: &PyAny,
+        signatures: &PyAny,
+    ) -> PyResult<Vec<bool>> {
+        let (messages, signatures) = batch(messages, signatures)?;
+        let verifier = self.verifier.clone();
+        Ok(py.allow_threads(|| {
+            verify_batch(messages, signatures, move |m, s| verifier.verify(m, s))
+        }))
+    }
+
+    pub fn verify_many_async(
+        &self,
+        py: Python,
+        messages: &PyAny,
+        signatures: &PyAny,
+    ) -> PyResult<PyObject> {
+        let (messages, signatures) = batch(messages, signatures)?;
+        let verifier = self.verifier.clone();
+        spawn(py, move || {
+            Ok(verify_batch(messages, signatures, move |m, s| {
+                verifier.verify(m, s)
+            }))
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "PublicKey(algorithm={}, len={})",
+            self.verifier.algorithm().tag(),
+            self.public_key.len()
+        )
     }
 }
 
@@ -96,8 +545,40 @@ pub struct Signature {
 #[pymethods]
 impl Signature {
     #[new]
-    fn new(signature: Vec<u8>) -> Self {
-        Self { signature }
+    fn new(signature: BytesLike) -> Self {
+        Self {
+            signature: signature.0,
+        }
+    }
+
+    #[classmethod]
+    pub fn from_bytes(_cls: &PyType, data: BytesLike) -> Self {
+        Self::new(data)
+    }
+
+    pub fn to_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        PyBytes::new(py, &self.signature)
+    }
+
+    #[getter(signature)]
+    fn signature_bytes<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        self.to_bytes(py)
+    }
+
+    fn __bytes__<'py>(&self, py: Python<'py>) -> &'py PyBytes {
+        self.to_bytes(py)
+    }
+
+    fn __len__(&self) -> usize {
+        self.signature.len()
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp, py: Python) -> PyObject {
+        match op {
+            CompareOp::Eq => (self.signature == other.signature).into_py(py),
+            CompareOp::Ne => (self.signature != other.signature).into_py(py),
+            _ => py.NotImplemented(),
+        }
     }
 
     fn __repr__(&self) -> String {
@@ -110,7 +591,9 @@ impl serde::Serialize for Signature {
     where
         S: serde::Serializer,
     {
-        let signature_base64 = base64::engine::general_purpose::STANDARD.encode(&self.signature);
+        use serde::ser::SerializeMap;
+
+        let signature_base64 = crypto::signature_to_base64(&self.signature);
         let mut map = serializer.serialize_map(Some(1))?;
         map.serialize_entry("signature", &signature_base64)?;
         map.end()
@@ -119,46 +602,274 @@ impl serde::Serialize for Signature {
 
 /// Main module
 #[pymodule]
-fn quantumsafe_finance(_py: Python, m: &PyModule) -> PyResult<()> {
+fn quantumsafe_finance(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<KeyPairWrapper>()?;
+    m.add_class::<PublicKey>()?;
+    m.add_class::<Signature>()?;
     m.add_function(wrap_pyfunction!(sign_message, m)?)?;
     m.add_function(wrap_pyfunction!(verify_signature, m)?)?;
+    m.add("QuantumSafeError", py.get_type::<QuantumSafeError>())?;
+    m.add("InvalidKeyError", py.get_type::<InvalidKeyError>())?;
+    m.add(
+        "InvalidAlgorithmError",
+        py.get_type::<InvalidAlgorithmError>(),
+    )?;
+    m.add("EncodingError", py.get_type::<EncodingError>())?;
+    m.add(
+        "ALGORITHMS",
+        PyTuple::new(py, ALGORITHMS.map(|algorithm| algorithm.tag())),
+    )?;
     Ok(())
 }
 
-/// Sign a message using quantum-safe signature
+/// Sign a message with an HMAC-SHA3-256 private key
 #[pyfunction]
-pub fn sign_message(message: &str, private_key: &[u8]) -> PyResult<Signature> {
-    let signature = crypto::sign_message(message.as_bytes(), private_key);
+pub fn sign_message(py: Python, message: BytesLike, private_key: BytesLike) -> PyResult<Signature> {
+    let signature = py.allow_threads(|| crypto::sign_message(&message.0, &private_key.0));
     Ok(Signature { signature })
 }
 
-/// Verify a signature
+/// Verify an HMAC-SHA3-256 signature, which needs the private key
 #[pyfunction]
-pub fn verify_signature(message: &str, signature: &[u8], public_key: &[u8]) -> PyResult<bool> {
-    Ok(crypto::verify_signature(
-        message.as_bytes(),
-        signature,
-        public_key,
-    ))
+pub fn verify_signature(
+    py: Python,
+    message: BytesLike,
+    signature: BytesLike,
+    private_key: BytesLike,
+) -> PyResult<bool> {
+    Ok(py.allow_threads(|| crypto::verify_signature(&message.0, &signature.0, &private_key.0)))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pyo3::types::PyByteArray;
     use pyo3::Python;
 
+    fn bytes(py: Python, data: &[u8]) -> BytesLike {
+        PyBytes::new(py, data).extract().unwrap()
+    }
+
     #[test]
     fn test_python_bindings() {
         pyo3::prepare_freethreaded_python();
-        Python::with_gil(|_py| {
-            let key_pair = KeyPairWrapper::new().unwrap();
-            let message = "Test message";
+        Python::with_gil(|py| {
+            let key_pair = KeyPairWrapper::new(py, "hmac-sha3-256").unwrap();
+            let private_key = &key_pair.key_pair.private_key;
+
+            let signature =
+                sign_message(py, bytes(py, b"Test message"), bytes(py, private_key)).unwrap();
+            let message = PyString::new(py, "Test message").extract().unwrap();
+            assert!(verify_signature(
+                py,
+                message,
+                bytes(py, &signature.signature),
+                bytes(py, private_key)
+            )
+            .unwrap());
+        });
+    }
+
+    #[test]
+    fn test_all_algorithms_and_buffers() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            // The small SLH-DSA sets take minutes to sign in debug builds
+            let fast = |a: &Algorithm| !a.tag().starts_with("slh-dsa") || a.tag().ends_with('f');
+            for algorithm in ALGORITHMS.into_iter().filter(fast) {
+                let key_pair = KeyPairWrapper::new(py, algorithm.tag()).unwrap();
+                let message = PyByteArray::new(py, b"settlement batch 42");
+                let signature = key_pair.sign(py, message.extract().unwrap()).unwrap();
+                let view = py
+                    .eval("memoryview(b'settlement batch 42')", None, None)
+                    .unwrap();
+                assert!(key_pair.verify(
+                    py,
+                    view.extract().unwrap(),
+                    bytes(py, &signature.signature)
+                ));
+                assert!(!key_pair.verify(py, bytes(py, b"other"), bytes(py, &signature.signature)));
 
-            let signature = sign_message(message, &key_pair.private_key).unwrap();
-            assert!(
-                verify_signature(message, &signature.signature, &key_pair.private_key).unwrap()
+                let restored = KeyPairWrapper::from_json(
+                    py.get_type::<KeyPairWrapper>(),
+                    py,
+                    &key_pair.to_json(),
+                )
+                .unwrap();
+                assert_eq!(restored.key_pair.algorithm, algorithm);
+                assert!(restored.verify(
+                    py,
+                    bytes(py, b"settlement batch 42"),
+                    bytes(py, &signature.signature)
+                ));
+            }
+        });
+    }
+
+    #[test]
+    fn test_public_key_and_signature_round_trip() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let key_pair = KeyPairWrapper::new(py, "falcon-512").unwrap();
+            let signature = key_pair.sign(py, bytes(py, b"payload")).unwrap();
+            let copy = Signature::from_bytes(
+                py.get_type::<Signature>(),
+                bytes(py, signature.to_bytes(py).as_bytes()),
             );
+            assert_eq!(copy.signature, signature.signature);
+
+            let public_key =
+                PublicKey::from_key_pair(py.get_type::<PublicKey>(), &key_pair).unwrap();
+            let decoded = PublicKey::from_bytes(
+                py.get_type::<PublicKey>(),
+                bytes(py, public_key.to_bytes(py).as_bytes()),
+            )
+            .unwrap();
+            assert_eq!(decoded.algorithm(), "falcon-512");
+            let signature = Py::new(py, copy).unwrap();
+            assert!(decoded.verify(
+                py,
+                bytes(py, b"payload"),
+                signature.as_ref(py).extract().unwrap()
+            ));
+        });
+    }
+
+    #[test]
+    fn test_errors() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let e = KeyPairWrapper::new(py, "rsa-2048").err().unwrap();
+            assert!(e.is_instance_of::<InvalidAlgorithmError>(py));
+            assert!(e.is_instance_of::<QuantumSafeError>(py));
+
+            let cls = py.get_type::<KeyPairWrapper>();
+            let e = KeyPairWrapper::from_json(cls, py, "{").err().unwrap();
+            assert!(e.is_instance_of::<EncodingError>(py));
+            let mut key_pair = KeyPairWrapper::new(py, "falcon-512").unwrap();
+            Arc::get_mut(&mut key_pair.key_pair).unwrap().private_key[0] ^= 1;
+            let e = KeyPairWrapper::from_json(cls, py, &key_pair.to_json())
+                .err()
+                .unwrap();
+            assert!(e.is_instance_of::<InvalidKeyError>(py));
+
+            let cls = py.get_type::<PublicKey>();
+            let e = PublicKey::from_bytes(cls, bytes(py, b"")).err().unwrap();
+            assert!(e.is_instance_of::<EncodingError>(py));
+            let hmac = KeyPairWrapper::new(py, "hmac-sha3-256").unwrap();
+            let e = PublicKey::from_key_pair(cls, &hmac).err().unwrap();
+            assert!(e.is_instance_of::<InvalidAlgorithmError>(py));
+
+            let e = PyAny::extract::<BytesLike>(py.eval("42", None, None).unwrap())
+                .err()
+                .unwrap();
+            assert!(e.is_instance_of::<PyTypeError>(py));
+        });
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let key_pair = crypto::generate_key_pair();
+        let messages: Vec<Vec<u8>> = (0..1000u32).map(|i| i.to_be_bytes().to_vec()).collect();
+        let mut signatures: Vec<Vec<u8>> =
+            messages.iter().map(|m| key_pair.sign(m).unwrap()).collect();
+        signatures[3][0] ^= 1;
+        signatures[999].clear();
+
+        let key_pair = Arc::new(key_pair);
+        for len in [10, 1000] {
+            let key_pair = key_pair.clone();
+            let results = verify_batch(
+                messages[..len].to_vec(),
+                signatures[..len].to_vec(),
+                move |m, s| key_pair.verify(m, s),
+            );
+            assert_eq!(results.len(), len);
+            for (i, valid) in results.into_iter().enumerate() {
+                assert_eq!(valid, i != 3 && i != 999, "index {}", i);
+            }
+        }
+        assert!(verify_batch(Vec::new(), Vec::new(), |_, _| true).is_empty());
+    }
+
+    #[test]
+    fn test_verify_batch_from_every_worker() {
+        // Batches started on all workers at once still finish
+        let (sender, receiver) = mpsc::channel();
+        let jobs = 2 * parallelism();
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            WorkerPool::get().execute(Box::new(move || {
+                let batch = vec![Vec::new(); 8 * BATCH_CHUNK];
+                let results = verify_batch(batch.clone(), batch, |_, _| true);
+                sender.send(results.len()).unwrap();
+            }));
+        }
+        for _ in 0..jobs {
+            let len = receiver.recv_timeout(std::time::Duration::from_secs(30));
+            assert_eq!(len, Ok(8 * BATCH_CHUNK));
+        }
+    }
+
+    #[test]
+    fn test_verify_many_and_async() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let globals = pyo3::types::PyDict::new(py);
+            globals
+                .set_item("__builtins__", py.import("builtins").unwrap())
+                .unwrap();
+            globals
+                .set_item("KeyPairWrapper", py.get_type::<KeyPairWrapper>())
+                .unwrap();
+            globals
+                .set_item("PublicKey", py.get_type::<PublicKey>())
+                .unwrap();
+            py.run(
+                r#"
+import asyncio
+
+async def main():
+    key_pair = await KeyPairWrapper.generate_async("falcon-512")
+    public_key = PublicKey.from_key_pair(key_pair)
+    messages = [b"a", bytearray(b"b"), memoryview(b"c")]
+    signatures = await asyncio.gather(*(key_pair.sign_async(m) for m in messages))
+    signatures[1] = signatures[0]
+    return (
+        key_pair.verify_many(messages, signatures),
+        public_key.verify_many(tuple(messages), signatures),
+        await public_key.verify_many_async(messages, signatures),
+        await key_pair.verify_async(b"a", signatures[0]),
+    )
+
+results = asyncio.run(main())
+"#,
+                Some(globals),
+                None,
+            )
+            .unwrap();
+            let results: (Vec<bool>, Vec<bool>, Vec<bool>, bool) =
+                globals.get_item("results").unwrap().extract().unwrap();
+            let expected = vec![true, false, true];
+            assert_eq!(
+                results,
+                (expected.clone(), expected.clone(), expected, true)
+            );
+
+            let key_pair = KeyPairWrapper::new(py, "hmac-sha3-256").unwrap();
+            let list = py.eval("[b'a', b'b']", None, None).unwrap();
+            let e = key_pair
+                .verify_many(py, list, py.eval("[b'a']", None, None).unwrap())
+                .unwrap_err();
+            assert!(e.is_instance_of::<PyValueError>(py));
+            let e = key_pair
+                .verify_many(py, PyBytes::new(py, b"ab"), list)
+                .unwrap_err();
+            assert!(e.is_instance_of::<PyTypeError>(py));
+            let e = key_pair
+                .sign_async(py, bytes(py, b"no running loop"))
+                .unwrap_err();
+            assert!(e.is_instance_of::<PyRuntimeError>(py));
         });
     }
 }
@@ -1,136 +1,291 @@
 #![allow(clippy::uninlined_format_args)]
-#![allow(clippy::new_without_default)]
 
 //! TypeScript bindings for QuantumSafe Finance
+//!
+//! Packaged by the `wasm` crate with `wasm-pack build wasm --target nodejs`;
+//! the generated `.d.ts` carries the types. Messages may be a `Uint8Array` or
+//! a string, signed as UTF-8, and signatures a `Uint8Array` or `Signature`.
+//! Failures throw an `Error` whose `name` is one of `QuantumSafeErrorName`;
+//! arguments of the wrong type throw a `TypeError`.
 
-use crate::crypto;
-use base64::engine::general_purpose;
-use base64::Engine;
-use js_sys::Uint8Array;
-use serde::{ser::SerializeMap, Deserialize};
-use std::string::String;
+use crate::crypto::{self, Algorithm, CryptoError, KeyPair, PublicKeyVerifier};
+use js_sys::{Reflect, Uint8Array};
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+#[wasm_bindgen(typescript_custom_section)]
+const TS_ERRORS: &str = r#"
+export type QuantumSafeErrorName = "InvalidKeyError" | "InvalidAlgorithmError" | "EncodingError";
+
+/** Error thrown by every fallible call in this package */
+export interface QuantumSafeError extends Error {
+    name: QuantumSafeErrorName;
+}
+"#;
+
+/// Algorithms returned by `algorithms()`
+const ALGORITHMS: [Algorithm; 14] = Algorithm::ALL;
+
+/// Error thrown to JavaScript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A key is malformed or does not match its algorithm
+    InvalidKey(String),
+    /// An unknown algorithm, or one that cannot perform the operation
+    InvalidAlgorithm(String),
+    /// Serialized key material or a signature cannot be decoded
+    Encoding(String),
+    /// An argument has the wrong JavaScript type
+    Type(String),
+}
+
+impl Error {
+    /// `name` of the thrown JavaScript error
+    pub fn name(&self) -> &'static str {
+        match self {
+            Error::InvalidKey(_) => "InvalidKeyError",
+            Error::InvalidAlgorithm(_) => "InvalidAlgorithmError",
+            Error::Encoding(_) => "EncodingError",
+            Error::Type(_) => "TypeError",
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Error::InvalidKey(message)
+            | Error::InvalidAlgorithm(message)
+            | Error::Encoding(message)
+            | Error::Type(message) => message,
+        }
+    }
+}
+
+impl From<CryptoError> for Error {
+    fn from(e: CryptoError) -> Self {
+        Error::InvalidKey(e.to_string())
+    }
+}
+
+impl From<Error> for JsValue {
+    fn from(e: Error) -> Self {
+        if let Error::Type(message) = &e {
+            return js_sys::TypeError::new(message).into();
+        }
+        let error = js_sys::Error::new(e.message());
+        error.set_name(e.name());
+        error.into()
+    }
+}
+
+fn algorithm_from_tag(tag: Option<String>) -> Result<Algorithm, Error> {
+    match tag {
+        None => Ok(Algorithm::HmacSha3_256),
+        Some(tag) => Algorithm::from_tag(&tag)
+            .ok_or_else(|| Error::InvalidAlgorithm(format!("unknown algorithm {:?}", tag))),
+    }
+}
+
+/// Bytes of a `Uint8Array | string` message
+fn message_bytes(message: &JsValue) -> Result<Vec<u8>, Error> {
+    if let Some(text) = message.as_string() {
+        return Ok(text.into_bytes());
+    }
+    message
+        .dyn_ref::<Uint8Array>()
+        .map(Uint8Array::to_vec)
+        .ok_or_else(|| Error::Type("message must be a Uint8Array or string".into()))
+}
+
+/// Bytes of a `Uint8Array | Signature` signature
+fn signature_bytes(signature: &JsValue) -> Result<Vec<u8>, Error> {
+    let bytes = match signature.dyn_ref::<Uint8Array>() {
+        Some(_) => signature.clone(),
+        None if signature.is_object() => {
+            Reflect::get(signature, &"signature".into()).unwrap_or(JsValue::UNDEFINED)
+        }
+        None => JsValue::UNDEFINED,
+    };
+    bytes
+        .dyn_ref::<Uint8Array>()
+        .map(Uint8Array::to_vec)
+        .ok_or_else(|| Error::Type("signature must be a Uint8Array or Signature".into()))
+}
+
+/// Tags of the supported algorithms
+#[wasm_bindgen]
+pub fn algorithms() -> Vec<String> {
+    ALGORITHMS.iter().map(|a| a.tag().to_string()).collect()
+}
 
 /// Quantum-safe key pair
 #[wasm_bindgen]
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct KeyPairWrapper {
-    #[wasm_bindgen(skip)]
-    pub public_key: Box<[u8]>,
-    #[wasm_bindgen(skip)]
-    pub private_key: Box<[u8]>,
-}
-
-impl serde::Serialize for KeyPairWrapper {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let public_key_base64 = base64::engine::general_purpose::STANDARD.encode(&self.public_key);
-        let private_key_base64 =
-            base64::engine::general_purpose::STANDARD.encode(&self.private_key);
-        let mut map = serializer.serialize_map(Some(2))?;
-        map.serialize_entry("public_key", &public_key_base64)?;
-        map.serialize_entry("private_key", &private_key_base64)?;
-        map.end()
-    }
-}
-
-impl<'de> serde::Deserialize<'de> for KeyPairWrapper {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(Deserialize)]
-        struct KeyPairData {
-            public_key: String,
-            private_key: String,
+    key_pair: KeyPair,
+}
+
+impl KeyPairWrapper {
+    fn generate_for(algorithm: Algorithm) -> Self {
+        Self {
+            key_pair: crypto::generate_key_pair_for(algorithm),
         }
+    }
 
-        let data = KeyPairData::deserialize(deserializer)?;
-        let public_key = base64::engine::general_purpose::STANDARD
-            .decode(&data.public_key)
-            .map_err(|e| {
-                serde::de::Error::custom(format!("Invalid base64 in public_key: {}", e))
-            })?;
-        let private_key = base64::engine::general_purpose::STANDARD
-            .decode(&data.private_key)
-            .map_err(|e| {
-                serde::de::Error::custom(format!("Invalid base64 in private_key: {}", e))
-            })?;
+    fn sign_bytes(&self, message: &[u8]) -> Result<Signature, Error> {
+        let signature = self.key_pair.sign(message)?;
+        Ok(Signature { signature })
+    }
 
-        Ok(Self {
-            public_key: public_key.into_boxed_slice(),
-            private_key: private_key.into_boxed_slice(),
-        })
+    fn parse(json: &str) -> Result<Self, Error> {
+        let key_pair = crypto::key_pair_from_json(json).map_err(|e| {
+            if e.is_data() {
+                Error::InvalidKey(e.to_string())
+            } else {
+                Error::Encoding(format!("invalid JSON: {}", e))
+            }
+        })?;
+        Ok(Self { key_pair })
     }
 }
 
 #[wasm_bindgen]
 impl KeyPairWrapper {
+    /// Generate a key pair, HMAC-SHA3-256 unless `algorithm` is given
     #[wasm_bindgen(constructor)]
-    pub fn new() -> Self {
-        let key_pair = crypto::generate_key_pair();
-        Self {
-            public_key: key_pair.public_key.into_boxed_slice(),
-            private_key: key_pair.private_key.into_boxed_slice(),
-        }
+    pub fn new(algorithm: Option<String>) -> Result<KeyPairWrapper, Error> {
+        Ok(Self::generate_for(algorithm_from_tag(algorithm)?))
     }
 
-    #[wasm_bindgen(getter)]
-    pub fn public_key(&self) -> Uint8Array {
-        Uint8Array::from(&self.public_key[..])
+    pub fn generate(algorithm: Option<String>) -> Result<KeyPairWrapper, Error> {
+        Self::new(algorithm)
     }
 
     #[wasm_bindgen(getter)]
-    pub fn private_key(&self) -> Uint8Array {
-        Uint8Array::from(&self.private_key[..])
+    pub fn algorithm(&self) -> String {
+        self.key_pair.algorithm.tag().to_string()
     }
 
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> Vec<u8> {
+        self.key_pair.public_key.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = privateKey)]
+    pub fn private_key(&self) -> Vec<u8> {
+        self.key_pair.private_key.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = keyId)]
+    pub fn key_id(&self) -> String {
+        self.key_pair.key_id().to_string()
+    }
+
+    /// Sign `message` with this key pair's algorithm
+    pub fn sign(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array | string")] message: JsValue,
+    ) -> Result<Signature, Error> {
+        self.sign_bytes(&message_bytes(&message)?)
+    }
+
+    /// Verify a signature produced by `sign`
+    pub fn verify(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array | string")] message: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array | Signature")] signature: JsValue,
+    ) -> Result<bool, Error> {
+        let message = message_bytes(&message)?;
+        Ok(self
+            .key_pair
+            .verify(&message, &signature_bytes(&signature)?))
+    }
+
+    /// Read a key pair written by `toJson` or any other binding
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<KeyPairWrapper, Error> {
+        Self::parse(json)
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
     pub fn to_json(&self) -> String {
-        serde_json::to_string(&self).unwrap()
+        crypto::key_pair_to_json(&self.key_pair)
     }
 }
 
-/// Quantum-safe signature
+/// Public half of an asymmetric key pair, decoded once for verifying many signatures
 #[wasm_bindgen]
-#[derive(Debug, Clone)]
-pub struct Signature {
-    #[wasm_bindgen(skip)]
-    pub signature: Box<[u8]>,
-}
-
-impl serde::Serialize for Signature {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: serde::Serializer,
-    {
-        let signature_base64 = base64::engine::general_purpose::STANDARD.encode(&self.signature);
-        let mut map = serializer.serialize_map(Some(1))?;
-        map.serialize_entry("signature", &signature_base64)?;
-        map.end()
-    }
-}
-
-impl<'de> serde::Deserialize<'de> for Signature {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        #[derive(Deserialize)]
-        struct SignatureData {
-            signature: String,
-        }
-
-        let data = SignatureData::deserialize(deserializer)?;
-        let signature = base64::engine::general_purpose::STANDARD
-            .decode(&data.signature)
-            .map_err(|e| serde::de::Error::custom(format!("Invalid base64 in signature: {}", e)))?;
+#[derive(Debug)]
+pub struct PublicKey {
+    public_key: Vec<u8>,
+    verifier: PublicKeyVerifier,
+}
 
+impl PublicKey {
+    fn decode(algorithm: Algorithm, public_key: &[u8]) -> Result<Self, Error> {
+        if algorithm == Algorithm::HmacSha3_256 {
+            return Err(Error::InvalidAlgorithm(
+                "hmac-sha3-256 cannot verify without the private key".into(),
+            ));
+        }
         Ok(Self {
-            signature: signature.into_boxed_slice(),
+            public_key: public_key.to_vec(),
+            verifier: PublicKeyVerifier::new(algorithm, public_key)?,
         })
     }
+
+    fn from_encoded(encoded: &[u8]) -> Result<Self, Error> {
+        let (algorithm, public_key) = crypto::decode_public_key(encoded)
+            .ok_or_else(|| Error::Encoding("malformed public key encoding".into()))?;
+        Self::decode(algorithm, public_key)
+    }
+}
+
+#[wasm_bindgen]
+impl PublicKey {
+    /// Decode a public key written by `toBytes`
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(encoded: &[u8]) -> Result<PublicKey, Error> {
+        Self::from_encoded(encoded)
+    }
+
+    #[wasm_bindgen(js_name = fromKeyPair)]
+    pub fn from_key_pair(key_pair: &KeyPairWrapper) -> Result<PublicKey, Error> {
+        Self::decode(key_pair.key_pair.algorithm, &key_pair.key_pair.public_key)
+    }
+
+    /// Algorithm tag followed by the raw key, as `encode_public_key` writes it
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        crypto::encode_public_key(self.verifier.algorithm(), &self.public_key)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn algorithm(&self) -> String {
+        self.verifier.algorithm().tag().to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = keyId)]
+    pub fn key_id(&self) -> String {
+        crypto::key_id(self.verifier.algorithm(), &self.public_key).to_string()
+    }
+
+    pub fn verify(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array | string")] message: JsValue,
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array | Signature")] signature: JsValue,
+    ) -> Result<bool, Error> {
+        let message = message_bytes(&message)?;
+        Ok(self
+            .verifier
+            .verify(&message, &signature_bytes(&signature)?))
+    }
+}
+
+/// Quantum-safe signature
+#[wasm_bindgen]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    signature: Vec<u8>,
 }
 
 #[wasm_bindgen]
@@ -138,103 +293,146 @@ impl Signature {
     #[wasm_bindgen(constructor)]
     pub fn new(signature: &[u8]) -> Self {
         Self {
-            signature: signature.to_vec().into_boxed_slice(),
+            signature: signature.to_vec(),
         }
     }
 
     #[wasm_bindgen(getter)]
-    pub fn signature(&self) -> Uint8Array {
-        Uint8Array::from(&self.signature[..])
+    pub fn signature(&self) -> Vec<u8> {
+        self.signature.clone()
     }
 
-    pub fn to_json(&self) -> String {
-        serde_json::to_string(&self).unwrap()
+    #[wasm_bindgen(getter)]
+    pub fn length(&self) -> usize {
+        self.signature.len()
     }
-}
 
-/// Sign a message using quantum-safe signature
-#[wasm_bindgen]
-pub fn sign_message(message: &[u8], private_key: &[u8]) -> Signature {
-    let signature = crypto::sign_message(message, private_key);
-    Signature::new(&signature)
-}
+    pub fn equals(&self, other: &Signature) -> bool {
+        self == other
+    }
 
-/// Verify a signature
-#[wasm_bindgen]
-pub fn verify_signature(message: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
-    crypto::verify_signature(message, signature, public_key)
+    #[wasm_bindgen(js_name = toBase64)]
+    pub fn to_base64(&self) -> String {
+        crypto::signature_to_base64(&self.signature)
+    }
+
+    #[wasm_bindgen(js_name = fromBase64)]
+    pub fn from_base64(text: &str) -> Result<Signature, Error> {
+        let signature = crypto::signature_from_base64(text)
+            .map_err(|e| Error::Encoding(format!("invalid base64: {}", e)))?;
+        Ok(Self { signature })
+    }
 }
 
-/// Convert Rust Vec<u8> to JavaScript Uint8Array
-#[wasm_bindgen]
-pub fn vec_to_uint8array(vec: Vec<u8>) -> js_sys::Uint8Array {
-    let array = js_sys::Uint8Array::from(vec.as_slice());
-    std::mem::forget(vec); // Prevent double-free
-    array
+/// Sign a message with an HMAC-SHA3-256 private key
+#[wasm_bindgen(js_name = signMessage)]
+pub fn sign_message(
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array | string")] message: JsValue,
+    private_key: &[u8],
+) -> Result<Signature, Error> {
+    let signature = crypto::sign_message(&message_bytes(&message)?, private_key);
+    Ok(Signature { signature })
 }
 
-/// Convert JavaScript Uint8Array to Rust Vec<u8>
-#[wasm_bindgen]
-pub fn uint8array_to_vec(array: js_sys::Uint8Array) -> Vec<u8> {
-    let vec = array.to_vec();
-    std::mem::forget(array); // Prevent double-free
-    vec
+/// Verify an HMAC-SHA3-256 signature; HMAC needs the private key
+#[wasm_bindgen(js_name = verifySignature)]
+pub fn verify_signature(
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array | string")] message: JsValue,
+    #[wasm_bindgen(unchecked_param_type = "Uint8Array | Signature")] signature: JsValue,
+    private_key: &[u8],
+) -> Result<bool, Error> {
+    let message = message_bytes(&message)?;
+    Ok(crypto::verify_signature(
+        &message,
+        &signature_bytes(&signature)?,
+        private_key,
+    ))
 }
 
 /// Convert signature to base64 string
-#[wasm_bindgen]
+#[wasm_bindgen(js_name = signatureToBase64)]
 pub fn signature_to_base64(signature: &[u8]) -> String {
-    let mut buffer = String::new();
-    general_purpose::STANDARD.encode_string(signature, &mut buffer);
-    buffer
+    crypto::signature_to_base64(signature)
 }
 
 /// Convert base64 string to signature
-#[wasm_bindgen]
-pub fn signature_from_base64(base64_str: &str) -> Result<Vec<u8>, JsValue> {
-    let mut buffer = Vec::new();
-    general_purpose::STANDARD
-        .decode_vec(base64_str, &mut buffer)
-        .map_err(|e| JsValue::from_str(&format!("Invalid base64: {}", e)))
-        .map(|_| buffer)
+#[wasm_bindgen(js_name = signatureFromBase64)]
+pub fn signature_from_base64(text: &str) -> Result<Vec<u8>, Error> {
+    Signature::from_base64(text).map(|s| s.signature)
 }
 
 #[cfg(test)]
 mod tests {
-    #[cfg(target_arch = "wasm32")]
+    use super::*;
+
     #[test]
-    fn test_key_pair() {
-        let key_pair = KeyPairWrapper::new();
-        assert_eq!(key_pair.public_key().length(), 32);
-        assert_eq!(key_pair.private_key().length(), 32);
+    fn test_key_pairs() {
+        // The small SLH-DSA sets take minutes to sign in debug builds
+        let fast = |a: &Algorithm| !a.tag().starts_with("slh-dsa") || a.tag().ends_with('f');
+        for algorithm in ALGORITHMS.into_iter().filter(fast) {
+            let key_pair = KeyPairWrapper::generate_for(algorithm);
+            let signature = key_pair.sign_bytes(b"Test message").unwrap();
+            assert!(key_pair
+                .key_pair
+                .verify(b"Test message", &signature.signature));
+
+            let parsed = KeyPairWrapper::parse(&key_pair.to_json()).unwrap();
+            assert_eq!(parsed.algorithm(), algorithm.tag());
+            assert_eq!(parsed.public_key(), key_pair.public_key());
+            assert_eq!(parsed.key_id(), key_pair.key_id());
+        }
+        let algorithms = algorithms();
+        assert_eq!(algorithms.len(), 14);
+        assert_eq!(
+            algorithms[..3],
+            ["hmac-sha3-256", "falcon-512", "falcon-1024"]
+        );
+        assert!(algorithms.contains(&"ml-dsa-65-ed25519".to_string()));
     }
 
-    #[cfg(target_arch = "wasm32")]
     #[test]
-    fn test_signature() {
-        let key_pair = KeyPairWrapper::new();
-        let message = b"Test message";
-        let private_key = uint8array_to_vec(key_pair.private_key());
-        let signature = sign_message(message, &private_key);
-        let signature_array = signature.signature();
-        let signature_slice = uint8array_to_vec(signature_array);
-        let public_key = uint8array_to_vec(key_pair.public_key());
-        assert!(verify_signature(message, &signature_slice, &public_key));
-    }
-
-    #[cfg(target_arch = "wasm32")]
+    fn test_public_key() {
+        let key_pair = KeyPairWrapper::generate_for(Algorithm::Falcon512);
+        let signature = key_pair.sign_bytes(b"m").unwrap();
+        let public_key = PublicKey::from_key_pair(&key_pair).unwrap();
+        let decoded = PublicKey::from_encoded(&public_key.to_bytes()).unwrap();
+        assert_eq!(decoded.algorithm(), "falcon-512");
+        assert_eq!(decoded.key_id(), key_pair.key_id());
+        assert!(decoded.verifier.verify(b"m", &signature.signature));
+
+        let copy = Signature::from_base64(&signature.to_base64()).unwrap();
+        assert!(copy.equals(&signature));
+    }
+
     #[test]
-    fn test_serialization() {
-        let key_pair = KeyPairWrapper::new();
-        let json = key_pair.to_json();
-        let deserialized: KeyPairWrapper = serde_json::from_str(&json).unwrap();
+    fn test_errors() {
+        assert_eq!(
+            algorithm_from_tag(Some("rsa-2048".into()))
+                .unwrap_err()
+                .name(),
+            "InvalidAlgorithmError"
+        );
+        assert_eq!(algorithm_from_tag(None), Ok(Algorithm::HmacSha3_256));
+        assert_eq!(
+            KeyPairWrapper::parse("not json").unwrap_err().name(),
+            "EncodingError"
+        );
+        assert_eq!(
+            KeyPairWrapper::parse("{}").unwrap_err().name(),
+            "InvalidKeyError"
+        );
+        assert_eq!(
+            PublicKey::from_encoded(b"").unwrap_err().name(),
+            "EncodingError"
+        );
+        let hmac = KeyPairWrapper::generate_for(Algorithm::HmacSha3_256);
         assert_eq!(
-            key_pair.public_key().to_vec(),
-            deserialized.public_key().to_vec()
+            PublicKey::from_key_pair(&hmac).unwrap_err().name(),
+            "InvalidAlgorithmError"
         );
         assert_eq!(
-            key_pair.private_key().to_vec(),
-            deserialized.private_key().to_vec()
+            Signature::from_base64("!").unwrap_err().name(),
+            "EncodingError"
         );
     }
 }
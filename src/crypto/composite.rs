@@ -0,0 +1,243 @@
+//! Composite ML-DSA + Ed25519 signatures
+//!
+//! Follows draft-ietf-lamps-pq-composite-sigs: both components sign
+//! M' = Prefix || Label || len(ctx) || ctx || SHA-512(M), ML-DSA with the
+//! label as its context string, and a signature is valid only if both
+//! components are. Keys and signatures concatenate the components' encodings,
+//! ML-DSA first; the private key is the ML-DSA seed followed by the Ed25519
+//! seed. The draft is not yet an RFC, so its OIDs and labels may still change.
+
+#[cfg(feature = "alloc")]
+use super::lib::CryptoError;
+use super::ml_dsa;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use ed25519_dalek::{Signature, VerifyingKey, PUBLIC_KEY_LENGTH};
+#[cfg(feature = "alloc")]
+use ed25519_dalek::{Signer, SigningKey, SECRET_KEY_LENGTH};
+#[cfg(feature = "alloc")]
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+#[cfg(feature = "alloc")]
+use zeroize::Zeroize;
+
+/// Domain separator shared by every composite signature algorithm
+const PREFIX: &[u8] = b"CompositeAlgorithmSignatures2025";
+/// Longest M' over the labels below, with an empty context
+const MAX_MESSAGE_LEN: usize = 128;
+const ED25519_SIGNATURE_LEN: usize = 64;
+
+/// Composite parameter set
+#[derive(Debug)]
+pub(crate) struct Params {
+    ml_dsa: &'static ml_dsa::Params,
+    label: &'static [u8],
+}
+
+pub(crate) const ML_DSA_44_ED25519: Params = Params {
+    ml_dsa: &ml_dsa::ML_DSA_44,
+    label: b"COMPSIG-MLDSA44-Ed25519-SHA512",
+};
+
+pub(crate) const ML_DSA_65_ED25519: Params = Params {
+    ml_dsa: &ml_dsa::ML_DSA_65,
+    label: b"COMPSIG-MLDSA65-Ed25519-SHA512",
+};
+
+impl Params {
+    pub(crate) const fn public_key_len(&self) -> usize {
+        self.ml_dsa.public_key_len() + PUBLIC_KEY_LENGTH
+    }
+
+    pub(crate) const fn signature_len(&self) -> usize {
+        self.ml_dsa.signature_len() + ED25519_SIGNATURE_LEN
+    }
+
+    /// M' for `message` with an empty context
+    fn message_representative(&self, message: &[u8]) -> ([u8; MAX_MESSAGE_LEN], usize) {
+        let mut representative = [0u8; MAX_MESSAGE_LEN];
+        let mut len = 0;
+        for part in [PREFIX, self.label, &[0], &Sha512::digest(message)] {
+            representative[len..len + part.len()].copy_from_slice(part);
+            len += part.len();
+        }
+        (representative, len)
+    }
+}
+
+/// Split a private key into the ML-DSA seed and the Ed25519 signing key
+#[cfg(feature = "alloc")]
+fn split_private_key(private_key: &[u8]) -> Result<(&[u8], SigningKey), CryptoError> {
+    if private_key.len() != ml_dsa::SEED_LEN + SECRET_KEY_LENGTH {
+        return Err(CryptoError::InvalidPrivateKey);
+    }
+    let (seed, ed25519) = private_key.split_at(ml_dsa::SEED_LEN);
+    let mut ed25519_seed = [0u8; SECRET_KEY_LENGTH];
+    ed25519_seed.copy_from_slice(ed25519);
+    let signing_key = SigningKey::from_bytes(&ed25519_seed);
+    ed25519_seed.zeroize();
+    Ok((seed, signing_key))
+}
+
+/// Generate a `(public_key, private_key)` pair
+#[cfg(feature = "alloc")]
+pub(crate) fn generate<R: CryptoRng + RngCore>(params: &Params, rng: &mut R) -> (Vec<u8>, Vec<u8>) {
+    let (mut public_key, mut private_key) = ml_dsa::generate(params.ml_dsa, rng);
+    private_key.resize(ml_dsa::SEED_LEN + SECRET_KEY_LENGTH, 0);
+    rng.fill_bytes(&mut private_key[ml_dsa::SEED_LEN..]);
+    let (_, signing_key) = split_private_key(&private_key).expect("private key length");
+    public_key.extend_from_slice(signing_key.verifying_key().as_bytes());
+    (public_key, private_key)
+}
+
+/// Sign `message` with both components
+#[cfg(feature = "alloc")]
+pub(crate) fn sign<R: CryptoRng + RngCore>(
+    params: &Params,
+    private_key: &[u8],
+    message: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>, CryptoError> {
+    let (seed, signing_key) = split_private_key(private_key)?;
+    let (representative, len) = params.message_representative(message);
+    let representative = &representative[..len];
+    let mut signature = ml_dsa::sign(params.ml_dsa, seed, params.label, representative, rng)?;
+    signature.extend_from_slice(&signing_key.sign(representative).to_bytes());
+    Ok(signature)
+}
+
+/// Public key decoded once for repeated verification
+#[derive(Debug, Clone)]
+pub(crate) struct Verifier {
+    params: &'static Params,
+    ml_dsa: ml_dsa::Verifier,
+    ed25519: VerifyingKey,
+}
+
+impl Verifier {
+    pub(crate) fn new(params: &'static Params, public_key: &[u8]) -> Option<Self> {
+        if public_key.len() != params.public_key_len() {
+            return None;
+        }
+        let (ml_dsa_key, ed25519_key) = public_key.split_at(params.ml_dsa.public_key_len());
+        Some(Verifier {
+            params,
+            ml_dsa: ml_dsa::Verifier::new(params.ml_dsa, ml_dsa_key)?,
+            ed25519: VerifyingKey::from_bytes(ed25519_key.try_into().ok()?).ok()?,
+        })
+    }
+
+    /// Valid only if both component signatures are
+    pub(crate) fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        if signature.len() != self.params.signature_len() {
+            return false;
+        }
+        let (ml_dsa_signature, ed25519_signature) =
+            signature.split_at(self.params.ml_dsa.signature_len());
+        let Ok(ed25519_signature) = Signature::from_slice(ed25519_signature) else {
+            return false;
+        };
+        let (representative, len) = self.params.message_representative(message);
+        let representative = &representative[..len];
+        self.ml_dsa
+            .verify_with_context(self.params.label, representative, ml_dsa_signature)
+            && self
+                .ed25519
+                .verify_strict(representative, &ed25519_signature)
+                .is_ok()
+    }
+}
+
+/// Check that both halves of `private_key` match `public_key`
+#[cfg(feature = "alloc")]
+pub(crate) fn matches(params: &Params, public_key: &[u8], private_key: &[u8]) -> bool {
+    let Ok((seed, signing_key)) = split_private_key(private_key) else {
+        return false;
+    };
+    if public_key.len() != params.public_key_len() {
+        return false;
+    }
+    let (ml_dsa_key, ed25519_key) = public_key.split_at(params.ml_dsa.public_key_len());
+    ml_dsa::matches(params.ml_dsa, ml_dsa_key, seed)
+        && signing_key.verifying_key().as_bytes() == ed25519_key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{verify_with_public_key as verify, Algorithm};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SETS: [(Algorithm, &Params); 2] = [
+        (Algorithm::MlDsa44Ed25519, &ML_DSA_44_ED25519),
+        (Algorithm::MlDsa65Ed25519, &ML_DSA_65_ED25519),
+    ];
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Signatures assembled outside the crate from OpenSSL's ML-DSA and
+    /// Python's Ed25519
+    #[test]
+    fn test_independent_vectors() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let vectors: serde_json::Value =
+            serde_json::from_str(include_str!("../../tests/vectors/composite.json")).unwrap();
+        for vector in vectors["vectors"].as_array().unwrap() {
+            let tag = vector["algorithm"].as_str().unwrap();
+            let (algorithm, params) = SETS.into_iter().find(|(a, _)| a.tag() == tag).unwrap();
+            let private_key = hex(vector["private_key"].as_str().unwrap());
+            let public_key = hex(vector["public_key"].as_str().unwrap());
+            let message = hex(vector["message"].as_str().unwrap());
+            let expected = hex(vector["signature"].as_str().unwrap());
+            assert!(matches(params, &public_key, &private_key));
+            assert!(verify(algorithm, &public_key, &message, &expected));
+            assert!(!verify(algorithm, &public_key, b"other", &expected));
+
+            // ML-DSA is hedged, Ed25519 deterministic
+            let signature = sign(params, &private_key, &message, &mut rng).unwrap();
+            let ed25519 = params.ml_dsa.signature_len();
+            assert_eq!(signature[ed25519..], expected[ed25519..]);
+            assert!(verify(algorithm, &public_key, &message, &signature));
+        }
+    }
+
+    #[test]
+    fn test_both_components_required() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let (algorithm, params) = SETS[0];
+        let (public_key, private_key) = generate(params, &mut rng);
+        assert_eq!(public_key.len(), 1312 + 32);
+        let signature = sign(params, &private_key, b"m", &mut rng).unwrap();
+        assert_eq!(signature.len(), 2420 + 64);
+        assert!(verify(algorithm, &public_key, b"m", &signature));
+
+        let ml_dsa_len = params.ml_dsa.signature_len();
+        for offset in [10, ml_dsa_len + 10] {
+            let mut tampered = signature.clone();
+            tampered[offset] ^= 1;
+            assert!(!verify(algorithm, &public_key, b"m", &tampered));
+        }
+        // A bare ML-DSA signature over the same message is not accepted
+        let bare = ml_dsa::sign(params.ml_dsa, &private_key[..32], &[], b"m", &mut rng).unwrap();
+        let mut stripped = bare;
+        stripped.extend_from_slice(&signature[ml_dsa_len..]);
+        assert!(!verify(algorithm, &public_key, b"m", &stripped));
+        assert!(!verify(
+            Algorithm::MlDsa65Ed25519,
+            &public_key,
+            b"m",
+            &signature
+        ));
+
+        let (_, other) = generate(params, &mut rng);
+        let mut mixed = private_key[..32].to_vec();
+        mixed.extend_from_slice(&other[32..]);
+        assert!(!matches(params, &public_key, &mixed));
+    }
+}
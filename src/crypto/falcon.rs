@@ -0,0 +1,167 @@
+//! Falcon (FN-DSA) signatures
+//!
+//! Backed by the `fn-dsa` crate. Floating-point steps follow strict IEEE-754
+//! rounding, so the same key and random input give the same signature on
+//! every platform. Division and square root, which are variable-time on
+//! common CPUs, use the crate's constant-time integer emulation; targets
+//! without a strict hardware FPU use emulation throughout.
+
+use super::lib::{Algorithm, CryptoError};
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use fn_dsa::{sign_key_size, signature_size};
+use fn_dsa::{
+    vrfy_key_size, KeyPairGenerator, KeyPairGeneratorStandard, SigningKey, SigningKeyStandard,
+    VerifyingKey, VerifyingKeyStandard, DOMAIN_NONE, FN_DSA_LOGN_1024, FN_DSA_LOGN_512,
+    HASH_ID_RAW,
+};
+use rand::{CryptoRng, RngCore};
+
+fn logn(algorithm: Algorithm) -> Option<u32> {
+    match algorithm {
+        Algorithm::Falcon512 => Some(FN_DSA_LOGN_512),
+        Algorithm::Falcon1024 => Some(FN_DSA_LOGN_1024),
+        _ => None,
+    }
+}
+
+/// Generate a `(public_key, private_key)` pair
+#[cfg(feature = "alloc")]
+pub(crate) fn generate<R: CryptoRng + RngCore>(
+    algorithm: Algorithm,
+    rng: &mut R,
+) -> (Vec<u8>, Vec<u8>) {
+    let logn = logn(algorithm).expect("not a Falcon algorithm");
+    let mut private_key = vec![0u8; sign_key_size(logn)];
+    let mut public_key = vec![0u8; vrfy_key_size(logn)];
+    generate_into(algorithm, rng, &mut public_key, &mut private_key);
+    (public_key, private_key)
+}
+
+/// Generate a key pair into buffers sized for `algorithm`
+pub(crate) fn generate_into<R: CryptoRng + RngCore>(
+    algorithm: Algorithm,
+    rng: &mut R,
+    public_key: &mut [u8],
+    private_key: &mut [u8],
+) {
+    let logn = logn(algorithm).expect("not a Falcon algorithm");
+    KeyPairGeneratorStandard::default().keygen(logn, rng, private_key, public_key);
+}
+
+/// Sign `message` with an encoded private key of `algorithm`
+#[cfg(feature = "alloc")]
+pub(crate) fn sign<R: CryptoRng + RngCore>(
+    algorithm: Algorithm,
+    private_key: &[u8],
+    message: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>, CryptoError> {
+    let logn = logn(algorithm).ok_or(CryptoError::InvalidPrivateKey)?;
+    let mut signature = vec![0u8; signature_size(logn)];
+    sign_into(algorithm, private_key, message, rng, &mut signature)?;
+    Ok(signature)
+}
+
+/// Sign `message` into a buffer of exactly the signature size
+pub(crate) fn sign_into<R: CryptoRng + RngCore>(
+    algorithm: Algorithm,
+    private_key: &[u8],
+    message: &[u8],
+    rng: &mut R,
+    signature: &mut [u8],
+) -> Result<(), CryptoError> {
+    let mut key = SigningKeyStandard::decode(private_key)
+        .filter(|key| Some(key.get_logn()) == logn(algorithm))
+        .ok_or(CryptoError::InvalidPrivateKey)?;
+    key.sign(rng, &DOMAIN_NONE, &HASH_ID_RAW, message, signature)
+        .ok_or(CryptoError::InvalidPrivateKey)
+}
+
+/// Public key decoded once for repeated verification
+#[derive(Debug, Clone)]
+pub(crate) struct Verifier(VerifyingKeyStandard);
+
+impl Verifier {
+    /// Decode an encoded public key of `algorithm`
+    pub(crate) fn new(algorithm: Algorithm, public_key: &[u8]) -> Option<Self> {
+        if logn(algorithm).map(vrfy_key_size) != Some(public_key.len()) {
+            return None;
+        }
+        VerifyingKeyStandard::decode(public_key).map(Verifier)
+    }
+
+    pub(crate) fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        self.0
+            .verify(signature, &DOMAIN_NONE, &HASH_ID_RAW, message)
+    }
+}
+
+/// Verify `signature` against an encoded public key of `algorithm`
+pub(crate) fn verify(
+    algorithm: Algorithm,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    Verifier::new(algorithm, public_key).is_some_and(|key| key.verify(message, signature))
+}
+
+/// Check that `private_key` is valid and belongs to `public_key`
+#[cfg(feature = "alloc")]
+pub(crate) fn matches(algorithm: Algorithm, public_key: &[u8], private_key: &[u8]) -> bool {
+    match SigningKeyStandard::decode(private_key) {
+        Some(key) if Some(key.get_logn()) == logn(algorithm) => {
+            let mut derived = vec![0u8; vrfy_key_size(key.get_logn())];
+            key.to_verifying_key(&mut derived);
+            derived == public_key
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use sha3::{Digest, Sha3_256};
+
+    #[test]
+    fn test_deterministic_across_platforms() {
+        // Recorded on x86_64; any platform must reproduce it bit for bit
+        let (public_key, private_key) =
+            generate(Algorithm::Falcon512, &mut StdRng::seed_from_u64(1));
+        let signature = sign(
+            Algorithm::Falcon512,
+            &private_key,
+            b"authorise 42.00 EUR",
+            &mut StdRng::seed_from_u64(2),
+        )
+        .unwrap();
+        let digest = Sha3_256::new()
+            .chain_update(&private_key)
+            .chain_update(&public_key)
+            .chain_update(&signature)
+            .finalize();
+        let hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(
+            hex,
+            "ca5b408f0c26fbf79a296ed671d3b444cb62c89e2b6857127ae1a2d5e7feace0"
+        );
+    }
+
+    #[test]
+    fn test_sizes_and_mismatch() {
+        let mut rng = rand::thread_rng();
+        let (public_key, private_key) = generate(Algorithm::Falcon1024, &mut rng);
+        assert_eq!(public_key.len(), 1793);
+        let signature = sign(Algorithm::Falcon1024, &private_key, b"m", &mut rng).unwrap();
+        assert_eq!(signature.len(), 1280);
+        assert!(verify(Algorithm::Falcon1024, &public_key, b"m", &signature));
+        assert!(!verify(Algorithm::Falcon512, &public_key, b"m", &signature));
+        assert!(matches(Algorithm::Falcon1024, &public_key, &private_key));
+        assert!(sign(Algorithm::Falcon512, &private_key, b"m", &mut rng).is_err());
+    }
+}
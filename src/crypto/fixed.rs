@@ -0,0 +1,526 @@
+//! Fixed-size keys, signatures and ciphertexts
+//!
+//! Every parameter set fixes the size of its keys, signatures and
+//! ciphertexts, so these types are plain byte arrays that can live on the
+//! stack or in static memory. Secret keys are neither `Copy` nor `Clone` and
+//! are zeroized on drop. Signing, verification and encapsulation work on
+//! them directly without `Vec`, though the image still needs a global
+//! allocator as described in the crate documentation.
+
+use super::falcon;
+use super::lib::{hash, hmac, verify_signature, Algorithm, CryptoError};
+use core::fmt;
+use core::marker::PhantomData;
+use fn_dsa::{sign_key_size, signature_size, vrfy_key_size, FN_DSA_LOGN_1024, FN_DSA_LOGN_512};
+use hmac::Mac;
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{EncodedSizeUser, KemCore};
+use rand::{CryptoRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+/// Length of an ML-KEM shared secret in bytes
+pub const SHARED_SECRET_LEN: usize = 32;
+
+/// Byte array backing the types of a parameter set
+pub trait ByteArray: AsRef<[u8]> + AsMut<[u8]> + Copy + Eq + Zeroize {
+    const LEN: usize;
+
+    fn zeroed() -> Self;
+}
+
+impl<const N: usize> ByteArray for [u8; N] {
+    const LEN: usize = N;
+
+    fn zeroed() -> Self {
+        [0; N]
+    }
+}
+
+/// Parameter set with fixed key sizes
+pub trait ParameterSet {
+    /// Stable name, equal to `Algorithm::tag` for signature schemes
+    const NAME: &'static str;
+
+    type PublicKeyBytes: ByteArray;
+    type SecretKeyBytes: ByteArray;
+}
+
+/// Signature scheme working on fixed-size types
+pub trait SignatureScheme: ParameterSet + Sized {
+    const ALGORITHM: Algorithm;
+
+    type SignatureBytes: ByteArray;
+
+    fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> (PublicKey<Self>, SecretKey<Self>);
+
+    fn sign<R: CryptoRng + RngCore>(
+        secret_key: &SecretKey<Self>,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Signature<Self>, CryptoError>;
+}
+
+/// Signature scheme whose signatures verify with the public key alone
+pub trait PublicKeyScheme: SignatureScheme {
+    /// Same result as `verify_with_public_key` on the encoded key
+    fn verify(public_key: &PublicKey<Self>, message: &[u8], signature: &Signature<Self>) -> bool;
+}
+
+/// Key encapsulation mechanism working on fixed-size types
+pub trait Kem: ParameterSet + Sized {
+    type CiphertextBytes: ByteArray;
+
+    fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> (PublicKey<Self>, SecretKey<Self>);
+
+    fn encapsulate<R: CryptoRng + RngCore>(
+        public_key: &PublicKey<Self>,
+        rng: &mut R,
+    ) -> (Ciphertext<Self>, [u8; SHARED_SECRET_LEN]);
+
+    /// Never fails; a forged ciphertext yields an unrelated secret
+    fn decapsulate(
+        secret_key: &SecretKey<Self>,
+        ciphertext: &Ciphertext<Self>,
+    ) -> [u8; SHARED_SECRET_LEN];
+}
+
+macro_rules! fixed_bytes {
+    ($(#[$doc:meta])* $name:ident, $bound:ident, $bytes:ident, $error:expr) => {
+        $(#[$doc])*
+        pub struct $name<P: $bound>(P::$bytes, PhantomData<P>);
+
+        impl<P: $bound> $name<P> {
+            pub fn new(bytes: P::$bytes) -> Self {
+                $name(bytes, PhantomData)
+            }
+
+            /// Copy from a slice of exactly the parameter set's length
+            pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+                if bytes.len() != P::$bytes::LEN {
+                    return Err($error);
+                }
+                let mut array = P::$bytes::zeroed();
+                array.as_mut().copy_from_slice(bytes);
+                Ok($name::new(array))
+            }
+
+            pub fn as_bytes(&self) -> &[u8] {
+                self.0.as_ref()
+            }
+
+            pub fn as_array(&self) -> &P::$bytes {
+                &self.0
+            }
+        }
+    };
+    ($(#[$doc:meta])* $name:ident, $bound:ident, $bytes:ident, $error:expr, Copy) => {
+        fixed_bytes!($(#[$doc])* $name, $bound, $bytes, $error);
+
+        impl<P: $bound> $name<P> {
+            pub fn into_inner(self) -> P::$bytes {
+                self.0
+            }
+        }
+
+        impl<P: $bound> Clone for $name<P> {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<P: $bound> Copy for $name<P> {}
+    };
+}
+
+fixed_bytes!(
+    /// Public key of parameter set `P`
+    PublicKey,
+    ParameterSet,
+    PublicKeyBytes,
+    CryptoError::InvalidPublicKey,
+    Copy
+);
+fixed_bytes!(
+    /// Secret key of parameter set `P`, zeroized on drop
+    SecretKey,
+    ParameterSet,
+    SecretKeyBytes,
+    CryptoError::InvalidPrivateKey
+);
+fixed_bytes!(
+    /// Signature made with scheme `P`
+    Signature,
+    SignatureScheme,
+    SignatureBytes,
+    CryptoError::InvalidSignature,
+    Copy
+);
+fixed_bytes!(
+    /// Ciphertext encapsulated with mechanism `P`
+    Ciphertext,
+    Kem,
+    CiphertextBytes,
+    CryptoError::InvalidCiphertext,
+    Copy
+);
+
+impl<P: ParameterSet> Zeroize for SecretKey<P> {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl<P: ParameterSet> Drop for SecretKey<P> {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl<P: ParameterSet> ZeroizeOnDrop for SecretKey<P> {}
+
+impl<P: ParameterSet> PartialEq for PublicKey<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<P: ParameterSet> Eq for PublicKey<P> {}
+
+impl<P: SignatureScheme> PartialEq for Signature<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<P: SignatureScheme> Eq for Signature<P> {}
+
+impl<P: Kem> PartialEq for Ciphertext<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<P: Kem> Eq for Ciphertext<P> {}
+
+impl<P: ParameterSet> fmt::Debug for PublicKey<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "PublicKey<{}>({} bytes)",
+            P::NAME,
+            P::PublicKeyBytes::LEN
+        )
+    }
+}
+
+impl<P: ParameterSet> fmt::Debug for SecretKey<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretKey<{}>(..)", P::NAME)
+    }
+}
+
+impl<P: SignatureScheme> fmt::Debug for Signature<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Signature<{}>({} bytes)",
+            P::NAME,
+            P::SignatureBytes::LEN
+        )
+    }
+}
+
+impl<P: Kem> fmt::Debug for Ciphertext<P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Ciphertext<{}>({} bytes)",
+            P::NAME,
+            P::CiphertextBytes::LEN
+        )
+    }
+}
+
+impl<P: SignatureScheme> SecretKey<P> {
+    pub fn sign<R: CryptoRng + RngCore>(
+        &self,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Signature<P>, CryptoError> {
+        P::sign(self, message, rng)
+    }
+}
+
+impl<P: PublicKeyScheme> PublicKey<P> {
+    pub fn verify(&self, message: &[u8], signature: &Signature<P>) -> bool {
+        P::verify(self, message, signature)
+    }
+}
+
+impl<P: Kem> PublicKey<P> {
+    pub fn encapsulate<R: CryptoRng + RngCore>(
+        &self,
+        rng: &mut R,
+    ) -> (Ciphertext<P>, [u8; SHARED_SECRET_LEN]) {
+        P::encapsulate(self, rng)
+    }
+}
+
+impl<P: Kem> SecretKey<P> {
+    pub fn decapsulate(&self, ciphertext: &Ciphertext<P>) -> [u8; SHARED_SECRET_LEN] {
+        P::decapsulate(self, ciphertext)
+    }
+}
+
+/// HMAC-SHA3-256; the public key is the SHA3-256 hash of the secret key
+///
+/// Tags only verify with the secret key, so this is not a `PublicKeyScheme`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HmacSha3_256 {}
+
+impl ParameterSet for HmacSha3_256 {
+    const NAME: &'static str = "hmac-sha3-256";
+
+    type PublicKeyBytes = [u8; 32];
+    type SecretKeyBytes = [u8; 32];
+}
+
+impl SignatureScheme for HmacSha3_256 {
+    const ALGORITHM: Algorithm = Algorithm::HmacSha3_256;
+
+    type SignatureBytes = [u8; 32];
+
+    fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> (PublicKey<Self>, SecretKey<Self>) {
+        let mut secret_key = [0u8; 32];
+        rng.fill_bytes(&mut secret_key);
+        (
+            PublicKey::new(hash(&secret_key)),
+            SecretKey::new(secret_key),
+        )
+    }
+
+    fn sign<R: CryptoRng + RngCore>(
+        secret_key: &SecretKey<Self>,
+        message: &[u8],
+        _: &mut R,
+    ) -> Result<Signature<Self>, CryptoError> {
+        let tag = hmac(message, secret_key.as_bytes()).finalize().into_bytes();
+        Ok(Signature::new(tag.into()))
+    }
+}
+
+impl SecretKey<HmacSha3_256> {
+    /// Verify a tag made with this key
+    pub fn verify(&self, message: &[u8], signature: &Signature<HmacSha3_256>) -> bool {
+        verify_signature(message, signature.as_bytes(), self.as_bytes())
+    }
+}
+
+/// Falcon-512 (FN-DSA)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Falcon512 {}
+
+/// Falcon-1024 (FN-DSA)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Falcon1024 {}
+
+macro_rules! falcon_scheme {
+    ($name:ident, $algorithm:expr, $logn:expr) => {
+        impl ParameterSet for $name {
+            const NAME: &'static str = $algorithm.tag();
+
+            type PublicKeyBytes = [u8; vrfy_key_size($logn)];
+            type SecretKeyBytes = [u8; sign_key_size($logn)];
+        }
+
+        impl SignatureScheme for $name {
+            const ALGORITHM: Algorithm = $algorithm;
+
+            type SignatureBytes = [u8; signature_size($logn)];
+
+            fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> (PublicKey<Self>, SecretKey<Self>) {
+                let mut public_key = [0u8; vrfy_key_size($logn)];
+                let mut secret_key = [0u8; sign_key_size($logn)];
+                falcon::generate_into($algorithm, rng, &mut public_key, &mut secret_key);
+                (PublicKey::new(public_key), SecretKey::new(secret_key))
+            }
+
+            fn sign<R: CryptoRng + RngCore>(
+                secret_key: &SecretKey<Self>,
+                message: &[u8],
+                rng: &mut R,
+            ) -> Result<Signature<Self>, CryptoError> {
+                let mut signature = [0u8; signature_size($logn)];
+                falcon::sign_into(
+                    $algorithm,
+                    secret_key.as_bytes(),
+                    message,
+                    rng,
+                    &mut signature,
+                )?;
+                Ok(Signature::new(signature))
+            }
+        }
+
+        impl PublicKeyScheme for $name {
+            fn verify(
+                public_key: &PublicKey<Self>,
+                message: &[u8],
+                signature: &Signature<Self>,
+            ) -> bool {
+                falcon::verify(
+                    $algorithm,
+                    public_key.as_bytes(),
+                    message,
+                    signature.as_bytes(),
+                )
+            }
+        }
+    };
+}
+
+falcon_scheme!(Falcon512, Algorithm::Falcon512, FN_DSA_LOGN_512);
+falcon_scheme!(Falcon1024, Algorithm::Falcon1024, FN_DSA_LOGN_1024);
+
+/// ML-KEM-768 (FIPS 203)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MlKem768 {}
+
+type DecapsulationKey = <ml_kem::MlKem768 as KemCore>::DecapsulationKey;
+type EncapsulationKey = <ml_kem::MlKem768 as KemCore>::EncapsulationKey;
+
+impl ParameterSet for MlKem768 {
+    const NAME: &'static str = "ml-kem-768";
+
+    type PublicKeyBytes = [u8; 1184];
+    type SecretKeyBytes = [u8; 2400];
+}
+
+impl Kem for MlKem768 {
+    type CiphertextBytes = [u8; 1088];
+
+    fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> (PublicKey<Self>, SecretKey<Self>) {
+        let (dk, ek) = ml_kem::MlKem768::generate(rng);
+        (
+            PublicKey::new(ek.as_bytes().into()),
+            SecretKey::new(dk.as_bytes().into()),
+        )
+    }
+
+    fn encapsulate<R: CryptoRng + RngCore>(
+        public_key: &PublicKey<Self>,
+        rng: &mut R,
+    ) -> (Ciphertext<Self>, [u8; SHARED_SECRET_LEN]) {
+        let ek = EncapsulationKey::from_bytes(public_key.as_array().into());
+        let (ct, secret) = ek.encapsulate(rng).expect("ML-KEM encapsulation");
+        (Ciphertext::new(ct.into()), secret.into())
+    }
+
+    fn decapsulate(
+        secret_key: &SecretKey<Self>,
+        ciphertext: &Ciphertext<Self>,
+    ) -> [u8; SHARED_SECRET_LEN] {
+        let dk = DecapsulationKey::from_bytes(secret_key.as_array().into());
+        dk.decapsulate(ciphertext.as_array().into())
+            .expect("ML-KEM decapsulation")
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, sign_message, verify_with_public_key};
+
+    #[test]
+    fn test_falcon_round_trip() {
+        let mut rng = rand::thread_rng();
+        let (public_key, secret_key) = Falcon512::generate(&mut rng);
+        let signature = secret_key.sign(b"settle batch 7", &mut rng).unwrap();
+        assert_eq!(signature.as_bytes().len(), 666);
+        assert!(public_key.verify(b"settle batch 7", &signature));
+        assert!(!public_key.verify(b"settle batch 8", &signature));
+        assert!(verify_with_public_key(
+            Algorithm::Falcon512,
+            public_key.as_bytes(),
+            b"settle batch 7",
+            signature.as_bytes()
+        ));
+
+        let (public_key, secret_key) = Falcon1024::generate(&mut rng);
+        let signature = secret_key.sign(b"m", &mut rng).unwrap();
+        assert_eq!(public_key.as_bytes().len(), 1793);
+        assert!(public_key.verify(b"m", &signature));
+    }
+
+    #[test]
+    fn test_from_existing_key_pair() {
+        let key_pair = generate_key_pair_for(Algorithm::Falcon1024);
+        let secret_key = SecretKey::<Falcon1024>::from_bytes(&key_pair.private_key).unwrap();
+        let public_key = PublicKey::<Falcon1024>::from_bytes(&key_pair.public_key).unwrap();
+        let signature = secret_key.sign(b"m", &mut rand::thread_rng()).unwrap();
+        assert!(key_pair.verify(b"m", signature.as_bytes()));
+        assert!(public_key.verify(b"m", &signature));
+
+        assert_eq!(
+            SecretKey::<Falcon512>::from_bytes(&key_pair.private_key).unwrap_err(),
+            CryptoError::InvalidPrivateKey
+        );
+        assert_eq!(
+            Signature::<Falcon1024>::from_bytes(&signature.as_bytes()[1..]).unwrap_err(),
+            CryptoError::InvalidSignature
+        );
+        let mut bad = *secret_key.as_array();
+        bad[0] = 0xff;
+        assert_eq!(
+            SecretKey::<Falcon1024>::new(bad)
+                .sign(b"m", &mut rand::thread_rng())
+                .unwrap_err(),
+            CryptoError::InvalidPrivateKey
+        );
+    }
+
+    #[test]
+    fn test_hmac() {
+        let (public_key, secret_key) = HmacSha3_256::generate(&mut rand::thread_rng());
+        let signature = secret_key.sign(b"m", &mut rand::thread_rng()).unwrap();
+        assert_eq!(
+            signature.as_bytes(),
+            sign_message(b"m", secret_key.as_bytes())
+        );
+        assert!(secret_key.verify(b"m", &signature));
+        assert!(!secret_key.verify(b"n", &signature));
+        assert_eq!(public_key.as_bytes(), hash(secret_key.as_bytes()));
+        assert_eq!(format!("{:?}", secret_key), "SecretKey<hmac-sha3-256>(..)");
+    }
+
+    #[test]
+    fn test_secret_key_zeroized_on_drop() {
+        fn zeroized_on_drop<T: ZeroizeOnDrop>(_: &T) {}
+
+        let (_, mut secret_key) = Falcon512::generate(&mut rand::thread_rng());
+        zeroized_on_drop(&secret_key);
+        assert!(secret_key.as_bytes().iter().any(|&b| b != 0));
+        // `Drop` runs the same `zeroize`
+        secret_key.zeroize();
+        assert!(secret_key.as_bytes().iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_ml_kem_768() {
+        let mut rng = rand::thread_rng();
+        let (public_key, secret_key) = MlKem768::generate(&mut rng);
+        let (ciphertext, secret) = public_key.encapsulate(&mut rng);
+        assert_eq!(secret_key.decapsulate(&ciphertext), secret);
+
+        let copy = Ciphertext::<MlKem768>::from_bytes(ciphertext.as_bytes()).unwrap();
+        assert_eq!(copy, ciphertext);
+        let mut forged = ciphertext.into_inner();
+        forged[0] ^= 1;
+        assert_ne!(secret_key.decapsulate(&Ciphertext::new(forged)), secret);
+        assert_eq!(
+            PublicKey::<MlKem768>::from_bytes(&[0; 1183]).unwrap_err(),
+            CryptoError::InvalidPublicKey
+        );
+    }
+}
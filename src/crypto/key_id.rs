@@ -0,0 +1,269 @@
+//! Key identifiers and fingerprints
+
+use super::lib::Algorithm;
+#[cfg(feature = "std")]
+use super::lib::CryptoError;
+#[cfg(feature = "alloc")]
+use super::lib::KeyPair;
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+use core::fmt;
+use core::str::FromStr;
+#[cfg(feature = "alloc")]
+use serde::Deserialize;
+use serde::Serialize;
+use sha3::{Digest, Sha3_256};
+
+/// Length of a key identifier in bytes
+pub const KEY_ID_LEN: usize = 16;
+
+/// Length of a key fingerprint in bytes
+pub const FINGERPRINT_LEN: usize = 32;
+
+/// Stable identifier of a public key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KeyId(pub [u8; KEY_ID_LEN]);
+
+/// SHA3-256 fingerprint of a public key
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fingerprint(pub [u8; FINGERPRINT_LEN]);
+
+/// Error returned when parsing a hex encoded key identifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseKeyIdError;
+
+impl fmt::Display for ParseKeyIdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key id must be {} hex characters", KEY_ID_LEN * 2)
+    }
+}
+
+impl core::error::Error for ParseKeyIdError {}
+
+/// Encode a public key together with its algorithm tag
+///
+/// Layout: `tag_len (1 byte) || tag || public_key`.
+#[cfg(feature = "alloc")]
+pub fn encode_public_key(algorithm: Algorithm, public_key: &[u8]) -> Vec<u8> {
+    let tag = algorithm.tag().as_bytes();
+    let mut encoded = Vec::with_capacity(1 + tag.len() + public_key.len());
+    encoded.push(tag.len() as u8);
+    encoded.extend_from_slice(tag);
+    encoded.extend_from_slice(public_key);
+    encoded
+}
+
+/// Split an `encode_public_key` encoding into its algorithm and key
+pub fn decode_public_key(encoded: &[u8]) -> Option<(Algorithm, &[u8])> {
+    let (&tag_len, rest) = encoded.split_first()?;
+    if rest.len() < tag_len as usize {
+        return None;
+    }
+    let (tag, public_key) = rest.split_at(tag_len as usize);
+    let algorithm = Algorithm::from_tag(core::str::from_utf8(tag).ok()?)?;
+    Some((algorithm, public_key))
+}
+
+/// Compute the fingerprint of an encoded public key
+pub fn fingerprint(algorithm: Algorithm, public_key: &[u8]) -> Fingerprint {
+    tagged_fingerprint(algorithm.tag(), public_key)
+}
+
+/// Fingerprint of a public key of a scheme outside `Algorithm`, such as
+/// ML-KEM or a token-held key, encoded under `tag`
+pub fn tagged_fingerprint(tag: &str, public_key: &[u8]) -> Fingerprint {
+    let tag = tag.as_bytes();
+    let digest = Sha3_256::new()
+        .chain_update([tag.len() as u8])
+        .chain_update(tag)
+        .chain_update(public_key)
+        .finalize();
+    Fingerprint(digest.into())
+}
+
+/// Compute the key identifier of a public key
+pub fn key_id(algorithm: Algorithm, public_key: &[u8]) -> KeyId {
+    fingerprint(algorithm, public_key).key_id()
+}
+
+impl Fingerprint {
+    /// Key identifier derived from this fingerprint
+    pub fn key_id(&self) -> KeyId {
+        let mut bytes = [0u8; KEY_ID_LEN];
+        bytes.copy_from_slice(&self.0[..KEY_ID_LEN]);
+        KeyId(bytes)
+    }
+}
+
+/// Renders as upper-case hex in groups of four, e.g. `1A2B 3C4D ...`
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, chunk) in self.0.chunks(2).enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            for byte in chunk {
+                write!(f, "{:02X}", byte)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for KeyId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeyId {
+    type Err = ParseKeyIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // `from_str_radix` alone would accept a sign, as in "+f"
+        if s.len() != KEY_ID_LEN * 2 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseKeyIdError);
+        }
+        let mut bytes = [0u8; KEY_ID_LEN];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[i * 2..i * 2 + 2], 16).map_err(|_| ParseKeyIdError)?;
+        }
+        Ok(KeyId(bytes))
+    }
+}
+
+impl Serialize for KeyId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'de> Deserialize<'de> for KeyId {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl KeyPair {
+    /// Stable identifier of this key pair
+    pub fn key_id(&self) -> KeyId {
+        key_id(self.algorithm, &self.public_key)
+    }
+
+    /// Fingerprint of this key pair's public key
+    pub fn fingerprint(&self) -> Fingerprint {
+        fingerprint(self.algorithm, &self.public_key)
+    }
+}
+
+/// Sign a message and prefix the signature with the signer's key id
+#[cfg(feature = "std")]
+pub fn sign_message_with_key_id(
+    message: &[u8],
+    key_pair: &KeyPair,
+) -> Result<Vec<u8>, CryptoError> {
+    let mut signature = key_pair.key_id().0.to_vec();
+    signature.extend_from_slice(&key_pair.sign(message)?);
+    Ok(signature)
+}
+
+/// Read the key id embedded in a signature
+pub fn signature_key_id(signature: &[u8]) -> Option<KeyId> {
+    split_key_id(signature).map(|(key_id, _)| key_id)
+}
+
+/// Verify a signature carrying an embedded key id
+///
+/// Fails if the embedded key id does not belong to `key_pair`.
+#[cfg(feature = "alloc")]
+pub fn verify_signature_with_key_id(message: &[u8], signature: &[u8], key_pair: &KeyPair) -> bool {
+    match split_key_id(signature) {
+        Some((key_id, inner)) => key_id == key_pair.key_id() && key_pair.verify(message, inner),
+        None => false,
+    }
+}
+
+fn split_key_id(signature: &[u8]) -> Option<(KeyId, &[u8])> {
+    if signature.len() < KEY_ID_LEN {
+        return None;
+    }
+    let (id, inner) = signature.split_at(KEY_ID_LEN);
+    let mut bytes = [0u8; KEY_ID_LEN];
+    bytes.copy_from_slice(id);
+    Some((KeyId(bytes), inner))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_key_pair;
+
+    #[test]
+    fn test_key_id_is_stable() {
+        let key_pair = generate_key_pair();
+        assert_eq!(key_pair.key_id(), key_pair.key_id());
+        assert_eq!(key_pair.key_id(), key_pair.fingerprint().key_id());
+        assert_ne!(key_pair.key_id(), generate_key_pair().key_id());
+    }
+
+    #[test]
+    fn test_public_key_encoding_round_trip() {
+        let encoded = encode_public_key(Algorithm::Falcon512, &[1, 2, 3]);
+        assert_eq!(
+            decode_public_key(&encoded),
+            Some((Algorithm::Falcon512, &[1u8, 2, 3][..]))
+        );
+        assert_eq!(decode_public_key(&encoded[..5]), None);
+        assert_eq!(decode_public_key(&[]), None);
+        assert_eq!(decode_public_key(b"\x03abc\x01"), None);
+    }
+
+    #[test]
+    fn test_key_id_round_trip() {
+        let key_id = generate_key_pair().key_id();
+        let parsed: KeyId = key_id.to_string().parse().unwrap();
+        assert_eq!(parsed, key_id);
+        assert!("zz".parse::<KeyId>().is_err());
+        let signed = format!("+{}", &key_id.to_string()[1..]);
+        assert!(signed.parse::<KeyId>().is_err());
+        assert!("+f".repeat(KEY_ID_LEN).parse::<KeyId>().is_err());
+
+        let json = serde_json::to_string(&key_id).unwrap();
+        assert_eq!(serde_json::from_str::<KeyId>(&json).unwrap(), key_id);
+    }
+
+    #[test]
+    fn test_fingerprint_rendering() {
+        let rendered = Fingerprint([0xab; FINGERPRINT_LEN]).to_string();
+        assert_eq!(rendered.split(' ').count(), FINGERPRINT_LEN / 2);
+        assert!(rendered.starts_with("ABAB ABAB"));
+    }
+
+    #[test]
+    fn test_signature_with_key_id() {
+        let message = b"Test message";
+        let key_pair = generate_key_pair();
+        let other = generate_key_pair();
+
+        let signature = sign_message_with_key_id(message, &key_pair).unwrap();
+        assert_eq!(signature_key_id(&signature), Some(key_pair.key_id()));
+        assert!(verify_signature_with_key_id(message, &signature, &key_pair));
+        assert!(!verify_signature_with_key_id(message, &signature, &other));
+        assert!(!verify_signature_with_key_id(
+            b"Other", &signature, &key_pair
+        ));
+        assert_eq!(signature_key_id(&[0u8; 4]), None);
+    }
+}
@@ -1,77 +1,373 @@
 //! Quantum-safe cryptography engine implementation
+//!
+//! Builds without `std`. Verification and the fixed-size types in `fixed`
+//! need neither `std` nor the `alloc` feature; `KeyPair`, the `Vec` based
+//! signing and the JSON and base64 encodings need `alloc` and take the random
+//! number generator from the caller. The `thread_rng` conveniences need `std`.
+//! The `alloc` crate is linked regardless, by `fn-dsa` through `zeroize`, so
+//! firmware still needs a global allocator.
 
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec, vec::Vec};
+#[cfg(feature = "alloc")]
 use base64::engine::general_purpose;
+#[cfg(feature = "alloc")]
 use base64::Engine;
+use core::fmt;
 use hmac::{Hmac, Mac};
-use rand::{thread_rng, Rng};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+#[cfg(feature = "alloc")]
+use rand::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
-use serde_json;
 use sha3::{Digest, Sha3_256};
-use std::string::String;
+
+use super::{composite, falcon, ml_dsa, slh_dsa};
+
+/// Signature algorithm a key belongs to
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Algorithm {
+    /// Symmetric HMAC; verification needs the private key
+    #[default]
+    HmacSha3_256,
+    /// Falcon-512 (FN-DSA), 666-byte signatures
+    Falcon512,
+    /// Falcon-1024 (FN-DSA), 1280-byte signatures
+    Falcon1024,
+    /// ML-DSA-44 (FIPS 204), 2420-byte signatures
+    MlDsa44,
+    /// ML-DSA-65 (FIPS 204), 3309-byte signatures
+    MlDsa65,
+    /// ML-DSA-87 (FIPS 204), 4627-byte signatures
+    MlDsa87,
+    /// SLH-DSA-SHAKE-128s (FIPS 205), 7856-byte signatures
+    SlhDsaShake128s,
+    /// SLH-DSA-SHAKE-128f (FIPS 205), 17088-byte signatures
+    SlhDsaShake128f,
+    /// SLH-DSA-SHAKE-192s (FIPS 205), 16224-byte signatures
+    SlhDsaShake192s,
+    /// SLH-DSA-SHAKE-192f (FIPS 205), 35664-byte signatures
+    SlhDsaShake192f,
+    /// SLH-DSA-SHAKE-256s (FIPS 205), 29792-byte signatures
+    SlhDsaShake256s,
+    /// SLH-DSA-SHAKE-256f (FIPS 205), 49856-byte signatures
+    SlhDsaShake256f,
+    /// Composite ML-DSA-44 + Ed25519 (draft), 2484-byte signatures
+    MlDsa44Ed25519,
+    /// Composite ML-DSA-65 + Ed25519 (draft), 3373-byte signatures
+    MlDsa65Ed25519,
+}
+
+impl Algorithm {
+    /// Every algorithm, HMAC first
+    pub const ALL: [Algorithm; 14] = [
+        Algorithm::HmacSha3_256,
+        Algorithm::Falcon512,
+        Algorithm::Falcon1024,
+        Algorithm::MlDsa44,
+        Algorithm::MlDsa65,
+        Algorithm::MlDsa87,
+        Algorithm::SlhDsaShake128s,
+        Algorithm::SlhDsaShake128f,
+        Algorithm::SlhDsaShake192s,
+        Algorithm::SlhDsaShake192f,
+        Algorithm::SlhDsaShake256s,
+        Algorithm::SlhDsaShake256f,
+        Algorithm::MlDsa44Ed25519,
+        Algorithm::MlDsa65Ed25519,
+    ];
+
+    /// Stable tag bound into key identifiers and encodings
+    pub const fn tag(&self) -> &'static str {
+        match self {
+            Algorithm::HmacSha3_256 => "hmac-sha3-256",
+            Algorithm::Falcon512 => "falcon-512",
+            Algorithm::Falcon1024 => "falcon-1024",
+            Algorithm::MlDsa44 => "ml-dsa-44",
+            Algorithm::MlDsa65 => "ml-dsa-65",
+            Algorithm::MlDsa87 => "ml-dsa-87",
+            Algorithm::SlhDsaShake128s => "slh-dsa-shake-128s",
+            Algorithm::SlhDsaShake128f => "slh-dsa-shake-128f",
+            Algorithm::SlhDsaShake192s => "slh-dsa-shake-192s",
+            Algorithm::SlhDsaShake192f => "slh-dsa-shake-192f",
+            Algorithm::SlhDsaShake256s => "slh-dsa-shake-256s",
+            Algorithm::SlhDsaShake256f => "slh-dsa-shake-256f",
+            Algorithm::MlDsa44Ed25519 => "ml-dsa-44-ed25519",
+            Algorithm::MlDsa65Ed25519 => "ml-dsa-65-ed25519",
+        }
+    }
+
+    /// Algorithm with the given `tag`
+    pub fn from_tag(tag: &str) -> Option<Algorithm> {
+        Algorithm::ALL
+            .into_iter()
+            .find(|algorithm| algorithm.tag() == tag)
+    }
+}
+
+/// Implementation behind an algorithm, with its parameter set
+enum Scheme {
+    Hmac,
+    Falcon,
+    MlDsa(&'static ml_dsa::Params),
+    SlhDsa(&'static slh_dsa::Params),
+    Composite(&'static composite::Params),
+}
+
+impl Algorithm {
+    fn scheme(&self) -> Scheme {
+        match self {
+            Algorithm::HmacSha3_256 => Scheme::Hmac,
+            Algorithm::Falcon512 | Algorithm::Falcon1024 => Scheme::Falcon,
+            Algorithm::MlDsa44 => Scheme::MlDsa(&ml_dsa::ML_DSA_44),
+            Algorithm::MlDsa65 => Scheme::MlDsa(&ml_dsa::ML_DSA_65),
+            Algorithm::MlDsa87 => Scheme::MlDsa(&ml_dsa::ML_DSA_87),
+            Algorithm::SlhDsaShake128s => Scheme::SlhDsa(&slh_dsa::SLH_DSA_SHAKE_128S),
+            Algorithm::SlhDsaShake128f => Scheme::SlhDsa(&slh_dsa::SLH_DSA_SHAKE_128F),
+            Algorithm::SlhDsaShake192s => Scheme::SlhDsa(&slh_dsa::SLH_DSA_SHAKE_192S),
+            Algorithm::SlhDsaShake192f => Scheme::SlhDsa(&slh_dsa::SLH_DSA_SHAKE_192F),
+            Algorithm::SlhDsaShake256s => Scheme::SlhDsa(&slh_dsa::SLH_DSA_SHAKE_256S),
+            Algorithm::SlhDsaShake256f => Scheme::SlhDsa(&slh_dsa::SLH_DSA_SHAKE_256F),
+            Algorithm::MlDsa44Ed25519 => Scheme::Composite(&composite::ML_DSA_44_ED25519),
+            Algorithm::MlDsa65Ed25519 => Scheme::Composite(&composite::ML_DSA_65_ED25519),
+        }
+    }
+}
+
+/// Error raised when a key cannot be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    /// The private key is malformed or does not match the key's algorithm
+    InvalidPrivateKey,
+    /// The public key is malformed or cannot verify without the private key
+    InvalidPublicKey,
+    /// The signature has the wrong length for the algorithm
+    InvalidSignature,
+    /// The ciphertext has the wrong length for the mechanism
+    InvalidCiphertext,
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::InvalidPrivateKey => write!(f, "invalid private key"),
+            CryptoError::InvalidPublicKey => write!(f, "invalid public key"),
+            CryptoError::InvalidSignature => write!(f, "invalid signature"),
+            CryptoError::InvalidCiphertext => write!(f, "invalid ciphertext"),
+        }
+    }
+}
+
+impl core::error::Error for CryptoError {}
 
 /// Quantum-safe key pair
+#[cfg(feature = "alloc")]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct KeyPair {
     pub public_key: Vec<u8>,
     pub private_key: Vec<u8>,
+    /// Absent in key pairs serialized before algorithms were recorded
+    #[serde(default)]
+    pub algorithm: Algorithm,
+}
+
+#[cfg(feature = "alloc")]
+impl KeyPair {
+    /// Sign a message with this key pair's algorithm
+    #[cfg(feature = "std")]
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.sign_with_rng(message, &mut thread_rng())
+    }
+
+    /// Sign a message, drawing the signing randomness from `rng`
+    pub fn sign_with_rng<R: CryptoRng + RngCore>(
+        &self,
+        message: &[u8],
+        rng: &mut R,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let private_key = &self.private_key;
+        match self.algorithm.scheme() {
+            Scheme::Hmac => Ok(sign_message(message, private_key)),
+            Scheme::Falcon => falcon::sign(self.algorithm, private_key, message, rng),
+            Scheme::MlDsa(params) => ml_dsa::sign(params, private_key, &[], message, rng),
+            Scheme::SlhDsa(params) => slh_dsa::sign(params, private_key, message, rng),
+            Scheme::Composite(params) => composite::sign(params, private_key, message, rng),
+        }
+    }
+
+    /// Verify a signature produced by `sign`
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match self.algorithm {
+            Algorithm::HmacSha3_256 => verify_signature(message, signature, &self.private_key),
+            algorithm => verify_with_public_key(algorithm, &self.public_key, message, signature),
+        }
+    }
 }
 
 /// Generate a new quantum-safe key pair
+#[cfg(feature = "std")]
 pub fn generate_key_pair() -> KeyPair {
-    let mut rng = thread_rng();
-    let private_key: Vec<u8> = (0..32).map(|_| rng.gen()).collect();
-    let public_key = hash(&private_key);
+    generate_key_pair_for(Algorithm::HmacSha3_256)
+}
+
+/// Generate a new key pair for `algorithm`
+#[cfg(feature = "std")]
+pub fn generate_key_pair_for(algorithm: Algorithm) -> KeyPair {
+    generate_key_pair_with_rng(algorithm, &mut thread_rng())
+}
+
+/// Generate a new key pair for `algorithm` from the caller's `rng`
+#[cfg(feature = "alloc")]
+pub fn generate_key_pair_with_rng<R: CryptoRng + RngCore>(
+    algorithm: Algorithm,
+    rng: &mut R,
+) -> KeyPair {
+    let (public_key, private_key) = match algorithm.scheme() {
+        Scheme::Hmac => {
+            let mut private_key = vec![0u8; 32];
+            rng.fill_bytes(&mut private_key);
+            (hash(&private_key).to_vec(), private_key)
+        }
+        Scheme::Falcon => falcon::generate(algorithm, rng),
+        Scheme::MlDsa(params) => ml_dsa::generate(params, rng),
+        Scheme::SlhDsa(params) => slh_dsa::generate(params, rng),
+        Scheme::Composite(params) => composite::generate(params, rng),
+    };
 
     KeyPair {
         public_key,
         private_key,
+        algorithm,
+    }
+}
+
+/// Verify a signature using only the signer's public key
+///
+/// Always fails for `HmacSha3_256`, which cannot be verified without the
+/// private key.
+pub fn verify_with_public_key(
+    algorithm: Algorithm,
+    public_key: &[u8],
+    message: &[u8],
+    signature: &[u8],
+) -> bool {
+    PublicKeyVerifier::new(algorithm, public_key)
+        .is_ok_and(|verifier| verifier.verify(message, signature))
+}
+
+/// Public key decoded once for verifying many signatures
+///
+/// Only algorithms with public-key verification are accepted.
+#[derive(Debug, Clone)]
+pub struct PublicKeyVerifier {
+    algorithm: Algorithm,
+    key: VerifierKey,
+}
+
+#[derive(Debug, Clone)]
+enum VerifierKey {
+    Falcon(falcon::Verifier),
+    MlDsa(ml_dsa::Verifier),
+    SlhDsa(slh_dsa::Verifier),
+    Composite(composite::Verifier),
+}
+
+impl PublicKeyVerifier {
+    pub fn new(algorithm: Algorithm, public_key: &[u8]) -> Result<Self, CryptoError> {
+        let key = match algorithm.scheme() {
+            Scheme::Hmac => None,
+            Scheme::Falcon => falcon::Verifier::new(algorithm, public_key).map(VerifierKey::Falcon),
+            Scheme::MlDsa(params) => {
+                ml_dsa::Verifier::new(params, public_key).map(VerifierKey::MlDsa)
+            }
+            Scheme::SlhDsa(params) => {
+                slh_dsa::Verifier::new(params, public_key).map(VerifierKey::SlhDsa)
+            }
+            Scheme::Composite(params) => {
+                composite::Verifier::new(params, public_key).map(VerifierKey::Composite)
+            }
+        };
+        Ok(PublicKeyVerifier {
+            algorithm,
+            key: key.ok_or(CryptoError::InvalidPublicKey)?,
+        })
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    /// Same result as `verify_with_public_key` with the original key
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        match &self.key {
+            VerifierKey::Falcon(key) => key.verify(message, signature),
+            VerifierKey::MlDsa(key) => key.verify(message, signature),
+            VerifierKey::SlhDsa(key) => key.verify(message, signature),
+            VerifierKey::Composite(key) => key.verify(message, signature),
+        }
     }
 }
 
 /// Hash function using SHA3-256
-fn hash(data: &[u8]) -> Vec<u8> {
-    let mut hasher = Sha3_256::new();
-    hasher.update(data);
-    hasher.finalize().to_vec()
+pub(crate) fn hash(data: &[u8]) -> [u8; 32] {
+    Sha3_256::digest(data).into()
 }
 
-/// Sign a message using quantum-safe signature
-pub fn sign_message(message: &[u8], private_key: &[u8]) -> Vec<u8> {
+pub(crate) fn hmac(message: &[u8], private_key: &[u8]) -> Hmac<Sha3_256> {
     let mut mac =
         Hmac::<Sha3_256>::new_from_slice(private_key).expect("HMAC can take key of any size");
     mac.update(message);
-    mac.finalize().into_bytes().to_vec()
+    mac
+}
+
+/// Sign a message using quantum-safe signature
+#[cfg(feature = "alloc")]
+pub fn sign_message(message: &[u8], private_key: &[u8]) -> Vec<u8> {
+    hmac(message, private_key).finalize().into_bytes().to_vec()
 }
 
 /// Verify a signature
 pub fn verify_signature(message: &[u8], signature: &[u8], private_key: &[u8]) -> bool {
-    let mut mac =
-        Hmac::<Sha3_256>::new_from_slice(private_key).expect("HMAC can take key of any size");
-    mac.update(message);
-    let computed_signature = mac.finalize().into_bytes().to_vec();
-    signature == computed_signature
+    hmac(message, private_key).verify_slice(signature).is_ok()
 }
 
 /// Convert key pair to JSON string
+#[cfg(feature = "alloc")]
 pub fn key_pair_to_json(key_pair: &KeyPair) -> String {
     serde_json::to_string(key_pair).expect("Failed to serialize key pair")
 }
 
 /// Convert JSON string to key pair
+///
+/// Public-key key pairs are rejected unless the private key matches the
+/// public key.
+#[cfg(feature = "alloc")]
 pub fn key_pair_from_json(json: &str) -> Result<KeyPair, serde_json::Error> {
-    serde_json::from_str(json)
+    let key_pair: KeyPair = serde_json::from_str(json)?;
+    let (public_key, private_key) = (&key_pair.public_key, &key_pair.private_key);
+    let matches = match key_pair.algorithm.scheme() {
+        Scheme::Hmac => true,
+        Scheme::Falcon => falcon::matches(key_pair.algorithm, public_key, private_key),
+        Scheme::MlDsa(params) => ml_dsa::matches(params, public_key, private_key),
+        Scheme::SlhDsa(params) => slh_dsa::matches(params, public_key, private_key),
+        Scheme::Composite(params) => composite::matches(params, public_key, private_key),
+    };
+    if !matches {
+        return Err(serde::de::Error::custom(
+            "private key does not match public key",
+        ));
+    }
+    Ok(key_pair)
 }
 
 /// Convert signature to base64 string
+#[cfg(feature = "alloc")]
 pub fn signature_to_base64(signature: &[u8]) -> String {
-    let mut buffer = Vec::new();
-    general_purpose::STANDARD
-        .encode_slice(signature, &mut buffer)
-        .unwrap();
-    String::from_utf8(buffer).unwrap()
+    general_purpose::STANDARD.encode(signature)
 }
 
 /// Convert base64 string to signature
+#[cfg(feature = "alloc")]
 pub fn signature_from_base64(base64_str: &str) -> Result<Vec<u8>, base64::DecodeError> {
     let mut buffer = Vec::new();
     general_purpose::STANDARD.decode_vec(base64_str, &mut buffer)?;
@@ -91,6 +387,82 @@ mod tests {
         assert!(verify_signature(message, &signature, &key_pair.private_key));
     }
 
+    #[test]
+    fn test_falcon_key_pair() {
+        let message = b"Card-present authorisation";
+        let key_pair = generate_key_pair_for(Algorithm::Falcon512);
+        assert_eq!(key_pair.public_key.len(), 897);
+
+        let signature = key_pair.sign(message).unwrap();
+        assert_eq!(signature.len(), 666);
+        assert!(key_pair.verify(message, &signature));
+        assert!(verify_with_public_key(
+            Algorithm::Falcon512,
+            &key_pair.public_key,
+            message,
+            &signature
+        ));
+        assert!(!key_pair.verify(b"Other message", &signature));
+
+        let verifier = PublicKeyVerifier::new(Algorithm::Falcon512, &key_pair.public_key).unwrap();
+        assert!(verifier.verify(message, &signature));
+        assert!(!verifier.verify(b"Other message", &signature));
+        for (algorithm, public_key) in [
+            (Algorithm::Falcon1024, &key_pair.public_key[..]),
+            (Algorithm::Falcon512, &key_pair.public_key[1..]),
+            (Algorithm::HmacSha3_256, &key_pair.public_key[..]),
+        ] {
+            assert_eq!(
+                PublicKeyVerifier::new(algorithm, public_key).unwrap_err(),
+                CryptoError::InvalidPublicKey
+            );
+        }
+
+        let parsed = key_pair_from_json(&key_pair_to_json(&key_pair)).unwrap();
+        assert_eq!(parsed.algorithm, Algorithm::Falcon512);
+        assert!(parsed.verify(message, &signature));
+
+        let mut forged = parsed;
+        forged.public_key = generate_key_pair_for(Algorithm::Falcon512).public_key;
+        assert!(key_pair_from_json(&key_pair_to_json(&forged)).is_err());
+    }
+
+    #[test]
+    fn test_legacy_json_defaults_to_hmac() {
+        let json = r#"{"public_key":[1],"private_key":[2]}"#;
+        let key_pair = key_pair_from_json(json).unwrap();
+        assert_eq!(key_pair.algorithm, Algorithm::HmacSha3_256);
+    }
+
+    #[test]
+    fn test_cross_language_vectors() {
+        fn hex(text: &str) -> Vec<u8> {
+            (0..text.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+                .collect()
+        }
+
+        let vectors: serde_json::Value =
+            serde_json::from_str(include_str!("../../tests/vectors/signatures.json")).unwrap();
+        for vector in vectors["vectors"].as_array().unwrap() {
+            let algorithm = Algorithm::from_tag(vector["algorithm"].as_str().unwrap()).unwrap();
+            let key_pair = key_pair_from_json(vector["key_pair"].as_str().unwrap()).unwrap();
+            let message = hex(vector["message"].as_str().unwrap());
+            let signature = hex(vector["signature"].as_str().unwrap());
+            assert_eq!(key_pair.algorithm, algorithm);
+            assert!(key_pair.verify(&message, &signature));
+            assert!(!key_pair.verify(b"tampered", &signature));
+
+            if let Some(public_key) = vector["public_key"].as_str() {
+                let public_key = hex(public_key);
+                let (decoded, raw) = crate::crypto::decode_public_key(&public_key).unwrap();
+                assert_eq!((decoded, raw), (algorithm, &key_pair.public_key[..]));
+                assert!(verify_with_public_key(algorithm, raw, &message, &signature));
+            }
+        }
+    }
+
     #[test]
     fn test_hash() {
         let data = b"Hello, world!";
@@ -0,0 +1,427 @@
+//! Hierarchical signatures (HSS) and the stateful signer
+
+use super::params::{LmotsType, LmsType, LMS_ID_LEN};
+use super::state::StateStore;
+use super::tree::{self, LmsKey, LmsTree};
+use rand::{thread_rng, RngCore};
+use sha3::{Digest, Sha3_256};
+use std::{fmt, io};
+
+const STATE_MAGIC: &[u8; 8] = b"QSFHSS01";
+const STATE_CHECKSUM_LEN: usize = 8;
+/// Parameter sets of one HSS level
+pub type HssLevel = (LmsType, LmotsType);
+
+/// Maximum number of HSS levels (RFC 8554 section 6)
+pub const HSS_MAX_LEVELS: usize = 8;
+
+/// Stateful hash-based signature error
+#[derive(Debug)]
+pub enum LmsError {
+    Io(io::Error),
+    InvalidParameters(&'static str),
+    /// `generate` was called on a store that already holds a key
+    StateExists,
+    /// `load` found no stored key
+    NoState,
+    CorruptState,
+    /// Every one-time key has been used
+    Exhausted,
+}
+
+impl fmt::Display for LmsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LmsError::Io(e) => write!(f, "state I/O error: {}", e),
+            LmsError::InvalidParameters(reason) => write!(f, "invalid parameters: {}", reason),
+            LmsError::StateExists => write!(f, "state store already holds a key"),
+            LmsError::NoState => write!(f, "state store holds no key"),
+            LmsError::CorruptState => write!(f, "stored state is corrupt"),
+            LmsError::Exhausted => write!(f, "all one-time keys have been used"),
+        }
+    }
+}
+
+impl std::error::Error for LmsError {}
+
+impl From<io::Error> for LmsError {
+    fn from(e: io::Error) -> Self {
+        LmsError::Io(e)
+    }
+}
+
+/// HSS public key: `u32str(L) || pub[0]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HssPublicKey(Vec<u8>);
+
+impl HssPublicKey {
+    /// Parse an encoded public key
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let levels = u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?) as usize;
+        let (lms, ots) = tree::public_key_types(&bytes[4..])?;
+        ((1..=HSS_MAX_LEVELS).contains(&levels) && lms.m() == ots.n()).then(|| Self(bytes.to_vec()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+
+    /// Verify an HSS signature (RFC 8554 algorithm 8)
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        // `from_bytes` checked 1 <= levels <= 8
+        let levels = u32::from_be_bytes(self.0[..4].try_into().unwrap());
+        match signature.get(..4) {
+            Some(nspk) if u32::from_be_bytes(nspk.try_into().unwrap()) == levels - 1 => {}
+            _ => return false,
+        }
+        let mut key = &self.0[4..];
+        let mut rest = &signature[4..];
+        for _ in 1..levels {
+            let Some(sig_len) = tree::signature_len(rest).filter(|&n| n <= rest.len()) else {
+                return false;
+            };
+            let (lms_signature, after) = rest.split_at(sig_len);
+            let Some(key_len) = tree::public_key_len(after).filter(|&n| n <= after.len()) else {
+                return false;
+            };
+            let (child, after) = after.split_at(key_len);
+            if !tree::verify(key, child, lms_signature) {
+                return false;
+            }
+            key = child;
+            rest = after;
+        }
+        tree::verify(key, message, rest)
+    }
+}
+
+/// HSS signing key whose state lives in a `StateStore`
+///
+/// Child trees are derived from the root seed, so the stored state is just the
+/// parameters, the root seed and the next unused index. That index is
+/// persisted before each signature is computed.
+pub struct HssSigner<S: StateStore> {
+    levels: Vec<HssLevel>,
+    next: u64,
+    store: S,
+    trees: Vec<Option<(u64, LmsTree)>>,
+    public_key: HssPublicKey,
+}
+
+impl<S: StateStore> HssSigner<S> {
+    /// Generate a key with one `HssLevel` pair per level, top first
+    ///
+    /// Fails with `StateExists` rather than overwrite a key already in `store`.
+    pub fn generate(levels: &[HssLevel], mut store: S) -> Result<Self, LmsError> {
+        validate(levels)?;
+        if store.load()?.is_some() {
+            return Err(LmsError::StateExists);
+        }
+        let (lms, ots) = levels[0];
+        let mut id = [0u8; LMS_ID_LEN];
+        let mut seed = vec![0u8; lms.m()];
+        thread_rng().fill_bytes(&mut id);
+        thread_rng().fill_bytes(&mut seed);
+        let root = LmsKey { lms, ots, id, seed };
+        let mut signer = Self::with_root(levels.to_vec(), root, 0, store);
+        let state = signer.encode_state();
+        signer.store.store(&state)?;
+        Ok(signer)
+    }
+
+    /// Resume signing with the key held in `store`
+    pub fn load(mut store: S) -> Result<Self, LmsError> {
+        let state = store.load()?.ok_or(LmsError::NoState)?;
+        let (levels, root, next) = decode_state(&state).ok_or(LmsError::CorruptState)?;
+        Ok(Self::with_root(levels, root, next, store))
+    }
+
+    fn with_root(levels: Vec<HssLevel>, root: LmsKey, next: u64, store: S) -> Self {
+        let root = LmsTree::generate(root);
+        let mut public_key = (levels.len() as u32).to_be_bytes().to_vec();
+        public_key.extend(root.public_key());
+        let mut trees: Vec<_> = levels.iter().map(|_| None).collect();
+        trees[0] = Some((0, root));
+        Self {
+            levels,
+            next,
+            store,
+            trees,
+            public_key: HssPublicKey(public_key),
+        }
+    }
+
+    pub fn public_key(&self) -> &HssPublicKey {
+        &self.public_key
+    }
+
+    /// Total number of signatures the key can produce
+    pub fn capacity(&self) -> u64 {
+        1 << total_height(&self.levels)
+    }
+
+    /// Number of signatures that can still be produced
+    pub fn remaining(&self) -> u64 {
+        self.capacity() - self.next
+    }
+
+    /// Sign `message` with the next unused one-time key
+    ///
+    /// The index is consumed even if persisting it fails, so a failed call
+    /// never leads to reuse; the signer must not be used after such an error
+    /// until the store is healthy again.
+    pub fn sign(&mut self, message: &[u8]) -> Result<Vec<u8>, LmsError> {
+        let index = self.next;
+        if index >= self.capacity() {
+            return Err(LmsError::Exhausted);
+        }
+        self.next = index + 1;
+        let state = self.encode_state();
+        self.store.store(&state)?;
+        Ok(self.sign_at(index, message))
+    }
+
+    fn sign_at(&mut self, index: u64, message: &[u8]) -> Vec<u8> {
+        let heights: Vec<usize> = self.levels.iter().map(|(lms, _)| lms.h()).collect();
+        let mut qs = Vec::with_capacity(heights.len());
+        let mut below = total_height(&self.levels);
+        for level in 0..heights.len() {
+            let prefix = index >> below;
+            below -= heights[level];
+            qs.push(((index >> below) & ((1 << heights[level]) - 1)) as u32);
+            let cached = matches!(&self.trees[level], Some((p, _)) if *p == prefix);
+            if !cached {
+                let (lms, ots) = self.levels[level];
+                let (_, parent) = self.trees[level - 1].as_ref().unwrap();
+                let key = parent.key().child(qs[level - 1], lms, ots);
+                self.trees[level] = Some((prefix, LmsTree::generate(key)));
+            }
+        }
+
+        let trees: Vec<&LmsTree> = self.trees.iter().map(|t| &t.as_ref().unwrap().1).collect();
+        let mut signature = ((trees.len() - 1) as u32).to_be_bytes().to_vec();
+        for level in 1..trees.len() {
+            let child = trees[level].public_key();
+            signature.extend(trees[level - 1].sign(qs[level - 1], &child));
+            signature.extend(child);
+        }
+        signature.extend(trees[trees.len() - 1].sign(qs[trees.len() - 1], message));
+        signature
+    }
+
+    fn encode_state(&self) -> Vec<u8> {
+        let root = self.trees[0].as_ref().unwrap().1.key();
+        let mut state = STATE_MAGIC.to_vec();
+        state.push(self.levels.len() as u8);
+        for (lms, ots) in &self.levels {
+            state.extend_from_slice(&lms.code().to_be_bytes());
+            state.extend_from_slice(&ots.code().to_be_bytes());
+        }
+        state.extend_from_slice(&root.id);
+        state.extend_from_slice(&root.seed);
+        state.extend_from_slice(&self.next.to_be_bytes());
+        let checksum = Sha3_256::digest(&state);
+        state.extend_from_slice(&checksum[..STATE_CHECKSUM_LEN]);
+        state
+    }
+}
+
+fn total_height(levels: &[HssLevel]) -> usize {
+    levels.iter().map(|(lms, _)| lms.h()).sum()
+}
+
+fn validate(levels: &[HssLevel]) -> Result<(), LmsError> {
+    if levels.is_empty() || levels.len() > HSS_MAX_LEVELS {
+        return Err(LmsError::InvalidParameters(
+            "HSS needs between 1 and 8 levels",
+        ));
+    }
+    if levels.iter().any(|(lms, ots)| lms.m() != ots.n()) {
+        return Err(LmsError::InvalidParameters(
+            "LMS and LM-OTS hash lengths must match",
+        ));
+    }
+    if total_height(levels) > 63 {
+        return Err(LmsError::InvalidParameters(
+            "total tree height must not exceed 63",
+        ));
+    }
+    Ok(())
+}
+
+fn decode_state(state: &[u8]) -> Option<(Vec<HssLevel>, LmsKey, u64)> {
+    let (body, checksum) = state.split_at(state.len().checked_sub(STATE_CHECKSUM_LEN)?);
+    if Sha3_256::digest(body)[..STATE_CHECKSUM_LEN] != *checksum || !body.starts_with(STATE_MAGIC) {
+        return None;
+    }
+    let mut rest = &body[STATE_MAGIC.len()..];
+    let mut take = |n: usize| {
+        let (head, tail) = rest.split_at_checked(n)?;
+        rest = tail;
+        Some(head)
+    };
+    let u32_at = |b: &[u8]| u32::from_be_bytes(b.try_into().unwrap());
+
+    let count = take(1)?[0] as usize;
+    let mut levels = Vec::with_capacity(count);
+    for _ in 0..count {
+        let lms = LmsType::from_code(u32_at(take(4)?))?;
+        let ots = LmotsType::from_code(u32_at(take(4)?))?;
+        levels.push((lms, ots));
+    }
+    validate(&levels).ok()?;
+    let (lms, ots) = levels[0];
+    let id = take(LMS_ID_LEN)?.try_into().ok()?;
+    let seed = take(lms.m())?.to_vec();
+    let next = u64::from_be_bytes(take(8)?.try_into().ok()?);
+    if !rest.is_empty() || next > 1 << total_height(&levels) {
+        return None;
+    }
+    Some((levels, LmsKey { lms, ots, id, seed }, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::lms::{FileStateStore, MemoryStateStore};
+
+    const M24: HssLevel = (LmsType::ShakeM24H5, LmotsType::ShakeN24W2);
+    const M32: HssLevel = (LmsType::ShakeM32H5, LmotsType::ShakeN32W4);
+
+    /// Store that records every write and can be made to fail
+    #[derive(Default)]
+    struct FlakyStore {
+        writes: Vec<Vec<u8>>,
+        fail: bool,
+    }
+
+    impl StateStore for &mut FlakyStore {
+        fn load(&mut self) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.writes.last().cloned())
+        }
+
+        fn store(&mut self, state: &[u8]) -> io::Result<()> {
+            if self.fail {
+                return Err(io::Error::other("disk full"));
+            }
+            self.writes.push(state.to_vec());
+            Ok(())
+        }
+    }
+
+    fn lms_index(signature: &[u8]) -> u32 {
+        u32::from_be_bytes(signature[4..8].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_single_level_until_exhausted() {
+        let mut signer = HssSigner::generate(&[M24], MemoryStateStore::new()).unwrap();
+        let public_key = HssPublicKey::from_bytes(&signer.public_key().to_bytes()).unwrap();
+        assert_eq!(signer.capacity(), 32);
+
+        for i in 0..32 {
+            let message = format!("firmware {}", i);
+            let signature = signer.sign(message.as_bytes()).unwrap();
+            assert_eq!(lms_index(&signature), i);
+            assert!(public_key.verify(message.as_bytes(), &signature));
+            assert!(!public_key.verify(b"firmware", &signature));
+        }
+        assert_eq!(signer.remaining(), 0);
+        assert!(matches!(signer.sign(b"one more"), Err(LmsError::Exhausted)));
+    }
+
+    #[test]
+    fn test_two_levels_across_trees() {
+        let mut signer = HssSigner::generate(&[M32, M24], MemoryStateStore::new()).unwrap();
+        let public_key = signer.public_key().clone();
+        assert_eq!(signer.capacity(), 1024);
+
+        let mut last = Vec::new();
+        for i in 0..34 {
+            let message = [i as u8];
+            last = signer.sign(&message).unwrap();
+            assert!(public_key.verify(&message, &last), "signature {}", i);
+        }
+        assert!(!public_key.verify(&[0], &last));
+
+        let mut tampered = last.clone();
+        tampered[100] ^= 1;
+        assert!(!public_key.verify(&[33], &tampered));
+        assert!(!public_key.verify(&[33], &last[..last.len() - 1]));
+    }
+
+    #[test]
+    fn test_untrusted_level_count() {
+        let mut signer = HssSigner::generate(&[M24], MemoryStateStore::new()).unwrap();
+        let public_key = signer.public_key().clone();
+        let mut signature = signer.sign(b"m").unwrap();
+        for nspk in [u32::MAX, 1] {
+            signature[..4].copy_from_slice(&nspk.to_be_bytes());
+            assert!(!public_key.verify(b"m", &signature));
+        }
+        assert!(!public_key.verify(b"m", &[0xff; 3]));
+    }
+
+    #[test]
+    fn test_index_persisted_before_release() {
+        let mut store = FlakyStore::default();
+        let mut signer = HssSigner::generate(&[M24], &mut store).unwrap();
+        signer.sign(b"first").unwrap();
+
+        signer.store.fail = true;
+        assert!(matches!(signer.sign(b"lost"), Err(LmsError::Io(_))));
+        signer.store.fail = false;
+        // Index 1 may have been written before the failure, so it is skipped
+        assert_eq!(lms_index(&signer.sign(b"second").unwrap()), 2);
+        drop(signer);
+
+        // A restart resumes after every index that was released
+        let (_, _, next) = decode_state(store.writes.last().unwrap()).unwrap();
+        assert_eq!(next, 3);
+        let mut resumed = HssSigner::load(&mut store).unwrap();
+        assert_eq!(lms_index(&resumed.sign(b"third").unwrap()), 3);
+    }
+
+    #[test]
+    fn test_file_state_store() {
+        let dir = std::env::temp_dir().join(format!("qsf-lms-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("root.hss");
+
+        let mut signer = HssSigner::generate(&[M24], FileStateStore::new(&path)).unwrap();
+        let public_key = signer.public_key().clone();
+        signer.sign(b"release 1.0").unwrap();
+        drop(signer);
+
+        assert!(matches!(
+            HssSigner::generate(&[M24], FileStateStore::new(&path)),
+            Err(LmsError::StateExists)
+        ));
+        let mut signer = HssSigner::load(FileStateStore::new(&path)).unwrap();
+        assert_eq!(signer.public_key(), &public_key);
+        assert_eq!(signer.remaining(), 31);
+        let signature = signer.sign(b"release 1.1").unwrap();
+        assert_eq!(lms_index(&signature), 1);
+        assert!(public_key.verify(b"release 1.1", &signature));
+
+        let mut state = std::fs::read(&path).unwrap();
+        state[20] ^= 1;
+        std::fs::write(&path, state).unwrap();
+        assert!(matches!(
+            HssSigner::load(FileStateStore::new(&path)),
+            Err(LmsError::CorruptState)
+        ));
+        assert!(matches!(
+            HssSigner::load(FileStateStore::new(dir.join("missing"))),
+            Err(LmsError::NoState)
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_parameters() {
+        let mixed = (LmsType::ShakeM32H5, LmotsType::ShakeN24W4);
+        assert!(HssSigner::generate(&[mixed], MemoryStateStore::new()).is_err());
+        assert!(HssSigner::generate(&[], MemoryStateStore::new()).is_err());
+    }
+}
@@ -0,0 +1,24 @@
+//! Stateful hash-based signatures: LMS/HSS (RFC 8554) and XMSS^MT (RFC 8391),
+//! with the SHAKE256 parameter sets from NIST SP 800-208
+//!
+//! Only the SHAKE256 parameter sets are provided, so the existing `sha3`
+//! dependency covers every hash; for XMSS^MT these are the `n = 32` sets.
+//!
+//! Every one-time key may sign exactly once. `HssSigner` and `XmssMtSigner`
+//! persist the next unused index through a `StateStore` before computing a
+//! signature, so an index is skipped rather than reused if the process dies
+//! mid-signature.
+
+pub use self::hss::*;
+pub use self::params::*;
+pub use self::state::*;
+pub use self::xmss_mt::*;
+
+mod hss;
+mod ots;
+mod params;
+mod state;
+mod tree;
+mod wots;
+mod xmss;
+mod xmss_mt;
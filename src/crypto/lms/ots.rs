@@ -0,0 +1,146 @@
+//! LM-OTS one-time signatures (RFC 8554 section 4)
+
+use super::params::{shake, LmotsType};
+
+const D_PBLC: [u8; 2] = [0x80, 0x80];
+const D_MESG: [u8; 2] = [0x81, 0x81];
+
+/// Private chain start `x[i]`, derived from the tree seed (RFC 8554 appendix A)
+fn private_element(ots: LmotsType, id: &[u8], q: u32, i: u16, seed: &[u8]) -> Vec<u8> {
+    shake(
+        ots.n(),
+        &[id, &q.to_be_bytes(), &i.to_be_bytes(), &[0xff], seed],
+    )
+}
+
+/// Apply the chain function for steps `from..to` of chain `i`
+fn chain(
+    ots: LmotsType,
+    id: &[u8],
+    q: u32,
+    i: u16,
+    from: usize,
+    to: usize,
+    start: Vec<u8>,
+) -> Vec<u8> {
+    let mut tmp = start;
+    for j in from..to {
+        tmp = shake(
+            ots.n(),
+            &[id, &q.to_be_bytes(), &i.to_be_bytes(), &[j as u8], &tmp],
+        );
+    }
+    tmp
+}
+
+/// Winternitz digit `i` of `s`
+fn coef(s: &[u8], i: usize, w: usize) -> usize {
+    let byte = s[i * w / 8] as usize;
+    let shift = 8 - (w * (i % (8 / w)) + w);
+    (byte >> shift) & ((1 << w) - 1)
+}
+
+/// Digits of `Q || Cksm(Q)` that select the chain positions
+fn digits(ots: LmotsType, q_hash: &[u8]) -> Vec<usize> {
+    let (w, (p, ls)) = (ots.w(), ots.p_ls());
+    let max = (1 << w) - 1;
+    let sum: usize = (0..8 * ots.n() / w).map(|i| max - coef(q_hash, i, w)).sum();
+    let mut buf = q_hash.to_vec();
+    buf.extend_from_slice(&((sum as u16) << ls).to_be_bytes());
+    (0..p).map(|i| coef(&buf, i, w)).collect()
+}
+
+fn message_hash(ots: LmotsType, id: &[u8], q: u32, c: &[u8], message: &[u8]) -> Vec<u8> {
+    shake(ots.n(), &[id, &q.to_be_bytes(), &D_MESG, c, message])
+}
+
+fn public_hash(ots: LmotsType, id: &[u8], q: u32, ends: &[Vec<u8>]) -> Vec<u8> {
+    let q = q.to_be_bytes();
+    let mut parts: Vec<&[u8]> = vec![id, &q, &D_PBLC];
+    parts.extend(ends.iter().map(|e| e.as_slice()));
+    shake(ots.n(), &parts)
+}
+
+/// Public key hash `K` of one-time key `q`
+pub(super) fn public_key(ots: LmotsType, id: &[u8], q: u32, seed: &[u8]) -> Vec<u8> {
+    let max = (1 << ots.w()) - 1;
+    let ends: Vec<Vec<u8>> = (0..ots.p_ls().0 as u16)
+        .map(|i| chain(ots, id, q, i, 0, max, private_element(ots, id, q, i, seed)))
+        .collect();
+    public_hash(ots, id, q, &ends)
+}
+
+/// Sign `message` with one-time key `q` using the randomizer `c`
+pub(super) fn sign(
+    ots: LmotsType,
+    id: &[u8],
+    q: u32,
+    seed: &[u8],
+    c: &[u8],
+    message: &[u8],
+) -> Vec<u8> {
+    let digits = digits(ots, &message_hash(ots, id, q, c, message));
+    let mut signature = ots.code().to_be_bytes().to_vec();
+    signature.extend_from_slice(c);
+    for (i, a) in digits.into_iter().enumerate() {
+        let i = i as u16;
+        signature.extend(chain(
+            ots,
+            id,
+            q,
+            i,
+            0,
+            a,
+            private_element(ots, id, q, i, seed),
+        ));
+    }
+    signature
+}
+
+/// Public key hash implied by a signature, or `None` if it is malformed
+pub(super) fn candidate_public_key(
+    ots: LmotsType,
+    id: &[u8],
+    q: u32,
+    signature: &[u8],
+    message: &[u8],
+) -> Option<Vec<u8>> {
+    if signature.len() != ots.signature_len() || signature[..4] != ots.code().to_be_bytes() {
+        return None;
+    }
+    let n = ots.n();
+    let max = (1 << ots.w()) - 1;
+    let c = &signature[4..4 + n];
+    let digits = digits(ots, &message_hash(ots, id, q, c, message));
+    let ends: Vec<Vec<u8>> = signature[4 + n..]
+        .chunks(n)
+        .zip(digits)
+        .enumerate()
+        .map(|(i, (y, a))| chain(ots, id, q, i as u16, a, max, y.to_vec()))
+        .collect();
+    Some(public_hash(ots, id, q, &ends))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ots_sign_verify() {
+        let (id, seed) = ([7u8; 16], [9u8; 32]);
+        for ots in [LmotsType::ShakeN32W4, LmotsType::ShakeN24W2] {
+            let c = vec![1u8; ots.n()];
+            let key = public_key(ots, &id, 3, &seed[..ots.n()]);
+            let signature = sign(ots, &id, 3, &seed[..ots.n()], &c, b"firmware");
+            assert_eq!(signature.len(), ots.signature_len());
+            assert_eq!(
+                candidate_public_key(ots, &id, 3, &signature, b"firmware"),
+                Some(key.clone())
+            );
+            assert_ne!(
+                candidate_public_key(ots, &id, 3, &signature, b"malware"),
+                Some(key)
+            );
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! LMS, LM-OTS and XMSS^MT parameter sets
+
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+/// Length of the LMS key pair identifier `I`
+pub const LMS_ID_LEN: usize = 16;
+
+/// LM-OTS parameter set (SHAKE256 variants from SP 800-208)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmotsType {
+    ShakeN32W1,
+    ShakeN32W2,
+    ShakeN32W4,
+    ShakeN32W8,
+    ShakeN24W1,
+    ShakeN24W2,
+    ShakeN24W4,
+    ShakeN24W8,
+}
+
+/// LMS parameter set (SHAKE256 variants from SP 800-208)
+///
+/// Heights above 15 are better served by an extra HSS level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LmsType {
+    ShakeM32H5,
+    ShakeM32H10,
+    ShakeM32H15,
+    ShakeM24H5,
+    ShakeM24H10,
+    ShakeM24H15,
+}
+
+impl LmotsType {
+    const ALL: [LmotsType; 8] = [
+        LmotsType::ShakeN32W1,
+        LmotsType::ShakeN32W2,
+        LmotsType::ShakeN32W4,
+        LmotsType::ShakeN32W8,
+        LmotsType::ShakeN24W1,
+        LmotsType::ShakeN24W2,
+        LmotsType::ShakeN24W4,
+        LmotsType::ShakeN24W8,
+    ];
+
+    /// IANA type code
+    pub fn code(&self) -> u32 {
+        9 + Self::ALL.iter().position(|t| t == self).unwrap() as u32
+    }
+
+    /// Parameter set for an IANA type code
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.get(code.checked_sub(9)? as usize).copied()
+    }
+
+    /// Hash output length in bytes
+    pub fn n(&self) -> usize {
+        if self.code() <= 12 {
+            32
+        } else {
+            24
+        }
+    }
+
+    /// Winternitz parameter in bits
+    pub fn w(&self) -> usize {
+        1 << ((self.code() - 9) % 4)
+    }
+
+    /// Number of `n`-byte chains, and the checksum left shift (RFC 8554 appendix B)
+    pub(super) fn p_ls(&self) -> (usize, u32) {
+        let (n, w) = (self.n(), self.w());
+        let u = (8 * n).div_ceil(w);
+        let max_sum = ((1 << w) - 1) * u;
+        let v = (usize::BITS - max_sum.leading_zeros()) as usize;
+        let v = v.div_ceil(w);
+        (u + v, (16 - v * w) as u32)
+    }
+
+    /// Length of an LM-OTS signature
+    pub fn signature_len(&self) -> usize {
+        4 + self.n() * (self.p_ls().0 + 1)
+    }
+}
+
+impl LmsType {
+    const ALL: [LmsType; 6] = [
+        LmsType::ShakeM32H5,
+        LmsType::ShakeM32H10,
+        LmsType::ShakeM32H15,
+        LmsType::ShakeM24H5,
+        LmsType::ShakeM24H10,
+        LmsType::ShakeM24H15,
+    ];
+
+    /// IANA type code
+    pub fn code(&self) -> u32 {
+        match self {
+            LmsType::ShakeM32H5 => 15,
+            LmsType::ShakeM32H10 => 16,
+            LmsType::ShakeM32H15 => 17,
+            LmsType::ShakeM24H5 => 20,
+            LmsType::ShakeM24H10 => 21,
+            LmsType::ShakeM24H15 => 22,
+        }
+    }
+
+    /// Parameter set for an IANA type code
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.iter().find(|t| t.code() == code).copied()
+    }
+
+    /// Hash output length in bytes
+    pub fn m(&self) -> usize {
+        if self.code() < 20 {
+            32
+        } else {
+            24
+        }
+    }
+
+    /// Tree height
+    pub fn h(&self) -> usize {
+        match self {
+            LmsType::ShakeM32H5 | LmsType::ShakeM24H5 => 5,
+            LmsType::ShakeM32H10 | LmsType::ShakeM24H10 => 10,
+            LmsType::ShakeM32H15 | LmsType::ShakeM24H15 => 15,
+        }
+    }
+
+    /// Length of an LMS public key
+    pub fn public_key_len(&self) -> usize {
+        8 + LMS_ID_LEN + self.m()
+    }
+
+    /// Length of an LMS signature using `ots`
+    pub fn signature_len(&self, ots: LmotsType) -> usize {
+        4 + ots.signature_len() + 4 + self.h() * self.m()
+    }
+}
+
+/// XMSS^MT parameter set (SHAKE256 with `n = 32` from SP 800-208)
+///
+/// Named after the total height and the number of layers. Each layer is a
+/// tree of height `h / d`, so the sets with 20-high layers take minutes to
+/// generate and sign across tree boundaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmssMtType {
+    ShakeN32H20D2,
+    ShakeN32H20D4,
+    ShakeN32H40D2,
+    ShakeN32H40D4,
+    ShakeN32H40D8,
+    ShakeN32H60D3,
+    ShakeN32H60D6,
+    ShakeN32H60D12,
+}
+
+/// Hash output length of the XMSS^MT parameter sets
+pub const XMSS_N: usize = 32;
+
+/// Number of `n`-byte WOTS+ chains for `w = 16`
+pub(super) const WOTS_LEN: usize = 67;
+
+impl XmssMtType {
+    const ALL: [XmssMtType; 8] = [
+        XmssMtType::ShakeN32H20D2,
+        XmssMtType::ShakeN32H20D4,
+        XmssMtType::ShakeN32H40D2,
+        XmssMtType::ShakeN32H40D4,
+        XmssMtType::ShakeN32H40D8,
+        XmssMtType::ShakeN32H60D3,
+        XmssMtType::ShakeN32H60D6,
+        XmssMtType::ShakeN32H60D12,
+    ];
+
+    /// Algorithm OID from SP 800-208
+    pub fn oid(&self) -> u32 {
+        0x29 + Self::ALL.iter().position(|t| t == self).unwrap() as u32
+    }
+
+    /// Parameter set for an OID
+    pub fn from_oid(oid: u32) -> Option<Self> {
+        Self::ALL.get(oid.checked_sub(0x29)? as usize).copied()
+    }
+
+    /// Total height of the hypertree
+    pub fn h(&self) -> usize {
+        match self {
+            XmssMtType::ShakeN32H20D2 | XmssMtType::ShakeN32H20D4 => 20,
+            XmssMtType::ShakeN32H40D2 | XmssMtType::ShakeN32H40D4 | XmssMtType::ShakeN32H40D8 => 40,
+            _ => 60,
+        }
+    }
+
+    /// Number of layers
+    pub fn d(&self) -> usize {
+        match self {
+            XmssMtType::ShakeN32H20D2 | XmssMtType::ShakeN32H40D2 => 2,
+            XmssMtType::ShakeN32H60D3 => 3,
+            XmssMtType::ShakeN32H20D4 | XmssMtType::ShakeN32H40D4 => 4,
+            XmssMtType::ShakeN32H60D6 => 6,
+            XmssMtType::ShakeN32H40D8 => 8,
+            XmssMtType::ShakeN32H60D12 => 12,
+        }
+    }
+
+    /// Height of the tree in each layer
+    pub fn tree_height(&self) -> usize {
+        self.h() / self.d()
+    }
+
+    /// Length of the signature index
+    pub(super) fn index_len(&self) -> usize {
+        self.h().div_ceil(8)
+    }
+
+    /// Length of an XMSS^MT public key: `OID || root || SEED`
+    pub fn public_key_len(&self) -> usize {
+        4 + 2 * XMSS_N
+    }
+
+    /// Length of an XMSS^MT signature
+    pub fn signature_len(&self) -> usize {
+        self.index_len() + XMSS_N + (self.h() + self.d() * WOTS_LEN) * XMSS_N
+    }
+}
+
+/// SHAKE256 truncated to `n` bytes over the concatenation of `parts`
+pub(super) fn shake(n: usize, parts: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Shake256::default();
+    for part in parts {
+        hasher.update(part);
+    }
+    let mut out = vec![0u8; n];
+    hasher.finalize_xof().read(&mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lmots_parameters() {
+        // p and ls from RFC 8554 table 1 and SP 800-208 table 3
+        let expected = [
+            (LmotsType::ShakeN32W1, 265, 7),
+            (LmotsType::ShakeN32W2, 133, 6),
+            (LmotsType::ShakeN32W4, 67, 4),
+            (LmotsType::ShakeN32W8, 34, 0),
+            (LmotsType::ShakeN24W1, 200, 8),
+            (LmotsType::ShakeN24W2, 101, 6),
+            (LmotsType::ShakeN24W4, 51, 4),
+            (LmotsType::ShakeN24W8, 26, 0),
+        ];
+        for (ots, p, ls) in expected {
+            assert_eq!(ots.p_ls(), (p, ls), "{:?}", ots);
+            assert_eq!(LmotsType::from_code(ots.code()), Some(ots));
+        }
+        assert_eq!(LmotsType::ShakeN32W8.code(), 12);
+        assert_eq!(LmotsType::ShakeN24W1.code(), 13);
+    }
+
+    #[test]
+    fn test_lms_codes() {
+        for lms in LmsType::ALL {
+            assert_eq!(LmsType::from_code(lms.code()), Some(lms));
+        }
+        assert_eq!(LmsType::from_code(5), None);
+    }
+
+    #[test]
+    fn test_xmss_mt_sizes() {
+        // Signature sizes from RFC 8391 section 5.4, which SP 800-208 shares
+        assert_eq!(XmssMtType::ShakeN32H20D2.signature_len(), 4963);
+        assert_eq!(XmssMtType::ShakeN32H40D8.signature_len(), 18469);
+        assert_eq!(XmssMtType::ShakeN32H60D12.signature_len(), 27688);
+        for xmss in XmssMtType::ALL {
+            assert_eq!(XmssMtType::from_oid(xmss.oid()), Some(xmss));
+            assert_eq!(xmss.tree_height() * xmss.d(), xmss.h());
+        }
+        assert_eq!(XmssMtType::ShakeN32H60D12.oid(), 0x30);
+        assert_eq!(XmssMtType::from_oid(0x31), None);
+    }
+}
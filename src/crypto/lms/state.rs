@@ -0,0 +1,155 @@
+//! Persistence of stateful signing keys
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Distinguishes temporary files written by one process
+static STORE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Durable storage for the private state of a stateful signing key
+///
+/// `store` must not return until the new state would survive a crash or power
+/// loss; the signer relies on this to never release a signature whose index
+/// could be handed out again.
+pub trait StateStore {
+    /// Read the current state, or `None` if nothing has been stored
+    fn load(&mut self) -> io::Result<Option<Vec<u8>>>;
+
+    /// Atomically replace the stored state
+    fn store(&mut self, state: &[u8]) -> io::Result<()>;
+}
+
+/// State kept in a single file, replaced via a synced temporary file and rename
+///
+/// The temporary file is a new owner-only file next to the state file, so a
+/// stale or planted file cannot be written through and the seed is never
+/// readable by other users.
+///
+/// The file holds the private seed; protect it like any other key file. Only
+/// one signer may use a state file at a time, and restoring an old copy from
+/// a backup will reuse one-time keys.
+pub struct FileStateStore {
+    path: PathBuf,
+}
+
+impl FileStateStore {
+    /// Use the state file at `path`
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl StateStore for FileStateStore {
+    fn load(&mut self) -> io::Result<Option<Vec<u8>>> {
+        match fs::read(&self.path) {
+            Ok(state) => Ok(Some(state)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn store(&mut self, state: &[u8]) -> io::Result<()> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let mut name = std::ffi::OsString::from(".");
+        name.push(self.path.file_name().unwrap_or_default());
+        name.push(format!(
+            ".{}.{}.tmp",
+            std::process::id(),
+            STORE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let tmp = dir.join(name);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        options.mode(0o600);
+        let written = options.open(&tmp).and_then(|mut file| {
+            file.write_all(state)?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|()| fs::rename(&tmp, &self.path)) {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+        // Make the rename itself durable
+        sync_dir(dir)
+    }
+}
+
+/// Flush a directory entry change to disk
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened for syncing outside Unix
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// State held in memory only, for tests and short-lived keys
+#[derive(Default)]
+pub struct MemoryStateStore {
+    state: Option<Vec<u8>>,
+}
+
+impl MemoryStateStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StateStore for MemoryStateStore {
+    fn load(&mut self) -> io::Result<Option<Vec<u8>>> {
+        Ok(self.state.clone())
+    }
+
+    fn store(&mut self, state: &[u8]) -> io::Result<()> {
+        self.state = Some(state.to_vec());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_store_replaces_state() {
+        let dir = std::env::temp_dir().join(format!("qsf-state-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("signer.state");
+        // A leftover file under the old fixed temporary name is left alone
+        fs::write(path.with_extension("tmp"), b"stale").unwrap();
+
+        let mut store = FileStateStore::new(&path);
+        assert_eq!(store.load().unwrap(), None);
+        store.store(b"first").unwrap();
+        store.store(b"second").unwrap();
+        assert_eq!(store.load().unwrap().unwrap(), b"second");
+        assert_eq!(fs::read(path.with_extension("tmp")).unwrap(), b"stale");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 2);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,188 @@
+//! LMS Merkle trees (RFC 8554 section 5)
+
+use super::ots;
+use super::params::{shake, LmotsType, LmsType, LMS_ID_LEN};
+
+const D_LEAF: [u8; 2] = [0x82, 0x82];
+const D_INTR: [u8; 2] = [0x83, 0x83];
+
+/// Secret material of one LMS tree
+#[derive(Clone)]
+pub(super) struct LmsKey {
+    pub lms: LmsType,
+    pub ots: LmotsType,
+    pub id: [u8; LMS_ID_LEN],
+    pub seed: Vec<u8>,
+}
+
+impl LmsKey {
+    /// Key of the tree signed by one-time key `q`, derived as in the
+    /// reference implementation so no extra secrets need to be stored
+    pub fn child(&self, q: u32, lms: LmsType, ots: LmotsType) -> Self {
+        let derive = |tag: u16, n: usize| {
+            shake(
+                n,
+                &[
+                    &self.id,
+                    &q.to_be_bytes(),
+                    &tag.to_be_bytes(),
+                    &[0xff],
+                    &self.seed,
+                ],
+            )
+        };
+        let mut id = [0u8; LMS_ID_LEN];
+        id.copy_from_slice(&derive(0xfffe, LMS_ID_LEN));
+        Self {
+            lms,
+            ots,
+            id,
+            seed: derive(0xffff, lms.m()),
+        }
+    }
+
+    /// Randomizer for one-time key `q`
+    ///
+    /// Deriving it from the seed makes re-signing the same message with the
+    /// same index reproduce the same signature instead of leaking chain values.
+    fn randomizer(&self, q: u32) -> Vec<u8> {
+        shake(
+            self.ots.n(),
+            &[
+                &self.id,
+                &q.to_be_bytes(),
+                &0xfffdu16.to_be_bytes(),
+                &[0xff],
+                &self.seed,
+            ],
+        )
+    }
+}
+
+impl Drop for LmsKey {
+    fn drop(&mut self) {
+        self.seed.iter_mut().for_each(|b| *b = 0);
+    }
+}
+
+/// LMS tree with every node cached, indexed from the root at 1
+pub(super) struct LmsTree {
+    key: LmsKey,
+    nodes: Vec<Vec<u8>>,
+}
+
+impl LmsTree {
+    /// Compute all one-time public keys and the tree above them
+    pub fn generate(key: LmsKey) -> Self {
+        let (m, leaves) = (key.lms.m(), 1usize << key.lms.h());
+        let mut nodes = vec![Vec::new(); 2 * leaves];
+        for q in 0..leaves {
+            let r = (leaves + q) as u32;
+            let k = ots::public_key(key.ots, &key.id, q as u32, &key.seed);
+            nodes[leaves + q] = shake(m, &[&key.id, &r.to_be_bytes(), &D_LEAF, &k]);
+        }
+        for r in (1..leaves).rev() {
+            nodes[r] = shake(
+                m,
+                &[
+                    &key.id,
+                    &(r as u32).to_be_bytes(),
+                    &D_INTR,
+                    &nodes[2 * r],
+                    &nodes[2 * r + 1],
+                ],
+            );
+        }
+        Self { key, nodes }
+    }
+
+    pub fn key(&self) -> &LmsKey {
+        &self.key
+    }
+
+    /// `u32str(type) || u32str(otstype) || I || T[1]`
+    pub fn public_key(&self) -> Vec<u8> {
+        let mut public_key = self.key.lms.code().to_be_bytes().to_vec();
+        public_key.extend_from_slice(&self.key.ots.code().to_be_bytes());
+        public_key.extend_from_slice(&self.key.id);
+        public_key.extend_from_slice(&self.nodes[1]);
+        public_key
+    }
+
+    /// Sign with one-time key `q`; the caller guarantees `q` is never reused
+    pub fn sign(&self, q: u32, message: &[u8]) -> Vec<u8> {
+        let key = &self.key;
+        let mut signature = q.to_be_bytes().to_vec();
+        signature.extend(ots::sign(
+            key.ots,
+            &key.id,
+            q,
+            &key.seed,
+            &key.randomizer(q),
+            message,
+        ));
+        signature.extend_from_slice(&key.lms.code().to_be_bytes());
+        let mut r = (1usize << key.lms.h()) + q as usize;
+        while r > 1 {
+            signature.extend_from_slice(&self.nodes[r ^ 1]);
+            r >>= 1;
+        }
+        signature
+    }
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+/// Parameter sets of an encoded LMS public key
+pub(super) fn public_key_types(public_key: &[u8]) -> Option<(LmsType, LmotsType)> {
+    let lms = LmsType::from_code(read_u32(public_key, 0)?)?;
+    let ots = LmotsType::from_code(read_u32(public_key, 4)?)?;
+    (public_key.len() == lms.public_key_len()).then_some((lms, ots))
+}
+
+/// Length of the LMS public key at the start of `bytes`
+pub(super) fn public_key_len(bytes: &[u8]) -> Option<usize> {
+    Some(LmsType::from_code(read_u32(bytes, 0)?)?.public_key_len())
+}
+
+/// Length of the LMS signature at the start of `signature`
+pub(super) fn signature_len(signature: &[u8]) -> Option<usize> {
+    let ots = LmotsType::from_code(read_u32(signature, 4)?)?;
+    let lms = LmsType::from_code(read_u32(signature, 4 + ots.signature_len())?)?;
+    Some(lms.signature_len(ots))
+}
+
+/// Verify an LMS signature (RFC 8554 algorithm 6)
+pub(super) fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    let Some((lms, ots)) = public_key_types(public_key) else {
+        return false;
+    };
+    if lms.m() != ots.n() || signature.len() != lms.signature_len(ots) {
+        return false;
+    }
+    let (m, h) = (lms.m(), lms.h());
+    let id = &public_key[8..8 + LMS_ID_LEN];
+    let q = read_u32(signature, 0).unwrap();
+    let ots_end = 4 + ots.signature_len();
+    if q >> h != 0 || read_u32(signature, ots_end) != Some(lms.code()) {
+        return false;
+    }
+    let Some(k) = ots::candidate_public_key(ots, id, q, &signature[4..ots_end], message) else {
+        return false;
+    };
+
+    let mut r = (1u32 << h) + q;
+    let mut node = shake(m, &[id, &r.to_be_bytes(), &D_LEAF, &k]);
+    for sibling in signature[ots_end + 4..].chunks(m) {
+        let parent = (r / 2).to_be_bytes();
+        node = if r % 2 == 1 {
+            shake(m, &[id, &parent, &D_INTR, sibling, &node])
+        } else {
+            shake(m, &[id, &parent, &D_INTR, &node, sibling])
+        };
+        r /= 2;
+    }
+    node == public_key[8 + LMS_ID_LEN..]
+}
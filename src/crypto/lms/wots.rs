@@ -0,0 +1,101 @@
+//! WOTS+ one-time signatures with `w = 16` (RFC 8391 section 3)
+
+use super::params::{WOTS_LEN, XMSS_N};
+use super::xmss::{chain_step, prf_keygen, Address};
+
+const W: u32 = 16;
+const LEN1: usize = 2 * XMSS_N;
+
+/// Length of a WOTS+ signature
+pub(super) const SIGNATURE_LEN: usize = WOTS_LEN * XMSS_N;
+
+/// Apply the chain function for steps `from..to` of the chain at `address`
+fn chain(start: &[u8], from: u32, to: u32, pub_seed: &[u8], address: Address) -> Vec<u8> {
+    let mut tmp = start.to_vec();
+    for j in from..to {
+        tmp = chain_step(&tmp, pub_seed, address.hash(j));
+    }
+    tmp
+}
+
+/// Base-16 digits of `message` followed by those of its checksum
+fn digits(message: &[u8]) -> Vec<u32> {
+    let mut digits: Vec<u32> = message
+        .iter()
+        .flat_map(|b| [(b >> 4) as u32, (b & 0xf) as u32])
+        .collect();
+    let checksum: u32 = digits.iter().map(|d| W - 1 - d).sum();
+    // 12 checksum bits, shifted to the top of two bytes
+    let checksum = ((checksum << 4) as u16).to_be_bytes();
+    digits.extend([checksum[0] >> 4, checksum[0] & 0xf, checksum[1] >> 4].map(u32::from));
+    debug_assert_eq!(digits.len(), LEN1 + 3);
+    digits
+}
+
+fn secret(sk_seed: &[u8], pub_seed: &[u8], address: Address, i: u32) -> Vec<u8> {
+    prf_keygen(sk_seed, pub_seed, address.chain(i))
+}
+
+/// Chain ends of the key pair at `address`
+pub(super) fn public_key(sk_seed: &[u8], pub_seed: &[u8], address: Address) -> Vec<Vec<u8>> {
+    (0..WOTS_LEN as u32)
+        .map(|i| {
+            let start = secret(sk_seed, pub_seed, address, i);
+            chain(&start, 0, W - 1, pub_seed, address.chain(i))
+        })
+        .collect()
+}
+
+/// Sign an `n`-byte digest with the key pair at `address`
+pub(super) fn sign(message: &[u8], sk_seed: &[u8], pub_seed: &[u8], address: Address) -> Vec<u8> {
+    let mut signature = Vec::with_capacity(SIGNATURE_LEN);
+    for (i, a) in (0..).zip(digits(message)) {
+        let start = secret(sk_seed, pub_seed, address, i);
+        signature.extend(chain(&start, 0, a, pub_seed, address.chain(i)));
+    }
+    signature
+}
+
+/// Chain ends implied by a signature of `message`
+pub(super) fn public_key_from_signature(
+    signature: &[u8],
+    message: &[u8],
+    pub_seed: &[u8],
+    address: Address,
+) -> Vec<Vec<u8>> {
+    (0..)
+        .zip(signature.chunks(XMSS_N).zip(digits(message)))
+        .map(|(i, (value, a))| chain(value, a, W - 1, pub_seed, address.chain(i)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum_digits() {
+        // All-zero digits give the maximum checksum, 64 * 15 = 0x3c0
+        let digits = digits(&[0; XMSS_N]);
+        assert_eq!(digits.len(), WOTS_LEN);
+        assert_eq!(&digits[LEN1..], &[3, 0xc, 0]);
+        assert_eq!(&super::digits(&[0xff; XMSS_N])[LEN1..], &[0, 0, 0]);
+    }
+
+    #[test]
+    fn test_wots_sign_verify() {
+        let (sk_seed, pub_seed) = ([3u8; XMSS_N], [4u8; XMSS_N]);
+        let address = Address::new(0, 1).ots(2);
+        let key = public_key(&sk_seed, &pub_seed, address);
+        let signature = sign(&[9; XMSS_N], &sk_seed, &pub_seed, address);
+        assert_eq!(signature.len(), SIGNATURE_LEN);
+        assert_eq!(
+            public_key_from_signature(&signature, &[9; XMSS_N], &pub_seed, address),
+            key
+        );
+        assert_ne!(
+            public_key_from_signature(&signature, &[8; XMSS_N], &pub_seed, address),
+            key
+        );
+    }
+}
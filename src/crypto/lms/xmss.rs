@@ -0,0 +1,268 @@
+//! XMSS hash functions, addresses and trees (RFC 8391 section 4.1)
+
+use super::params::{shake, XMSS_N};
+use super::wots;
+
+/// Hash function address `ADRS` (RFC 8391 section 2.5)
+#[derive(Clone, Copy)]
+pub(super) struct Address([u32; 8]);
+
+impl Address {
+    /// Address of tree `tree` in layer `layer`
+    pub fn new(layer: u32, tree: u64) -> Self {
+        Self([layer, (tree >> 32) as u32, tree as u32, 0, 0, 0, 0, 0])
+    }
+
+    fn with(mut self, word: usize, value: u32) -> Self {
+        self.0[word] = value;
+        self
+    }
+
+    /// Changing the type clears the words that follow it
+    fn typed(mut self, kind: u32) -> Self {
+        self.0[3..].copy_from_slice(&[kind, 0, 0, 0, 0]);
+        self
+    }
+
+    /// WOTS+ key pair of leaf `leaf`
+    pub fn ots(self, leaf: u32) -> Self {
+        self.typed(0).with(4, leaf)
+    }
+
+    /// L-tree compressing the WOTS+ public key of leaf `leaf`
+    pub fn ltree(self, leaf: u32) -> Self {
+        self.typed(1).with(4, leaf)
+    }
+
+    /// Node of the main tree
+    pub fn hash_tree(self) -> Self {
+        self.typed(2)
+    }
+
+    pub fn chain(self, i: u32) -> Self {
+        self.with(5, i)
+    }
+
+    pub fn hash(self, i: u32) -> Self {
+        self.with(6, i)
+    }
+
+    /// Height of the nodes being combined, in L-trees and the main tree
+    pub fn tree_height(self, height: u32) -> Self {
+        self.with(5, height)
+    }
+
+    /// Index of the parent node, in L-trees and the main tree
+    pub fn tree_index(self, index: u32) -> Self {
+        self.with(6, index)
+    }
+
+    fn key_and_mask(self, i: u32) -> Self {
+        self.with(7, i)
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (chunk, word) in bytes.chunks_mut(4).zip(self.0) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        bytes
+    }
+}
+
+/// `toByte(x, n)`
+pub(super) fn to_byte(x: u64, n: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; n];
+    let be = x.to_be_bytes();
+    let len = n.min(8);
+    bytes[n - len..].copy_from_slice(&be[8 - len..]);
+    bytes
+}
+
+/// SHAKE256 over `toByte(domain, n) || key || parts` (SP 800-208 section 5.2)
+fn keyed(domain: u8, key: &[u8], parts: &[&[u8]]) -> Vec<u8> {
+    let padding = to_byte(domain as u64, XMSS_N);
+    let mut input: Vec<&[u8]> = vec![&padding, key];
+    input.extend_from_slice(parts);
+    shake(XMSS_N, &input)
+}
+
+pub(super) fn prf(key: &[u8], message: &[u8]) -> Vec<u8> {
+    keyed(3, key, &[message])
+}
+
+/// Secret WOTS+ chain start for `address`
+pub(super) fn prf_keygen(sk_seed: &[u8], pub_seed: &[u8], address: Address) -> Vec<u8> {
+    keyed(4, sk_seed, &[pub_seed, &address.to_bytes()])
+}
+
+/// Message digest `H_msg(r || root || toByte(index, n), message)`
+pub(super) fn h_msg(r: &[u8], root: &[u8], index: u64, message: &[u8]) -> Vec<u8> {
+    keyed(2, r, &[root, &to_byte(index, XMSS_N), message])
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(x, y)| x ^ y).collect()
+}
+
+/// Key and bitmask for `address`
+fn mask(pub_seed: &[u8], address: Address, i: u32) -> Vec<u8> {
+    prf(pub_seed, &address.key_and_mask(i).to_bytes())
+}
+
+/// Keyed chaining function `F` applied after masking `value`
+pub(super) fn chain_step(value: &[u8], pub_seed: &[u8], address: Address) -> Vec<u8> {
+    let key = mask(pub_seed, address, 0);
+    keyed(0, &key, &[&xor(value, &mask(pub_seed, address, 1))])
+}
+
+/// `RAND_HASH(left, right, SEED, ADRS)`
+fn rand_hash(left: &[u8], right: &[u8], pub_seed: &[u8], address: Address) -> Vec<u8> {
+    let key = mask(pub_seed, address, 0);
+    keyed(
+        1,
+        &key,
+        &[
+            &xor(left, &mask(pub_seed, address, 1)),
+            &xor(right, &mask(pub_seed, address, 2)),
+        ],
+    )
+}
+
+/// Compress a WOTS+ public key into one node
+fn ltree(mut nodes: Vec<Vec<u8>>, pub_seed: &[u8], address: Address) -> Vec<u8> {
+    let mut height = 0;
+    while nodes.len() > 1 {
+        let level = address.tree_height(height);
+        nodes = nodes
+            .chunks(2)
+            .enumerate()
+            .map(|(i, pair)| match pair {
+                [left, right] => rand_hash(left, right, pub_seed, level.tree_index(i as u32)),
+                _ => pair[0].clone(),
+            })
+            .collect();
+        height += 1;
+    }
+    nodes.pop().unwrap()
+}
+
+/// Root implied by a WOTS+ signature on `message` and its authentication path
+pub(super) fn root_from_signature(
+    leaf: u32,
+    wots_signature: &[u8],
+    auth_path: &[u8],
+    message: &[u8],
+    pub_seed: &[u8],
+    address: Address,
+) -> Vec<u8> {
+    let ends =
+        wots::public_key_from_signature(wots_signature, message, pub_seed, address.ots(leaf));
+    let mut node = ltree(ends, pub_seed, address.ltree(leaf));
+    for (k, sibling) in auth_path.chunks(XMSS_N).enumerate() {
+        let parent = address
+            .hash_tree()
+            .tree_height(k as u32)
+            .tree_index(leaf >> (k + 1));
+        node = if (leaf >> k) & 1 == 0 {
+            rand_hash(&node, sibling, pub_seed, parent)
+        } else {
+            rand_hash(sibling, &node, pub_seed, parent)
+        };
+    }
+    node
+}
+
+/// XMSS tree with every node cached, indexed from the root at 1
+pub(super) struct XmssTree {
+    address: Address,
+    height: usize,
+    nodes: Vec<Vec<u8>>,
+}
+
+impl XmssTree {
+    /// Compute every leaf of the tree at `address` and the nodes above them
+    pub fn generate(sk_seed: &[u8], pub_seed: &[u8], address: Address, height: usize) -> Self {
+        let leaves = 1usize << height;
+        let mut nodes = vec![Vec::new(); 2 * leaves];
+        for leaf in 0..leaves {
+            let ends = wots::public_key(sk_seed, pub_seed, address.ots(leaf as u32));
+            nodes[leaves + leaf] = ltree(ends, pub_seed, address.ltree(leaf as u32));
+        }
+        for k in 0..height {
+            let first = leaves >> (k + 1);
+            for j in 0..first {
+                let parent = address
+                    .hash_tree()
+                    .tree_height(k as u32)
+                    .tree_index(j as u32);
+                let r = first + j;
+                nodes[r] = rand_hash(&nodes[2 * r], &nodes[2 * r + 1], pub_seed, parent);
+            }
+        }
+        Self {
+            address,
+            height,
+            nodes,
+        }
+    }
+
+    pub fn root(&self) -> &[u8] {
+        &self.nodes[1]
+    }
+
+    /// WOTS+ signature of `message` with leaf `leaf`, then its authentication
+    /// path; the caller guarantees `leaf` is never reused
+    pub fn sign(&self, sk_seed: &[u8], pub_seed: &[u8], leaf: u32, message: &[u8]) -> Vec<u8> {
+        let mut signature = wots::sign(message, sk_seed, pub_seed, self.address.ots(leaf));
+        let mut r = (1usize << self.height) + leaf as usize;
+        while r > 1 {
+            signature.extend_from_slice(&self.nodes[r ^ 1]);
+            r >>= 1;
+        }
+        signature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_layout() {
+        let address = Address::new(2, 0x0102_0304_0506_0708)
+            .ltree(9)
+            .tree_height(1)
+            .tree_index(3);
+        let bytes = address.key_and_mask(2).to_bytes();
+        assert_eq!(
+            &bytes[..16],
+            &[0, 0, 0, 2, 1, 2, 3, 4, 5, 6, 7, 8, 0, 0, 0, 1]
+        );
+        assert_eq!(
+            &bytes[16..],
+            &[0, 0, 0, 9, 0, 0, 0, 1, 0, 0, 0, 3, 0, 0, 0, 2]
+        );
+        // The type change clears the L-tree fields
+        assert_eq!(address.hash_tree().to_bytes()[16..], [0; 16]);
+        assert_eq!(to_byte(0x0102, 3), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_tree_signature() {
+        let (sk_seed, pub_seed) = ([1u8; XMSS_N], [2u8; XMSS_N]);
+        let address = Address::new(1, 5);
+        let tree = XmssTree::generate(&sk_seed, &pub_seed, address, 3);
+        let message = [7u8; XMSS_N];
+        for leaf in [0, 5] {
+            let signature = tree.sign(&sk_seed, &pub_seed, leaf, &message);
+            let (wots_signature, auth_path) = signature.split_at(wots::SIGNATURE_LEN);
+            let root = |message: &[u8], address| {
+                root_from_signature(leaf, wots_signature, auth_path, message, &pub_seed, address)
+            };
+            assert_eq!(root(&message, address), tree.root());
+            assert_ne!(root(&[8u8; XMSS_N], address), tree.root());
+            assert_ne!(root(&message, Address::new(1, 6)), tree.root());
+        }
+    }
+}
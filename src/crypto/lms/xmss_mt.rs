@@ -0,0 +1,335 @@
+//! XMSS^MT multi-tree signatures and the stateful signer (RFC 8391 section 4.2)
+
+use super::hss::LmsError;
+use super::params::{XmssMtType, WOTS_LEN, XMSS_N};
+use super::state::StateStore;
+use super::xmss::{self, Address, XmssTree};
+use rand::{thread_rng, RngCore};
+use sha3::{Digest, Sha3_256};
+
+const STATE_MAGIC: &[u8; 8] = b"QSFXMT01";
+const STATE_CHECKSUM_LEN: usize = 8;
+
+/// XMSS^MT public key: `OID || root || SEED`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmssMtPublicKey(Vec<u8>);
+
+impl XmssMtPublicKey {
+    /// Parse an encoded public key
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let xmss = XmssMtType::from_oid(u32::from_be_bytes(bytes.get(..4)?.try_into().ok()?))?;
+        (bytes.len() == xmss.public_key_len()).then(|| Self(bytes.to_vec()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.clone()
+    }
+
+    pub fn parameters(&self) -> XmssMtType {
+        XmssMtType::from_oid(u32::from_be_bytes(self.0[..4].try_into().unwrap())).unwrap()
+    }
+
+    /// Verify an XMSS^MT signature (RFC 8391 algorithm 17)
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        let xmss = self.parameters();
+        if signature.len() != xmss.signature_len() {
+            return false;
+        }
+        let (root, pub_seed) = self.0[4..].split_at(XMSS_N);
+        let (index, rest) = signature.split_at(xmss.index_len());
+        let index = index.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        if index >> xmss.h() != 0 {
+            return false;
+        }
+        let (r, mut rest) = rest.split_at(XMSS_N);
+        let mut node = xmss::h_msg(r, root, index, message);
+        let height = xmss.tree_height();
+        for layer in 0..xmss.d() {
+            let (leaf, tree) = position(xmss, index, layer);
+            let (wots_signature, after) = rest.split_at(WOTS_LEN * XMSS_N);
+            let (auth_path, after) = after.split_at(height * XMSS_N);
+            let address = Address::new(layer as u32, tree);
+            node = xmss::root_from_signature(
+                leaf,
+                wots_signature,
+                auth_path,
+                &node,
+                pub_seed,
+                address,
+            );
+            rest = after;
+        }
+        node == root
+    }
+}
+
+/// Leaf and tree index of signature `index` in `layer`, counted from the bottom
+fn position(xmss: XmssMtType, index: u64, layer: usize) -> (u32, u64) {
+    let height = xmss.tree_height();
+    let above = index >> (height * layer);
+    let leaf = above & ((1 << height) - 1);
+    (leaf as u32, above >> height)
+}
+
+/// Secret seeds of an XMSS^MT key
+struct XmssMtKey {
+    xmss: XmssMtType,
+    sk_seed: [u8; XMSS_N],
+    sk_prf: [u8; XMSS_N],
+    pub_seed: [u8; XMSS_N],
+}
+
+impl Drop for XmssMtKey {
+    fn drop(&mut self) {
+        self.sk_seed.iter_mut().for_each(|b| *b = 0);
+        self.sk_prf.iter_mut().for_each(|b| *b = 0);
+    }
+}
+
+/// XMSS^MT signing key whose state lives in a `StateStore`
+///
+/// The stored state is the parameter set, the three seeds and the next unused
+/// index, which is persisted before each signature is computed. Trees are
+/// rebuilt from the seeds as signing reaches them.
+pub struct XmssMtSigner<S: StateStore> {
+    key: XmssMtKey,
+    next: u64,
+    store: S,
+    /// Tree index and tree per layer, bottom first
+    trees: Vec<Option<(u64, XmssTree)>>,
+    public_key: XmssMtPublicKey,
+}
+
+impl<S: StateStore> XmssMtSigner<S> {
+    /// Generate a key with parameter set `xmss`
+    ///
+    /// Fails with `StateExists` rather than overwrite a key already in `store`.
+    pub fn generate(xmss: XmssMtType, mut store: S) -> Result<Self, LmsError> {
+        if store.load()?.is_some() {
+            return Err(LmsError::StateExists);
+        }
+        let mut key = XmssMtKey {
+            xmss,
+            sk_seed: [0; XMSS_N],
+            sk_prf: [0; XMSS_N],
+            pub_seed: [0; XMSS_N],
+        };
+        thread_rng().fill_bytes(&mut key.sk_seed);
+        thread_rng().fill_bytes(&mut key.sk_prf);
+        thread_rng().fill_bytes(&mut key.pub_seed);
+        let mut signer = Self::with_key(key, 0, store);
+        let state = signer.encode_state();
+        signer.store.store(&state)?;
+        Ok(signer)
+    }
+
+    /// Resume signing with the key held in `store`
+    pub fn load(mut store: S) -> Result<Self, LmsError> {
+        let state = store.load()?.ok_or(LmsError::NoState)?;
+        let (key, next) = decode_state(&state).ok_or(LmsError::CorruptState)?;
+        Ok(Self::with_key(key, next, store))
+    }
+
+    fn with_key(key: XmssMtKey, next: u64, store: S) -> Self {
+        let xmss = key.xmss;
+        let top = xmss.d() - 1;
+        let tree = XmssTree::generate(
+            &key.sk_seed,
+            &key.pub_seed,
+            Address::new(top as u32, 0),
+            xmss.tree_height(),
+        );
+        let mut public_key = xmss.oid().to_be_bytes().to_vec();
+        public_key.extend_from_slice(tree.root());
+        public_key.extend_from_slice(&key.pub_seed);
+        let mut trees: Vec<_> = (0..xmss.d()).map(|_| None).collect();
+        trees[top] = Some((0, tree));
+        Self {
+            key,
+            next,
+            store,
+            trees,
+            public_key: XmssMtPublicKey(public_key),
+        }
+    }
+
+    pub fn public_key(&self) -> &XmssMtPublicKey {
+        &self.public_key
+    }
+
+    /// Total number of signatures the key can produce
+    pub fn capacity(&self) -> u64 {
+        1 << self.key.xmss.h()
+    }
+
+    /// Number of signatures that can still be produced
+    pub fn remaining(&self) -> u64 {
+        self.capacity() - self.next
+    }
+
+    /// Sign `message` with the next unused one-time key
+    ///
+    /// The index is consumed even if persisting it fails, so a failed call
+    /// never leads to reuse; the signer must not be used after such an error
+    /// until the store is healthy again.
+    pub fn sign(&mut self, message: &[u8]) -> Result<Vec<u8>, LmsError> {
+        let index = self.next;
+        if index >= self.capacity() {
+            return Err(LmsError::Exhausted);
+        }
+        self.next = index + 1;
+        let state = self.encode_state();
+        self.store.store(&state)?;
+        Ok(self.sign_at(index, message))
+    }
+
+    fn sign_at(&mut self, index: u64, message: &[u8]) -> Vec<u8> {
+        let key = &self.key;
+        let xmss = key.xmss;
+        let root = &self.public_key.0[4..4 + XMSS_N];
+        let r = xmss::prf(&key.sk_prf, &xmss::to_byte(index, 32));
+        let mut node = xmss::h_msg(&r, root, index, message);
+
+        let mut signature = xmss::to_byte(index, xmss.index_len());
+        signature.extend(r);
+        for layer in 0..xmss.d() {
+            let (leaf, tree) = position(xmss, index, layer);
+            let cached = matches!(&self.trees[layer], Some((t, _)) if *t == tree);
+            if !cached {
+                let address = Address::new(layer as u32, tree);
+                let generated =
+                    XmssTree::generate(&key.sk_seed, &key.pub_seed, address, xmss.tree_height());
+                self.trees[layer] = Some((tree, generated));
+            }
+            let (_, tree) = self.trees[layer].as_ref().unwrap();
+            signature.extend(tree.sign(&key.sk_seed, &key.pub_seed, leaf, &node));
+            node = tree.root().to_vec();
+        }
+        signature
+    }
+
+    fn encode_state(&self) -> Vec<u8> {
+        let key = &self.key;
+        let mut state = STATE_MAGIC.to_vec();
+        state.extend_from_slice(&key.xmss.oid().to_be_bytes());
+        state.extend_from_slice(&key.sk_seed);
+        state.extend_from_slice(&key.sk_prf);
+        state.extend_from_slice(&key.pub_seed);
+        state.extend_from_slice(&self.next.to_be_bytes());
+        let checksum = Sha3_256::digest(&state);
+        state.extend_from_slice(&checksum[..STATE_CHECKSUM_LEN]);
+        state
+    }
+}
+
+fn decode_state(state: &[u8]) -> Option<(XmssMtKey, u64)> {
+    let (body, checksum) = state.split_at(state.len().checked_sub(STATE_CHECKSUM_LEN)?);
+    if Sha3_256::digest(body)[..STATE_CHECKSUM_LEN] != *checksum || !body.starts_with(STATE_MAGIC) {
+        return None;
+    }
+    let body = &body[STATE_MAGIC.len()..];
+    if body.len() != 4 + 3 * XMSS_N + 8 {
+        return None;
+    }
+    let xmss = XmssMtType::from_oid(u32::from_be_bytes(body[..4].try_into().ok()?))?;
+    let seed = |i: usize| {
+        body[4 + i * XMSS_N..4 + (i + 1) * XMSS_N]
+            .try_into()
+            .unwrap()
+    };
+    let key = XmssMtKey {
+        xmss,
+        sk_seed: seed(0),
+        sk_prf: seed(1),
+        pub_seed: seed(2),
+    };
+    let next = u64::from_be_bytes(body[4 + 3 * XMSS_N..].try_into().ok()?);
+    (next <= 1 << xmss.h()).then_some((key, next))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::lms::{FileStateStore, MemoryStateStore};
+
+    const PARAMETERS: XmssMtType = XmssMtType::ShakeN32H20D4;
+
+    fn index(signature: &[u8]) -> u32 {
+        u32::from_be_bytes([0, signature[0], signature[1], signature[2]])
+    }
+
+    #[test]
+    fn test_sign_across_trees() {
+        let mut signer = XmssMtSigner::generate(PARAMETERS, MemoryStateStore::new()).unwrap();
+        let public_key = XmssMtPublicKey::from_bytes(&signer.public_key().to_bytes()).unwrap();
+        assert_eq!(public_key.parameters(), PARAMETERS);
+        assert_eq!(signer.capacity(), 1 << 20);
+
+        // Index 32 is the first leaf of the second bottom tree
+        let mut last = Vec::new();
+        for i in 0..33 {
+            let message = format!("firmware {}", i);
+            last = signer.sign(message.as_bytes()).unwrap();
+            assert_eq!(last.len(), PARAMETERS.signature_len());
+            assert_eq!(index(&last), i);
+            assert!(
+                public_key.verify(message.as_bytes(), &last),
+                "signature {}",
+                i
+            );
+        }
+        assert!(!public_key.verify(b"firmware 31", &last));
+
+        let mut tampered = last.clone();
+        tampered[3000] ^= 1;
+        assert!(!public_key.verify(b"firmware 32", &tampered));
+        let mut reindexed = last.clone();
+        reindexed[2] = 31;
+        assert!(!public_key.verify(b"firmware 32", &reindexed));
+        assert!(!public_key.verify(b"firmware 32", &last[..last.len() - 1]));
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let xmss = XmssMtType::ShakeN32H60D12;
+        let mut public_key = xmss.oid().to_be_bytes().to_vec();
+        public_key.extend([0u8; 2 * XMSS_N]);
+        let public_key = XmssMtPublicKey::from_bytes(&public_key).unwrap();
+        let mut signature = vec![0u8; xmss.signature_len()];
+        signature[0] = 0x10;
+        assert!(!public_key.verify(b"m", &signature));
+        assert!(XmssMtPublicKey::from_bytes(&[0, 0, 0, 0x31]).is_none());
+    }
+
+    #[test]
+    fn test_file_state_store() {
+        let dir = std::env::temp_dir().join(format!("qsf-xmss-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("root.xmss");
+
+        let mut signer = XmssMtSigner::generate(PARAMETERS, FileStateStore::new(&path)).unwrap();
+        let public_key = signer.public_key().clone();
+        signer.sign(b"release 1.0").unwrap();
+        drop(signer);
+
+        assert!(matches!(
+            XmssMtSigner::generate(PARAMETERS, FileStateStore::new(&path)),
+            Err(LmsError::StateExists)
+        ));
+        let mut signer = XmssMtSigner::load(FileStateStore::new(&path)).unwrap();
+        assert_eq!(signer.public_key(), &public_key);
+        assert_eq!(signer.remaining(), (1 << 20) - 1);
+        let signature = signer.sign(b"release 1.1").unwrap();
+        assert_eq!(index(&signature), 1);
+        assert!(public_key.verify(b"release 1.1", &signature));
+
+        let mut state = std::fs::read(&path).unwrap();
+        state[20] ^= 1;
+        std::fs::write(&path, state).unwrap();
+        assert!(matches!(
+            XmssMtSigner::load(FileStateStore::new(&path)),
+            Err(LmsError::CorruptState)
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
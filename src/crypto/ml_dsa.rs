@@ -0,0 +1,857 @@
+//! ML-DSA (FIPS 204) signatures
+//!
+//! Written from the standard over the `sha3` crate's SHAKE functions. A
+//! private key is the 32-byte seed ξ and is expanded again for every
+//! signature; public keys and signatures use the FIPS 204 encodings, which are
+//! also what X.509 carries. Signing is hedged with 32 bytes from the caller's
+//! random number generator. Verification does not allocate.
+
+#[cfg(feature = "alloc")]
+use super::lib::CryptoError;
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+use core::fmt;
+#[cfg(feature = "alloc")]
+use rand::{CryptoRng, RngCore};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::{Shake128, Shake256};
+#[cfg(feature = "alloc")]
+use zeroize::Zeroize;
+
+const N: usize = 256;
+const Q: i32 = 8_380_417;
+const D: u32 = 13;
+/// Largest `k` and `l` over the parameter sets
+const MAX_K: usize = 8;
+const MAX_L: usize = 7;
+#[cfg(feature = "alloc")]
+const MAX_PUBLIC_KEY_LEN: usize = 32 + 320 * MAX_K;
+/// Bits per coefficient of `t1` in a public key
+const T1_BITS: u32 = 10;
+/// 256⁻¹ mod q, the scaling of the inverse NTT
+const N_INV: i32 = 8_347_681;
+
+#[cfg(feature = "alloc")]
+/// Length of a private key, the seed ξ
+pub(crate) const SEED_LEN: usize = 32;
+
+type Poly = [i32; N];
+
+/// ζ^BitRev8(k) mod q for the primitive 512th root of unity ζ = 1753
+const ZETAS: [i32; N] = {
+    let mut zetas = [0; N];
+    let mut k = 0;
+    while k < N {
+        let mut exponent = (k as u8).reverse_bits();
+        let mut base = 1753i64;
+        let mut zeta = 1i64;
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                zeta = zeta * base % Q as i64;
+            }
+            base = base * base % Q as i64;
+            exponent >>= 1;
+        }
+        zetas[k] = zeta as i32;
+        k += 1;
+    }
+    zetas
+};
+
+/// ML-DSA parameter set
+#[derive(Debug)]
+pub(crate) struct Params {
+    name: &'static str,
+    k: usize,
+    l: usize,
+    #[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+    eta: i32,
+    tau: usize,
+    /// Length of the commitment hash c̃, λ/4 bytes
+    c_tilde_len: usize,
+    /// γ1 = 2^gamma1_bits
+    gamma1_bits: u32,
+    gamma2: i32,
+    beta: i32,
+    omega: usize,
+}
+
+pub(crate) const ML_DSA_44: Params = Params {
+    name: "ML-DSA-44",
+    k: 4,
+    l: 4,
+    eta: 2,
+    tau: 39,
+    c_tilde_len: 32,
+    gamma1_bits: 17,
+    gamma2: (Q - 1) / 88,
+    beta: 78,
+    omega: 80,
+};
+
+pub(crate) const ML_DSA_65: Params = Params {
+    name: "ML-DSA-65",
+    k: 6,
+    l: 5,
+    eta: 4,
+    tau: 49,
+    c_tilde_len: 48,
+    gamma1_bits: 19,
+    gamma2: (Q - 1) / 32,
+    beta: 196,
+    omega: 55,
+};
+
+pub(crate) const ML_DSA_87: Params = Params {
+    name: "ML-DSA-87",
+    k: 8,
+    l: 7,
+    eta: 2,
+    tau: 60,
+    c_tilde_len: 64,
+    gamma1_bits: 19,
+    gamma2: (Q - 1) / 32,
+    beta: 120,
+    omega: 75,
+};
+
+impl Params {
+    pub(crate) const fn public_key_len(&self) -> usize {
+        32 + 320 * self.k
+    }
+
+    pub(crate) const fn signature_len(&self) -> usize {
+        self.c_tilde_len + self.l * 32 * (self.gamma1_bits as usize + 1) + self.omega + self.k
+    }
+
+    const fn gamma1(&self) -> i32 {
+        1 << self.gamma1_bits
+    }
+
+    /// Bits per coefficient of an encoded `w1`
+    const fn w1_bits(&self) -> u32 {
+        if self.gamma2 == (Q - 1) / 88 {
+            6
+        } else {
+            4
+        }
+    }
+}
+
+fn shake128(parts: &[&[u8]]) -> impl XofReader {
+    let mut hasher = Shake128::default();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize_xof()
+}
+
+fn shake256(parts: &[&[u8]]) -> impl XofReader {
+    let mut hasher = Shake256::default();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize_xof()
+}
+
+fn add(a: i32, b: i32) -> i32 {
+    let sum = a + b;
+    if sum >= Q {
+        sum - Q
+    } else {
+        sum
+    }
+}
+
+fn sub(a: i32, b: i32) -> i32 {
+    let difference = a - b;
+    if difference < 0 {
+        difference + Q
+    } else {
+        difference
+    }
+}
+
+fn mul(a: i32, b: i32) -> i32 {
+    (a as i64 * b as i64 % Q as i64) as i32
+}
+
+#[cfg(feature = "alloc")]
+/// Representative of `a` mod q in (-q/2, q/2]
+fn centered(a: i32) -> i32 {
+    if a > (Q - 1) / 2 {
+        a - Q
+    } else {
+        a
+    }
+}
+
+/// Representative in [0, q) of a value in (-q, q)
+fn reduced(a: i32) -> i32 {
+    if a < 0 {
+        a + Q
+    } else {
+        a
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn infinity_norm(a: &Poly) -> i32 {
+    a.iter().map(|&c| centered(c).abs()).max().unwrap_or(0)
+}
+
+fn ntt(w: &mut Poly) {
+    let mut m = 0;
+    let mut len = 128;
+    while len >= 1 {
+        let mut start = 0;
+        while start < N {
+            m += 1;
+            let zeta = ZETAS[m];
+            for j in start..start + len {
+                let t = mul(zeta, w[j + len]);
+                w[j + len] = sub(w[j], t);
+                w[j] = add(w[j], t);
+            }
+            start += 2 * len;
+        }
+        len /= 2;
+    }
+}
+
+fn inverse_ntt(w: &mut Poly) {
+    let mut m = N;
+    let mut len = 1;
+    while len < N {
+        let mut start = 0;
+        while start < N {
+            m -= 1;
+            let zeta = Q - ZETAS[m];
+            for j in start..start + len {
+                let t = w[j];
+                w[j] = add(t, w[j + len]);
+                w[j + len] = mul(zeta, sub(t, w[j + len]));
+            }
+            start += 2 * len;
+        }
+        len *= 2;
+    }
+    for c in w.iter_mut() {
+        *c = mul(*c, N_INV);
+    }
+}
+
+fn pointwise(a: &Poly, b: &Poly) -> Poly {
+    let mut product = [0; N];
+    for i in 0..N {
+        product[i] = mul(a[i], b[i]);
+    }
+    product
+}
+
+/// Pack `bits`-bit values least significant bit first (SimpleBitPack)
+fn pack(values: impl Iterator<Item = u32>, bits: u32, out: &mut [u8]) {
+    let mut accumulator = 0u64;
+    let mut held = 0;
+    let mut i = 0;
+    for value in values {
+        accumulator |= (value as u64) << held;
+        held += bits;
+        while held >= 8 {
+            out[i] = accumulator as u8;
+            accumulator >>= 8;
+            held -= 8;
+            i += 1;
+        }
+    }
+}
+
+/// Inverse of `pack` for one polynomial
+fn unpack(input: &[u8], bits: u32) -> [u32; N] {
+    let mut values = [0; N];
+    let mut accumulator = 0u64;
+    let mut held = 0;
+    let mut bytes = input.iter();
+    for value in values.iter_mut() {
+        while held < bits {
+            accumulator |= (*bytes.next().unwrap_or(&0) as u64) << held;
+            held += 8;
+        }
+        *value = (accumulator & ((1 << bits) - 1)) as u32;
+        accumulator >>= bits;
+        held -= bits;
+    }
+    values
+}
+
+/// Entry `(row, col)` of the matrix Â, in NTT form (RejNTTPoly)
+fn sample_ntt(rho: &[u8], row: usize, col: usize) -> Poly {
+    let mut xof = shake128(&[rho, &[col as u8, row as u8]]);
+    let mut a = [0; N];
+    let mut j = 0;
+    let mut bytes = [0u8; 3];
+    while j < N {
+        xof.read(&mut bytes);
+        let z = bytes[0] as i32 | (bytes[1] as i32) << 8 | ((bytes[2] & 0x7f) as i32) << 16;
+        if z < Q {
+            a[j] = z;
+            j += 1;
+        }
+    }
+    a
+}
+
+#[cfg(feature = "alloc")]
+/// Polynomial with coefficients in [-η, η] (RejBoundedPoly)
+fn sample_bounded(rho_prime: &[u8], index: u16, eta: i32) -> Poly {
+    let mut xof = shake256(&[rho_prime, &index.to_le_bytes()]);
+    let mut a = [0; N];
+    let mut j = 0;
+    let mut byte = [0u8; 1];
+    while j < N {
+        xof.read(&mut byte);
+        for half in [byte[0] & 0x0f, byte[0] >> 4] {
+            let coefficient = match (eta, half) {
+                (2, b) if b < 15 => 2 - (b % 5) as i32,
+                (4, b) if b < 9 => 4 - b as i32,
+                _ => continue,
+            };
+            if j < N {
+                a[j] = reduced(coefficient);
+                j += 1;
+            }
+        }
+    }
+    a
+}
+
+/// Challenge with τ coefficients of ±1 (SampleInBall)
+fn sample_in_ball(c_tilde: &[u8], tau: usize) -> Poly {
+    let mut xof = shake256(&[c_tilde]);
+    let mut signs = [0u8; 8];
+    xof.read(&mut signs);
+    let signs = u64::from_le_bytes(signs);
+    let mut c = [0; N];
+    let mut byte = [0u8; 1];
+    for i in N - tau..N {
+        loop {
+            xof.read(&mut byte);
+            if byte[0] as usize <= i {
+                break;
+            }
+        }
+        let j = byte[0] as usize;
+        c[i] = c[j];
+        c[j] = if (signs >> (i + tau - N)) & 1 == 1 {
+            Q - 1
+        } else {
+            1
+        };
+    }
+    c
+}
+
+/// Row `row` of Â ∘ `v_hat`
+fn matrix_row(params: &Params, rho: &[u8], row: usize, v_hat: &[Poly]) -> Poly {
+    let mut sum = [0; N];
+    for (col, v) in v_hat.iter().enumerate().take(params.l) {
+        let product = pointwise(&sample_ntt(rho, row, col), v);
+        for i in 0..N {
+            sum[i] = add(sum[i], product[i]);
+        }
+    }
+    sum
+}
+
+/// `(r1, r0)` with r = r1·2γ2 + r0 (Decompose)
+fn decompose(r: i32, gamma2: i32) -> (i32, i32) {
+    let mut r0 = r % (2 * gamma2);
+    if r0 > gamma2 {
+        r0 -= 2 * gamma2;
+    }
+    if r - r0 == Q - 1 {
+        (0, r0 - 1)
+    } else {
+        ((r - r0) / (2 * gamma2), r0)
+    }
+}
+
+fn use_hint(hint: bool, r: i32, gamma2: i32) -> i32 {
+    let m = (Q - 1) / (2 * gamma2);
+    let (r1, r0) = decompose(r, gamma2);
+    match (hint, r0 > 0) {
+        (false, _) => r1,
+        (true, true) => (r1 + 1) % m,
+        (true, false) => (r1 - 1 + m) % m,
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Secret key material derived from a seed
+struct Expanded {
+    key: [u8; 32],
+    tr: [u8; 64],
+    rho: [u8; 32],
+    s1_hat: [Poly; MAX_L],
+    s2_hat: [Poly; MAX_K],
+    t0_hat: [Poly; MAX_K],
+}
+
+#[cfg(feature = "alloc")]
+impl Drop for Expanded {
+    fn drop(&mut self) {
+        self.key.zeroize();
+        self.s1_hat.zeroize();
+        self.s2_hat.zeroize();
+        self.t0_hat.zeroize();
+    }
+}
+
+#[cfg(feature = "alloc")]
+/// Derive the key pair of `seed` (ML-DSA.KeyGen_internal), writing the public
+/// key to `public_key`
+fn expand(params: &Params, seed: &[u8; SEED_LEN], public_key: &mut [u8]) -> Expanded {
+    let mut h = [0u8; 128];
+    shake256(&[seed, &[params.k as u8, params.l as u8]]).read(&mut h);
+    let mut expanded = Expanded {
+        key: [0; 32],
+        tr: [0; 64],
+        rho: [0; 32],
+        s1_hat: [[0; N]; MAX_L],
+        s2_hat: [[0; N]; MAX_K],
+        t0_hat: [[0; N]; MAX_K],
+    };
+    expanded.rho.copy_from_slice(&h[..32]);
+    expanded.key.copy_from_slice(&h[96..]);
+    let rho_prime = &h[32..96];
+
+    for r in 0..params.l {
+        expanded.s1_hat[r] = sample_bounded(rho_prime, r as u16, params.eta);
+        ntt(&mut expanded.s1_hat[r]);
+    }
+    public_key[..32].copy_from_slice(&expanded.rho);
+    for r in 0..params.k {
+        let mut s2 = sample_bounded(rho_prime, (params.l + r) as u16, params.eta);
+        let mut t = matrix_row(params, &expanded.rho, r, &expanded.s1_hat);
+        inverse_ntt(&mut t);
+        let mut t1 = [0u32; N];
+        for i in 0..N {
+            // Power2Round
+            let value = add(t[i], s2[i]);
+            let mut t0 = value & ((1 << D) - 1);
+            if t0 > 1 << (D - 1) {
+                t0 -= 1 << D;
+            }
+            t1[i] = ((value - t0) >> D) as u32;
+            expanded.t0_hat[r][i] = reduced(t0);
+        }
+        pack(
+            t1.into_iter(),
+            T1_BITS,
+            &mut public_key[32 + 320 * r..32 + 320 * (r + 1)],
+        );
+        ntt(&mut s2);
+        expanded.s2_hat[r] = s2;
+        ntt(&mut expanded.t0_hat[r]);
+        t.zeroize();
+    }
+    shake256(&[&public_key[..params.public_key_len()]]).read(&mut expanded.tr);
+    h.zeroize();
+    expanded
+}
+
+/// μ = H(tr || M') for the pure ML-DSA message M' = 0 || |ctx| || ctx || M
+fn message_representative(tr: &[u8], context: &[u8], message: &[u8]) -> [u8; 64] {
+    let mut mu = [0u8; 64];
+    shake256(&[tr, &[0, context.len() as u8], context, message]).read(&mut mu);
+    mu
+}
+
+#[cfg(feature = "alloc")]
+/// ML-DSA.Sign_internal over the pure message with `context`, into a buffer
+/// of exactly the signature length
+fn sign_internal(
+    params: &Params,
+    seed: &[u8; SEED_LEN],
+    context: &[u8],
+    message: &[u8],
+    rnd: &[u8; 32],
+    signature: &mut [u8],
+) {
+    let mut public_key = [0u8; MAX_PUBLIC_KEY_LEN];
+    let sk = expand(params, seed, &mut public_key);
+    let mu = message_representative(&sk.tr, context, message);
+    let mut rho_prime = [0u8; 64];
+    shake256(&[&sk.key, rnd, &mu]).read(&mut rho_prime);
+
+    let gamma1 = params.gamma1();
+    let z_bits = params.gamma1_bits + 1;
+    let z_len = 32 * z_bits as usize;
+    let w1_len = 32 * params.w1_bits() as usize;
+    let mut kappa = 0u16;
+    let mut y = [[0; N]; MAX_L];
+    let mut z = [[0; N]; MAX_L];
+    let mut w = [[0; N]; MAX_K];
+    let mut hints = [[false; N]; MAX_K];
+    loop {
+        // ExpandMask
+        for (r, y) in y.iter_mut().enumerate().take(params.l) {
+            let mut bytes = [0u8; 32 * 20];
+            shake256(&[&rho_prime, &kappa.wrapping_add(r as u16).to_le_bytes()])
+                .read(&mut bytes[..z_len]);
+            for (c, v) in y.iter_mut().zip(unpack(&bytes[..z_len], z_bits)) {
+                *c = reduced(gamma1 - v as i32);
+            }
+        }
+        kappa = kappa.wrapping_add(params.l as u16);
+
+        let mut y_hat = y;
+        for poly in y_hat.iter_mut().take(params.l) {
+            ntt(poly);
+        }
+        let mut w1_bytes = [0u8; 32 * 6 * MAX_K];
+        for r in 0..params.k {
+            w[r] = matrix_row(params, &sk.rho, r, &y_hat);
+            inverse_ntt(&mut w[r]);
+            pack(
+                w[r].iter().map(|&c| decompose(c, params.gamma2).0 as u32),
+                params.w1_bits(),
+                &mut w1_bytes[w1_len * r..w1_len * (r + 1)],
+            );
+        }
+        y_hat.zeroize();
+
+        let c_tilde = &mut signature[..params.c_tilde_len];
+        shake256(&[&mu, &w1_bytes[..w1_len * params.k]]).read(c_tilde);
+        let mut c_hat = sample_in_ball(c_tilde, params.tau);
+        ntt(&mut c_hat);
+
+        let mut rejected = false;
+        for r in 0..params.l {
+            let mut cs1 = pointwise(&c_hat, &sk.s1_hat[r]);
+            inverse_ntt(&mut cs1);
+            for i in 0..N {
+                z[r][i] = add(y[r][i], cs1[i]);
+            }
+            rejected |= infinity_norm(&z[r]) >= gamma1 - params.beta;
+        }
+        let mut hint_count = 0;
+        for r in 0..params.k {
+            if rejected {
+                break;
+            }
+            let mut cs2 = pointwise(&c_hat, &sk.s2_hat[r]);
+            inverse_ntt(&mut cs2);
+            let mut ct0 = pointwise(&c_hat, &sk.t0_hat[r]);
+            inverse_ntt(&mut ct0);
+            rejected |= infinity_norm(&ct0) >= params.gamma2;
+            for i in 0..N {
+                let low = sub(w[r][i], cs2[i]);
+                rejected |= decompose(low, params.gamma2).1.abs() >= params.gamma2 - params.beta;
+                hints[r][i] =
+                    decompose(add(low, ct0[i]), params.gamma2).0 != decompose(low, params.gamma2).0;
+                hint_count += hints[r][i] as usize;
+            }
+        }
+        if rejected || hint_count > params.omega {
+            continue;
+        }
+
+        let mut offset = params.c_tilde_len;
+        for poly in z.iter().take(params.l) {
+            pack(
+                poly.iter().map(|&c| (gamma1 - centered(c)) as u32),
+                z_bits,
+                &mut signature[offset..offset + z_len],
+            );
+            offset += z_len;
+        }
+        // HintBitPack
+        let hint_bytes = &mut signature[offset..];
+        hint_bytes.fill(0);
+        let mut index = 0;
+        for r in 0..params.k {
+            for (i, &hint) in hints[r].iter().enumerate() {
+                if hint {
+                    hint_bytes[index] = i as u8;
+                    index += 1;
+                }
+            }
+            hint_bytes[params.omega + r] = index as u8;
+        }
+        break;
+    }
+    y.zeroize();
+    z.zeroize();
+    rho_prime.zeroize();
+}
+
+/// Decode the hint vector of a signature (HintBitUnpack)
+fn decode_hints(params: &Params, bytes: &[u8], hints: &mut [[bool; N]; MAX_K]) -> bool {
+    let mut index = 0;
+    for (r, hint) in hints.iter_mut().enumerate().take(params.k) {
+        let end = bytes[params.omega + r] as usize;
+        if end < index || end > params.omega {
+            return false;
+        }
+        let first = index;
+        while index < end {
+            if index > first && bytes[index - 1] >= bytes[index] {
+                return false;
+            }
+            hint[bytes[index] as usize] = true;
+            index += 1;
+        }
+    }
+    bytes[index..params.omega].iter().all(|&b| b == 0)
+}
+
+/// Public key decoded once for repeated verification
+#[derive(Clone)]
+pub(crate) struct Verifier {
+    params: &'static Params,
+    rho: [u8; 32],
+    tr: [u8; 64],
+    /// NTT(t1 · 2^d)
+    t1_hat: [Poly; MAX_K],
+}
+
+impl fmt::Debug for Verifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Verifier")
+            .field("params", &self.params.name)
+            .finish()
+    }
+}
+
+impl Verifier {
+    pub(crate) fn new(params: &'static Params, public_key: &[u8]) -> Option<Self> {
+        if public_key.len() != params.public_key_len() {
+            return None;
+        }
+        let mut verifier = Verifier {
+            params,
+            rho: [0; 32],
+            tr: [0; 64],
+            t1_hat: [[0; N]; MAX_K],
+        };
+        verifier.rho.copy_from_slice(&public_key[..32]);
+        shake256(&[public_key]).read(&mut verifier.tr);
+        for (r, t1_hat) in verifier.t1_hat.iter_mut().enumerate().take(params.k) {
+            let t1 = unpack(&public_key[32 + 320 * r..32 + 320 * (r + 1)], T1_BITS);
+            for (c, v) in t1_hat.iter_mut().zip(t1) {
+                *c = (v << D) as i32;
+            }
+            ntt(t1_hat);
+        }
+        Some(verifier)
+    }
+
+    /// Verify a pure ML-DSA signature with an empty context
+    pub(crate) fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        self.verify_with_context(&[], message, signature)
+    }
+
+    /// ML-DSA.Verify with context string `context`
+    pub(crate) fn verify_with_context(
+        &self,
+        context: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> bool {
+        let params = self.params;
+        if signature.len() != params.signature_len() || context.len() > 255 {
+            return false;
+        }
+        let gamma1 = params.gamma1();
+        let z_bits = params.gamma1_bits + 1;
+        let z_len = 32 * z_bits as usize;
+        let (c_tilde, rest) = signature.split_at(params.c_tilde_len);
+        let (z_bytes, hint_bytes) = rest.split_at(z_len * params.l);
+
+        let mut z_hat = [[0; N]; MAX_L];
+        for (r, z) in z_hat.iter_mut().enumerate().take(params.l) {
+            for (c, v) in z
+                .iter_mut()
+                .zip(unpack(&z_bytes[z_len * r..z_len * (r + 1)], z_bits))
+            {
+                let value = gamma1 - v as i32;
+                if value.abs() >= gamma1 - params.beta {
+                    return false;
+                }
+                *c = reduced(value);
+            }
+            ntt(z);
+        }
+        let mut hints = [[false; N]; MAX_K];
+        if !decode_hints(params, hint_bytes, &mut hints) {
+            return false;
+        }
+
+        let mu = message_representative(&self.tr, context, message);
+        let mut c_hat = sample_in_ball(c_tilde, params.tau);
+        ntt(&mut c_hat);
+        let w1_len = 32 * params.w1_bits() as usize;
+        let mut w1_bytes = [0u8; 32 * 6 * MAX_K];
+        for r in 0..params.k {
+            let mut w = matrix_row(params, &self.rho, r, &z_hat);
+            let ct1 = pointwise(&c_hat, &self.t1_hat[r]);
+            for i in 0..N {
+                w[i] = sub(w[i], ct1[i]);
+            }
+            inverse_ntt(&mut w);
+            pack(
+                w.iter()
+                    .zip(hints[r])
+                    .map(|(&c, hint)| use_hint(hint, c, params.gamma2) as u32),
+                params.w1_bits(),
+                &mut w1_bytes[w1_len * r..w1_len * (r + 1)],
+            );
+        }
+        let mut expected = [0u8; 64];
+        shake256(&[&mu, &w1_bytes[..w1_len * params.k]]).read(&mut expected[..params.c_tilde_len]);
+        expected[..params.c_tilde_len] == *c_tilde
+    }
+}
+
+/// Public key of the seed `private_key`
+#[cfg(feature = "alloc")]
+pub(crate) fn public_key(params: &Params, private_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let seed =
+        <&[u8; SEED_LEN]>::try_from(private_key).map_err(|_| CryptoError::InvalidPrivateKey)?;
+    let mut public_key = vec![0u8; params.public_key_len()];
+    expand(params, seed, &mut public_key);
+    Ok(public_key)
+}
+
+/// Generate a `(public_key, private_key)` pair
+#[cfg(feature = "alloc")]
+pub(crate) fn generate<R: CryptoRng + RngCore>(params: &Params, rng: &mut R) -> (Vec<u8>, Vec<u8>) {
+    let mut seed = vec![0u8; SEED_LEN];
+    rng.fill_bytes(&mut seed);
+    let public_key = public_key(params, &seed).expect("seed has the right length");
+    (public_key, seed)
+}
+
+/// Sign `message` with `context` (ML-DSA.Sign), hedged with randomness from `rng`
+#[cfg(feature = "alloc")]
+pub(crate) fn sign<R: CryptoRng + RngCore>(
+    params: &Params,
+    private_key: &[u8],
+    context: &[u8],
+    message: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>, CryptoError> {
+    let seed =
+        <&[u8; SEED_LEN]>::try_from(private_key).map_err(|_| CryptoError::InvalidPrivateKey)?;
+    debug_assert!(context.len() <= 255);
+    let mut rnd = [0u8; 32];
+    rng.fill_bytes(&mut rnd);
+    let mut signature = vec![0u8; params.signature_len()];
+    sign_internal(params, seed, context, message, &rnd, &mut signature);
+    Ok(signature)
+}
+
+/// Check that `private_key` is a seed whose public key is `public_key`
+#[cfg(feature = "alloc")]
+pub(crate) fn matches(params: &Params, public_key: &[u8], private_key: &[u8]) -> bool {
+    self::public_key(params, private_key).is_ok_and(|derived| derived == public_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{verify_with_public_key as verify, Algorithm};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SETS: [(Algorithm, &Params); 3] = [
+        (Algorithm::MlDsa44, &ML_DSA_44),
+        (Algorithm::MlDsa65, &ML_DSA_65),
+        (Algorithm::MlDsa87, &ML_DSA_87),
+    ];
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_ntt_roundtrip() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut poly = [0; N];
+        for c in poly.iter_mut() {
+            *c = (rng.next_u32() % Q as u32) as i32;
+        }
+        let original = poly;
+        ntt(&mut poly);
+        assert_ne!(poly, original);
+        inverse_ntt(&mut poly);
+        assert_eq!(poly, original);
+    }
+
+    /// Keys, deterministic signatures and context strings generated with
+    /// OpenSSL 3.5 (`-pkeyopt hexseed:` and `-pkeyopt deterministic:1`)
+    #[test]
+    fn test_openssl_vectors() {
+        let vectors: serde_json::Value =
+            serde_json::from_str(include_str!("../../tests/vectors/ml_dsa.json")).unwrap();
+        for vector in vectors["vectors"].as_array().unwrap() {
+            let tag = vector["algorithm"].as_str().unwrap();
+            let (_, params) = SETS.into_iter().find(|(a, _)| a.tag() == tag).unwrap();
+            let seed = hex(vector["seed"].as_str().unwrap());
+            let expected_public_key = hex(vector["public_key"].as_str().unwrap());
+            let context = hex(vector["context"].as_str().unwrap());
+            let message = hex(vector["message"].as_str().unwrap());
+            let expected = hex(vector["signature"].as_str().unwrap());
+
+            let public_key = public_key(params, &seed).unwrap();
+            assert_eq!(public_key, expected_public_key, "{}", params.name);
+            let mut signature = vec![0u8; params.signature_len()];
+            let seed = seed.as_slice().try_into().unwrap();
+            sign_internal(params, seed, &context, &message, &[0; 32], &mut signature);
+            assert_eq!(signature, expected, "{}", params.name);
+
+            let verifier = Verifier::new(params, &public_key).unwrap();
+            assert!(verifier.verify_with_context(&context, &message, &signature));
+            assert!(!verifier.verify_with_context(b"other", &message, &signature));
+            assert!(!verifier.verify_with_context(&context, b"other", &signature));
+        }
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for (algorithm, params) in SETS {
+            let (public_key, private_key) = generate(params, &mut rng);
+            assert_eq!(public_key.len(), params.public_key_len());
+            assert!(matches(params, &public_key, &private_key));
+
+            let signature = sign(params, &private_key, &[], b"m", &mut rng).unwrap();
+            assert_eq!(signature.len(), params.signature_len());
+            assert!(verify(algorithm, &public_key, b"m", &signature));
+            // Hedged signing gives a fresh signature each time
+            assert_ne!(
+                sign(params, &private_key, &[], b"m", &mut rng).unwrap(),
+                signature
+            );
+
+            let mut tampered = signature.clone();
+            tampered[params.c_tilde_len + 5] ^= 1;
+            assert!(!verify(algorithm, &public_key, b"m", &tampered));
+            // Hint indices out of order
+            let hints = params.signature_len() - params.omega - params.k;
+            let mut tampered = signature.clone();
+            tampered[hints + params.omega] = params.omega as u8 + 1;
+            assert!(!verify(algorithm, &public_key, b"m", &tampered));
+            assert!(!verify(algorithm, &public_key, b"m", &signature[1..]));
+        }
+        let params = &ML_DSA_44;
+        let (public_key, _) = generate(params, &mut rng);
+        let (_, other) = generate(params, &mut rng);
+        assert!(!matches(params, &public_key, &other));
+        assert!(!matches(params, &public_key, &other[1..]));
+    }
+}
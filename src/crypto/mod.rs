@@ -1,3 +1,17 @@
+pub use self::fixed::*;
+pub use self::key_id::*;
 pub use self::lib::*;
+#[cfg(feature = "std")]
+pub use self::shamir::*;
 
+mod composite;
+mod falcon;
+mod fixed;
+mod key_id;
 mod lib;
+#[cfg(feature = "std")]
+pub mod lms;
+mod ml_dsa;
+#[cfg(feature = "std")]
+mod shamir;
+mod slh_dsa;
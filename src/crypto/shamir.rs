@@ -0,0 +1,373 @@
+//! Shamir secret sharing over GF(256) for private key backup
+//!
+//! Each share carries metadata identifying its set, a checksum that detects
+//! corruption of the share itself, and a salted digest of the secret that is
+//! checked after recombination.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::{thread_rng, Rng};
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+use std::str::FromStr;
+
+const VERSION: u8 = 1;
+const SET_ID_LEN: usize = 8;
+const SALT_LEN: usize = 16;
+const DIGEST_LEN: usize = 16;
+const CHECKSUM_LEN: usize = 4;
+const HEADER_LEN: usize = 1 + SET_ID_LEN + 3 + SALT_LEN + DIGEST_LEN + 2;
+
+/// Prefix of the text encoding of a share
+pub const SHARE_PREFIX: &str = "qsf-share-v1:";
+
+/// Shamir secret sharing error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShamirError {
+    /// Threshold or share count out of range, or empty secret
+    InvalidParameters,
+    NotEnoughShares,
+    /// Shares belong to different splits
+    MismatchedShares,
+    DuplicateShare,
+    /// Share failed its integrity check or is malformed
+    CorruptShare,
+    /// Recombined secret does not match the digest in the shares
+    SecretMismatch,
+}
+
+impl fmt::Display for ShamirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            ShamirError::InvalidParameters => "invalid threshold, share count or secret",
+            ShamirError::NotEnoughShares => "not enough shares to recover the secret",
+            ShamirError::MismatchedShares => "shares belong to different splits",
+            ShamirError::DuplicateShare => "duplicate share",
+            ShamirError::CorruptShare => "share is corrupt",
+            ShamirError::SecretMismatch => "recovered secret failed its integrity check",
+        };
+        f.write_str(message)
+    }
+}
+
+impl std::error::Error for ShamirError {}
+
+/// Information shared by all shares of one split
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareMetadata {
+    /// Random identifier of the split
+    pub set_id: [u8; SET_ID_LEN],
+    /// Number of shares needed to recover the secret
+    pub threshold: u8,
+    /// Number of shares produced
+    pub total: u8,
+    salt: [u8; SALT_LEN],
+    digest: [u8; DIGEST_LEN],
+}
+
+/// One share of a split secret
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    pub metadata: ShareMetadata,
+    /// Evaluation point, 1 to 255
+    pub index: u8,
+    pub value: Vec<u8>,
+}
+
+/// Split `secret` into `total` shares, any `threshold` of which recover it
+pub fn split_secret(secret: &[u8], threshold: u8, total: u8) -> Result<Vec<Share>, ShamirError> {
+    if secret.is_empty() || secret.len() > u16::MAX as usize || threshold == 0 || threshold > total
+    {
+        return Err(ShamirError::InvalidParameters);
+    }
+
+    let mut rng = thread_rng();
+    let mut set_id = [0u8; SET_ID_LEN];
+    let mut salt = [0u8; SALT_LEN];
+    rng.fill(&mut set_id);
+    rng.fill(&mut salt);
+    let metadata = ShareMetadata {
+        set_id,
+        threshold,
+        total,
+        salt,
+        digest: secret_digest(&salt, secret),
+    };
+
+    let mut shares: Vec<Share> = (1..=total)
+        .map(|index| Share {
+            metadata,
+            index,
+            value: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+
+    let mut coefficients = vec![0u8; threshold as usize];
+    for &byte in secret {
+        coefficients[0] = byte;
+        rng.fill(&mut coefficients[1..]);
+        for share in &mut shares {
+            share.value.push(evaluate(&coefficients, share.index));
+        }
+    }
+    coefficients.iter_mut().for_each(|c| *c = 0);
+
+    Ok(shares)
+}
+
+/// Recover a secret from at least `threshold` shares of the same split
+pub fn combine_shares(shares: &[Share]) -> Result<Vec<u8>, ShamirError> {
+    let first = shares.first().ok_or(ShamirError::NotEnoughShares)?;
+    let metadata = first.metadata;
+    if shares.len() < metadata.threshold as usize {
+        return Err(ShamirError::NotEnoughShares);
+    }
+    for (i, share) in shares.iter().enumerate() {
+        if share.metadata != metadata || share.value.len() != first.value.len() {
+            return Err(ShamirError::MismatchedShares);
+        }
+        if share.index == 0 {
+            return Err(ShamirError::CorruptShare);
+        }
+        if shares[..i].iter().any(|s| s.index == share.index) {
+            return Err(ShamirError::DuplicateShare);
+        }
+    }
+
+    let shares = &shares[..metadata.threshold as usize];
+    let mut secret = vec![0u8; first.value.len()];
+    for (i, share) in shares.iter().enumerate() {
+        // Lagrange basis polynomial for this share, evaluated at zero
+        let mut basis = 1u8;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                basis = gf_mul(basis, gf_div(other.index, other.index ^ share.index));
+            }
+        }
+        for (byte, &y) in secret.iter_mut().zip(&share.value) {
+            *byte ^= gf_mul(y, basis);
+        }
+    }
+
+    if secret_digest(&metadata.salt, &secret) != metadata.digest {
+        secret.iter_mut().for_each(|b| *b = 0);
+        return Err(ShamirError::SecretMismatch);
+    }
+    Ok(secret)
+}
+
+impl Share {
+    /// Encode the share, followed by its checksum
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let m = &self.metadata;
+        let mut bytes = Vec::with_capacity(HEADER_LEN + self.value.len() + CHECKSUM_LEN);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&m.set_id);
+        bytes.extend_from_slice(&[m.threshold, m.total, self.index]);
+        bytes.extend_from_slice(&m.salt);
+        bytes.extend_from_slice(&m.digest);
+        bytes.extend_from_slice(&(self.value.len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&self.value);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        bytes
+    }
+
+    /// Decode a share produced by `to_bytes`, verifying its checksum
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ShamirError> {
+        if bytes.len() < HEADER_LEN + CHECKSUM_LEN || bytes[0] != VERSION {
+            return Err(ShamirError::CorruptShare);
+        }
+        let (body, sum) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(body) != sum {
+            return Err(ShamirError::CorruptShare);
+        }
+
+        let mut pos = 1;
+        let mut take = |len: usize| {
+            let field = &body[pos..pos + len];
+            pos += len;
+            field
+        };
+        let set_id = take(SET_ID_LEN).try_into().unwrap();
+        let [threshold, total, index]: [u8; 3] = take(3).try_into().unwrap();
+        let salt = take(SALT_LEN).try_into().unwrap();
+        let digest = take(DIGEST_LEN).try_into().unwrap();
+        let len = u16::from_be_bytes(take(2).try_into().unwrap()) as usize;
+        if body.len() != HEADER_LEN + len || index == 0 || threshold == 0 || threshold > total {
+            return Err(ShamirError::CorruptShare);
+        }
+
+        Ok(Share {
+            metadata: ShareMetadata {
+                set_id,
+                threshold,
+                total,
+                salt,
+                digest,
+            },
+            index,
+            value: body[HEADER_LEN..].to_vec(),
+        })
+    }
+}
+
+/// Text encoding: `qsf-share-v1:` followed by URL-safe base64
+impl fmt::Display for Share {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            SHARE_PREFIX,
+            URL_SAFE_NO_PAD.encode(self.to_bytes())
+        )
+    }
+}
+
+impl FromStr for Share {
+    type Err = ShamirError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let encoded = s
+            .trim()
+            .strip_prefix(SHARE_PREFIX)
+            .ok_or(ShamirError::CorruptShare)?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| ShamirError::CorruptShare)?;
+        Share::from_bytes(&bytes)
+    }
+}
+
+fn secret_digest(salt: &[u8], secret: &[u8]) -> [u8; DIGEST_LEN] {
+    let mut hasher = Sha3_256::new();
+    hasher.update(b"quantumsafe-finance/shamir/secret");
+    hasher.update(salt);
+    hasher.update(secret);
+    let mut digest = [0u8; DIGEST_LEN];
+    digest.copy_from_slice(&hasher.finalize()[..DIGEST_LEN]);
+    digest
+}
+
+fn checksum(data: &[u8]) -> [u8; CHECKSUM_LEN] {
+    let mut sum = [0u8; CHECKSUM_LEN];
+    sum.copy_from_slice(&Sha3_256::digest(data)[..CHECKSUM_LEN]);
+    sum
+}
+
+/// Evaluate a polynomial (lowest coefficient first) at `x` with Horner's rule
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0, |acc, &c| gf_mul(acc, x) ^ c)
+}
+
+/// Multiplication in GF(2^8) modulo x^8 + x^4 + x^3 + x + 1, without
+/// secret-dependent branches or table lookups
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (0x1b & carry);
+        b >>= 1;
+    }
+    product
+}
+
+/// Inverse in GF(2^8) as a^254; maps zero to zero
+fn gf_inv(a: u8) -> u8 {
+    let a2 = gf_mul(a, a);
+    let a4 = gf_mul(a2, a2);
+    let a8 = gf_mul(a4, a4);
+    let a16 = gf_mul(a8, a8);
+    let a32 = gf_mul(a16, a16);
+    let a64 = gf_mul(a32, a32);
+    let a128 = gf_mul(a64, a64);
+    [a2, a4, a8, a16, a32, a64]
+        .iter()
+        .fold(a128, |acc, &p| gf_mul(acc, p))
+}
+
+fn gf_div(a: u8, b: u8) -> u8 {
+    gf_mul(a, gf_inv(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::generate_key_pair;
+
+    #[test]
+    fn test_gf_arithmetic() {
+        assert_eq!(gf_mul(0x53, 0xca), 0x01);
+        for a in 1..=255u8 {
+            assert_eq!(gf_mul(a, gf_inv(a)), 1);
+        }
+        assert_eq!(gf_inv(0), 0);
+    }
+
+    #[test]
+    fn test_split_and_combine_private_key() {
+        let key_pair = generate_key_pair();
+        let shares = split_secret(&key_pair.private_key, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let picked: Vec<Share> = subset.iter().map(|&i| shares[i].clone()).collect();
+            assert_eq!(combine_shares(&picked).unwrap(), key_pair.private_key);
+        }
+        assert_eq!(
+            combine_shares(&shares[..2]),
+            Err(ShamirError::NotEnoughShares)
+        );
+    }
+
+    #[test]
+    fn test_text_encoding() {
+        let shares = split_secret(b"seed material", 2, 3).unwrap();
+        let encoded: Vec<String> = shares.iter().map(|s| s.to_string()).collect();
+        assert!(encoded[0].starts_with(SHARE_PREFIX));
+
+        let decoded: Vec<Share> = encoded[1..].iter().map(|s| s.parse().unwrap()).collect();
+        assert_eq!(decoded[0], shares[1]);
+        assert_eq!(combine_shares(&decoded).unwrap(), b"seed material");
+    }
+
+    #[test]
+    fn test_corrupted_share_detected() {
+        let shares = split_secret(b"seed material", 2, 3).unwrap();
+
+        let mut bytes = shares[0].to_bytes();
+        let last = bytes.len() - CHECKSUM_LEN - 1;
+        bytes[last] ^= 0x01;
+        assert_eq!(Share::from_bytes(&bytes), Err(ShamirError::CorruptShare));
+
+        let mut tampered = shares[0].clone();
+        tampered.value[0] ^= 0x01;
+        assert_eq!(
+            combine_shares(&[tampered, shares[1].clone()]),
+            Err(ShamirError::SecretMismatch)
+        );
+    }
+
+    #[test]
+    fn test_mismatched_and_duplicate_shares() {
+        let a = split_secret(b"secret", 2, 3).unwrap();
+        let b = split_secret(b"secret", 2, 3).unwrap();
+        assert_eq!(
+            combine_shares(&[a[0].clone(), b[1].clone()]),
+            Err(ShamirError::MismatchedShares)
+        );
+        assert_eq!(
+            combine_shares(&[a[0].clone(), a[0].clone()]),
+            Err(ShamirError::DuplicateShare)
+        );
+        assert_eq!(
+            split_secret(b"secret", 4, 3),
+            Err(ShamirError::InvalidParameters)
+        );
+        assert_eq!(split_secret(b"", 1, 1), Err(ShamirError::InvalidParameters));
+    }
+}
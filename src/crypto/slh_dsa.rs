@@ -0,0 +1,814 @@
+//! SLH-DSA (FIPS 205) signatures, SHAKE parameter sets
+//!
+//! Written from the standard over the `sha3` crate's SHAKE256. A private key
+//! is SK.seed || SK.prf || PK.seed || PK.root and a public key is
+//! PK.seed || PK.root, the FIPS 205 encodings that X.509 also carries.
+//! Signing is hedged with n bytes from the caller's random number generator.
+//! Verification does not allocate.
+
+#[cfg(feature = "alloc")]
+use super::lib::CryptoError;
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use rand::{CryptoRng, RngCore};
+use sha3::digest::{ExtendableOutput, Update, XofReader};
+use sha3::Shake256;
+
+/// Largest `n`, `k` and WOTS+ `len` over the parameter sets
+const MAX_N: usize = 32;
+const MAX_K: usize = 35;
+const MAX_LEN: usize = 2 * MAX_N + 3;
+/// Winternitz parameter w = 2^4
+const W: u32 = 16;
+
+const WOTS_HASH: u32 = 0;
+const WOTS_PK: u32 = 1;
+const TREE: u32 = 2;
+const FORS_TREE: u32 = 3;
+const FORS_ROOTS: u32 = 4;
+#[cfg(feature = "alloc")]
+const WOTS_PRF: u32 = 5;
+#[cfg(feature = "alloc")]
+const FORS_PRF: u32 = 6;
+
+type Node = [u8; MAX_N];
+
+/// SLH-DSA parameter set
+#[derive(Debug)]
+pub(crate) struct Params {
+    n: usize,
+    /// Total hypertree height
+    h: usize,
+    /// Hypertree layers
+    d: usize,
+    /// Height of one XMSS tree, h / d
+    tree_height: usize,
+    /// FORS tree height
+    a: usize,
+    /// FORS trees
+    k: usize,
+    /// Message digest length in bytes
+    m: usize,
+}
+
+pub(crate) const SLH_DSA_SHAKE_128S: Params = Params {
+    n: 16,
+    h: 63,
+    d: 7,
+    tree_height: 9,
+    a: 12,
+    k: 14,
+    m: 30,
+};
+
+pub(crate) const SLH_DSA_SHAKE_128F: Params = Params {
+    n: 16,
+    h: 66,
+    d: 22,
+    tree_height: 3,
+    a: 6,
+    k: 33,
+    m: 34,
+};
+
+pub(crate) const SLH_DSA_SHAKE_192S: Params = Params {
+    n: 24,
+    h: 63,
+    d: 7,
+    tree_height: 9,
+    a: 14,
+    k: 17,
+    m: 39,
+};
+
+pub(crate) const SLH_DSA_SHAKE_192F: Params = Params {
+    n: 24,
+    h: 66,
+    d: 22,
+    tree_height: 3,
+    a: 8,
+    k: 33,
+    m: 42,
+};
+
+pub(crate) const SLH_DSA_SHAKE_256S: Params = Params {
+    n: 32,
+    h: 64,
+    d: 8,
+    tree_height: 8,
+    a: 14,
+    k: 22,
+    m: 47,
+};
+
+pub(crate) const SLH_DSA_SHAKE_256F: Params = Params {
+    n: 32,
+    h: 68,
+    d: 17,
+    tree_height: 4,
+    a: 9,
+    k: 35,
+    m: 49,
+};
+
+impl Params {
+    /// Chains in a WOTS+ key, 2n + 3 for w = 16
+    const fn len(&self) -> usize {
+        2 * self.n + 3
+    }
+
+    const fn xmss_signature_len(&self) -> usize {
+        (self.len() + self.tree_height) * self.n
+    }
+
+    const fn fors_signature_len(&self) -> usize {
+        self.k * (self.a + 1) * self.n
+    }
+
+    pub(crate) const fn public_key_len(&self) -> usize {
+        2 * self.n
+    }
+
+    #[cfg(feature = "alloc")]
+    pub(crate) const fn private_key_len(&self) -> usize {
+        4 * self.n
+    }
+
+    pub(crate) const fn signature_len(&self) -> usize {
+        self.n + self.fors_signature_len() + self.d * self.xmss_signature_len()
+    }
+}
+
+/// Hash function address (ADRS)
+#[derive(Clone, Copy, Default)]
+struct Address([u8; 32]);
+
+impl Address {
+    fn set(&mut self, offset: usize, value: u32) {
+        self.0[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn set_layer(&mut self, layer: usize) {
+        self.set(0, layer as u32);
+    }
+
+    fn set_tree(&mut self, tree: u64) {
+        self.0[4..8].fill(0);
+        self.0[8..16].copy_from_slice(&tree.to_be_bytes());
+    }
+
+    /// Set the address type and clear the words after it
+    fn set_type(&mut self, kind: u32) {
+        self.set(16, kind);
+        self.0[20..].fill(0);
+    }
+
+    fn set_key_pair(&mut self, key_pair: u32) {
+        self.set(20, key_pair);
+    }
+
+    fn key_pair(&self) -> u32 {
+        u32::from_be_bytes(self.0[20..24].try_into().expect("4 bytes"))
+    }
+
+    /// Chain address of WOTS+ and tree height of XMSS and FORS
+    fn set_chain(&mut self, chain: u32) {
+        self.set(24, chain);
+    }
+
+    /// Hash address of WOTS+ and tree index of XMSS and FORS
+    fn set_hash(&mut self, hash: u32) {
+        self.set(28, hash);
+    }
+
+    fn hash(&self) -> u32 {
+        u32::from_be_bytes(self.0[28..32].try_into().expect("4 bytes"))
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn shake256(parts: &[&[u8]], out: &mut [u8]) {
+    let mut hasher = Shake256::default();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize_xof().read(out);
+}
+
+/// WOTS+ chain positions of an n-byte message followed by its checksum
+fn wots_digits(params: &Params, message: &[u8]) -> [u8; MAX_LEN] {
+    let mut digits = [0u8; MAX_LEN];
+    for (i, byte) in message.iter().enumerate() {
+        digits[2 * i] = byte >> 4;
+        digits[2 * i + 1] = byte & 0x0f;
+    }
+    let len1 = 2 * params.n;
+    let checksum: u32 = digits[..len1].iter().map(|&d| W - 1 - d as u32).sum();
+    // The 12-bit checksum, shifted into two bytes, gives three more digits
+    let checksum = checksum << 4;
+    for (i, digit) in digits[len1..params.len()].iter_mut().enumerate() {
+        *digit = ((checksum >> (12 - 4 * i)) & 0x0f) as u8;
+    }
+    digits
+}
+
+/// FORS leaf indices, `a` bits each (base_2b)
+fn fors_indices(params: &Params, digest: &[u8]) -> [u32; MAX_K] {
+    let mut indices = [0; MAX_K];
+    let mut bytes = digest.iter();
+    let mut total = 0u64;
+    let mut bits = 0;
+    for index in indices.iter_mut().take(params.k) {
+        while bits < params.a {
+            total = total << 8 | *bytes.next().unwrap_or(&0) as u64;
+            bits += 8;
+        }
+        bits -= params.a;
+        *index = ((total >> bits) & ((1 << params.a) - 1)) as u32;
+        total &= (1 << bits) - 1;
+    }
+    indices
+}
+
+/// Split the message digest into FORS message, tree index and leaf index
+fn split_digest<'a>(params: &Params, digest: &'a [u8]) -> (&'a [u8], u64, u32) {
+    let fors_len = (params.k * params.a).div_ceil(8);
+    let tree_bits = params.h - params.tree_height;
+    let tree_len = tree_bits.div_ceil(8);
+    let (fors, rest) = digest.split_at(fors_len);
+    let (tree, leaf) = rest.split_at(tree_len);
+    let tree = tree.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+    let tree = if tree_bits == 64 {
+        tree
+    } else {
+        tree & ((1 << tree_bits) - 1)
+    };
+    let leaf = leaf[..params.tree_height.div_ceil(8)]
+        .iter()
+        .fold(0u32, |acc, &b| acc << 8 | b as u32);
+    (fors, tree, leaf & ((1 << params.tree_height) - 1))
+}
+
+/// Hash functions keyed by PK.seed
+struct Context<'a> {
+    params: &'a Params,
+    pk_seed: &'a [u8],
+}
+
+impl Context<'_> {
+    /// F, H and T_l, which differ only in input length; also PRF when the
+    /// input is SK.seed
+    fn hash(&self, address: &Address, input: &[&[u8]]) -> Node {
+        let mut hasher = Shake256::default();
+        hasher.update(self.pk_seed);
+        hasher.update(&address.0);
+        for part in input {
+            hasher.update(part);
+        }
+        let mut node = [0; MAX_N];
+        hasher.finalize_xof().read(&mut node[..self.params.n]);
+        node
+    }
+
+    fn chain(&self, start: &[u8], from: u32, steps: u32, address: &mut Address) -> Node {
+        let n = self.params.n;
+        let mut node = [0; MAX_N];
+        node[..n].copy_from_slice(start);
+        for j in from..from + steps {
+            address.set_hash(j);
+            node = self.hash(address, &[&node[..n]]);
+        }
+        node
+    }
+
+    /// Compress the ends of the WOTS+ chains into a public key
+    fn wots_public_key(&self, ends: &[u8], address: &Address) -> Node {
+        let mut pk_address = *address;
+        pk_address.set_type(WOTS_PK);
+        pk_address.set_key_pair(address.key_pair());
+        self.hash(&pk_address, &[ends])
+    }
+
+    fn wots_public_key_from_signature(
+        &self,
+        signature: &[u8],
+        message: &[u8],
+        address: &mut Address,
+    ) -> Node {
+        let n = self.params.n;
+        let digits = wots_digits(self.params, message);
+        let mut ends = [0u8; MAX_LEN * MAX_N];
+        for i in 0..self.params.len() {
+            address.set_chain(i as u32);
+            let digit = digits[i] as u32;
+            let end = self.chain(&signature[i * n..][..n], digit, W - 1 - digit, address);
+            ends[i * n..][..n].copy_from_slice(&end[..n]);
+        }
+        self.wots_public_key(&ends[..self.params.len() * n], address)
+    }
+
+    /// Node of one step up the tree from `node` and its sibling `auth`
+    fn parent(
+        &self,
+        node: &Node,
+        auth: &[u8],
+        index: u32,
+        height: usize,
+        address: &mut Address,
+    ) -> Node {
+        let n = self.params.n;
+        address.set_chain(height as u32 + 1);
+        if (index >> height) & 1 == 0 {
+            address.set_hash(address.hash() / 2);
+            self.hash(address, &[&node[..n], auth])
+        } else {
+            address.set_hash((address.hash() - 1) / 2);
+            self.hash(address, &[auth, &node[..n]])
+        }
+    }
+
+    fn xmss_root_from_signature(
+        &self,
+        index: u32,
+        signature: &[u8],
+        message: &[u8],
+        address: &mut Address,
+    ) -> Node {
+        let n = self.params.n;
+        let (wots, auth) = signature.split_at(self.params.len() * n);
+        address.set_type(WOTS_HASH);
+        address.set_key_pair(index);
+        let mut node = self.wots_public_key_from_signature(wots, message, address);
+        address.set_type(TREE);
+        address.set_hash(index);
+        for height in 0..self.params.tree_height {
+            node = self.parent(&node, &auth[height * n..][..n], index, height, address);
+        }
+        node
+    }
+
+    fn hypertree_verify(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        mut tree: u64,
+        mut leaf: u32,
+        root: &[u8],
+    ) -> bool {
+        let n = self.params.n;
+        let mut node = [0; MAX_N];
+        node[..n].copy_from_slice(message);
+        let mut address = Address::default();
+        for (layer, xmss) in signature
+            .chunks(self.params.xmss_signature_len())
+            .enumerate()
+        {
+            if layer > 0 {
+                leaf = (tree & ((1 << self.params.tree_height) - 1)) as u32;
+                tree >>= self.params.tree_height;
+            }
+            address.set_layer(layer);
+            address.set_tree(tree);
+            let message = node;
+            node = self.xmss_root_from_signature(leaf, xmss, &message[..n], &mut address);
+        }
+        node[..n] == *root
+    }
+
+    fn fors_public_key_from_signature(
+        &self,
+        signature: &[u8],
+        message: &[u8],
+        address: &Address,
+    ) -> Node {
+        let params = self.params;
+        let n = params.n;
+        let mut roots = [0u8; MAX_K * MAX_N];
+        let mut address = *address;
+        for (i, &index) in fors_indices(params, message)
+            .iter()
+            .take(params.k)
+            .enumerate()
+        {
+            let tree = &signature[i * (params.a + 1) * n..][..(params.a + 1) * n];
+            let (secret, auth) = tree.split_at(n);
+            address.set_chain(0);
+            address.set_hash(((i as u32) << params.a) + index);
+            let mut node = self.hash(&address, &[secret]);
+            for height in 0..params.a {
+                node = self.parent(&node, &auth[height * n..][..n], index, height, &mut address);
+            }
+            roots[i * n..][..n].copy_from_slice(&node[..n]);
+        }
+        let mut pk_address = address;
+        pk_address.set_type(FORS_ROOTS);
+        pk_address.set_key_pair(address.key_pair());
+        self.hash(&pk_address, &[&roots[..params.k * n]])
+    }
+
+    /// slh_verify_internal over the message M' given in `message` parts
+    fn verify(&self, root: &[u8], message: &[&[u8]], signature: &[u8]) -> bool {
+        let params = self.params;
+        let n = params.n;
+        if signature.len() != params.signature_len() {
+            return false;
+        }
+        let (randomizer, rest) = signature.split_at(n);
+        let (fors, hypertree) = rest.split_at(params.fors_signature_len());
+        let mut digest = [0u8; 64];
+        let mut hasher = Shake256::default();
+        hasher.update(randomizer);
+        hasher.update(self.pk_seed);
+        hasher.update(root);
+        for part in message {
+            hasher.update(part);
+        }
+        hasher.finalize_xof().read(&mut digest[..params.m]);
+        let (fors_message, tree, leaf) = split_digest(params, &digest[..params.m]);
+
+        let mut address = Address::default();
+        address.set_tree(tree);
+        address.set_type(FORS_TREE);
+        address.set_key_pair(leaf);
+        let fors_key = self.fors_public_key_from_signature(fors, fors_message, &address);
+        self.hypertree_verify(&fors_key[..n], hypertree, tree, leaf, root)
+    }
+}
+
+/// Context with SK.seed for key generation and signing
+#[cfg(feature = "alloc")]
+struct Signer<'a> {
+    context: Context<'a>,
+    sk_seed: &'a [u8],
+}
+
+#[cfg(feature = "alloc")]
+impl Signer<'_> {
+    fn wots_secret(&self, address: &Address, chain: usize) -> Node {
+        let mut sk_address = *address;
+        sk_address.set_type(WOTS_PRF);
+        sk_address.set_key_pair(address.key_pair());
+        sk_address.set_chain(chain as u32);
+        self.context.hash(&sk_address, &[self.sk_seed])
+    }
+
+    fn wots_public_key(&self, address: &mut Address) -> Node {
+        let n = self.context.params.n;
+        let mut ends = [0u8; MAX_LEN * MAX_N];
+        for i in 0..self.context.params.len() {
+            let secret = self.wots_secret(address, i);
+            address.set_chain(i as u32);
+            let end = self.context.chain(&secret[..n], 0, W - 1, address);
+            ends[i * n..][..n].copy_from_slice(&end[..n]);
+        }
+        self.context
+            .wots_public_key(&ends[..self.context.params.len() * n], address)
+    }
+
+    fn wots_sign(&self, message: &[u8], address: &mut Address, signature: &mut [u8]) {
+        let n = self.context.params.n;
+        let digits = wots_digits(self.context.params, message);
+        for i in 0..self.context.params.len() {
+            let secret = self.wots_secret(address, i);
+            address.set_chain(i as u32);
+            let node = self
+                .context
+                .chain(&secret[..n], 0, digits[i] as u32, address);
+            signature[i * n..][..n].copy_from_slice(&node[..n]);
+        }
+    }
+
+    fn xmss_node(&self, index: u32, height: usize, address: &mut Address) -> Node {
+        if height == 0 {
+            address.set_type(WOTS_HASH);
+            address.set_key_pair(index);
+            return self.wots_public_key(address);
+        }
+        let n = self.context.params.n;
+        let left = self.xmss_node(2 * index, height - 1, address);
+        let right = self.xmss_node(2 * index + 1, height - 1, address);
+        address.set_type(TREE);
+        address.set_chain(height as u32);
+        address.set_hash(index);
+        self.context.hash(address, &[&left[..n], &right[..n]])
+    }
+
+    fn xmss_sign(&self, message: &[u8], index: u32, address: &mut Address, signature: &mut [u8]) {
+        let params = self.context.params;
+        let n = params.n;
+        let (wots, auth) = signature.split_at_mut(params.len() * n);
+        for height in 0..params.tree_height {
+            let node = self.xmss_node((index >> height) ^ 1, height, address);
+            auth[height * n..][..n].copy_from_slice(&node[..n]);
+        }
+        address.set_type(WOTS_HASH);
+        address.set_key_pair(index);
+        self.wots_sign(message, address, wots);
+    }
+
+    fn hypertree_sign(&self, message: &[u8], mut tree: u64, mut leaf: u32, signature: &mut [u8]) {
+        let params = self.context.params;
+        let n = params.n;
+        let mut root = [0; MAX_N];
+        root[..n].copy_from_slice(message);
+        let mut address = Address::default();
+        for (layer, xmss) in signature
+            .chunks_mut(params.xmss_signature_len())
+            .enumerate()
+        {
+            if layer > 0 {
+                leaf = (tree & ((1 << params.tree_height) - 1)) as u32;
+                tree >>= params.tree_height;
+            }
+            address.set_layer(layer);
+            address.set_tree(tree);
+            self.xmss_sign(&root[..n], leaf, &mut address, xmss);
+            if layer + 1 < params.d {
+                let message = root;
+                root =
+                    self.context
+                        .xmss_root_from_signature(leaf, xmss, &message[..n], &mut address);
+            }
+        }
+    }
+
+    fn fors_secret(&self, address: &Address, index: u32) -> Node {
+        let mut sk_address = *address;
+        sk_address.set_type(FORS_PRF);
+        sk_address.set_key_pair(address.key_pair());
+        sk_address.set_hash(index);
+        self.context.hash(&sk_address, &[self.sk_seed])
+    }
+
+    fn fors_node(&self, index: u32, height: usize, address: &mut Address) -> Node {
+        let n = self.context.params.n;
+        if height == 0 {
+            let secret = self.fors_secret(address, index);
+            address.set_chain(0);
+            address.set_hash(index);
+            return self.context.hash(address, &[&secret[..n]]);
+        }
+        let left = self.fors_node(2 * index, height - 1, address);
+        let right = self.fors_node(2 * index + 1, height - 1, address);
+        address.set_chain(height as u32);
+        address.set_hash(index);
+        self.context.hash(address, &[&left[..n], &right[..n]])
+    }
+
+    fn fors_sign(&self, message: &[u8], address: &mut Address, signature: &mut [u8]) {
+        let params = self.context.params;
+        let n = params.n;
+        for (i, &index) in fors_indices(params, message)
+            .iter()
+            .take(params.k)
+            .enumerate()
+        {
+            let tree = &mut signature[i * (params.a + 1) * n..][..(params.a + 1) * n];
+            let secret = self.fors_secret(address, ((i as u32) << params.a) + index);
+            tree[..n].copy_from_slice(&secret[..n]);
+            for height in 0..params.a {
+                let sibling = ((i as u32) << (params.a - height)) + ((index >> height) ^ 1);
+                let node = self.fors_node(sibling, height, address);
+                tree[(height + 1) * n..][..n].copy_from_slice(&node[..n]);
+            }
+        }
+    }
+
+    /// slh_sign_internal over the message M' given in `message` parts
+    fn sign(
+        &self,
+        sk_prf: &[u8],
+        root: &[u8],
+        message: &[&[u8]],
+        randomness: &[u8],
+        signature: &mut [u8],
+    ) {
+        let params = self.context.params;
+        let n = params.n;
+        let (randomizer, rest) = signature.split_at_mut(n);
+        let mut parts = vec![sk_prf, randomness];
+        parts.extend_from_slice(message);
+        shake256(&parts, randomizer);
+
+        let mut digest = [0u8; 64];
+        let mut parts = vec![&*randomizer, self.context.pk_seed, root];
+        parts.extend_from_slice(message);
+        shake256(&parts, &mut digest[..params.m]);
+        let (fors_message, tree, leaf) = split_digest(params, &digest[..params.m]);
+
+        let (fors, hypertree) = rest.split_at_mut(params.fors_signature_len());
+        let mut address = Address::default();
+        address.set_tree(tree);
+        address.set_type(FORS_TREE);
+        address.set_key_pair(leaf);
+        self.fors_sign(fors_message, &mut address, fors);
+        let fors_key = self
+            .context
+            .fors_public_key_from_signature(fors, fors_message, &address);
+        self.hypertree_sign(&fors_key[..n], tree, leaf, hypertree);
+    }
+
+    /// Root of the top XMSS tree, PK.root
+    fn root(&self) -> Node {
+        let mut address = Address::default();
+        address.set_layer(self.context.params.d - 1);
+        self.xmss_node(0, self.context.params.tree_height, &mut address)
+    }
+}
+
+/// Split a private key into a signer, SK.prf and PK.root
+#[cfg(feature = "alloc")]
+fn signer<'a>(
+    params: &'a Params,
+    private_key: &'a [u8],
+) -> Result<(Signer<'a>, &'a [u8], &'a [u8]), CryptoError> {
+    if private_key.len() != params.private_key_len() {
+        return Err(CryptoError::InvalidPrivateKey);
+    }
+    let (seeds, public_key) = private_key.split_at(2 * params.n);
+    let (sk_seed, sk_prf) = seeds.split_at(params.n);
+    let (pk_seed, root) = public_key.split_at(params.n);
+    let signer = Signer {
+        context: Context { params, pk_seed },
+        sk_seed,
+    };
+    Ok((signer, sk_prf, root))
+}
+
+/// Generate a `(public_key, private_key)` pair
+#[cfg(feature = "alloc")]
+pub(crate) fn generate<R: CryptoRng + RngCore>(params: &Params, rng: &mut R) -> (Vec<u8>, Vec<u8>) {
+    let n = params.n;
+    let mut private_key = vec![0u8; params.private_key_len()];
+    rng.fill_bytes(&mut private_key[..3 * n]);
+    let (seeds, root) = private_key.split_at_mut(3 * n);
+    let signer = Signer {
+        context: Context {
+            params,
+            pk_seed: &seeds[2 * n..],
+        },
+        sk_seed: &seeds[..n],
+    };
+    root[..n].copy_from_slice(&signer.root()[..n]);
+    (private_key[2 * n..].to_vec(), private_key)
+}
+
+/// Sign `message` (SLH-DSA.Sign with an empty context), hedged with
+/// randomness from `rng`
+#[cfg(feature = "alloc")]
+pub(crate) fn sign<R: CryptoRng + RngCore>(
+    params: &Params,
+    private_key: &[u8],
+    message: &[u8],
+    rng: &mut R,
+) -> Result<Vec<u8>, CryptoError> {
+    let (signer, sk_prf, root) = signer(params, private_key)?;
+    let mut randomness = [0u8; MAX_N];
+    rng.fill_bytes(&mut randomness[..params.n]);
+    let mut signature = vec![0u8; params.signature_len()];
+    signer.sign(
+        sk_prf,
+        root,
+        &[&[0, 0], message],
+        &randomness[..params.n],
+        &mut signature,
+    );
+    Ok(signature)
+}
+
+/// Public key decoded once for repeated verification
+#[derive(Debug, Clone)]
+pub(crate) struct Verifier {
+    params: &'static Params,
+    public_key: [u8; 2 * MAX_N],
+}
+
+impl Verifier {
+    pub(crate) fn new(params: &'static Params, public_key: &[u8]) -> Option<Self> {
+        if public_key.len() != params.public_key_len() {
+            return None;
+        }
+        let mut verifier = Verifier {
+            params,
+            public_key: [0; 2 * MAX_N],
+        };
+        verifier.public_key[..public_key.len()].copy_from_slice(public_key);
+        Some(verifier)
+    }
+
+    /// Verify a pure SLH-DSA signature with an empty context
+    pub(crate) fn verify(&self, message: &[u8], signature: &[u8]) -> bool {
+        let n = self.params.n;
+        let context = Context {
+            params: self.params,
+            pk_seed: &self.public_key[..n],
+        };
+        context.verify(&self.public_key[n..2 * n], &[&[0, 0], message], signature)
+    }
+}
+
+/// Check that `private_key` holds `public_key` and the seeds that produce it
+#[cfg(feature = "alloc")]
+pub(crate) fn matches(params: &Params, public_key: &[u8], private_key: &[u8]) -> bool {
+    let Ok((signer, _, root)) = signer(params, private_key) else {
+        return false;
+    };
+    private_key[2 * params.n..] == *public_key && signer.root()[..params.n] == *root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{verify_with_public_key as verify, Algorithm};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SETS: [(Algorithm, &Params); 6] = [
+        (Algorithm::SlhDsaShake128s, &SLH_DSA_SHAKE_128S),
+        (Algorithm::SlhDsaShake128f, &SLH_DSA_SHAKE_128F),
+        (Algorithm::SlhDsaShake192s, &SLH_DSA_SHAKE_192S),
+        (Algorithm::SlhDsaShake192f, &SLH_DSA_SHAKE_192F),
+        (Algorithm::SlhDsaShake256s, &SLH_DSA_SHAKE_256S),
+        (Algorithm::SlhDsaShake256f, &SLH_DSA_SHAKE_256F),
+    ];
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&text[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    /// Keys and deterministic signatures generated with OpenSSL 3.5
+    /// (`-pkeyopt hexseed:` and `-pkeyopt deterministic:1`)
+    #[test]
+    fn test_openssl_vectors() {
+        let vectors: serde_json::Value =
+            serde_json::from_str(include_str!("../../tests/vectors/slh_dsa.json")).unwrap();
+        for vector in vectors["vectors"].as_array().unwrap() {
+            let tag = vector["algorithm"].as_str().unwrap();
+            let (algorithm, params) = SETS.into_iter().find(|(a, _)| a.tag() == tag).unwrap();
+            let private_key = hex(vector["private_key"].as_str().unwrap());
+            let public_key = hex(vector["public_key"].as_str().unwrap());
+            let message = hex(vector["message"].as_str().unwrap());
+            let expected = hex(vector["signature"].as_str().unwrap());
+            assert!(matches(params, &public_key, &private_key), "{algorithm:?}");
+            assert!(verify(algorithm, &public_key, &message, &expected));
+            assert!(!verify(algorithm, &public_key, b"other", &expected));
+
+            // Deterministic signing uses PK.seed as the randomness. The "s"
+            // sets run the same code and take minutes in debug builds.
+            if params.tree_height <= 4 {
+                let (signer, sk_prf, root) = signer(params, &private_key).unwrap();
+                let mut signature = vec![0u8; params.signature_len()];
+                let pk_seed = &public_key[..params.n];
+                signer.sign(sk_prf, root, &[&[0, 0], &message], pk_seed, &mut signature);
+                assert_eq!(signature, expected, "{algorithm:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_sign_verify() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let (algorithm, params) = SETS[1];
+        let (public_key, private_key) = generate(params, &mut rng);
+        assert_eq!(public_key.len(), 32);
+        assert!(matches(params, &public_key, &private_key));
+
+        let signature = sign(params, &private_key, b"m", &mut rng).unwrap();
+        assert_eq!(signature.len(), 17088);
+        assert!(verify(algorithm, &public_key, b"m", &signature));
+        assert_ne!(
+            sign(params, &private_key, b"m", &mut rng).unwrap(),
+            signature
+        );
+        for offset in [0, 20, 4000, signature.len() - 1] {
+            let mut tampered = signature.clone();
+            tampered[offset] ^= 1;
+            assert!(!verify(algorithm, &public_key, b"m", &tampered));
+        }
+        assert!(!verify(algorithm, &public_key, b"m", &signature[1..]));
+
+        let mut forged = private_key.clone();
+        forged[0] ^= 1;
+        assert!(!matches(params, &public_key, &forged));
+        assert!(sign(params, &private_key[1..], b"m", &mut rng).is_err());
+    }
+
+    #[test]
+    fn test_sizes() {
+        for ((_, params), signature_len) in SETS
+            .into_iter()
+            .zip([7856, 17088, 16224, 35664, 29792, 49856])
+        {
+            assert_eq!(params.signature_len(), signature_len);
+            assert_eq!(params.h, params.d * params.tree_height);
+            let digest_len = (params.k * params.a).div_ceil(8)
+                + (params.h - params.tree_height).div_ceil(8)
+                + params.tree_height.div_ceil(8);
+            assert_eq!(params.m, digest_len);
+        }
+    }
+}
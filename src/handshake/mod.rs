@@ -0,0 +1,5 @@
+//! Post-quantum authenticated key exchange
+
+pub use self::protocol::*;
+
+mod protocol;
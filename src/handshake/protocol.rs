@@ -0,0 +1,518 @@
+//! Three-message handshake in the style of Noise XX
+//!
+//! ```text
+//! -> e                     initiator's ephemeral ML-KEM-768 key
+//! <- ct, s, sig, confirm   encapsulation, responder identity and signature
+//! -> s, sig, confirm       initiator identity and signature
+//! ```
+//!
+//! Every message is absorbed into a SHA3-256 transcript hash. Each signature
+//! covers the transcript up to that point, and each confirmation tag proves
+//! knowledge of the KEM shared secret. Unlike Noise XX, identities are sent
+//! in the clear.
+//!
+//! The state machine only produces and consumes byte strings; carrying them
+//! between peers is up to the caller.
+
+use crate::crypto::{key_id, verify_with_public_key, Algorithm, CryptoError, KeyId, KeyPair};
+use hmac::{Hmac, Mac};
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{Ciphertext, EncodedSizeUser, KemCore, MlKem768};
+use rand::thread_rng;
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+
+type DecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+
+const PROTOCOL: &[u8] = b"QSF-AKE-v1/ML-KEM-768/SHA3-256";
+const VERSION: u8 = 1;
+const TAG_LEN: usize = 32;
+/// ML-KEM-768 ciphertext size (FIPS 203, table 3)
+const CIPHERTEXT_LEN: usize = 1088;
+
+/// Handshake error
+#[derive(Debug)]
+pub enum HandshakeError {
+    /// Only public-key signature algorithms can authenticate a peer
+    UnsupportedAlgorithm(Algorithm),
+    /// The call does not match the current step, or the handshake already failed
+    OutOfOrder,
+    Malformed(&'static str),
+    UntrustedPeer(KeyId),
+    BadSignature,
+    BadConfirmation,
+    Crypto(CryptoError),
+}
+
+impl fmt::Display for HandshakeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HandshakeError::UnsupportedAlgorithm(alg) => {
+                write!(f, "{} cannot authenticate a handshake", alg.tag())
+            }
+            HandshakeError::OutOfOrder => write!(f, "handshake message out of order"),
+            HandshakeError::Malformed(reason) => write!(f, "malformed message: {}", reason),
+            HandshakeError::UntrustedPeer(id) => write!(f, "peer key {} is not trusted", id),
+            HandshakeError::BadSignature => write!(f, "peer signature is invalid"),
+            HandshakeError::BadConfirmation => write!(f, "key confirmation failed"),
+            HandshakeError::Crypto(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for HandshakeError {}
+
+impl From<CryptoError> for HandshakeError {
+    fn from(e: CryptoError) -> Self {
+        HandshakeError::Crypto(e)
+    }
+}
+
+/// Long-term identity presented by a peer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerIdentity {
+    pub algorithm: Algorithm,
+    pub public_key: Vec<u8>,
+}
+
+impl PeerIdentity {
+    pub fn key_id(&self) -> KeyId {
+        key_id(self.algorithm, &self.public_key)
+    }
+}
+
+/// Keys agreed by a completed handshake
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub peer: PeerIdentity,
+    /// Final transcript hash, usable for channel binding
+    pub handshake_hash: [u8; 32],
+    /// Key for traffic this side sends
+    pub send_key: [u8; 32],
+    /// Key for traffic this side receives
+    pub recv_key: [u8; 32],
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    WriteEphemeral,
+    ReadEphemeral,
+    WriteResponse,
+    ReadResponse,
+    WriteFinish,
+    ReadFinish,
+    Done,
+    Failed,
+}
+
+/// One side of a handshake
+pub struct Handshake<'a> {
+    identity: &'a KeyPair,
+    trusted: Vec<KeyId>,
+    initiator: bool,
+    step: Step,
+    hash: [u8; 32],
+    ephemeral: Option<DecapsulationKey>,
+    peer_ephemeral: Option<EncapsulationKey>,
+    secret: Option<[u8; 32]>,
+    peer: Option<PeerIdentity>,
+}
+
+impl<'a> Handshake<'a> {
+    /// Start a handshake as the initiator, accepting responders in `trusted`
+    pub fn initiator(identity: &'a KeyPair, trusted: &[KeyId]) -> Result<Self, HandshakeError> {
+        Self::new(identity, trusted, true)
+    }
+
+    /// Start a handshake as the responder, accepting initiators in `trusted`
+    pub fn responder(identity: &'a KeyPair, trusted: &[KeyId]) -> Result<Self, HandshakeError> {
+        Self::new(identity, trusted, false)
+    }
+
+    fn new(
+        identity: &'a KeyPair,
+        trusted: &[KeyId],
+        initiator: bool,
+    ) -> Result<Self, HandshakeError> {
+        algorithm_code(identity.algorithm)?;
+        Ok(Self {
+            identity,
+            trusted: trusted.to_vec(),
+            initiator,
+            step: if initiator {
+                Step::WriteEphemeral
+            } else {
+                Step::ReadEphemeral
+            },
+            hash: Sha3_256::digest(PROTOCOL).into(),
+            ephemeral: None,
+            peer_ephemeral: None,
+            secret: None,
+            peer: None,
+        })
+    }
+
+    /// Produce the next message to send to the peer
+    pub fn write_message(&mut self) -> Result<Vec<u8>, HandshakeError> {
+        let result = match self.step {
+            Step::WriteEphemeral => Ok(self.write_ephemeral()),
+            Step::WriteResponse => self.write_response(),
+            Step::WriteFinish => self.write_finish(),
+            _ => return Err(HandshakeError::OutOfOrder),
+        };
+        self.advance(result.is_ok());
+        result
+    }
+
+    /// Consume the next message received from the peer
+    pub fn read_message(&mut self, message: &[u8]) -> Result<(), HandshakeError> {
+        let result = match self.step {
+            Step::ReadEphemeral => self.read_ephemeral(message),
+            Step::ReadResponse => self.read_response(message),
+            Step::ReadFinish => self.read_authentication(message),
+            _ => return Err(HandshakeError::OutOfOrder),
+        };
+        self.advance(result.is_ok());
+        result
+    }
+
+    /// Whether the handshake has completed on this side
+    pub fn is_complete(&self) -> bool {
+        self.step == Step::Done
+    }
+
+    /// Derive the session keys of a completed handshake
+    pub fn into_session(self) -> Result<Session, HandshakeError> {
+        let (Step::Done, Some(secret), Some(peer)) = (self.step, self.secret, self.peer) else {
+            return Err(HandshakeError::OutOfOrder);
+        };
+        let i2r = prf(&secret, b"initiator to responder", &self.hash);
+        let r2i = prf(&secret, b"responder to initiator", &self.hash);
+        let (send_key, recv_key) = if self.initiator {
+            (i2r, r2i)
+        } else {
+            (r2i, i2r)
+        };
+        Ok(Session {
+            peer,
+            handshake_hash: self.hash,
+            send_key,
+            recv_key,
+        })
+    }
+
+    fn advance(&mut self, ok: bool) {
+        self.step = match (ok, self.step) {
+            (false, _) => Step::Failed,
+            (true, Step::WriteEphemeral) => Step::ReadResponse,
+            (true, Step::ReadEphemeral) => Step::WriteResponse,
+            (true, Step::WriteResponse) => Step::ReadFinish,
+            (true, Step::ReadResponse) => Step::WriteFinish,
+            (true, _) => Step::Done,
+        };
+    }
+
+    fn write_ephemeral(&mut self) -> Vec<u8> {
+        let (dk, ek) = MlKem768::generate(&mut thread_rng());
+        let mut message = vec![VERSION];
+        message.extend_from_slice(&ek.as_bytes());
+        self.mix(&message);
+        self.ephemeral = Some(dk);
+        message
+    }
+
+    fn read_ephemeral(&mut self, message: &[u8]) -> Result<(), HandshakeError> {
+        let mut reader = Reader(message);
+        if reader.take(1)? != [VERSION] {
+            return Err(HandshakeError::Malformed("unsupported version"));
+        }
+        let ek = reader.take(message.len() - 1)?;
+        let ek = ek
+            .try_into()
+            .map_err(|_| HandshakeError::Malformed("ephemeral key length"))?;
+        self.peer_ephemeral = Some(EncapsulationKey::from_bytes(ek));
+        self.mix(message);
+        Ok(())
+    }
+
+    fn write_response(&mut self) -> Result<Vec<u8>, HandshakeError> {
+        let ek = self
+            .peer_ephemeral
+            .take()
+            .ok_or(HandshakeError::OutOfOrder)?;
+        let (ct, secret) = ek
+            .encapsulate(&mut thread_rng())
+            .map_err(|_| HandshakeError::Malformed("ephemeral key"))?;
+        self.mix(&ct);
+        self.secret = Some(secret.into());
+        let mut message = ct.to_vec();
+        message.extend(self.write_authentication(b"responder confirm")?);
+        Ok(message)
+    }
+
+    fn read_response(&mut self, message: &[u8]) -> Result<(), HandshakeError> {
+        let dk = self.ephemeral.take().ok_or(HandshakeError::OutOfOrder)?;
+        let mut reader = Reader(message);
+        let ct: Ciphertext<MlKem768> = reader.take(CIPHERTEXT_LEN)?.try_into().unwrap();
+        // ML-KEM decapsulation never fails; a bad ciphertext yields a
+        // pseudorandom secret that the confirmation tag then rejects
+        let secret = dk.decapsulate(&ct).expect("ML-KEM decapsulation");
+        self.mix(&ct);
+        self.secret = Some(secret.into());
+        self.read_authentication(reader.0)
+    }
+
+    fn write_finish(&mut self) -> Result<Vec<u8>, HandshakeError> {
+        self.write_authentication(b"initiator confirm")
+    }
+
+    /// `identity || signature || confirm`, absorbing each part in turn
+    fn write_authentication(&mut self, label: &[u8]) -> Result<Vec<u8>, HandshakeError> {
+        let identity = self.identity;
+        let mut message = Vec::new();
+        message.push(algorithm_code(identity.algorithm)?);
+        put_with_len(&mut message, &identity.public_key);
+        self.mix(&message);
+
+        let mut signature = Vec::new();
+        put_with_len(&mut signature, &identity.sign(&self.hash)?);
+        self.mix(&signature);
+        message.extend(signature);
+
+        let tag = prf(self.secret.as_ref().unwrap(), label, &self.hash);
+        self.mix(&tag);
+        message.extend_from_slice(&tag);
+        Ok(message)
+    }
+
+    fn read_authentication(&mut self, message: &[u8]) -> Result<(), HandshakeError> {
+        let label: &[u8] = if self.initiator {
+            b"responder confirm"
+        } else {
+            b"initiator confirm"
+        };
+        let mut reader = Reader(message);
+        let code = reader.take(1)?;
+        let (encoded_key, public_key) = reader.take_with_len()?;
+        let peer = PeerIdentity {
+            algorithm: algorithm_from_code(code[0])?,
+            public_key: public_key.to_vec(),
+        };
+        if !self.trusted.contains(&peer.key_id()) {
+            return Err(HandshakeError::UntrustedPeer(peer.key_id()));
+        }
+        self.mix(&[code, encoded_key].concat());
+
+        let (encoded_signature, signature) = reader.take_with_len()?;
+        if !verify_with_public_key(peer.algorithm, public_key, &self.hash, signature) {
+            return Err(HandshakeError::BadSignature);
+        }
+        self.mix(encoded_signature);
+
+        let tag = reader.take(TAG_LEN)?;
+        if !reader.0.is_empty() {
+            return Err(HandshakeError::Malformed("trailing bytes"));
+        }
+        let mut mac = Hmac::<Sha3_256>::new_from_slice(self.secret.as_ref().unwrap())
+            .expect("HMAC can take key of any size");
+        mac.update(label);
+        mac.update(&self.hash);
+        mac.verify_slice(tag)
+            .map_err(|_| HandshakeError::BadConfirmation)?;
+        self.mix(tag);
+        self.peer = Some(peer);
+        Ok(())
+    }
+
+    /// `h = SHA3-256(h || u32(len) || data)`
+    fn mix(&mut self, data: &[u8]) {
+        let mut hasher = Sha3_256::new();
+        hasher.update(self.hash);
+        hasher.update((data.len() as u32).to_be_bytes());
+        hasher.update(data);
+        self.hash = hasher.finalize().into();
+    }
+}
+
+fn prf(secret: &[u8], label: &[u8], hash: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha3_256>::new_from_slice(secret).expect("HMAC can take key of any size");
+    mac.update(label);
+    mac.update(hash);
+    mac.finalize().into_bytes().into()
+}
+
+fn algorithm_code(algorithm: Algorithm) -> Result<u8, HandshakeError> {
+    match algorithm {
+        Algorithm::Falcon512 => Ok(1),
+        Algorithm::Falcon1024 => Ok(2),
+        Algorithm::MlDsa44 => Ok(3),
+        Algorithm::MlDsa65 => Ok(4),
+        Algorithm::MlDsa87 => Ok(5),
+        Algorithm::SlhDsaShake128s => Ok(6),
+        Algorithm::SlhDsaShake128f => Ok(7),
+        Algorithm::SlhDsaShake192s => Ok(8),
+        Algorithm::SlhDsaShake192f => Ok(9),
+        Algorithm::SlhDsaShake256s => Ok(10),
+        Algorithm::SlhDsaShake256f => Ok(11),
+        Algorithm::MlDsa44Ed25519 => Ok(12),
+        Algorithm::MlDsa65Ed25519 => Ok(13),
+        Algorithm::HmacSha3_256 => Err(HandshakeError::UnsupportedAlgorithm(algorithm)),
+    }
+}
+
+fn algorithm_from_code(code: u8) -> Result<Algorithm, HandshakeError> {
+    match code {
+        1 => Ok(Algorithm::Falcon512),
+        2 => Ok(Algorithm::Falcon1024),
+        3 => Ok(Algorithm::MlDsa44),
+        4 => Ok(Algorithm::MlDsa65),
+        5 => Ok(Algorithm::MlDsa87),
+        6 => Ok(Algorithm::SlhDsaShake128s),
+        7 => Ok(Algorithm::SlhDsaShake128f),
+        8 => Ok(Algorithm::SlhDsaShake192s),
+        9 => Ok(Algorithm::SlhDsaShake192f),
+        10 => Ok(Algorithm::SlhDsaShake256s),
+        11 => Ok(Algorithm::SlhDsaShake256f),
+        12 => Ok(Algorithm::MlDsa44Ed25519),
+        13 => Ok(Algorithm::MlDsa65Ed25519),
+        _ => Err(HandshakeError::Malformed("unknown signature algorithm")),
+    }
+}
+
+fn put_with_len(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], HandshakeError> {
+        if self.0.len() < n {
+            return Err(HandshakeError::Malformed("truncated message"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    /// Read a length-prefixed field, returning it with and without the prefix
+    fn take_with_len(&mut self) -> Result<(&'a [u8], &'a [u8]), HandshakeError> {
+        let start = self.0;
+        let len = self.take(2)?;
+        let data = self.take(u16::from_be_bytes([len[0], len[1]]) as usize)?;
+        Ok((&start[..2 + data.len()], data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair, generate_key_pair_for};
+
+    /// Pass messages between both sides in-process until both complete
+    fn run<'a>(
+        initiator: &mut Handshake<'a>,
+        responder: &mut Handshake<'a>,
+        mut tamper: impl FnMut(usize, &mut Vec<u8>),
+    ) -> Result<(), HandshakeError> {
+        let (mut sender, mut receiver) = (initiator, responder);
+        for step in 0.. {
+            if sender.is_complete() && receiver.is_complete() {
+                break;
+            }
+            let mut message = sender.write_message()?;
+            tamper(step, &mut message);
+            receiver.read_message(&message)?;
+            std::mem::swap(&mut sender, &mut receiver);
+        }
+        Ok(())
+    }
+
+    fn parties() -> (KeyPair, KeyPair) {
+        (
+            generate_key_pair_for(Algorithm::Falcon512),
+            generate_key_pair_for(Algorithm::Falcon1024),
+        )
+    }
+
+    #[test]
+    fn test_mutual_authentication() {
+        let (client, server) = parties();
+        let mut initiator = Handshake::initiator(&client, &[server.key_id()]).unwrap();
+        let mut responder = Handshake::responder(&server, &[client.key_id()]).unwrap();
+        run(&mut initiator, &mut responder, |_, _| {}).unwrap();
+
+        let client_session = initiator.into_session().unwrap();
+        let server_session = responder.into_session().unwrap();
+        assert_eq!(client_session.send_key, server_session.recv_key);
+        assert_eq!(client_session.recv_key, server_session.send_key);
+        assert_ne!(client_session.send_key, client_session.recv_key);
+        assert_eq!(client_session.handshake_hash, server_session.handshake_hash);
+        assert_eq!(client_session.peer.key_id(), server.key_id());
+        assert_eq!(server_session.peer.public_key, client.public_key);
+    }
+
+    #[test]
+    fn test_untrusted_peer() {
+        let (client, server) = parties();
+        let mut initiator = Handshake::initiator(&client, &[server.key_id()]).unwrap();
+        let mut responder = Handshake::responder(&server, &[]).unwrap();
+        let result = run(&mut initiator, &mut responder, |_, _| {});
+        assert!(matches!(result, Err(HandshakeError::UntrustedPeer(id)) if id == client.key_id()));
+        assert!(responder.into_session().is_err());
+    }
+
+    #[test]
+    fn test_tampering_detected() {
+        let (client, server) = parties();
+        let handshake = |tamper: &dyn Fn(usize, &mut Vec<u8>)| {
+            let mut initiator = Handshake::initiator(&client, &[server.key_id()]).unwrap();
+            let mut responder = Handshake::responder(&server, &[client.key_id()]).unwrap();
+            let result = run(&mut initiator, &mut responder, tamper);
+            assert!(matches!(
+                initiator.write_message(),
+                Err(HandshakeError::OutOfOrder)
+            ));
+            result
+        };
+
+        // Ciphertext of the response
+        let result = handshake(&|step, m| {
+            if step == 1 {
+                m[10] ^= 1
+            }
+        });
+        assert!(matches!(result, Err(HandshakeError::BadSignature)));
+        // Confirmation tag of the final message
+        let result = handshake(&|step, m| {
+            if step == 2 {
+                *m.last_mut().unwrap() ^= 1
+            }
+        });
+        assert!(matches!(result, Err(HandshakeError::BadConfirmation)));
+        // Truncated ephemeral key
+        let result = handshake(&|step, m| {
+            if step == 0 {
+                m.pop();
+            }
+        });
+        assert!(matches!(result, Err(HandshakeError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_requires_public_key_signatures() {
+        let key_pair = generate_key_pair();
+        assert!(matches!(
+            Handshake::initiator(&key_pair, &[]),
+            Err(HandshakeError::UnsupportedAlgorithm(_))
+        ));
+        let (client, _) = parties();
+        let mut responder = Handshake::responder(&client, &[]).unwrap();
+        assert!(matches!(
+            responder.write_message(),
+            Err(HandshakeError::OutOfOrder)
+        ));
+    }
+}
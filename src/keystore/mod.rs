@@ -0,0 +1,9 @@
+//! Local key store with NIST SP 800-57 lifecycle states
+
+pub use self::rotation::*;
+pub use self::state::*;
+pub use self::store::*;
+
+mod rotation;
+mod state;
+mod store;
@@ -0,0 +1,248 @@
+//! Scheduled key rotation
+
+use super::state::KeyState;
+use super::store::{KeyStore, KeyStoreData, KeyStoreError};
+use crate::crypto::{generate_key_pair_for, KeyId};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Active key whose cryptoperiod has ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RotationDue {
+    pub key_id: KeyId,
+    pub purpose: String,
+    /// Seconds since the Unix epoch at which the cryptoperiod ended
+    pub due_at: u64,
+}
+
+/// Completed rotation of one key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rotation {
+    pub retired: KeyId,
+    pub successor: KeyId,
+    /// The retired key verifies until this time
+    pub verify_until: u64,
+}
+
+/// Rotation policy with per-purpose cryptoperiods
+///
+/// On rotation a successor key for the same purpose and algorithm is
+/// generated and activated. The old key is deactivated: it stops signing immediately but
+/// still verifies for the grace period.
+#[derive(Debug, Clone)]
+pub struct RotationPolicy {
+    cryptoperiods: HashMap<String, Duration>,
+    grace_period: Duration,
+}
+
+impl RotationPolicy {
+    /// Create a policy with the given verification grace period
+    pub fn new(grace_period: Duration) -> Self {
+        Self {
+            cryptoperiods: HashMap::new(),
+            grace_period,
+        }
+    }
+
+    /// Set the cryptoperiod for keys of `purpose`
+    pub fn with_cryptoperiod(mut self, purpose: &str, cryptoperiod: Duration) -> Self {
+        self.cryptoperiods.insert(purpose.to_string(), cryptoperiod);
+        self
+    }
+
+    /// Cryptoperiod for keys of `purpose`, if the policy covers it
+    pub fn cryptoperiod(&self, purpose: &str) -> Option<Duration> {
+        self.cryptoperiods.get(purpose).copied()
+    }
+
+    /// Grace period during which rotated keys still verify
+    pub fn grace_period(&self) -> Duration {
+        self.grace_period
+    }
+
+    /// Active keys whose cryptoperiod has ended as of `now`
+    ///
+    /// Keys whose purpose has no cryptoperiod are never due.
+    pub fn due_for_rotation(&self, store: &KeyStore, now: u64) -> Vec<RotationDue> {
+        store
+            .keys()
+            .filter(|record| record.state == KeyState::Active)
+            .filter_map(|record| {
+                let cryptoperiod = self.cryptoperiod(&record.purpose)?;
+                let due_at = record.activated_at?.saturating_add(cryptoperiod.as_secs());
+                (due_at <= now).then(|| RotationDue {
+                    key_id: record.key_id,
+                    purpose: record.purpose.clone(),
+                    due_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Replace an active key with a freshly generated successor
+    ///
+    /// The whole rotation is written in one save and every timestamp it
+    /// records is `now`.
+    pub fn rotate(
+        &self,
+        store: &mut KeyStore,
+        key_id: &KeyId,
+        now: u64,
+    ) -> Result<Rotation, KeyStoreError> {
+        store.update(|data| self.apply(data, key_id, now))
+    }
+
+    /// Rotate every key that is due as of `now`, in one save
+    pub fn rotate_due(
+        &self,
+        store: &mut KeyStore,
+        now: u64,
+    ) -> Result<Vec<Rotation>, KeyStoreError> {
+        let due = self.due_for_rotation(store, now);
+        store.update(|data| {
+            due.iter()
+                .map(|due| self.apply(data, &due.key_id, now))
+                .collect()
+        })
+    }
+
+    fn apply(
+        &self,
+        data: &mut KeyStoreData,
+        key_id: &KeyId,
+        now: u64,
+    ) -> Result<Rotation, KeyStoreError> {
+        let (purpose, algorithm) = match data.get(key_id) {
+            Some(record) if record.state == KeyState::Active => {
+                (record.purpose.clone(), record.key_pair.algorithm)
+            }
+            Some(record) => {
+                return Err(KeyStoreError::KeyNotUsable {
+                    key_id: *key_id,
+                    state: record.state,
+                })
+            }
+            None => return Err(KeyStoreError::UnknownKey(*key_id)),
+        };
+
+        let successor = data.insert(generate_key_pair_for(algorithm), &purpose, now)?;
+        data.transition(&successor, KeyState::Active, None, now)?;
+        data.transition(key_id, KeyState::Deactivated, Some("rotated"), now)?;
+        let verify_until = now.saturating_add(self.grace_period.as_secs());
+        data.set_successor(key_id, successor, verify_until)?;
+
+        Ok(Rotation {
+            retired: *key_id,
+            successor,
+            verify_until,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::Algorithm;
+    use crate::keystore::store::unix_time;
+
+    const DAY: u64 = 24 * 60 * 60;
+
+    fn policy() -> RotationPolicy {
+        RotationPolicy::new(Duration::from_secs(7 * DAY))
+            .with_cryptoperiod("payments", Duration::from_secs(90 * DAY))
+    }
+
+    #[test]
+    fn test_due_for_rotation() {
+        let mut store = KeyStore::in_memory();
+        let payments = store.generate_for("payments").unwrap();
+        let other = store.generate().unwrap();
+        store.activate(&payments).unwrap();
+        store.activate(&other).unwrap();
+
+        let now = unix_time();
+        assert!(policy().due_for_rotation(&store, now).is_empty());
+
+        let due = policy().due_for_rotation(&store, now + 91 * DAY);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].key_id, payments);
+        assert_eq!(due[0].purpose, "payments");
+    }
+
+    #[test]
+    fn test_rotation_grace_window() {
+        let mut store = KeyStore::in_memory();
+        let old = store.generate_for("payments").unwrap();
+        store.activate(&old).unwrap();
+        let message = b"Test message";
+        let signature = store.sign(&old, message).unwrap();
+
+        let now = unix_time() + 91 * DAY;
+        let rotations = policy().rotate_due(&mut store, now).unwrap();
+        assert_eq!(rotations.len(), 1);
+        let successor = rotations[0].successor;
+
+        assert_eq!(store.state(&old), Some(KeyState::Deactivated));
+        assert_eq!(store.state(&successor), Some(KeyState::Active));
+        assert_eq!(store.get(&old).unwrap().successor, Some(successor));
+        assert_eq!(store.get(&successor).unwrap().purpose, "payments");
+        assert_eq!(store.get(&successor).unwrap().activated_at, Some(now));
+        assert!(store.history()[2..].iter().all(|t| t.at == now));
+
+        assert!(store.sign(&old, message).is_err());
+        assert!(store.verify_at(message, &signature, now + DAY).unwrap());
+        assert!(matches!(
+            store.verify_at(message, &signature, now + 8 * DAY),
+            Err(KeyStoreError::GraceExpired(_))
+        ));
+        assert!(policy().due_for_rotation(&store, unix_time()).is_empty());
+    }
+
+    #[test]
+    fn test_rotation_persisted() {
+        let path = std::env::temp_dir().join(format!("qsf-rotation-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut store = KeyStore::open(&path).unwrap();
+        let old = store.generate_for("payments").unwrap();
+        store.activate(&old).unwrap();
+        let rotation = policy().rotate(&mut store, &old, unix_time()).unwrap();
+        assert!(policy().rotate(&mut store, &old, unix_time()).is_err());
+
+        let store = KeyStore::open(&path).unwrap();
+        assert_eq!(store.state(&old), Some(KeyState::Deactivated));
+        assert_eq!(store.get(&old).unwrap().successor, Some(rotation.successor));
+        assert_eq!(store.state(&rotation.successor), Some(KeyState::Active));
+        assert_eq!(store.keys().count(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_keeps_algorithm() {
+        let mut store = KeyStore::in_memory();
+        let old = store
+            .import(generate_key_pair_for(Algorithm::Falcon512))
+            .unwrap();
+        store.activate(&old).unwrap();
+        let rotation = policy().rotate(&mut store, &old, unix_time()).unwrap();
+        let successor = store.get(&rotation.successor).unwrap();
+        assert_eq!(successor.key_pair.algorithm, Algorithm::Falcon512);
+
+        let message = b"Test message";
+        let signature = store.sign(&rotation.successor, message).unwrap();
+        assert!(store.verify(message, &signature).unwrap());
+    }
+
+    #[test]
+    fn test_long_periods_saturate() {
+        let mut store = KeyStore::in_memory();
+        let key_id = store.generate_for("payments").unwrap();
+        store.activate(&key_id).unwrap();
+        let policy = RotationPolicy::new(Duration::MAX)
+            .with_cryptoperiod("payments", Duration::from_secs(u64::MAX));
+        assert!(policy.due_for_rotation(&store, unix_time()).is_empty());
+
+        let rotation = policy.rotate(&mut store, &key_id, unix_time()).unwrap();
+        assert_eq!(rotation.verify_until, u64::MAX);
+    }
+}
@@ -0,0 +1,101 @@
+//! Key lifecycle states and transitions
+
+use crate::crypto::KeyId;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Key state as defined by NIST SP 800-57 Part 1, section 7
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum KeyState {
+    PreActivation,
+    Active,
+    Suspended,
+    Deactivated,
+    Compromised,
+    Destroyed,
+}
+
+impl KeyState {
+    /// Whether a key in this state may apply protection (sign)
+    pub fn can_sign(&self) -> bool {
+        *self == KeyState::Active
+    }
+
+    /// Whether a key in this state may process protected data (verify)
+    pub fn can_verify(&self) -> bool {
+        matches!(
+            self,
+            KeyState::Active | KeyState::Suspended | KeyState::Deactivated
+        )
+    }
+
+    /// Whether SP 800-57 allows moving from this state to `to`
+    pub fn can_transition_to(&self, to: KeyState) -> bool {
+        use KeyState::*;
+        matches!(
+            (self, to),
+            (PreActivation, Active)
+                | (PreActivation, Compromised)
+                | (PreActivation, Destroyed)
+                | (Active, Suspended)
+                | (Active, Deactivated)
+                | (Active, Compromised)
+                | (Suspended, Active)
+                | (Suspended, Deactivated)
+                | (Suspended, Compromised)
+                | (Deactivated, Compromised)
+                | (Deactivated, Destroyed)
+                | (Compromised, Destroyed)
+        )
+    }
+}
+
+impl fmt::Display for KeyState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            KeyState::PreActivation => "pre-activation",
+            KeyState::Active => "active",
+            KeyState::Suspended => "suspended",
+            KeyState::Deactivated => "deactivated",
+            KeyState::Compromised => "compromised",
+            KeyState::Destroyed => "destroyed",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Entry in the key store's transition history
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub key_id: KeyId,
+    /// `None` when the key entered the store
+    pub from: Option<KeyState>,
+    pub to: KeyState,
+    /// Seconds since the Unix epoch
+    pub at: u64,
+    pub reason: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_active_signs() {
+        assert!(KeyState::Active.can_sign());
+        assert!(!KeyState::Suspended.can_sign());
+        assert!(!KeyState::PreActivation.can_sign());
+        assert!(KeyState::Deactivated.can_verify());
+        assert!(!KeyState::Compromised.can_verify());
+    }
+
+    #[test]
+    fn test_transitions() {
+        assert!(KeyState::PreActivation.can_transition_to(KeyState::Active));
+        assert!(KeyState::Suspended.can_transition_to(KeyState::Active));
+        assert!(!KeyState::Deactivated.can_transition_to(KeyState::Active));
+        assert!(!KeyState::Destroyed.can_transition_to(KeyState::Active));
+        assert!(!KeyState::Active.can_transition_to(KeyState::Destroyed));
+    }
+}
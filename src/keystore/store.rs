@@ -0,0 +1,594 @@
+//! File-backed key store
+
+use super::state::{KeyState, Transition};
+use crate::crypto::{
+    generate_key_pair, sign_message_with_key_id, signature_key_id, verify_signature_with_key_id,
+    CryptoError, KeyId, KeyPair,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+#[cfg(unix)]
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Key store error
+#[derive(Debug)]
+pub enum KeyStoreError {
+    Io(io::Error),
+    Serialization(serde_json::Error),
+    UnknownKey(KeyId),
+    DuplicateKey(KeyId),
+    InvalidTransition {
+        key_id: KeyId,
+        from: KeyState,
+        to: KeyState,
+    },
+    /// The key exists but its state does not permit the operation
+    KeyNotUsable {
+        key_id: KeyId,
+        state: KeyState,
+    },
+    /// The key was rotated out and its verification grace window has ended
+    GraceExpired(KeyId),
+    Crypto(CryptoError),
+}
+
+impl fmt::Display for KeyStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyStoreError::Io(e) => write!(f, "key store I/O error: {}", e),
+            KeyStoreError::Serialization(e) => write!(f, "key store serialization error: {}", e),
+            KeyStoreError::UnknownKey(id) => write!(f, "unknown key {}", id),
+            KeyStoreError::DuplicateKey(id) => write!(f, "key {} already exists", id),
+            KeyStoreError::InvalidTransition { key_id, from, to } => {
+                write!(f, "key {} cannot move from {} to {}", key_id, from, to)
+            }
+            KeyStoreError::KeyNotUsable { key_id, state } => {
+                write!(f, "key {} is {}", key_id, state)
+            }
+            KeyStoreError::GraceExpired(id) => {
+                write!(f, "verification grace window of key {} has ended", id)
+            }
+            KeyStoreError::Crypto(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for KeyStoreError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            KeyStoreError::Io(e) => Some(e),
+            KeyStoreError::Serialization(e) => Some(e),
+            KeyStoreError::Crypto(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for KeyStoreError {
+    fn from(e: io::Error) -> Self {
+        KeyStoreError::Io(e)
+    }
+}
+
+impl From<CryptoError> for KeyStoreError {
+    fn from(e: CryptoError) -> Self {
+        KeyStoreError::Crypto(e)
+    }
+}
+
+impl From<serde_json::Error> for KeyStoreError {
+    fn from(e: serde_json::Error) -> Self {
+        KeyStoreError::Serialization(e)
+    }
+}
+
+/// Key held by the store
+#[derive(Serialize, Deserialize, Debug)]
+pub struct KeyRecord {
+    pub key_id: KeyId,
+    pub state: KeyState,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+    /// Purpose used to select the key's cryptoperiod
+    #[serde(default = "default_purpose")]
+    pub purpose: String,
+    /// When the key first became active
+    #[serde(default)]
+    pub activated_at: Option<u64>,
+    /// After deactivation, the key verifies only until this time
+    #[serde(default)]
+    pub verify_until: Option<u64>,
+    /// Key that replaced this one on rotation
+    #[serde(default)]
+    pub successor: Option<KeyId>,
+    /// Private key is emptied once the key is destroyed
+    pub key_pair: KeyPair,
+}
+
+/// Purpose assigned to keys created without one
+pub const DEFAULT_PURPOSE: &str = "default";
+
+fn default_purpose() -> String {
+    DEFAULT_PURPOSE.to_string()
+}
+
+impl Clone for KeyRecord {
+    fn clone(&self) -> Self {
+        Self {
+            key_id: self.key_id,
+            state: self.state,
+            created_at: self.created_at,
+            purpose: self.purpose.clone(),
+            activated_at: self.activated_at,
+            verify_until: self.verify_until,
+            successor: self.successor,
+            key_pair: KeyPair {
+                public_key: self.key_pair.public_key.clone(),
+                private_key: self.key_pair.private_key.clone(),
+                algorithm: self.key_pair.algorithm,
+            },
+        }
+    }
+}
+
+/// Contents of a key store; changes are applied to a copy and committed
+/// once persisted
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub(super) struct KeyStoreData {
+    keys: Vec<KeyRecord>,
+    history: Vec<Transition>,
+}
+
+impl KeyStoreData {
+    pub(super) fn get(&self, key_id: &KeyId) -> Option<&KeyRecord> {
+        self.keys.iter().find(|r| r.key_id == *key_id)
+    }
+
+    fn get_mut(&mut self, key_id: &KeyId) -> Result<&mut KeyRecord, KeyStoreError> {
+        self.keys
+            .iter_mut()
+            .find(|r| r.key_id == *key_id)
+            .ok_or(KeyStoreError::UnknownKey(*key_id))
+    }
+
+    pub(super) fn insert(
+        &mut self,
+        key_pair: KeyPair,
+        purpose: &str,
+        now: u64,
+    ) -> Result<KeyId, KeyStoreError> {
+        let key_id = key_pair.key_id();
+        if self.get(&key_id).is_some() {
+            return Err(KeyStoreError::DuplicateKey(key_id));
+        }
+        self.keys.push(KeyRecord {
+            key_id,
+            state: KeyState::PreActivation,
+            created_at: now,
+            purpose: purpose.to_string(),
+            activated_at: None,
+            verify_until: None,
+            successor: None,
+            key_pair,
+        });
+        self.history.push(Transition {
+            key_id,
+            from: None,
+            to: KeyState::PreActivation,
+            at: now,
+            reason: None,
+        });
+        Ok(key_id)
+    }
+
+    pub(super) fn transition(
+        &mut self,
+        key_id: &KeyId,
+        to: KeyState,
+        reason: Option<&str>,
+        now: u64,
+    ) -> Result<(), KeyStoreError> {
+        let record = self.get_mut(key_id)?;
+        let from = record.state;
+        if !from.can_transition_to(to) {
+            return Err(KeyStoreError::InvalidTransition {
+                key_id: *key_id,
+                from,
+                to,
+            });
+        }
+        record.state = to;
+        if to == KeyState::Active && record.activated_at.is_none() {
+            record.activated_at = Some(now);
+        }
+        if to == KeyState::Destroyed {
+            record.key_pair.private_key.iter_mut().for_each(|b| *b = 0);
+            record.key_pair.private_key.clear();
+        }
+        self.history.push(Transition {
+            key_id: *key_id,
+            from: Some(from),
+            to,
+            at: now,
+            reason: reason.map(str::to_string),
+        });
+        Ok(())
+    }
+
+    /// Record that `successor` replaced `key_id`, verifying until `verify_until`
+    pub(super) fn set_successor(
+        &mut self,
+        key_id: &KeyId,
+        successor: KeyId,
+        verify_until: u64,
+    ) -> Result<(), KeyStoreError> {
+        let record = self.get_mut(key_id)?;
+        record.successor = Some(successor);
+        record.verify_until = Some(verify_until);
+        Ok(())
+    }
+}
+
+/// Copies are discarded on every change, so wipe their private keys
+impl Drop for KeyStoreData {
+    fn drop(&mut self) {
+        for record in &mut self.keys {
+            record.key_pair.private_key.iter_mut().for_each(|b| *b = 0);
+        }
+    }
+}
+
+/// Key store tracking keys by id with lifecycle states
+///
+/// Every change is written to the backing file (if any) before the call
+/// returns, and a change that cannot be written leaves the store untouched.
+/// The transition history is append-only. Private keys are stored
+/// unencrypted; the file is replaced atomically and created readable by its
+/// owner only.
+pub struct KeyStore {
+    path: Option<PathBuf>,
+    data: KeyStoreData,
+}
+
+impl KeyStore {
+    /// Create a key store that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            data: KeyStoreData::default(),
+        }
+    }
+
+    /// Open the key store at `path`, creating it if it does not exist
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, KeyStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let data = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => KeyStoreData::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self {
+            path: Some(path),
+            data,
+        })
+    }
+
+    /// Generate a new key in the pre-activation state
+    pub fn generate(&mut self) -> Result<KeyId, KeyStoreError> {
+        self.generate_for(DEFAULT_PURPOSE)
+    }
+
+    /// Generate a new key for `purpose` in the pre-activation state
+    pub fn generate_for(&mut self, purpose: &str) -> Result<KeyId, KeyStoreError> {
+        self.insert(generate_key_pair(), purpose)
+    }
+
+    /// Add an existing key pair in the pre-activation state
+    pub fn import(&mut self, key_pair: KeyPair) -> Result<KeyId, KeyStoreError> {
+        self.insert(key_pair, DEFAULT_PURPOSE)
+    }
+
+    fn insert(&mut self, key_pair: KeyPair, purpose: &str) -> Result<KeyId, KeyStoreError> {
+        let now = unix_time();
+        self.update(|data| data.insert(key_pair, purpose, now))
+    }
+
+    /// Look up a key by id
+    pub fn get(&self, key_id: &KeyId) -> Option<&KeyRecord> {
+        self.data.get(key_id)
+    }
+
+    /// Current state of a key
+    pub fn state(&self, key_id: &KeyId) -> Option<KeyState> {
+        self.get(key_id).map(|r| r.state)
+    }
+
+    /// All keys in the store
+    pub fn keys(&self) -> impl Iterator<Item = &KeyRecord> {
+        self.data.keys.iter()
+    }
+
+    /// Transition history, oldest first
+    pub fn history(&self) -> &[Transition] {
+        &self.data.history
+    }
+
+    /// Move a key to a new state
+    pub fn transition(
+        &mut self,
+        key_id: &KeyId,
+        to: KeyState,
+        reason: Option<&str>,
+    ) -> Result<(), KeyStoreError> {
+        let now = unix_time();
+        self.update(|data| data.transition(key_id, to, reason, now))
+    }
+
+    /// Activate a pre-activation or suspended key
+    pub fn activate(&mut self, key_id: &KeyId) -> Result<(), KeyStoreError> {
+        self.transition(key_id, KeyState::Active, None)
+    }
+
+    /// Temporarily suspend an active key
+    pub fn suspend(&mut self, key_id: &KeyId, reason: &str) -> Result<(), KeyStoreError> {
+        self.transition(key_id, KeyState::Suspended, Some(reason))
+    }
+
+    /// Deactivate a key; it may still verify but no longer signs
+    pub fn deactivate(&mut self, key_id: &KeyId) -> Result<(), KeyStoreError> {
+        self.transition(key_id, KeyState::Deactivated, None)
+    }
+
+    /// Mark a key as compromised
+    pub fn compromise(&mut self, key_id: &KeyId, reason: &str) -> Result<(), KeyStoreError> {
+        self.transition(key_id, KeyState::Compromised, Some(reason))
+    }
+
+    /// Destroy a key's private material
+    pub fn destroy(&mut self, key_id: &KeyId) -> Result<(), KeyStoreError> {
+        self.transition(key_id, KeyState::Destroyed, None)
+    }
+
+    /// Sign a message with an active key
+    ///
+    /// The signature carries the key id so `verify` can find the key.
+    pub fn sign(&self, key_id: &KeyId, message: &[u8]) -> Result<Vec<u8>, KeyStoreError> {
+        let record = self.usable(key_id, KeyState::can_sign)?;
+        Ok(sign_message_with_key_id(message, &record.key_pair)?)
+    }
+
+    /// Verify a signature produced by `sign`
+    pub fn verify(&self, message: &[u8], signature: &[u8]) -> Result<bool, KeyStoreError> {
+        self.verify_at(message, signature, unix_time())
+    }
+
+    /// Verify a signature as of `now` (seconds since the Unix epoch)
+    pub fn verify_at(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        now: u64,
+    ) -> Result<bool, KeyStoreError> {
+        let key_id = match signature_key_id(signature) {
+            Some(key_id) => key_id,
+            None => return Ok(false),
+        };
+        let record = self.usable(&key_id, KeyState::can_verify)?;
+        if record.verify_until.is_some_and(|until| now > until) {
+            return Err(KeyStoreError::GraceExpired(key_id));
+        }
+        Ok(verify_signature_with_key_id(
+            message,
+            signature,
+            &record.key_pair,
+        ))
+    }
+
+    fn usable(
+        &self,
+        key_id: &KeyId,
+        allowed: fn(&KeyState) -> bool,
+    ) -> Result<&KeyRecord, KeyStoreError> {
+        let record = self.get(key_id).ok_or(KeyStoreError::UnknownKey(*key_id))?;
+        if !allowed(&record.state) {
+            return Err(KeyStoreError::KeyNotUsable {
+                key_id: *key_id,
+                state: record.state,
+            });
+        }
+        Ok(record)
+    }
+
+    /// Apply `change` to a copy of the store, persist the copy and only then
+    /// replace the store's contents with it
+    pub(super) fn update<T>(
+        &mut self,
+        change: impl FnOnce(&mut KeyStoreData) -> Result<T, KeyStoreError>,
+    ) -> Result<T, KeyStoreError> {
+        let mut data = self.data.clone();
+        let value = change(&mut data)?;
+        if let Some(path) = &self.path {
+            save(path, &data)?;
+        }
+        self.data = data;
+        Ok(value)
+    }
+}
+
+/// Distinguishes temporary files written by one process
+static SAVE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Write `data` to `path`, replacing it atomically
+///
+/// The contents go to a new owner-only file next to `path`, which is synced
+/// before the rename; the directory is synced after it so the rename itself
+/// is durable.
+fn save(path: &Path, data: &KeyStoreData) -> Result<(), KeyStoreError> {
+    let json = serde_json::to_vec(data)?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let mut name = std::ffi::OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        SAVE_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let tmp = dir.join(name);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let written = options.open(&tmp).and_then(|mut file| {
+        file.write_all(&json)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|()| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    sync_dir(dir)?;
+    Ok(())
+}
+
+/// Flush a directory entry change to disk
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    fs::File::open(dir)?.sync_all()
+}
+
+/// Directories cannot be opened for syncing outside Unix
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+/// Current time in seconds since the Unix epoch
+pub(crate) fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("qsf-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn test_only_active_keys_sign() {
+        let mut store = KeyStore::in_memory();
+        let key_id = store.generate().unwrap();
+        let message = b"Test message";
+
+        assert!(matches!(
+            store.sign(&key_id, message),
+            Err(KeyStoreError::KeyNotUsable { .. })
+        ));
+
+        store.activate(&key_id).unwrap();
+        let signature = store.sign(&key_id, message).unwrap();
+        assert!(store.verify(message, &signature).unwrap());
+
+        store.deactivate(&key_id).unwrap();
+        assert!(store.sign(&key_id, message).is_err());
+        assert!(store.verify(message, &signature).unwrap());
+
+        store.compromise(&key_id, "leaked").unwrap();
+        assert!(store.verify(message, &signature).is_err());
+    }
+
+    #[test]
+    fn test_invalid_transition() {
+        let mut store = KeyStore::in_memory();
+        let key_id = store.generate().unwrap();
+        assert!(matches!(
+            store.deactivate(&key_id),
+            Err(KeyStoreError::InvalidTransition { .. })
+        ));
+        assert_eq!(store.state(&key_id), Some(KeyState::PreActivation));
+        assert_eq!(store.history().len(), 1);
+    }
+
+    #[test]
+    fn test_destroy_clears_private_key() {
+        let mut store = KeyStore::in_memory();
+        let key_id = store.generate().unwrap();
+        store.destroy(&key_id).unwrap();
+        let record = store.get(&key_id).unwrap();
+        assert!(record.key_pair.private_key.is_empty());
+        assert!(!record.key_pair.public_key.is_empty());
+    }
+
+    #[test]
+    fn test_persistence() {
+        let path = temp_path("keystore");
+        let _ = fs::remove_file(&path);
+
+        let key_id = {
+            let mut store = KeyStore::open(&path).unwrap();
+            let key_id = store.generate().unwrap();
+            store.activate(&key_id).unwrap();
+            store.suspend(&key_id, "audit").unwrap();
+            key_id
+        };
+
+        let store = KeyStore::open(&path).unwrap();
+        assert_eq!(store.state(&key_id), Some(KeyState::Suspended));
+        let history = store.history();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[2].from, Some(KeyState::Active));
+        assert_eq!(history[2].reason.as_deref(), Some("audit"));
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_failed_save_leaves_store_unchanged() {
+        let path = temp_path("keystore-unwritable");
+        let _ = fs::remove_file(&path);
+        let mut store = KeyStore::open(&path).unwrap();
+        let key_id = store.generate().unwrap();
+
+        // A non-empty directory in place of the file makes the rename fail
+        fs::remove_file(&path).unwrap();
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("blocker"), b"").unwrap();
+
+        assert!(matches!(store.activate(&key_id), Err(KeyStoreError::Io(_))));
+        assert_eq!(store.state(&key_id), Some(KeyState::PreActivation));
+        assert_eq!(store.history().len(), 1);
+        assert!(store.generate().is_err());
+        assert_eq!(store.keys().count(), 1);
+
+        fs::remove_dir_all(&path).unwrap();
+        let leftovers = fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                let name = entry.file_name();
+                let name = name.to_string_lossy();
+                name.starts_with(".qsf-keystore-unwritable") && name.ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+}
@@ -1,6 +1,28 @@
 //! QuantumSafe Finance - Main library
+//!
+//! Without the default `std` feature the crate is `no_std` and only the
+//! `crypto` core is built; the `alloc` feature adds key pairs and signing.
+//! `fn-dsa` links the `alloc` crate through `zeroize` either way, so a
+//! `no_std` image must register a `#[global_allocator]`.
 
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod approval;
+#[cfg(feature = "std")]
+pub mod backend;
 pub mod bindings;
 pub mod crypto;
+#[cfg(feature = "std")]
+pub mod handshake;
+#[cfg(feature = "std")]
+pub mod keystore;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(feature = "x509")]
+pub mod x509;
 
 pub use crypto::*;
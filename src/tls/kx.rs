@@ -0,0 +1,139 @@
+//! `X25519MLKEM768` hybrid key exchange (draft-ietf-tls-ecdhe-mlkem)
+//!
+//! Key shares and the shared secret put the ML-KEM-768 part first and the
+//! X25519 part second. The secret stays safe while either component holds.
+
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{Ciphertext, EncodedSizeUser, KemCore, MlKem768};
+use rand::thread_rng;
+use rustls::crypto::{ActiveKeyExchange, CompletedKeyExchange, SharedSecret, SupportedKxGroup};
+use rustls::{Error, NamedGroup, PeerMisbehaved, ProtocolVersion};
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+type DecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+
+const ENCAPSULATION_KEY_LEN: usize = 1184;
+const CIPHERTEXT_LEN: usize = 1088;
+const X25519_LEN: usize = 32;
+
+/// The `X25519MLKEM768` key exchange group (code point 0x11EC)
+pub static X25519MLKEM768: &dyn SupportedKxGroup = &X25519MlKem768;
+
+#[derive(Debug)]
+struct X25519MlKem768;
+
+impl SupportedKxGroup for X25519MlKem768 {
+    fn start(&self) -> Result<Box<dyn ActiveKeyExchange>, Error> {
+        let (decapsulation_key, encapsulation_key) = MlKem768::generate(&mut thread_rng());
+        let x25519 = EphemeralSecret::random_from_rng(thread_rng());
+        let mut pub_key = encapsulation_key.as_bytes().to_vec();
+        pub_key.extend_from_slice(PublicKey::from(&x25519).as_bytes());
+        Ok(Box::new(Active {
+            decapsulation_key,
+            x25519,
+            pub_key,
+        }))
+    }
+
+    /// Server side: encapsulate to the client's key and answer with the ciphertext
+    fn start_and_complete(&self, peer_pub_key: &[u8]) -> Result<CompletedKeyExchange, Error> {
+        if peer_pub_key.len() != ENCAPSULATION_KEY_LEN + X25519_LEN {
+            return Err(PeerMisbehaved::InvalidKeyShare.into());
+        }
+        let (kem_share, x25519_share) = peer_pub_key.split_at(ENCAPSULATION_KEY_LEN);
+        let encapsulation_key = EncapsulationKey::from_bytes(kem_share.try_into().unwrap());
+        let (ciphertext, kem_secret) = encapsulation_key
+            .encapsulate(&mut thread_rng())
+            .map_err(|_| PeerMisbehaved::InvalidKeyShare)?;
+
+        let x25519 = EphemeralSecret::random_from_rng(thread_rng());
+        let mut pub_key = ciphertext.to_vec();
+        pub_key.extend_from_slice(PublicKey::from(&x25519).as_bytes());
+        let x25519_secret = diffie_hellman(x25519, x25519_share)?;
+
+        Ok(CompletedKeyExchange {
+            group: self.name(),
+            pub_key,
+            secret: SharedSecret::from([&kem_secret[..], &x25519_secret[..]].concat()),
+        })
+    }
+
+    fn name(&self) -> NamedGroup {
+        NamedGroup::X25519MLKEM768
+    }
+
+    fn usable_for_version(&self, version: ProtocolVersion) -> bool {
+        version == ProtocolVersion::TLSv1_3
+    }
+}
+
+struct Active {
+    decapsulation_key: DecapsulationKey,
+    x25519: EphemeralSecret,
+    pub_key: Vec<u8>,
+}
+
+impl ActiveKeyExchange for Active {
+    fn complete(self: Box<Self>, peer_pub_key: &[u8]) -> Result<SharedSecret, Error> {
+        if peer_pub_key.len() != CIPHERTEXT_LEN + X25519_LEN {
+            return Err(PeerMisbehaved::InvalidKeyShare.into());
+        }
+        let (ciphertext, x25519_share) = peer_pub_key.split_at(CIPHERTEXT_LEN);
+        let ciphertext: Ciphertext<MlKem768> = ciphertext.try_into().unwrap();
+        let kem_secret = self
+            .decapsulation_key
+            .decapsulate(&ciphertext)
+            .map_err(|_| PeerMisbehaved::InvalidKeyShare)?;
+        let x25519_secret = diffie_hellman(self.x25519, x25519_share)?;
+        Ok(SharedSecret::from(
+            [&kem_secret[..], &x25519_secret[..]].concat(),
+        ))
+    }
+
+    fn pub_key(&self) -> &[u8] {
+        &self.pub_key
+    }
+
+    fn group(&self) -> NamedGroup {
+        NamedGroup::X25519MLKEM768
+    }
+}
+
+/// X25519 with the peer's share, rejecting low-order points
+fn diffie_hellman(secret: EphemeralSecret, peer: &[u8]) -> Result<[u8; 32], Error> {
+    let peer: [u8; X25519_LEN] = peer.try_into().unwrap();
+    let shared = secret.diffie_hellman(&PublicKey::from(peer));
+    if !shared.was_contributory() {
+        return Err(PeerMisbehaved::InvalidKeyShare.into());
+    }
+    Ok(shared.to_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_both_sides_agree() {
+        let client = X25519MLKEM768.start().unwrap();
+        assert_eq!(client.pub_key().len(), ENCAPSULATION_KEY_LEN + X25519_LEN);
+        let server = X25519MLKEM768.start_and_complete(client.pub_key()).unwrap();
+        assert_eq!(server.pub_key.len(), CIPHERTEXT_LEN + X25519_LEN);
+
+        let secret = client.complete(&server.pub_key).unwrap();
+        assert_eq!(secret.secret_bytes(), server.secret.secret_bytes());
+        assert_eq!(secret.secret_bytes().len(), 64);
+    }
+
+    #[test]
+    fn test_rejects_bad_shares() {
+        assert!(X25519MLKEM768.start_and_complete(&[0; 10]).is_err());
+
+        let client = X25519MLKEM768.start().unwrap();
+        let mut share = client.pub_key().to_vec();
+        // All-zero X25519 share is a low-order point
+        share[ENCAPSULATION_KEY_LEN..].fill(0);
+        assert!(X25519MLKEM768.start_and_complete(&share).is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Hybrid post-quantum TLS 1.3 for rustls
+//!
+//! `provider()` extends the ring provider with the `X25519MLKEM768` key
+//! share and Falcon certificate signatures. Falcon has no IANA signature
+//! scheme or X.509 OID yet, so the values used here are experimental and
+//! only interoperate with peers configured the same way.
+
+pub use self::kx::*;
+pub use self::sign::*;
+
+pub mod proxy;
+
+mod kx;
+mod sign;
+
+use rustls::crypto::{ring, CryptoProvider, WebPkiSupportedAlgorithms};
+use std::sync::OnceLock;
+
+/// Ring provider preferring `X25519MLKEM768` and accepting Falcon certificates
+pub fn provider() -> CryptoProvider {
+    let ring = ring::default_provider();
+    let mut kx_groups = vec![X25519MLKEM768];
+    kx_groups.extend(ring.kx_groups);
+    CryptoProvider {
+        kx_groups,
+        signature_verification_algorithms: supported_algorithms(),
+        ..ring
+    }
+}
+
+/// Ring's verification algorithms with Falcon added, preferred first
+fn supported_algorithms() -> WebPkiSupportedAlgorithms {
+    static ALGORITHMS: OnceLock<WebPkiSupportedAlgorithms> = OnceLock::new();
+    *ALGORITHMS.get_or_init(|| {
+        let ring = ring::default_provider().signature_verification_algorithms;
+        let mut all = vec![FALCON512, FALCON1024];
+        all.extend_from_slice(ring.all);
+        let mut mapping = vec![
+            (FALCON512_SCHEME, &*Vec::leak(vec![FALCON512])),
+            (FALCON1024_SCHEME, &*Vec::leak(vec![FALCON1024])),
+        ];
+        mapping.extend_from_slice(ring.mapping);
+        WebPkiSupportedAlgorithms {
+            all: Vec::leak(all),
+            mapping: Vec::leak(mapping),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, Algorithm, KeyPair};
+    use crate::x509::{create_csr, CertificateAuthority, IssueOptions};
+    use rustls::pki_types::{CertificateDer, ServerName};
+    use rustls::sign::{CertifiedKey, SingleCertAndKey};
+    use rustls::version::TLS13;
+    use rustls::{
+        ClientConfig, ClientConnection, NamedGroup, RootCertStore, ServerConfig, ServerConnection,
+        StreamOwned,
+    };
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+    use x509_cert::der::Encode;
+
+    const HOUR: Duration = Duration::from_secs(3600);
+
+    /// Root CA and its DER certificate
+    pub(super) fn test_ca(algorithm: Algorithm) -> (CertificateAuthority, CertificateDer<'static>) {
+        let key = generate_key_pair_for(algorithm);
+        let ca = CertificateAuthority::new_root("CN=QSF Test CA", key, HOUR).unwrap();
+        let certificate = CertificateDer::from(ca.certificate().to_der().unwrap());
+        (ca, certificate)
+    }
+
+    /// Certificate for `key` valid for localhost
+    pub(super) fn localhost(ca: &CertificateAuthority, key: &KeyPair) -> CertificateDer<'static> {
+        let csr = create_csr("CN=localhost", key).unwrap();
+        let options = IssueOptions::leaf(HOUR).with_dns_name("localhost");
+        CertificateDer::from(ca.issue(&csr, &options).unwrap().to_der().unwrap())
+    }
+
+    pub(super) fn client_config(root: CertificateDer<'static>) -> Arc<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        roots.add(root).unwrap();
+        let config = ClientConfig::builder_with_provider(Arc::new(provider()))
+            .with_protocol_versions(&[&TLS13])
+            .unwrap()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        Arc::new(config)
+    }
+
+    /// Serve one connection that answers "ping" with "pong"
+    pub(super) fn spawn_server(chain: Vec<CertificateDer<'static>>, key: KeyPair) -> String {
+        let signing_key = Arc::new(FalconSigningKey::new(key).unwrap());
+        let certified = CertifiedKey::new(chain, signing_key);
+        let config = ServerConfig::builder_with_provider(Arc::new(provider()))
+            .with_protocol_versions(&[&TLS13])
+            .unwrap()
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(SingleCertAndKey::from(certified)));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (socket, _) = listener.accept().unwrap();
+            let connection = ServerConnection::new(Arc::new(config)).unwrap();
+            let mut tls = StreamOwned::new(connection, socket);
+            let mut request = [0u8; 4];
+            if tls.read_exact(&mut request).is_ok() {
+                tls.write_all(b"pong").unwrap();
+                tls.flush().unwrap();
+            }
+        });
+        addr
+    }
+
+    pub(super) fn connect(
+        addr: &str,
+        config: Arc<ClientConfig>,
+    ) -> StreamOwned<ClientConnection, TcpStream> {
+        let server_name = ServerName::try_from("localhost").unwrap();
+        let connection = ClientConnection::new(config, server_name).unwrap();
+        StreamOwned::new(connection, TcpStream::connect(addr).unwrap())
+    }
+
+    #[test]
+    fn test_loopback_handshake() {
+        let (ca, root) = test_ca(Algorithm::Falcon1024);
+        let server_key = generate_key_pair_for(Algorithm::Falcon512);
+        let leaf = localhost(&ca, &server_key);
+
+        let addr = spawn_server(vec![leaf], server_key);
+        let mut tls = connect(&addr, client_config(root));
+        tls.write_all(b"ping").unwrap();
+        let mut reply = [0u8; 4];
+        tls.read_exact(&mut reply).unwrap();
+
+        assert_eq!(&reply, b"pong");
+        let group = tls.conn.negotiated_key_exchange_group().unwrap();
+        assert_eq!(group.name(), NamedGroup::X25519MLKEM768);
+    }
+
+    #[test]
+    fn test_rejects_untrusted_certificate() {
+        let (ca, _) = test_ca(Algorithm::Falcon512);
+        let (_, other) = test_ca(Algorithm::Falcon512);
+        let server_key = generate_key_pair_for(Algorithm::Falcon512);
+        let leaf = localhost(&ca, &server_key);
+
+        let addr = spawn_server(vec![leaf], server_key);
+        let mut tls = connect(&addr, client_config(other));
+        assert!(tls.write_all(b"ping").and_then(|_| tls.flush()).is_err());
+    }
+}
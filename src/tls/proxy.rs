@@ -0,0 +1,993 @@
+//! TLS-terminating proxy behind the `qsf-sidecar` binary
+//!
+//! Clients connect with hybrid post-quantum TLS 1.3 and every connection is
+//! forwarded to a local upstream, either in plaintext or over TLS. The
+//! certificate files are polled and reloaded when they change: new
+//! connections use the new certificate, established ones are left alone.
+//! Events are reported through the `log` facade under the `qsf_sidecar`
+//! target.
+//!
+//! ```toml
+//! listen = "0.0.0.0:8443"
+//! upstream = "127.0.0.1:8080"
+//! cert_chain = "/etc/qsf/server.pem"
+//! private_key = "/etc/qsf/server-key.json"
+//! reload_interval_secs = 30
+//! max_connections = 1024
+//! metrics_listen = "127.0.0.1:9464"
+//!
+//! [upstream_tls]
+//! ca_file = "/etc/qsf/upstream-ca.pem"
+//! server_name = "backend.internal"
+//! ```
+
+use super::{provider, FalconSigningKey};
+use crate::crypto::key_pair_from_json;
+use log::{info, warn};
+use rustls::pki_types::pem::PemObject;
+use rustls::pki_types::{CertificateDer, ServerName};
+use rustls::server::{ClientHello, ResolvesServerCert};
+use rustls::sign::CertifiedKey;
+use rustls::version::TLS13;
+use rustls::ServerConnection;
+use rustls::{ClientConfig, ClientConnection, Connection, RootCertStore, ServerConfig};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock, TryLockError};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+const LOG_TARGET: &str = "qsf_sidecar";
+
+/// Time allowed for a peer to finish the TLS handshake, however it paces it
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause after a failed accept, so running out of descriptors does not spin
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Errors raised while configuring or running the proxy
+#[derive(Debug)]
+pub enum ProxyError {
+    Io(io::Error),
+    Config(String),
+    Tls(rustls::Error),
+}
+
+impl fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProxyError::Io(e) => write!(f, "I/O error: {}", e),
+            ProxyError::Config(msg) => write!(f, "Invalid configuration: {}", msg),
+            ProxyError::Tls(e) => write!(f, "TLS error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProxyError {}
+
+impl From<io::Error> for ProxyError {
+    fn from(e: io::Error) -> Self {
+        ProxyError::Io(e)
+    }
+}
+
+impl From<rustls::Error> for ProxyError {
+    fn from(e: rustls::Error) -> Self {
+        ProxyError::Tls(e)
+    }
+}
+
+/// Proxy settings, read from a TOML file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProxyConfig {
+    /// Address accepting hybrid TLS connections
+    pub listen: String,
+    /// Local service that receives the decrypted traffic
+    pub upstream: String,
+    /// PEM certificate chain, leaf first
+    pub cert_chain: PathBuf,
+    /// Falcon key pair as written by `key_pair_to_json`
+    pub private_key: PathBuf,
+    /// How often the certificate files are checked for changes
+    #[serde(default = "default_reload_interval")]
+    pub reload_interval_secs: u64,
+    /// Connections served at once; further clients are disconnected
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u64,
+    /// Address serving metrics in the Prometheus text format
+    #[serde(default)]
+    pub metrics_listen: Option<String>,
+    /// Connect to the upstream over TLS instead of plaintext
+    #[serde(default)]
+    pub upstream_tls: Option<UpstreamTls>,
+}
+
+/// TLS settings for the upstream connection
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpstreamTls {
+    /// PEM roots trusted for the upstream certificate
+    pub ca_file: PathBuf,
+    /// Name the upstream certificate must be valid for
+    pub server_name: String,
+}
+
+fn default_reload_interval() -> u64 {
+    30
+}
+
+fn default_max_connections() -> u64 {
+    1024
+}
+
+impl ProxyConfig {
+    /// Parse a configuration from TOML text
+    pub fn from_toml(text: &str) -> Result<Self, ProxyError> {
+        let config: ProxyConfig =
+            toml::from_str(text).map_err(|e| ProxyError::Config(e.to_string()))?;
+        if config.reload_interval_secs == 0 {
+            return Err(ProxyError::Config(
+                "reload_interval_secs must be positive".to_string(),
+            ));
+        }
+        if config.max_connections == 0 {
+            return Err(ProxyError::Config(
+                "max_connections must be positive".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Read and parse a configuration file
+    pub fn load(path: &Path) -> Result<Self, ProxyError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+}
+
+/// Connection counters shared by all proxy threads
+#[derive(Debug, Default)]
+pub struct Metrics {
+    pub connections_accepted: AtomicU64,
+    pub connections_active: AtomicU64,
+    /// Connections closed unserved because `max_connections` were active
+    pub connections_rejected: AtomicU64,
+    pub accept_failures: AtomicU64,
+    pub handshake_failures: AtomicU64,
+    pub upstream_failures: AtomicU64,
+    pub bytes_from_clients: AtomicU64,
+    pub bytes_to_clients: AtomicU64,
+    pub certificate_reloads: AtomicU64,
+    pub certificate_reload_failures: AtomicU64,
+}
+
+impl Metrics {
+    /// Counters in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let metrics = [
+            (
+                "connections_accepted_total",
+                "counter",
+                &self.connections_accepted,
+            ),
+            ("connections_active", "gauge", &self.connections_active),
+            (
+                "connections_rejected_total",
+                "counter",
+                &self.connections_rejected,
+            ),
+            ("accept_failures_total", "counter", &self.accept_failures),
+            (
+                "handshake_failures_total",
+                "counter",
+                &self.handshake_failures,
+            ),
+            (
+                "upstream_failures_total",
+                "counter",
+                &self.upstream_failures,
+            ),
+            (
+                "bytes_from_clients_total",
+                "counter",
+                &self.bytes_from_clients,
+            ),
+            ("bytes_to_clients_total", "counter", &self.bytes_to_clients),
+            (
+                "certificate_reloads_total",
+                "counter",
+                &self.certificate_reloads,
+            ),
+            (
+                "certificate_reload_failures_total",
+                "counter",
+                &self.certificate_reload_failures,
+            ),
+        ];
+        let mut out = String::new();
+        for (name, kind, value) in metrics {
+            out.push_str(&format!(
+                "# TYPE qsf_sidecar_{name} {kind}\nqsf_sidecar_{name} {}\n",
+                value.load(Ordering::Relaxed)
+            ));
+        }
+        out
+    }
+}
+
+/// Server certificate that is replaced when its files change on disk
+#[derive(Debug)]
+pub struct ReloadingResolver {
+    cert_chain: PathBuf,
+    private_key: PathBuf,
+    current: RwLock<(Arc<CertifiedKey>, Option<SystemTime>)>,
+}
+
+impl ReloadingResolver {
+    /// Load the certificate chain and Falcon key pair
+    pub fn new(cert_chain: &Path, private_key: &Path) -> Result<Self, ProxyError> {
+        let modified = modified(cert_chain, private_key);
+        let certified = load_certified_key(cert_chain, private_key)?;
+        Ok(ReloadingResolver {
+            cert_chain: cert_chain.to_path_buf(),
+            private_key: private_key.to_path_buf(),
+            current: RwLock::new((Arc::new(certified), modified)),
+        })
+    }
+
+    /// Reload if either file changed; a failed reload keeps the old certificate
+    pub fn reload_if_changed(&self) -> Result<bool, ProxyError> {
+        let modified = modified(&self.cert_chain, &self.private_key);
+        if modified == self.current.read().expect("Resolver lock poisoned").1 {
+            return Ok(false);
+        }
+        let certified = load_certified_key(&self.cert_chain, &self.private_key)?;
+        *self.current.write().expect("Resolver lock poisoned") = (Arc::new(certified), modified);
+        Ok(true)
+    }
+
+    /// Certificate currently handed to new connections
+    pub fn current(&self) -> Arc<CertifiedKey> {
+        self.current
+            .read()
+            .expect("Resolver lock poisoned")
+            .0
+            .clone()
+    }
+}
+
+impl ResolvesServerCert for ReloadingResolver {
+    fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+        Some(self.current())
+    }
+}
+
+/// Latest modification time of the two files
+fn modified(cert_chain: &Path, private_key: &Path) -> Option<SystemTime> {
+    let cert = fs::metadata(cert_chain).and_then(|m| m.modified()).ok()?;
+    let key = fs::metadata(private_key).and_then(|m| m.modified()).ok()?;
+    Some(cert.max(key))
+}
+
+fn load_certified_key(cert_chain: &Path, private_key: &Path) -> Result<CertifiedKey, ProxyError> {
+    let chain = load_certificates(cert_chain)?;
+    let key_pair = key_pair_from_json(&fs::read_to_string(private_key)?)
+        .map_err(|e| ProxyError::Config(format!("{}: {}", private_key.display(), e)))?;
+    let certified = CertifiedKey::new(chain, Arc::new(FalconSigningKey::new(key_pair)?));
+    certified.keys_match()?;
+    Ok(certified)
+}
+
+fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>, ProxyError> {
+    let invalid = |e| ProxyError::Config(format!("{}: {:?}", path.display(), e));
+    let chain = CertificateDer::pem_file_iter(path)
+        .map_err(invalid)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(invalid)?;
+    if chain.is_empty() {
+        return Err(ProxyError::Config(format!(
+            "{}: no certificates found",
+            path.display()
+        )));
+    }
+    Ok(chain)
+}
+
+/// Settings every connection thread needs
+struct Shared {
+    server_config: Arc<ServerConfig>,
+    upstream: String,
+    upstream_tls: Option<(Arc<ClientConfig>, ServerName<'static>)>,
+    metrics: Arc<Metrics>,
+}
+
+/// A bound proxy, ready to serve
+pub struct Proxy {
+    listener: TcpListener,
+    metrics_listener: Option<TcpListener>,
+    reload_interval: Duration,
+    max_connections: u64,
+    resolver: Arc<ReloadingResolver>,
+    shared: Arc<Shared>,
+}
+
+impl Proxy {
+    /// Load certificates and bind the listening sockets
+    pub fn bind(config: ProxyConfig) -> Result<Self, ProxyError> {
+        let resolver = Arc::new(ReloadingResolver::new(
+            &config.cert_chain,
+            &config.private_key,
+        )?);
+        let server_config = ServerConfig::builder_with_provider(Arc::new(provider()))
+            .with_protocol_versions(&[&TLS13])?
+            .with_no_client_auth()
+            .with_cert_resolver(resolver.clone());
+        let upstream_tls = match config.upstream_tls {
+            Some(upstream) => Some(upstream_client(&upstream)?),
+            None => None,
+        };
+        let metrics_listener = match &config.metrics_listen {
+            Some(addr) => Some(TcpListener::bind(addr)?),
+            None => None,
+        };
+        Ok(Proxy {
+            listener: TcpListener::bind(&config.listen)?,
+            metrics_listener,
+            reload_interval: Duration::from_secs(config.reload_interval_secs),
+            max_connections: config.max_connections,
+            resolver,
+            shared: Arc::new(Shared {
+                server_config: Arc::new(server_config),
+                upstream: config.upstream,
+                upstream_tls,
+                metrics: Arc::new(Metrics::default()),
+            }),
+        })
+    }
+
+    /// Address accepting TLS connections
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Address serving metrics, if enabled
+    pub fn metrics_addr(&self) -> Option<SocketAddr> {
+        self.metrics_listener
+            .as_ref()
+            .and_then(|l| l.local_addr().ok())
+    }
+
+    pub fn metrics(&self) -> Arc<Metrics> {
+        self.shared.metrics.clone()
+    }
+
+    pub fn resolver(&self) -> Arc<ReloadingResolver> {
+        self.resolver.clone()
+    }
+
+    /// Serve connections; failed accepts are logged and counted, not fatal
+    pub fn run(self) -> Result<(), ProxyError> {
+        let resolver = self.resolver.clone();
+        let metrics = self.shared.metrics.clone();
+        let interval = self.reload_interval;
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            match resolver.reload_if_changed() {
+                Ok(true) => {
+                    metrics.certificate_reloads.fetch_add(1, Ordering::Relaxed);
+                }
+                Ok(false) => {}
+                Err(e) => {
+                    metrics
+                        .certificate_reload_failures
+                        .fetch_add(1, Ordering::Relaxed);
+                    warn!(target: LOG_TARGET, "certificate reload failed: {}", e);
+                }
+            }
+        });
+        if let Some(listener) = self.metrics_listener {
+            let metrics = self.shared.metrics.clone();
+            thread::spawn(move || serve_metrics(listener, &metrics));
+        }
+        let metrics = self.shared.metrics.clone();
+        for client in self.listener.incoming() {
+            let client = match client {
+                Ok(client) => client,
+                Err(e) => {
+                    metrics.accept_failures.fetch_add(1, Ordering::Relaxed);
+                    warn!(target: LOG_TARGET, "accept failed: {}", e);
+                    thread::sleep(ACCEPT_BACKOFF);
+                    continue;
+                }
+            };
+            metrics.connections_accepted.fetch_add(1, Ordering::Relaxed);
+            // Only this thread adds to the gauge, so the check cannot race
+            if metrics.connections_active.load(Ordering::Relaxed) >= self.max_connections {
+                metrics.connections_rejected.fetch_add(1, Ordering::Relaxed);
+                warn!(
+                    target: LOG_TARGET,
+                    "{} connections active, disconnecting {:?}",
+                    self.max_connections,
+                    client.peer_addr()
+                );
+                continue;
+            }
+            metrics.connections_active.fetch_add(1, Ordering::Relaxed);
+            let shared = self.shared.clone();
+            thread::spawn(move || {
+                if let Err(e) = handle(&shared, client) {
+                    info!(target: LOG_TARGET, "connection closed: {}", e);
+                }
+                shared
+                    .metrics
+                    .connections_active
+                    .fetch_sub(1, Ordering::Relaxed);
+            });
+        }
+        Ok(())
+    }
+}
+
+fn upstream_client(
+    upstream: &UpstreamTls,
+) -> Result<(Arc<ClientConfig>, ServerName<'static>), ProxyError> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certificates(&upstream.ca_file)? {
+        roots.add(cert)?;
+    }
+    // The hybrid provider still negotiates classical groups and signatures
+    let config = ClientConfig::builder_with_provider(Arc::new(provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(upstream.server_name.clone())
+        .map_err(|e| ProxyError::Config(format!("upstream server_name: {}", e)))?;
+    Ok((Arc::new(config), server_name))
+}
+
+/// Terminate TLS on `client` and splice it to the upstream
+fn handle(shared: &Shared, client: TcpStream) -> Result<(), ProxyError> {
+    let mut connection = Connection::from(ServerConnection::new(shared.server_config.clone())?);
+    if let Err(e) = handshake(&mut connection, &client, HANDSHAKE_TIMEOUT) {
+        shared
+            .metrics
+            .handshake_failures
+            .fetch_add(1, Ordering::Relaxed);
+        return Err(e.into());
+    }
+    let client = Endpoint::Tls(client, Box::new(TlsState::new(connection)));
+
+    let upstream = connect_upstream(shared).inspect_err(|_| {
+        shared
+            .metrics
+            .upstream_failures
+            .fetch_add(1, Ordering::Relaxed);
+    })?;
+
+    let metrics = &shared.metrics;
+    thread::scope(|scope| {
+        scope.spawn(|| pump(&client, &upstream, &metrics.bytes_from_clients));
+        pump(&upstream, &client, &metrics.bytes_to_clients);
+    });
+    Ok(())
+}
+
+fn connect_upstream(shared: &Shared) -> Result<Endpoint, ProxyError> {
+    let socket = TcpStream::connect(&shared.upstream)?;
+    match &shared.upstream_tls {
+        None => Ok(Endpoint::Plain(socket)),
+        Some((config, server_name)) => {
+            let mut connection =
+                Connection::from(ClientConnection::new(config.clone(), server_name.clone())?);
+            handshake(&mut connection, &socket, HANDSHAKE_TIMEOUT)?;
+            Ok(Endpoint::Tls(socket, Box::new(TlsState::new(connection))))
+        }
+    }
+}
+
+/// Complete the handshake on `socket` within `timeout`
+///
+/// `complete_io` keeps reading until the handshake is done, so a peer
+/// trickling bytes could hold it open indefinitely under a per-read timeout.
+/// The I/O is driven here instead, and every read and write is bounded by the
+/// time left until the deadline.
+fn handshake(connection: &mut Connection, socket: &TcpStream, timeout: Duration) -> io::Result<()> {
+    drive_handshake(connection, socket, Instant::now() + timeout).map_err(|e| {
+        // Unix reports an expired socket timeout as `WouldBlock`
+        if e.kind() == io::ErrorKind::WouldBlock {
+            io::ErrorKind::TimedOut.into()
+        } else {
+            e
+        }
+    })
+}
+
+fn drive_handshake(
+    connection: &mut Connection,
+    mut socket: &TcpStream,
+    deadline: Instant,
+) -> io::Result<()> {
+    while connection.is_handshaking() {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        socket.set_write_timeout(Some(remaining))?;
+        if connection.wants_write() {
+            connection.write_tls(&mut socket)?;
+        } else if connection.read_tls(&mut socket)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        } else if let Err(e) = connection.process_new_packets() {
+            // Send the alert describing the failure
+            let _ = connection.write_tls(&mut socket);
+            return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+        }
+    }
+    while connection.wants_write() {
+        connection.write_tls(&mut socket)?;
+    }
+    socket.set_read_timeout(None)?;
+    socket.set_write_timeout(None)
+}
+
+/// One side of a proxied connection
+///
+/// Both copy directions share the TLS state. Records are encrypted under the
+/// connection lock and written to the socket after it is released, so a
+/// direction blocked on a slow peer never stops the other one from reading.
+enum Endpoint {
+    Plain(TcpStream),
+    Tls(TcpStream, Box<TlsState>),
+}
+
+struct TlsState {
+    connection: Mutex<Connection>,
+    /// Held while queued records are written, keeping them in order
+    send: Mutex<()>,
+}
+
+impl TlsState {
+    fn new(connection: Connection) -> Self {
+        TlsState {
+            connection: Mutex::new(connection),
+            send: Mutex::new(()),
+        }
+    }
+
+    fn connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().expect("Connection lock poisoned")
+    }
+
+    /// Write out every queued record
+    ///
+    /// Without `wait`, returns at once if another thread is sending; that
+    /// thread checks for records queued meanwhile before it lets go.
+    fn send(&self, mut socket: &TcpStream, wait: bool) -> io::Result<()> {
+        let mut wait = wait;
+        loop {
+            let guard = match self.send.try_lock() {
+                Ok(guard) => guard,
+                Err(TryLockError::Poisoned(e)) => e.into_inner(),
+                Err(TryLockError::WouldBlock) if wait => {
+                    self.send.lock().unwrap_or_else(PoisonError::into_inner)
+                }
+                Err(TryLockError::WouldBlock) => return Ok(()),
+            };
+            loop {
+                let mut pending = Vec::new();
+                {
+                    let mut connection = self.connection();
+                    while connection.wants_write() {
+                        connection.write_tls(&mut pending)?;
+                    }
+                }
+                if pending.is_empty() {
+                    break;
+                }
+                socket.write_all(&pending)?;
+            }
+            drop(guard);
+            // Records queued after the last check but before the unlock
+            if !self.connection().wants_write() {
+                return Ok(());
+            }
+            wait = false;
+        }
+    }
+}
+
+impl Endpoint {
+    fn socket(&self) -> &TcpStream {
+        match self {
+            Endpoint::Plain(socket) | Endpoint::Tls(socket, _) => socket,
+        }
+    }
+
+    /// Read plaintext; 0 means the peer finished sending
+    fn read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let (mut socket, tls) = match self {
+            Endpoint::Plain(socket) => return (&*socket).read(buf),
+            Endpoint::Tls(socket, tls) => (socket, tls),
+        };
+        let mut incoming = [0u8; 8192];
+        loop {
+            match tls.connection().reader().read(buf) {
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {}
+                result => return result,
+            }
+            let n = socket.read(&mut incoming)?;
+            if n == 0 {
+                return Ok(0);
+            }
+            let mut data = &incoming[..n];
+            while !data.is_empty() {
+                let state = {
+                    let mut connection = tls.connection();
+                    connection.read_tls(&mut data)?;
+                    connection.process_new_packets()
+                };
+                // Send alerts and post-handshake messages before reporting
+                tls.send(socket, false)?;
+                state.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            }
+        }
+    }
+
+    fn write_all(&self, data: &[u8]) -> io::Result<()> {
+        match self {
+            Endpoint::Plain(socket) => (&*socket).write_all(data),
+            Endpoint::Tls(socket, tls) => {
+                tls.connection().writer().write_all(data)?;
+                tls.send(socket, true)
+            }
+        }
+    }
+
+    /// Signal the end of our data, keeping the read side open
+    fn finish(&self) {
+        if let Endpoint::Tls(socket, tls) = self {
+            tls.connection().send_close_notify();
+            let _ = tls.send(socket, true);
+        }
+        let _ = self.socket().shutdown(Shutdown::Write);
+    }
+}
+
+/// Copy `from` into `to` until EOF; an error tears down both sides
+fn pump(from: &Endpoint, to: &Endpoint, counter: &AtomicU64) {
+    let mut buf = [0u8; 8192];
+    let result = loop {
+        match from.read(&mut buf) {
+            Ok(0) => break Ok(()),
+            Ok(n) => {
+                if let Err(e) = to.write_all(&buf[..n]) {
+                    break Err(e);
+                }
+                counter.fetch_add(n as u64, Ordering::Relaxed);
+            }
+            Err(e) => break Err(e),
+        }
+    };
+    match result {
+        Ok(()) => to.finish(),
+        Err(_) => {
+            let _ = from.socket().shutdown(Shutdown::Both);
+            let _ = to.socket().shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Answer every request on `listener` with the current metrics
+fn serve_metrics(listener: TcpListener, metrics: &Metrics) {
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else { continue };
+        let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+        let mut request = [0u8; 1024];
+        let _ = stream.read(&mut request);
+        let body = metrics.render();
+        let _ = write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, key_pair_to_json, Algorithm, KeyPair};
+    use crate::tls::tests::{client_config, connect, localhost, spawn_server, test_ca};
+    use base64::{engine::general_purpose, Engine as _};
+    use rustls::sign::SingleCertAndKey;
+    use std::fs::File;
+
+    fn write_pem(path: &Path, certs: &[&CertificateDer<'_>]) {
+        let mut pem = String::new();
+        for cert in certs {
+            let encoded = general_purpose::STANDARD.encode(cert);
+            pem.push_str("-----BEGIN CERTIFICATE-----\n");
+            for line in encoded.as_bytes().chunks(64) {
+                pem.push_str(std::str::from_utf8(line).unwrap());
+                pem.push('\n');
+            }
+            pem.push_str("-----END CERTIFICATE-----\n");
+        }
+        fs::write(path, pem).unwrap();
+    }
+
+    /// Write a leaf and its key, stamping both with a distinct mtime
+    fn install(dir: &Path, leaf: &CertificateDer<'_>, key: &KeyPair, generation: u64) {
+        write_pem(&dir.join("chain.pem"), &[leaf]);
+        fs::write(dir.join("key.json"), key_pair_to_json(key)).unwrap();
+        let stamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + generation);
+        for name in ["chain.pem", "key.json"] {
+            let file = File::options().write(true).open(dir.join(name)).unwrap();
+            file.set_modified(stamp).unwrap();
+        }
+    }
+
+    /// Plaintext upstream answering every "ping" with "pong"
+    fn spawn_upstream() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                thread::spawn(move || {
+                    let mut request = [0u8; 4];
+                    while stream.read_exact(&mut request).is_ok() {
+                        stream.write_all(b"pong").unwrap();
+                    }
+                });
+            }
+        });
+        addr
+    }
+
+    fn start(dir: &Path, upstream: &str, extra: &str) -> (SocketAddr, Proxy) {
+        let config = ProxyConfig::from_toml(&format!(
+            "listen = \"127.0.0.1:0\"\nupstream = \"{}\"\ncert_chain = {:?}\nprivate_key = {:?}\n{}",
+            upstream,
+            dir.join("chain.pem"),
+            dir.join("key.json"),
+            extra
+        ))
+        .unwrap();
+        let proxy = Proxy::bind(config).unwrap();
+        (proxy.local_addr().unwrap(), proxy)
+    }
+
+    fn ping(tls: &mut (impl Read + Write)) {
+        tls.write_all(b"ping").unwrap();
+        let mut reply = [0u8; 4];
+        tls.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"pong");
+    }
+
+    fn wait_for(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "condition not reached");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qsf-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_config() {
+        let minimal = "listen = \"0.0.0.0:8443\"\nupstream = \"127.0.0.1:8080\"\n\
+                       cert_chain = \"chain.pem\"\nprivate_key = \"key.json\"\n";
+        let config = ProxyConfig::from_toml(minimal).unwrap();
+        assert_eq!(config.reload_interval_secs, 30);
+        assert_eq!(config.max_connections, 1024);
+        assert!(config.metrics_listen.is_none() && config.upstream_tls.is_none());
+
+        let with_tls = format!(
+            "{}[upstream_tls]\nca_file = \"ca.pem\"\nserver_name = \"backend\"\n",
+            minimal
+        );
+        let upstream = ProxyConfig::from_toml(&with_tls).unwrap().upstream_tls;
+        assert_eq!(upstream.unwrap().server_name, "backend");
+
+        for invalid in [
+            format!("{}reload_interval_secs = 0\n", minimal),
+            format!("{}max_connections = 0\n", minimal),
+            format!("{}unknown = 1\n", minimal),
+            "listen = \"0.0.0.0:8443\"\n".to_string(),
+        ] {
+            assert!(matches!(
+                ProxyConfig::from_toml(&invalid),
+                Err(ProxyError::Config(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_proxy_reloads_certificates() {
+        let dir = temp_dir("sidecar");
+        let (authority, ca) = test_ca(Algorithm::Falcon512);
+        let first_key = generate_key_pair_for(Algorithm::Falcon512);
+        let first = localhost(&authority, &first_key);
+        install(&dir, &first, &first_key, 1);
+
+        let (addr, proxy) = start(&dir, &spawn_upstream(), "metrics_listen = \"127.0.0.1:0\"");
+        let (metrics, resolver) = (proxy.metrics(), proxy.resolver());
+        let metrics_addr = proxy.metrics_addr().unwrap();
+        let addr = addr.to_string();
+        thread::spawn(move || proxy.run());
+
+        let mut established = connect(&addr, client_config(ca.clone()));
+        ping(&mut established);
+        assert_eq!(established.conn.peer_certificates().unwrap()[0], first);
+
+        // A new certificate serves new connections without dropping old ones
+        let second_key = generate_key_pair_for(Algorithm::Falcon512);
+        let second = localhost(&authority, &second_key);
+        install(&dir, &second, &second_key, 2);
+        assert!(resolver.reload_if_changed().unwrap());
+        assert!(!resolver.reload_if_changed().unwrap());
+        let mut fresh = connect(&addr, client_config(ca));
+        ping(&mut fresh);
+        assert_eq!(fresh.conn.peer_certificates().unwrap()[0], second);
+        ping(&mut established);
+
+        // A key that does not match the certificate is refused
+        install(&dir, &first, &second_key, 3);
+        assert!(matches!(
+            resolver.reload_if_changed(),
+            Err(ProxyError::Tls(rustls::Error::InconsistentKeys(_)))
+        ));
+        assert_eq!(resolver.current().end_entity_cert().unwrap(), &second);
+
+        wait_for(|| metrics.bytes_to_clients.load(Ordering::Relaxed) == 12);
+        let mut scrape = TcpStream::connect(metrics_addr).unwrap();
+        scrape.write_all(b"GET /metrics HTTP/1.1\r\n\r\n").unwrap();
+        let mut response = String::new();
+        scrape.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("qsf_sidecar_connections_accepted_total 2\n"));
+        assert!(response.contains("qsf_sidecar_bytes_from_clients_total 12\n"));
+
+        drop(established);
+        drop(fresh);
+        wait_for(|| metrics.connections_active.load(Ordering::Relaxed) == 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_tls_upstream_and_failed_handshake() {
+        let dir = temp_dir("sidecar-upstream");
+        let (authority, ca) = test_ca(Algorithm::Falcon512);
+        let proxy_key = generate_key_pair_for(Algorithm::Falcon512);
+        install(&dir, &localhost(&authority, &proxy_key), &proxy_key, 1);
+        write_pem(&dir.join("ca.pem"), &[&ca]);
+
+        let upstream_key = generate_key_pair_for(Algorithm::Falcon512);
+        let upstream_cert = localhost(&authority, &upstream_key);
+        let upstream = spawn_server(vec![upstream_cert], upstream_key);
+        let upstream_tls = format!(
+            "[upstream_tls]\nca_file = {:?}\nserver_name = \"localhost\"",
+            dir.join("ca.pem")
+        );
+        let (addr, proxy) = start(&dir, &upstream, &upstream_tls);
+        let metrics = proxy.metrics();
+        thread::spawn(move || proxy.run());
+
+        let mut tls = connect(&addr.to_string(), client_config(ca));
+        ping(&mut tls);
+
+        let mut plaintext = TcpStream::connect(addr).unwrap();
+        plaintext.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        wait_for(|| metrics.handshake_failures.load(Ordering::Relaxed) == 1);
+        assert_eq!(metrics.upstream_failures.load(Ordering::Relaxed), 0);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_handshake_deadline() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut client = TcpStream::connect(addr).unwrap();
+            // A handshake record header promising 16 KiB, then one byte at a
+            // time, each well within a per-read timeout
+            client.write_all(&[0x16, 0x03, 0x01, 0x40, 0x00]).unwrap();
+            while client.write_all(&[0]).is_ok() {
+                thread::sleep(Duration::from_millis(50));
+            }
+        });
+        let (socket, _) = listener.accept().unwrap();
+
+        let (authority, _) = test_ca(Algorithm::Falcon512);
+        let key = generate_key_pair_for(Algorithm::Falcon512);
+        let certified = CertifiedKey::new(
+            vec![localhost(&authority, &key)],
+            Arc::new(FalconSigningKey::new(key).unwrap()),
+        );
+        let config = ServerConfig::builder_with_provider(Arc::new(provider()))
+            .with_protocol_versions(&[&TLS13])
+            .unwrap()
+            .with_no_client_auth()
+            .with_cert_resolver(Arc::new(SingleCertAndKey::from(certified)));
+        let mut connection = Connection::from(ServerConnection::new(Arc::new(config)).unwrap());
+
+        let started = Instant::now();
+        let e = handshake(&mut connection, &socket, Duration::from_millis(300)).unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::TimedOut);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let dir = temp_dir("sidecar-limit");
+        let (authority, ca) = test_ca(Algorithm::Falcon512);
+        let key = generate_key_pair_for(Algorithm::Falcon512);
+        install(&dir, &localhost(&authority, &key), &key, 1);
+        let (addr, proxy) = start(&dir, &spawn_upstream(), "max_connections = 1");
+        let metrics = proxy.metrics();
+        let addr = addr.to_string();
+        thread::spawn(move || proxy.run());
+
+        let mut first = connect(&addr, client_config(ca.clone()));
+        ping(&mut first);
+        let mut refused = connect(&addr, client_config(ca.clone()));
+        // The handshake runs on the first write or read and finds the socket closed
+        let mut reply = [0u8; 4];
+        assert!(refused.write_all(b"ping").is_err() || refused.read_exact(&mut reply).is_err());
+        assert_eq!(metrics.connections_rejected.load(Ordering::Relaxed), 1);
+
+        drop(first);
+        wait_for(|| metrics.connections_active.load(Ordering::Relaxed) == 0);
+        ping(&mut connect(&addr, client_config(ca)));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_both_directions_under_backpressure() {
+        // More than the loopback socket buffers can hold, in each direction
+        const LEN: usize = 16 << 20;
+        let dir = temp_dir("sidecar-duplex");
+        let (authority, ca) = test_ca(Algorithm::Falcon512);
+        let key = generate_key_pair_for(Algorithm::Falcon512);
+        install(&dir, &localhost(&authority, &key), &key, 1);
+
+        // Upstream that streams while it drains, like a bulk transfer service
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let upstream = listener.local_addr().unwrap().to_string();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            thread::spawn(move || writer.write_all(&vec![7u8; LEN]));
+            io::copy(&mut (&mut stream).take(LEN as u64), &mut io::sink()).unwrap();
+        });
+        let (addr, proxy) = start(&dir, &upstream, "");
+        thread::spawn(move || proxy.run());
+
+        // The client sends everything before reading, so the proxy has to
+        // keep decrypting while its writes to the client are blocked
+        let (done, finished) = std::sync::mpsc::channel();
+        let addr = addr.to_string();
+        thread::spawn(move || {
+            let mut tls = connect(&addr, client_config(ca));
+            tls.write_all(&vec![1u8; LEN]).unwrap();
+            let mut received = vec![0u8; LEN];
+            tls.read_exact(&mut received).unwrap();
+            done.send(received.iter().all(|&b| b == 7)).unwrap();
+        });
+        assert_eq!(finished.recv_timeout(Duration::from_secs(60)), Ok(true));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,154 @@
+//! Falcon certificates and handshake signatures
+
+use crate::crypto::{verify_with_public_key, Algorithm, KeyPair};
+use rustls::pki_types::{
+    AlgorithmIdentifier, InvalidSignature, SignatureVerificationAlgorithm, SubjectPublicKeyInfoDer,
+};
+use rustls::sign::{public_key_to_spki, Signer, SigningKey};
+use rustls::{Error, SignatureAlgorithm, SignatureScheme};
+use std::fmt;
+use std::sync::Arc;
+
+/// TLS signature scheme for Falcon-512 (experimental code point)
+pub const FALCON512_SCHEME: SignatureScheme = SignatureScheme::Unknown(0xfed7);
+/// TLS signature scheme for Falcon-1024 (experimental code point)
+pub const FALCON1024_SCHEME: SignatureScheme = SignatureScheme::Unknown(0xfeda);
+
+/// TLS 1.2 signature algorithm reported for Falcon keys
+///
+/// Falcon has no TLS 1.2 code point, and rustls only uses a key's algorithm
+/// to pick TLS 1.2 cipher suites. A private-use value matches none of them,
+/// so Falcon keys are only offered in TLS 1.3.
+const FALCON_TLS12_ALGORITHM: SignatureAlgorithm = SignatureAlgorithm::Unknown(0xfe);
+
+/// DER of the Falcon-512 AlgorithmIdentifier contents, OID 1.3.9999.3.11
+const FALCON512_ALG_ID: &[u8] = &[0x06, 0x05, 0x2b, 0xce, 0x0f, 0x03, 0x0b];
+/// DER of the Falcon-1024 AlgorithmIdentifier contents, OID 1.3.9999.3.14
+const FALCON1024_ALG_ID: &[u8] = &[0x06, 0x05, 0x2b, 0xce, 0x0f, 0x03, 0x0e];
+
+/// Verifies Falcon-512 certificate and handshake signatures
+pub static FALCON512: &dyn SignatureVerificationAlgorithm = &FalconVerify {
+    algorithm: Algorithm::Falcon512,
+    id: AlgorithmIdentifier::from_slice(FALCON512_ALG_ID),
+};
+
+/// Verifies Falcon-1024 certificate and handshake signatures
+pub static FALCON1024: &dyn SignatureVerificationAlgorithm = &FalconVerify {
+    algorithm: Algorithm::Falcon1024,
+    id: AlgorithmIdentifier::from_slice(FALCON1024_ALG_ID),
+};
+
+#[derive(Debug)]
+struct FalconVerify {
+    algorithm: Algorithm,
+    id: AlgorithmIdentifier,
+}
+
+impl SignatureVerificationAlgorithm for FalconVerify {
+    fn verify_signature(
+        &self,
+        public_key: &[u8],
+        message: &[u8],
+        signature: &[u8],
+    ) -> Result<(), InvalidSignature> {
+        if verify_with_public_key(self.algorithm, public_key, message, signature) {
+            Ok(())
+        } else {
+            Err(InvalidSignature)
+        }
+    }
+
+    fn public_key_alg_id(&self) -> AlgorithmIdentifier {
+        self.id
+    }
+
+    fn signature_alg_id(&self) -> AlgorithmIdentifier {
+        self.id
+    }
+}
+
+/// Server or client signing key backed by a Falcon `KeyPair`
+pub struct FalconSigningKey {
+    key_pair: Arc<KeyPair>,
+    scheme: SignatureScheme,
+}
+
+impl FalconSigningKey {
+    /// Wrap a Falcon key pair; other algorithms cannot sign TLS handshakes
+    pub fn new(key_pair: KeyPair) -> Result<Self, Error> {
+        let scheme = match key_pair.algorithm {
+            Algorithm::Falcon512 => FALCON512_SCHEME,
+            Algorithm::Falcon1024 => FALCON1024_SCHEME,
+            algorithm => {
+                return Err(Error::General(format!(
+                    "{} keys cannot sign TLS handshakes",
+                    algorithm.tag()
+                )))
+            }
+        };
+        Ok(Self {
+            key_pair: Arc::new(key_pair),
+            scheme,
+        })
+    }
+}
+
+impl fmt::Debug for FalconSigningKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FalconSigningKey")
+            .field("key_id", &self.key_pair.key_id())
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+impl SigningKey for FalconSigningKey {
+    fn choose_scheme(&self, offered: &[SignatureScheme]) -> Option<Box<dyn Signer>> {
+        offered.contains(&self.scheme).then(|| {
+            Box::new(FalconSigner {
+                key_pair: self.key_pair.clone(),
+                scheme: self.scheme,
+            }) as Box<dyn Signer>
+        })
+    }
+
+    fn public_key(&self) -> Option<SubjectPublicKeyInfoDer<'_>> {
+        let id = match self.key_pair.algorithm {
+            Algorithm::Falcon512 => FALCON512_ALG_ID,
+            _ => FALCON1024_ALG_ID,
+        };
+        Some(public_key_to_spki(
+            &AlgorithmIdentifier::from_slice(id),
+            &self.key_pair.public_key,
+        ))
+    }
+
+    fn algorithm(&self) -> SignatureAlgorithm {
+        FALCON_TLS12_ALGORITHM
+    }
+}
+
+struct FalconSigner {
+    key_pair: Arc<KeyPair>,
+    scheme: SignatureScheme,
+}
+
+impl fmt::Debug for FalconSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FalconSigner")
+            .field("scheme", &self.scheme)
+            .finish()
+    }
+}
+
+impl Signer for FalconSigner {
+    fn sign(&self, message: &[u8]) -> Result<Vec<u8>, Error> {
+        self.key_pair
+            .sign(message)
+            .map_err(|e| Error::General(e.to_string()))
+    }
+
+    fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+}
@@ -0,0 +1,189 @@
+//! Local certificate authority for test and internal certificates
+
+use super::cert::{
+    find_extension, is_ca, issue_certificate, subject_public_key_info, verify_csr, IssueOptions,
+};
+use super::crl::{build_crl, verify_crl, Revocation};
+use super::ocsp::respond;
+use super::X509Error;
+use crate::crypto::{verify_with_public_key, KeyPair};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use x509_cert::crl::CertificateList;
+use x509_cert::ext::pkix::BasicConstraints;
+use x509_cert::name::Name;
+use x509_cert::request::CertReq;
+use x509_cert::Certificate;
+use x509_ocsp::{OcspRequest, OcspResponse};
+
+/// A CA certificate together with its signing key
+pub struct CertificateAuthority {
+    certificate: Certificate,
+    key: KeyPair,
+}
+
+impl CertificateAuthority {
+    /// Create a self-signed root CA valid for `validity`
+    pub fn new_root(subject: &str, key: KeyPair, validity: Duration) -> Result<Self, X509Error> {
+        let spki = subject_public_key_info(key.algorithm, &key.public_key)?;
+        let options = IssueOptions::ca(validity, None);
+        let certificate = issue_certificate(Name::from_str(subject)?, spki, None, &key, &options)?;
+        Ok(Self { certificate, key })
+    }
+
+    /// Resume a CA from a certificate and the key it certifies
+    pub fn from_parts(certificate: Certificate, key: KeyPair) -> Result<Self, X509Error> {
+        let spki = subject_public_key_info(key.algorithm, &key.public_key)?;
+        if spki != certificate.tbs_certificate.subject_public_key_info {
+            return Err(X509Error::KeyMismatch);
+        }
+        let probe = b"certificate authority key check";
+        if !verify_with_public_key(key.algorithm, &key.public_key, probe, &key.sign(probe)?) {
+            return Err(X509Error::KeyMismatch);
+        }
+        if !is_ca(&certificate)? {
+            return Err(X509Error::NotCa);
+        }
+        Ok(Self { certificate, key })
+    }
+
+    /// This CA's own certificate
+    pub fn certificate(&self) -> &Certificate {
+        &self.certificate
+    }
+
+    /// Issue a certificate for the subject and key of a verified request
+    pub fn issue(&self, csr: &CertReq, options: &IssueOptions) -> Result<Certificate, X509Error> {
+        verify_csr(csr)?;
+        let own_limit = find_extension::<BasicConstraints>(&self.certificate)?
+            .and_then(|c| c.path_len_constraint);
+        if options.ca && own_limit == Some(0) {
+            return Err(X509Error::PathLenExceeded);
+        }
+        issue_certificate(
+            csr.info.subject.clone(),
+            csr.info.public_key.clone(),
+            Some(&self.certificate),
+            &self.key,
+            options,
+        )
+    }
+
+    /// Sign CRL number `crl_number` listing `revoked`, valid for `validity`
+    pub fn issue_crl(
+        &self,
+        revoked: &[Revocation],
+        crl_number: u64,
+        validity: Duration,
+    ) -> Result<CertificateList, X509Error> {
+        build_crl(&self.certificate, &self.key, revoked, crl_number, validity)
+    }
+
+    /// Answer an OCSP request from `crl`, which must be one of this CA's CRLs
+    pub fn ocsp_response(
+        &self,
+        request: &OcspRequest,
+        crl: &CertificateList,
+    ) -> Result<OcspResponse, X509Error> {
+        verify_crl(crl, &self.certificate)?;
+        respond(
+            &self.certificate,
+            &self.key,
+            crl,
+            request,
+            SystemTime::now(),
+        )
+    }
+}
+
+impl fmt::Debug for CertificateAuthority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CertificateAuthority")
+            .field("subject", &self.certificate.tbs_certificate.subject)
+            .field("key_id", &self.key.key_id())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, Algorithm};
+    use crate::x509::{create_csr, dns_names, spki_public_key, verify_issued_by};
+    use x509_cert::der::{Decode, Encode};
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    #[test]
+    fn test_root_and_leaf() {
+        let ca = CertificateAuthority::new_root(
+            "CN=QSF Internal Root",
+            generate_key_pair_for(Algorithm::Falcon1024),
+            DAY * 365,
+        )
+        .unwrap();
+        let root = ca.certificate();
+        verify_issued_by(root, root).unwrap();
+        assert!(is_ca(root).unwrap());
+
+        let key = generate_key_pair_for(Algorithm::Falcon512);
+        let csr = create_csr("CN=api.internal", &key).unwrap();
+        let options = IssueOptions::leaf(DAY).with_dns_name("api.internal");
+        let leaf = ca.issue(&csr, &options).unwrap();
+
+        let leaf = Certificate::from_der(&leaf.to_der().unwrap()).unwrap();
+        verify_issued_by(&leaf, root).unwrap();
+        assert!(!is_ca(&leaf).unwrap());
+        assert_eq!(dns_names(&leaf).unwrap(), vec!["api.internal"]);
+        assert_eq!(
+            spki_public_key(&leaf.tbs_certificate.subject_public_key_info).unwrap(),
+            (Algorithm::Falcon512, key.public_key)
+        );
+        assert_ne!(
+            leaf.tbs_certificate.serial_number,
+            root.tbs_certificate.serial_number
+        );
+    }
+
+    #[test]
+    fn test_from_parts_checks_key() {
+        let key = generate_key_pair_for(Algorithm::Falcon512);
+        let public_key = key.public_key.clone();
+        let ca = CertificateAuthority::new_root("CN=Root", key, DAY).unwrap();
+        let certificate = ca.certificate().clone();
+
+        let other = generate_key_pair_for(Algorithm::Falcon512);
+        assert!(matches!(
+            CertificateAuthority::from_parts(certificate.clone(), other),
+            Err(X509Error::KeyMismatch)
+        ));
+        let mut forged = generate_key_pair_for(Algorithm::Falcon512);
+        forged.public_key = public_key;
+        assert!(matches!(
+            CertificateAuthority::from_parts(certificate, forged),
+            Err(X509Error::KeyMismatch)
+        ));
+    }
+
+    #[test]
+    fn test_path_len_limits_issuance() {
+        let root = CertificateAuthority::new_root(
+            "CN=Root",
+            generate_key_pair_for(Algorithm::Falcon512),
+            DAY,
+        )
+        .unwrap();
+        let key = generate_key_pair_for(Algorithm::Falcon512);
+        let csr = create_csr("CN=Issuing CA", &key).unwrap();
+        let certificate = root.issue(&csr, &IssueOptions::ca(DAY, Some(0))).unwrap();
+        let issuing = CertificateAuthority::from_parts(certificate, key).unwrap();
+
+        let sub = create_csr("CN=Sub CA", &generate_key_pair_for(Algorithm::Falcon512)).unwrap();
+        assert!(matches!(
+            issuing.issue(&sub, &IssueOptions::ca(DAY, None)),
+            Err(X509Error::PathLenExceeded)
+        ));
+        issuing.issue(&sub, &IssueOptions::leaf(DAY)).unwrap();
+    }
+}
@@ -0,0 +1,482 @@
+//! Certificate and certification request encoding
+
+use super::X509Error;
+use crate::crypto::{key_id, verify_with_public_key, Algorithm, KeyPair};
+use rand::{thread_rng, Rng};
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+use x509_cert::certificate::{Certificate, TbsCertificate, Version};
+use x509_cert::der::asn1::{BitString, Ia5String, OctetString};
+use x509_cert::der::oid::{AssociatedOid, ObjectIdentifier};
+use x509_cert::der::{Decode, Encode, Tag};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::{
+    AuthorityKeyIdentifier, BasicConstraints, KeyUsage, KeyUsages, SubjectAltName,
+    SubjectKeyIdentifier,
+};
+use x509_cert::ext::Extension;
+use x509_cert::name::Name;
+use x509_cert::request::{self, CertReq, CertReqInfo};
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::spki::{AlgorithmIdentifierOwned, SubjectPublicKeyInfoOwned};
+use x509_cert::time::{Time, Validity};
+
+/// Falcon-512 public key and signature algorithm (OQS experimental arc)
+pub const FALCON512_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.9999.3.11");
+/// Falcon-1024 public key and signature algorithm (OQS experimental arc)
+pub const FALCON1024_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.3.9999.3.14");
+/// id-ml-dsa-44 (NIST CSOR)
+pub const ML_DSA_44_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.17");
+/// id-ml-dsa-65 (NIST CSOR)
+pub const ML_DSA_65_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.18");
+/// id-ml-dsa-87 (NIST CSOR)
+pub const ML_DSA_87_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.19");
+/// id-slh-dsa-shake-128s (NIST CSOR)
+pub const SLH_DSA_SHAKE_128S_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.26");
+/// id-slh-dsa-shake-128f (NIST CSOR)
+pub const SLH_DSA_SHAKE_128F_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.27");
+/// id-slh-dsa-shake-192s (NIST CSOR)
+pub const SLH_DSA_SHAKE_192S_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.28");
+/// id-slh-dsa-shake-192f (NIST CSOR)
+pub const SLH_DSA_SHAKE_192F_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.29");
+/// id-slh-dsa-shake-256s (NIST CSOR)
+pub const SLH_DSA_SHAKE_256S_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.30");
+/// id-slh-dsa-shake-256f (NIST CSOR)
+pub const SLH_DSA_SHAKE_256F_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.3.31");
+/// id-MLDSA44-Ed25519-SHA512 (draft-ietf-lamps-pq-composite-sigs; may change
+/// before the draft is published)
+pub const ML_DSA_44_ED25519_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.6.39");
+/// id-MLDSA65-Ed25519-SHA512 (draft-ietf-lamps-pq-composite-sigs; may change
+/// before the draft is published)
+pub const ML_DSA_65_ED25519_OID: ObjectIdentifier =
+    ObjectIdentifier::new_unwrap("1.3.6.1.5.5.7.6.48");
+
+/// Length of generated serial numbers in bytes
+const SERIAL_LEN: usize = 16;
+
+/// How far `notBefore` is backdated to tolerate clock skew between hosts
+const CLOCK_SKEW: Duration = Duration::from_secs(300);
+
+/// OID identifying `algorithm` in keys and signatures
+pub fn algorithm_oid(algorithm: Algorithm) -> Result<ObjectIdentifier, X509Error> {
+    match algorithm {
+        Algorithm::Falcon512 => Ok(FALCON512_OID),
+        Algorithm::Falcon1024 => Ok(FALCON1024_OID),
+        Algorithm::MlDsa44 => Ok(ML_DSA_44_OID),
+        Algorithm::MlDsa65 => Ok(ML_DSA_65_OID),
+        Algorithm::MlDsa87 => Ok(ML_DSA_87_OID),
+        Algorithm::SlhDsaShake128s => Ok(SLH_DSA_SHAKE_128S_OID),
+        Algorithm::SlhDsaShake128f => Ok(SLH_DSA_SHAKE_128F_OID),
+        Algorithm::SlhDsaShake192s => Ok(SLH_DSA_SHAKE_192S_OID),
+        Algorithm::SlhDsaShake192f => Ok(SLH_DSA_SHAKE_192F_OID),
+        Algorithm::SlhDsaShake256s => Ok(SLH_DSA_SHAKE_256S_OID),
+        Algorithm::SlhDsaShake256f => Ok(SLH_DSA_SHAKE_256F_OID),
+        Algorithm::MlDsa44Ed25519 => Ok(ML_DSA_44_ED25519_OID),
+        Algorithm::MlDsa65Ed25519 => Ok(ML_DSA_65_ED25519_OID),
+        Algorithm::HmacSha3_256 => Err(X509Error::UnsupportedKey),
+    }
+}
+
+/// Algorithm identified by `oid`
+pub fn oid_algorithm(oid: ObjectIdentifier) -> Result<Algorithm, X509Error> {
+    Algorithm::ALL
+        .into_iter()
+        .find(|&algorithm| algorithm_oid(algorithm).ok() == Some(oid))
+        .ok_or(X509Error::UnknownAlgorithm(oid))
+}
+
+pub(super) fn algorithm_identifier(
+    algorithm: Algorithm,
+) -> Result<AlgorithmIdentifierOwned, X509Error> {
+    Ok(AlgorithmIdentifierOwned {
+        oid: algorithm_oid(algorithm)?,
+        parameters: None,
+    })
+}
+
+/// SubjectPublicKeyInfo holding a raw public key
+pub fn subject_public_key_info(
+    algorithm: Algorithm,
+    public_key: &[u8],
+) -> Result<SubjectPublicKeyInfoOwned, X509Error> {
+    Ok(SubjectPublicKeyInfoOwned {
+        algorithm: algorithm_identifier(algorithm)?,
+        subject_public_key: BitString::from_bytes(public_key)?,
+    })
+}
+
+/// Algorithm and raw public key held in a SubjectPublicKeyInfo
+pub fn spki_public_key(
+    spki: &SubjectPublicKeyInfoOwned,
+) -> Result<(Algorithm, Vec<u8>), X509Error> {
+    let algorithm = oid_algorithm(spki.algorithm.oid)?;
+    let public_key = spki
+        .subject_public_key
+        .as_bytes()
+        .ok_or_else(|| Tag::BitString.value_error())?;
+    Ok((algorithm, public_key.to_vec()))
+}
+
+/// Sign the DER encoding of `tbs` with `key`
+pub(super) fn sign(
+    key: &KeyPair,
+    tbs: &impl Encode,
+) -> Result<(AlgorithmIdentifierOwned, BitString), X509Error> {
+    let algorithm = algorithm_identifier(key.algorithm)?;
+    let signature = key.sign(&tbs.to_der()?)?;
+    Ok((algorithm, BitString::from_bytes(&signature)?))
+}
+
+/// Check `signature` over the DER encoding of `tbs` with the key in `spki`
+pub(super) fn verify(
+    tbs: &impl Encode,
+    algorithm: &AlgorithmIdentifierOwned,
+    signature: &BitString,
+    spki: &SubjectPublicKeyInfoOwned,
+) -> Result<(), X509Error> {
+    let (key_algorithm, public_key) = spki_public_key(spki)?;
+    if oid_algorithm(algorithm.oid)? != key_algorithm {
+        return Err(X509Error::BadSignature);
+    }
+    let signature = signature.as_bytes().ok_or(X509Error::BadSignature)?;
+    if verify_with_public_key(key_algorithm, &public_key, &tbs.to_der()?, signature) {
+        Ok(())
+    } else {
+        Err(X509Error::BadSignature)
+    }
+}
+
+/// PKCS#10 request for `subject`, e.g. `"CN=payments,O=Example"`
+///
+/// The request is signed with `key` as proof of possession.
+pub fn create_csr(subject: &str, key: &KeyPair) -> Result<CertReq, X509Error> {
+    let info = CertReqInfo {
+        version: request::Version::V1,
+        subject: Name::from_str(subject)?,
+        public_key: subject_public_key_info(key.algorithm, &key.public_key)?,
+        attributes: Default::default(),
+    };
+    let (algorithm, signature) = sign(key, &info)?;
+    Ok(CertReq {
+        info,
+        algorithm,
+        signature,
+    })
+}
+
+/// Check a request's proof-of-possession signature
+pub fn verify_csr(csr: &CertReq) -> Result<(), X509Error> {
+    verify(
+        &csr.info,
+        &csr.algorithm,
+        &csr.signature,
+        &csr.info.public_key,
+    )
+}
+
+/// Check that `issuer` named and signed `certificate`
+pub fn verify_issued_by(certificate: &Certificate, issuer: &Certificate) -> Result<(), X509Error> {
+    let tbs = &certificate.tbs_certificate;
+    if tbs.issuer != issuer.tbs_certificate.subject {
+        return Err(X509Error::UnknownIssuer);
+    }
+    if tbs.signature != certificate.signature_algorithm {
+        return Err(X509Error::BadSignature);
+    }
+    verify(
+        tbs,
+        &certificate.signature_algorithm,
+        &certificate.signature,
+        &issuer.tbs_certificate.subject_public_key_info,
+    )
+}
+
+/// Decode the extension of type `T`, if present
+pub fn find_extension<T>(certificate: &Certificate) -> Result<Option<T>, X509Error>
+where
+    T: AssociatedOid + for<'a> Decode<'a>,
+{
+    decode_extension(&certificate.tbs_certificate.extensions)
+}
+
+/// Decode the extension of type `T` from any extension list
+pub(super) fn decode_extension<T>(
+    extensions: &Option<Vec<Extension>>,
+) -> Result<Option<T>, X509Error>
+where
+    T: AssociatedOid + for<'a> Decode<'a>,
+{
+    match extensions.iter().flatten().find(|e| e.extn_id == T::OID) {
+        Some(extension) => Ok(Some(T::from_der(extension.extn_value.as_bytes())?)),
+        None => Ok(None),
+    }
+}
+
+/// Whether the basic constraints mark `certificate` as a CA
+pub fn is_ca(certificate: &Certificate) -> Result<bool, X509Error> {
+    Ok(find_extension::<BasicConstraints>(certificate)?.is_some_and(|c| c.ca))
+}
+
+/// DNS names in the subject alternative name extension
+pub fn dns_names(certificate: &Certificate) -> Result<Vec<String>, X509Error> {
+    let Some(SubjectAltName(names)) = find_extension(certificate)? else {
+        return Ok(Vec::new());
+    };
+    Ok(names
+        .into_iter()
+        .filter_map(|name| match name {
+            GeneralName::DnsName(dns) => Some(dns.to_string()),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Profile of a certificate to issue
+#[derive(Debug, Clone)]
+pub struct IssueOptions {
+    /// Time from issuance until expiry
+    pub validity: Duration,
+    /// Whether the subject may issue certificates
+    pub ca: bool,
+    /// Most intermediates allowed below a CA subject
+    pub path_len: Option<u8>,
+    /// Subject alternative DNS names
+    pub dns_names: Vec<String>,
+}
+
+impl IssueOptions {
+    /// End-entity certificate for signing
+    pub fn leaf(validity: Duration) -> Self {
+        Self {
+            validity,
+            ca: false,
+            path_len: None,
+            dns_names: Vec::new(),
+        }
+    }
+
+    /// CA certificate, optionally limiting the intermediates below it
+    pub fn ca(validity: Duration, path_len: Option<u8>) -> Self {
+        Self {
+            validity,
+            ca: true,
+            path_len,
+            dns_names: Vec::new(),
+        }
+    }
+
+    /// Add a subject alternative DNS name
+    pub fn with_dns_name(mut self, name: &str) -> Self {
+        self.dns_names.push(name.to_string());
+        self
+    }
+}
+
+pub(super) fn extension<T: AssociatedOid + Encode>(
+    value: &T,
+    critical: bool,
+) -> Result<Extension, X509Error> {
+    Ok(Extension {
+        extn_id: T::OID,
+        critical,
+        extn_value: OctetString::new(value.to_der()?)?,
+    })
+}
+
+fn random_serial() -> Result<SerialNumber, X509Error> {
+    let mut serial = [0u8; SERIAL_LEN];
+    thread_rng().fill(&mut serial);
+    // Positive and without a leading zero byte, as DER requires
+    serial[0] = (serial[0] & 0x7f) | 0x40;
+    Ok(SerialNumber::new(&serial)?)
+}
+
+/// Certificate binding `subject` to `spki`, signed by `issuer_key`
+///
+/// `issuer` is `None` for a self-signed certificate.
+pub(super) fn issue_certificate(
+    subject: Name,
+    spki: SubjectPublicKeyInfoOwned,
+    issuer: Option<&Certificate>,
+    issuer_key: &KeyPair,
+    options: &IssueOptions,
+) -> Result<Certificate, X509Error> {
+    let (algorithm, public_key) = spki_public_key(&spki)?;
+    let key_usage = if options.ca {
+        KeyUsages::KeyCertSign | KeyUsages::CRLSign
+    } else {
+        KeyUsages::DigitalSignature.into()
+    };
+    let authority_key_id = key_id(issuer_key.algorithm, &issuer_key.public_key);
+    let mut extensions = vec![
+        extension(
+            &BasicConstraints {
+                ca: options.ca,
+                path_len_constraint: options.path_len.filter(|_| options.ca),
+            },
+            true,
+        )?,
+        extension(&KeyUsage(key_usage), true)?,
+        extension(
+            &SubjectKeyIdentifier(OctetString::new(key_id(algorithm, &public_key).0)?),
+            false,
+        )?,
+        extension(
+            &AuthorityKeyIdentifier {
+                key_identifier: Some(OctetString::new(authority_key_id.0)?),
+                authority_cert_issuer: None,
+                authority_cert_serial_number: None,
+            },
+            false,
+        )?,
+    ];
+    if !options.dns_names.is_empty() {
+        let names = options
+            .dns_names
+            .iter()
+            .map(|name| Ok(GeneralName::DnsName(Ia5String::new(name)?)))
+            .collect::<Result<Vec<_>, X509Error>>()?;
+        extensions.push(extension(&SubjectAltName(names), false)?);
+    }
+
+    let now = SystemTime::now();
+    let signature_algorithm = algorithm_identifier(issuer_key.algorithm)?;
+    let tbs_certificate = TbsCertificate {
+        version: Version::V3,
+        serial_number: random_serial()?,
+        signature: signature_algorithm,
+        issuer: issuer.map_or_else(|| subject.clone(), |i| i.tbs_certificate.subject.clone()),
+        validity: Validity {
+            not_before: Time::try_from(now - CLOCK_SKEW)?,
+            not_after: Time::try_from(now + options.validity)?,
+        },
+        subject,
+        subject_public_key_info: spki,
+        issuer_unique_id: None,
+        subject_unique_id: None,
+        extensions: Some(extensions),
+    };
+    let (signature_algorithm, signature) = sign(issuer_key, &tbs_certificate)?;
+    Ok(Certificate {
+        tbs_certificate,
+        signature_algorithm,
+        signature,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair, generate_key_pair_for};
+    use x509_cert::der::pem::LineEnding;
+    use x509_cert::der::{DecodePem, EncodePem};
+
+    #[test]
+    fn test_csr_roundtrip() {
+        let key = generate_key_pair_for(Algorithm::Falcon1024);
+        let csr = create_csr("CN=payments,O=QuantumSafe Finance", &key).unwrap();
+        let pem = csr.to_pem(LineEnding::LF).unwrap();
+        assert!(pem.starts_with("-----BEGIN CERTIFICATE REQUEST-----"));
+
+        let parsed = CertReq::from_pem(&pem).unwrap();
+        assert_eq!(parsed, csr);
+        verify_csr(&parsed).unwrap();
+        assert_eq!(
+            spki_public_key(&parsed.info.public_key).unwrap(),
+            (Algorithm::Falcon1024, key.public_key.clone())
+        );
+        assert_eq!(
+            parsed.info.subject.to_string(),
+            "CN=payments,O=QuantumSafe Finance"
+        );
+    }
+
+    #[test]
+    fn test_csr_rejects_tampering() {
+        let key = generate_key_pair_for(Algorithm::Falcon512);
+        let other = generate_key_pair_for(Algorithm::Falcon512);
+        let mut csr = create_csr("CN=payments", &key).unwrap();
+        csr.info.public_key = subject_public_key_info(other.algorithm, &other.public_key).unwrap();
+        assert!(matches!(verify_csr(&csr), Err(X509Error::BadSignature)));
+
+        csr.info.public_key.algorithm.oid = ObjectIdentifier::new_unwrap("1.2.3.4");
+        assert!(matches!(
+            verify_csr(&csr),
+            Err(X509Error::UnknownAlgorithm(_))
+        ));
+    }
+
+    #[test]
+    fn test_nist_and_composite_keys() {
+        for algorithm in [
+            Algorithm::MlDsa65,
+            Algorithm::SlhDsaShake128f,
+            Algorithm::MlDsa44Ed25519,
+        ] {
+            let key = generate_key_pair_for(algorithm);
+            let csr = create_csr("CN=payments", &key).unwrap();
+            let parsed = CertReq::from_der(&csr.to_der().unwrap()).unwrap();
+            verify_csr(&parsed).unwrap();
+            assert_eq!(
+                spki_public_key(&parsed.info.public_key).unwrap(),
+                (algorithm, key.public_key.clone())
+            );
+            assert!(parsed.info.public_key.algorithm.parameters.is_none());
+        }
+    }
+
+    /// Self-signed roots from `openssl req -x509 -newkey ML-DSA-44` and
+    /// `-newkey SLH-DSA-SHAKE-128f` (OpenSSL 3.5)
+    #[test]
+    fn test_openssl_certificates() {
+        for (pem, algorithm) in [
+            (
+                include_str!("../../tests/vectors/ml_dsa_44_root.pem"),
+                Algorithm::MlDsa44,
+            ),
+            (
+                include_str!("../../tests/vectors/slh_dsa_shake_128f_root.pem"),
+                Algorithm::SlhDsaShake128f,
+            ),
+        ] {
+            let certificate = Certificate::from_pem(pem).unwrap();
+            let spki = &certificate.tbs_certificate.subject_public_key_info;
+            assert_eq!(spki_public_key(spki).unwrap().0, algorithm);
+            verify_issued_by(&certificate, &certificate).unwrap();
+
+            let mut tampered = certificate.clone();
+            tampered.tbs_certificate.serial_number = SerialNumber::from(7u32);
+            assert!(matches!(
+                verify_issued_by(&tampered, &certificate),
+                Err(X509Error::BadSignature)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_hmac_keys_cannot_be_certified() {
+        assert!(matches!(
+            create_csr("CN=legacy", &generate_key_pair()),
+            Err(X509Error::UnsupportedKey)
+        ));
+        for tag in [
+            "falcon-512",
+            "ml-dsa-87",
+            "slh-dsa-shake-256s",
+            "ml-dsa-65-ed25519",
+        ] {
+            let algorithm = Algorithm::from_tag(tag).unwrap();
+            assert_eq!(
+                oid_algorithm(algorithm_oid(algorithm).unwrap()).unwrap(),
+                algorithm
+            );
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Certificate revocation lists (RFC 5280 section 5)
+
+use super::cert::{
+    algorithm_identifier, decode_extension, extension, find_extension, sign, verify,
+};
+use super::X509Error;
+use crate::crypto::{key_id, KeyPair};
+use std::time::{Duration, SystemTime};
+use x509_cert::crl::{CertificateList, RevokedCert, TbsCertList};
+use x509_cert::der::asn1::{OctetString, Uint};
+use x509_cert::der::pem::{self, LineEnding};
+use x509_cert::der::{Decode, Encode, Tag};
+use x509_cert::ext::pkix::{AuthorityKeyIdentifier, CrlNumber, CrlReason, KeyUsage};
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::time::Time;
+use x509_cert::{Certificate, Version};
+
+/// PEM label of an encoded CRL
+pub const CRL_PEM_LABEL: &str = "X509 CRL";
+
+/// A certificate to list on a CRL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Revocation {
+    pub serial_number: SerialNumber,
+    pub revoked_at: SystemTime,
+    pub reason: Option<CrlReason>,
+}
+
+impl Revocation {
+    /// Revoke `certificate` as of `revoked_at`
+    pub fn new(certificate: &Certificate, revoked_at: SystemTime) -> Self {
+        Self {
+            serial_number: certificate.tbs_certificate.serial_number.clone(),
+            revoked_at,
+            reason: None,
+        }
+    }
+
+    /// Record why the certificate was revoked, e.g. `KeyCompromise`
+    pub fn with_reason(mut self, reason: CrlReason) -> Self {
+        self.reason = Some(reason);
+        self
+    }
+}
+
+/// CRL from `issuer`, valid from now until `validity` has passed
+pub(super) fn build_crl(
+    issuer: &Certificate,
+    key: &KeyPair,
+    revoked: &[Revocation],
+    crl_number: u64,
+    validity: Duration,
+) -> Result<CertificateList, X509Error> {
+    let entries = revoked
+        .iter()
+        .map(|revocation| {
+            let reason = match revocation.reason {
+                Some(reason) => Some(vec![extension(&reason, false)?]),
+                None => None,
+            };
+            Ok(RevokedCert {
+                serial_number: revocation.serial_number.clone(),
+                revocation_date: Time::try_from(revocation.revoked_at)?,
+                crl_entry_extensions: reason,
+            })
+        })
+        .collect::<Result<Vec<_>, X509Error>>()?;
+    let authority_key_id = AuthorityKeyIdentifier {
+        key_identifier: Some(OctetString::new(key_id(key.algorithm, &key.public_key).0)?),
+        authority_cert_issuer: None,
+        authority_cert_serial_number: None,
+    };
+    let number = CrlNumber(Uint::new(&crl_number.to_be_bytes())?);
+
+    let now = SystemTime::now();
+    let tbs_cert_list = TbsCertList {
+        version: Version::V2,
+        signature: algorithm_identifier(key.algorithm)?,
+        issuer: issuer.tbs_certificate.subject.clone(),
+        this_update: Time::try_from(now)?,
+        next_update: Some(Time::try_from(now + validity)?),
+        revoked_certificates: (!entries.is_empty()).then_some(entries),
+        crl_extensions: Some(vec![
+            extension(&authority_key_id, false)?,
+            extension(&number, false)?,
+        ]),
+    };
+    let (signature_algorithm, signature) = sign(key, &tbs_cert_list)?;
+    Ok(CertificateList {
+        tbs_cert_list,
+        signature_algorithm,
+        signature,
+    })
+}
+
+/// Check a CRL's issuer name and signature, and the issuer's right to sign it
+pub fn verify_crl(crl: &CertificateList, issuer: &Certificate) -> Result<(), X509Error> {
+    let tbs = &crl.tbs_cert_list;
+    if tbs.issuer != issuer.tbs_certificate.subject {
+        return Err(X509Error::UnknownIssuer);
+    }
+    if tbs.signature != crl.signature_algorithm {
+        return Err(X509Error::BadSignature);
+    }
+    verify(
+        tbs,
+        &crl.signature_algorithm,
+        &crl.signature,
+        &issuer.tbs_certificate.subject_public_key_info,
+    )?;
+    match find_extension::<KeyUsage>(issuer)? {
+        Some(usage) if !usage.crl_sign() => Err(X509Error::NotCa),
+        _ => Ok(()),
+    }
+}
+
+/// Parse a CRL from DER or PEM
+pub fn decode_crl(data: &[u8]) -> Result<CertificateList, X509Error> {
+    if !data.starts_with(b"-----BEGIN") {
+        return Ok(CertificateList::from_der(data)?);
+    }
+    let (label, der) = pem::decode_vec(data).map_err(x509_cert::der::Error::from)?;
+    if label != CRL_PEM_LABEL {
+        let error = pem::Error::UnexpectedTypeLabel {
+            expected: CRL_PEM_LABEL,
+        };
+        return Err(x509_cert::der::Error::from(error).into());
+    }
+    Ok(CertificateList::from_der(&der)?)
+}
+
+/// PEM encoding of a CRL
+pub fn crl_to_pem(crl: &CertificateList) -> Result<String, X509Error> {
+    let pem = pem::encode_string(CRL_PEM_LABEL, LineEnding::LF, &crl.to_der()?)
+        .map_err(x509_cert::der::Error::from)?;
+    Ok(pem)
+}
+
+/// Sequence number the issuer assigned to `crl`
+pub fn crl_number(crl: &CertificateList) -> Result<Option<u64>, X509Error> {
+    let Some(CrlNumber(number)) = decode_extension(&crl.tbs_cert_list.crl_extensions)? else {
+        return Ok(None);
+    };
+    let bytes = number.as_bytes();
+    if bytes.len() > 8 {
+        return Err(Tag::Integer.value_error().into());
+    }
+    let mut padded = [0u8; 8];
+    padded[8 - bytes.len()..].copy_from_slice(bytes);
+    Ok(Some(u64::from_be_bytes(padded)))
+}
+
+/// Whether `time` falls between the CRL's thisUpdate and nextUpdate
+pub fn is_current(crl: &CertificateList, time: SystemTime) -> bool {
+    let tbs = &crl.tbs_cert_list;
+    tbs.this_update.to_system_time() <= time
+        && tbs
+            .next_update
+            .is_some_and(|next| time <= next.to_system_time())
+}
+
+/// The CRL entry for `certificate`, if it is listed
+pub fn find_revocation<'a>(
+    crl: &'a CertificateList,
+    certificate: &Certificate,
+) -> Option<&'a RevokedCert> {
+    if crl.tbs_cert_list.issuer != certificate.tbs_certificate.issuer {
+        return None;
+    }
+    find_serial(crl, &certificate.tbs_certificate.serial_number)
+}
+
+pub(super) fn find_serial<'a>(
+    crl: &'a CertificateList,
+    serial_number: &SerialNumber,
+) -> Option<&'a RevokedCert> {
+    crl.tbs_cert_list
+        .revoked_certificates
+        .iter()
+        .flatten()
+        .find(|entry| &entry.serial_number == serial_number)
+}
+
+/// Reason recorded in a CRL entry
+pub fn revocation_reason(entry: &RevokedCert) -> Result<Option<CrlReason>, X509Error> {
+    decode_extension(&entry.crl_entry_extensions)
+}
+
+/// Check each certificate on a validated path against its issuer's CRL
+///
+/// `path` runs from the leaf to the root as returned by
+/// `TrustStore::verify`. Every issuer needs a current CRL in `crls`;
+/// the root itself is not checked. Of an issuer's current CRLs the one with
+/// the highest CRL number is used, so an older CRL that has not yet expired
+/// cannot hide a later revocation.
+pub fn check_revocation(
+    path: &[Certificate],
+    crls: &[CertificateList],
+    time: SystemTime,
+) -> Result<(), X509Error> {
+    for pair in path.windows(2) {
+        let (certificate, issuer) = (&pair[0], &pair[1]);
+        let crl = crls
+            .iter()
+            .filter(|crl| is_current(crl, time) && verify_crl(crl, issuer).is_ok())
+            .max_by_key(|crl| crl_number(crl).ok().flatten())
+            .ok_or(X509Error::RevocationUnknown)?;
+        if find_revocation(crl, certificate).is_some() {
+            return Err(X509Error::Revoked);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, Algorithm};
+    use crate::x509::tests::{setup, DAY};
+    use crate::x509::{CertificateAuthority, TrustStore};
+
+    #[test]
+    fn test_crl_round_trip() {
+        let (ca, revoked, kept) = setup();
+        let revocation =
+            Revocation::new(&revoked, SystemTime::now()).with_reason(CrlReason::KeyCompromise);
+        let crl = ca.issue_crl(&[revocation], 7, DAY).unwrap();
+
+        let pem = crl_to_pem(&crl).unwrap();
+        assert!(pem.starts_with("-----BEGIN X509 CRL-----"));
+        assert_eq!(decode_crl(pem.as_bytes()).unwrap(), crl);
+        let crl = decode_crl(&crl.to_der().unwrap()).unwrap();
+
+        verify_crl(&crl, ca.certificate()).unwrap();
+        assert_eq!(crl_number(&crl).unwrap(), Some(7));
+        assert!(is_current(&crl, SystemTime::now()));
+        assert!(!is_current(&crl, SystemTime::now() + DAY * 2));
+        let entry = find_revocation(&crl, &revoked).unwrap();
+        assert_eq!(
+            revocation_reason(entry).unwrap(),
+            Some(CrlReason::KeyCompromise)
+        );
+        assert!(find_revocation(&crl, &kept).is_none());
+    }
+
+    #[test]
+    fn test_rejects_foreign_or_tampered_crl() {
+        let (ca, revoked, _) = setup();
+        let crl = ca
+            .issue_crl(&[Revocation::new(&revoked, SystemTime::now())], 1, DAY)
+            .unwrap();
+
+        let other = CertificateAuthority::new_root(
+            "CN=Root",
+            generate_key_pair_for(Algorithm::Falcon512),
+            DAY,
+        )
+        .unwrap();
+        assert!(matches!(
+            verify_crl(&crl, other.certificate()),
+            Err(X509Error::BadSignature)
+        ));
+
+        let mut tampered = crl.clone();
+        tampered.tbs_cert_list.revoked_certificates = None;
+        assert!(matches!(
+            verify_crl(&tampered, ca.certificate()),
+            Err(X509Error::BadSignature)
+        ));
+    }
+
+    #[test]
+    fn test_check_revocation_on_path() {
+        let (ca, revoked, kept) = setup();
+        let mut store = TrustStore::new();
+        store.add(ca.certificate().clone()).unwrap();
+        let now = SystemTime::now();
+        let crl = ca
+            .issue_crl(&[Revocation::new(&revoked, now)], 1, DAY)
+            .unwrap();
+
+        let path = store.verify(&kept, &[], now).unwrap();
+        check_revocation(&path, std::slice::from_ref(&crl), now).unwrap();
+        assert!(matches!(
+            check_revocation(&path, &[], now),
+            Err(X509Error::RevocationUnknown)
+        ));
+        assert!(matches!(
+            check_revocation(&path, std::slice::from_ref(&crl), now + DAY * 2),
+            Err(X509Error::RevocationUnknown)
+        ));
+
+        let path = store.verify(&revoked, &[], now).unwrap();
+        assert!(matches!(
+            check_revocation(&path, std::slice::from_ref(&crl), now),
+            Err(X509Error::Revoked)
+        ));
+
+        // An older CRL still within its validity does not mask the revocation
+        let older = ca.issue_crl(&[], 0, DAY).unwrap();
+        for crls in [[older.clone(), crl.clone()], [crl, older]] {
+            assert!(matches!(
+                check_revocation(&path, &crls, now),
+                Err(X509Error::Revoked)
+            ));
+        }
+    }
+}
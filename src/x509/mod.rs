@@ -0,0 +1,131 @@
+//! X.509 certificates and certification requests for the crate's signature keys
+//!
+//! Subject keys and signatures may use any of the crate's public-key
+//! algorithms. ML-DSA and SLH-DSA use the NIST OIDs with absent parameters and
+//! the raw FIPS 204 and FIPS 205 encodings, so certificates interoperate with
+//! other implementations such as OpenSSL 3.5. Composite ML-DSA + Ed25519 uses
+//! the OIDs of draft-ietf-lamps-pq-composite-sigs, which may change before
+//! publication. Falcon has no assigned X.509 OID yet, so the Open Quantum Safe
+//! experimental arcs are used and Falcon certificates only interoperate with
+//! peers configured the same way.
+
+pub use self::ca::*;
+pub use self::cert::*;
+pub use self::crl::*;
+pub use self::ocsp::*;
+pub use self::path::*;
+pub use x509_cert::crl::CertificateList;
+pub use x509_cert::ext::pkix::CrlReason;
+pub use x509_cert::request::CertReq;
+pub use x509_cert::Certificate;
+pub use x509_ocsp::{CertStatus, OcspRequest, OcspResponse, OcspResponseStatus};
+
+mod ca;
+mod cert;
+mod crl;
+mod ocsp;
+mod path;
+#[cfg(feature = "ocsp")]
+pub mod responder;
+
+use crate::crypto::CryptoError;
+use std::fmt;
+use x509_cert::der;
+use x509_cert::der::oid::ObjectIdentifier;
+
+/// Errors raised while building, parsing or validating certificates
+#[derive(Debug)]
+pub enum X509Error {
+    /// Malformed DER or PEM, or a value that cannot be encoded
+    Der(der::Error),
+    /// The key's algorithm cannot appear in a certificate
+    UnsupportedKey,
+    /// A signature or key uses an algorithm this crate does not implement
+    UnknownAlgorithm(ObjectIdentifier),
+    /// The private key does not match the certificate or request
+    KeyMismatch,
+    /// A certificate or request signature does not verify
+    BadSignature,
+    /// A certificate is outside its validity period
+    NotValidAt,
+    /// An issuing certificate is not a CA or may not sign certificates
+    NotCa,
+    /// The chain is longer than an issuer's path length constraint allows
+    PathLenExceeded,
+    /// No trusted root or supplied intermediate issued a certificate
+    UnknownIssuer,
+    /// A critical extension that this validator does not understand
+    UnsupportedCriticalExtension(ObjectIdentifier),
+    /// A certificate on the path has been revoked
+    Revoked,
+    /// No current CRL or OCSP response covers a certificate
+    RevocationUnknown,
+    /// The OCSP responder did not return a successful response
+    OcspStatus(OcspResponseStatus),
+    /// The OCSP response does not echo the request's nonce
+    NonceMismatch,
+    Crypto(CryptoError),
+}
+
+impl fmt::Display for X509Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            X509Error::Der(e) => write!(f, "Encoding error: {}", e),
+            X509Error::UnsupportedKey => write!(f, "Key algorithm cannot be certified"),
+            X509Error::UnknownAlgorithm(oid) => write!(f, "Unknown algorithm {}", oid),
+            X509Error::KeyMismatch => write!(f, "Private key does not match"),
+            X509Error::BadSignature => write!(f, "Signature verification failed"),
+            X509Error::NotValidAt => write!(f, "Certificate is expired or not yet valid"),
+            X509Error::NotCa => write!(f, "Issuer is not a certificate authority"),
+            X509Error::PathLenExceeded => write!(f, "Path length constraint exceeded"),
+            X509Error::UnknownIssuer => write!(f, "Issuer is not trusted"),
+            X509Error::UnsupportedCriticalExtension(oid) => {
+                write!(f, "Unsupported critical extension {}", oid)
+            }
+            X509Error::Revoked => write!(f, "Certificate has been revoked"),
+            X509Error::RevocationUnknown => write!(f, "No current revocation information"),
+            X509Error::OcspStatus(status) => write!(f, "OCSP responder returned {:?}", status),
+            X509Error::NonceMismatch => write!(f, "OCSP response nonce does not match"),
+            X509Error::Crypto(e) => write!(f, "Crypto error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for X509Error {}
+
+impl From<der::Error> for X509Error {
+    fn from(e: der::Error) -> Self {
+        X509Error::Der(e)
+    }
+}
+
+impl From<CryptoError> for X509Error {
+    fn from(e: CryptoError) -> Self {
+        X509Error::Crypto(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, Algorithm};
+    use std::time::Duration;
+
+    pub(super) const DAY: Duration = Duration::from_secs(86_400);
+
+    /// Root CA with two leaves, one to revoke and one to keep
+    pub(super) fn setup() -> (CertificateAuthority, Certificate, Certificate) {
+        let ca = CertificateAuthority::new_root(
+            "CN=Root",
+            generate_key_pair_for(Algorithm::Falcon512),
+            DAY,
+        )
+        .unwrap();
+        let issue = |name| {
+            let csr = create_csr(name, &generate_key_pair_for(Algorithm::Falcon512)).unwrap();
+            ca.issue(&csr, &IssueOptions::leaf(DAY)).unwrap()
+        };
+        let (revoked, kept) = (issue("CN=revoked"), issue("CN=kept"));
+        (ca, revoked, kept)
+    }
+}
@@ -0,0 +1,317 @@
+//! OCSP requests and responses (RFC 6960)
+//!
+//! CertIDs hash the issuer name and key with SHA3-256 instead of SHA-1, and
+//! responses are signed by the issuing CA itself. Requests for other hash
+//! algorithms are answered with status `unknown`.
+
+use super::cert::{extension, sign, verify};
+use super::crl::{find_serial, is_current, revocation_reason};
+use super::X509Error;
+use crate::crypto::KeyPair;
+use rand::{thread_rng, RngCore};
+use sha3::{Digest, Sha3_256};
+use std::time::SystemTime;
+use x509_cert::crl::CertificateList;
+use x509_cert::der::asn1::OctetString;
+use x509_cert::der::oid::db::rfc6960::ID_PKIX_OCSP_BASIC;
+use x509_cert::der::oid::ObjectIdentifier;
+use x509_cert::der::{Decode, Encode};
+use x509_cert::serial_number::SerialNumber;
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::Certificate;
+use x509_ocsp::ext::Nonce;
+use x509_ocsp::{
+    BasicOcspResponse, CertId, CertStatus, OcspGeneralizedTime, OcspRequest, OcspResponse,
+    OcspResponseStatus, Request, ResponderId, ResponseData, RevokedInfo, SingleResponse,
+    TbsRequest, Version,
+};
+
+/// SHA3-256, used to hash issuer names and keys in CertIDs
+pub const SHA3_256_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.2.8");
+
+/// Length of the nonce sent with each request
+const NONCE_LEN: usize = 16;
+
+fn sha3(data: &[u8]) -> Result<OctetString, X509Error> {
+    Ok(OctetString::new(Sha3_256::digest(data).to_vec())?)
+}
+
+/// CertID for `serial_number` issued by `issuer`
+fn issuer_cert_id(issuer: &Certificate, serial_number: &SerialNumber) -> Result<CertId, X509Error> {
+    let issuer_key = &issuer
+        .tbs_certificate
+        .subject_public_key_info
+        .subject_public_key;
+    Ok(CertId {
+        hash_algorithm: AlgorithmIdentifierOwned {
+            oid: SHA3_256_OID,
+            parameters: None,
+        },
+        issuer_name_hash: sha3(&issuer.tbs_certificate.subject.to_der()?)?,
+        issuer_key_hash: sha3(issuer_key.raw_bytes())?,
+        serial_number: serial_number.clone(),
+    })
+}
+
+/// CertID identifying `certificate` issued by `issuer`
+pub fn cert_id(certificate: &Certificate, issuer: &Certificate) -> Result<CertId, X509Error> {
+    issuer_cert_id(issuer, &certificate.tbs_certificate.serial_number)
+}
+
+/// Request for the status of `certificate`, carrying a fresh random nonce
+pub fn ocsp_request(
+    certificate: &Certificate,
+    issuer: &Certificate,
+) -> Result<OcspRequest, X509Error> {
+    let mut nonce = [0u8; NONCE_LEN];
+    thread_rng().fill_bytes(&mut nonce);
+    Ok(OcspRequest {
+        tbs_request: TbsRequest {
+            version: Version::V1,
+            requestor_name: None,
+            request_list: vec![Request {
+                req_cert: cert_id(certificate, issuer)?,
+                single_request_extensions: None,
+            }],
+            request_extensions: Some(vec![extension(&Nonce::new(nonce)?, false)?]),
+        },
+        optional_signature: None,
+    })
+}
+
+/// Answer `request` from `crl`, signing as the CA `issuer`
+///
+/// A stale CRL yields `tryLater` rather than vouching for its contents.
+pub(super) fn respond(
+    issuer: &Certificate,
+    key: &KeyPair,
+    crl: &CertificateList,
+    request: &OcspRequest,
+    now: SystemTime,
+) -> Result<OcspResponse, X509Error> {
+    if !is_current(crl, now) {
+        return Ok(OcspResponse::try_later());
+    }
+    let this_update = OcspGeneralizedTime::from(crl.tbs_cert_list.this_update);
+    let next_update = crl.tbs_cert_list.next_update.map(OcspGeneralizedTime::from);
+    let responses = request
+        .tbs_request
+        .request_list
+        .iter()
+        .map(|single| {
+            let id = &single.req_cert;
+            let ours = issuer_cert_id(issuer, &id.serial_number)?;
+            let cert_status = if *id != ours {
+                CertStatus::unknown()
+            } else if let Some(entry) = find_serial(crl, &id.serial_number) {
+                CertStatus::revoked(RevokedInfo {
+                    revocation_time: entry.revocation_date.into(),
+                    revocation_reason: revocation_reason(entry)?,
+                })
+            } else {
+                CertStatus::good()
+            };
+            Ok(SingleResponse {
+                cert_id: id.clone(),
+                cert_status,
+                this_update,
+                next_update,
+                single_extensions: None,
+            })
+        })
+        .collect::<Result<Vec<_>, X509Error>>()?;
+    let nonce = match request.nonce() {
+        Some(nonce) => Some(vec![extension(&nonce, false)?]),
+        None => None,
+    };
+
+    let tbs_response_data = ResponseData {
+        version: Version::V1,
+        responder_id: ResponderId::ByName(issuer.tbs_certificate.subject.clone()),
+        produced_at: OcspGeneralizedTime::try_from(now)?,
+        responses,
+        response_extensions: nonce,
+    };
+    let (signature_algorithm, signature) = sign(key, &tbs_response_data)?;
+    Ok(OcspResponse::successful(BasicOcspResponse {
+        tbs_response_data,
+        signature_algorithm,
+        signature,
+        certs: None,
+    })?)
+}
+
+/// Status of `certificate` from a CA-signed response to `request`
+///
+/// Checks the response status, the issuer's signature, the request nonce
+/// and that `time` falls within the response's validity window.
+pub fn verify_ocsp_response(
+    response: &OcspResponse,
+    request: &OcspRequest,
+    certificate: &Certificate,
+    issuer: &Certificate,
+    time: SystemTime,
+) -> Result<CertStatus, X509Error> {
+    if response.response_status != OcspResponseStatus::Successful {
+        return Err(X509Error::OcspStatus(response.response_status));
+    }
+    let bytes = response
+        .response_bytes
+        .as_ref()
+        .filter(|bytes| bytes.response_type == ID_PKIX_OCSP_BASIC)
+        .ok_or(X509Error::RevocationUnknown)?;
+    let basic = BasicOcspResponse::from_der(bytes.response.as_bytes())?;
+    let data = &basic.tbs_response_data;
+    if data.responder_id != ResponderId::ByName(issuer.tbs_certificate.subject.clone()) {
+        return Err(X509Error::UnknownIssuer);
+    }
+    verify(
+        data,
+        &basic.signature_algorithm,
+        &basic.signature,
+        &issuer.tbs_certificate.subject_public_key_info,
+    )?;
+    if let Some(nonce) = request.nonce() {
+        if data.nonce() != Some(nonce) {
+            return Err(X509Error::NonceMismatch);
+        }
+    }
+
+    let id = cert_id(certificate, issuer)?;
+    let single = data
+        .responses
+        .iter()
+        .find(|single| single.cert_id == id)
+        .ok_or(X509Error::RevocationUnknown)?;
+    let fresh = single.this_update.0.to_system_time() <= time
+        && single
+            .next_update
+            .is_some_and(|next| time <= next.0.to_system_time());
+    if !fresh {
+        return Err(X509Error::RevocationUnknown);
+    }
+    Ok(single.cert_status)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, Algorithm};
+    use crate::x509::tests::{setup, DAY};
+    use crate::x509::{CertificateAuthority, Revocation};
+    use x509_cert::ext::pkix::CrlReason;
+
+    fn round_trip(response: &OcspResponse) -> OcspResponse {
+        OcspResponse::from_der(&response.to_der().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_good_and_revoked() {
+        let (ca, revoked, kept) = setup();
+        let root = ca.certificate();
+        let now = SystemTime::now();
+        let revocation = Revocation::new(&revoked, now).with_reason(CrlReason::Superseded);
+        let crl = ca.issue_crl(&[revocation], 1, DAY).unwrap();
+
+        let request = ocsp_request(&kept, root).unwrap();
+        let request = OcspRequest::from_der(&request.to_der().unwrap()).unwrap();
+        let response = round_trip(&ca.ocsp_response(&request, &crl).unwrap());
+        let status = verify_ocsp_response(&response, &request, &kept, root, now).unwrap();
+        assert_eq!(status, CertStatus::good());
+
+        let request = ocsp_request(&revoked, root).unwrap();
+        let response = round_trip(&ca.ocsp_response(&request, &crl).unwrap());
+        match verify_ocsp_response(&response, &request, &revoked, root, now).unwrap() {
+            CertStatus::Revoked(info) => {
+                assert_eq!(info.revocation_reason, Some(CrlReason::Superseded))
+            }
+            status => panic!("expected revoked, got {:?}", status),
+        }
+        assert!(matches!(
+            verify_ocsp_response(&response, &request, &revoked, root, now + DAY * 2),
+            Err(X509Error::RevocationUnknown)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_replay_and_forgery() {
+        let (ca, _, kept) = setup();
+        let root = ca.certificate();
+        let now = SystemTime::now();
+        let crl = ca.issue_crl(&[], 1, DAY).unwrap();
+        let request = ocsp_request(&kept, root).unwrap();
+        let response = ca.ocsp_response(&request, &crl).unwrap();
+
+        let fresh = ocsp_request(&kept, root).unwrap();
+        assert!(matches!(
+            verify_ocsp_response(&response, &fresh, &kept, root, now),
+            Err(X509Error::NonceMismatch)
+        ));
+
+        let impostor = CertificateAuthority::new_root(
+            "CN=Root",
+            generate_key_pair_for(Algorithm::Falcon512),
+            DAY,
+        )
+        .unwrap();
+        let forged = respond(
+            root,
+            &generate_key_pair_for(Algorithm::Falcon512),
+            &crl,
+            &request,
+            now,
+        )
+        .unwrap();
+        assert!(matches!(
+            verify_ocsp_response(&forged, &request, &kept, root, now),
+            Err(X509Error::BadSignature)
+        ));
+        assert!(matches!(
+            impostor.ocsp_response(&request, &crl),
+            Err(X509Error::BadSignature)
+        ));
+    }
+
+    #[test]
+    fn test_stale_crl_and_foreign_certificate() {
+        let (ca, _, kept) = setup();
+        let root = ca.certificate();
+        let now = SystemTime::now();
+        let crl = ca.issue_crl(&[], 1, DAY).unwrap();
+        let request = ocsp_request(&kept, root).unwrap();
+
+        let stale = respond(
+            root,
+            &generate_key_pair_for(Algorithm::Falcon512),
+            &crl,
+            &request,
+            now + DAY * 2,
+        )
+        .unwrap();
+        assert!(matches!(
+            verify_ocsp_response(&stale, &request, &kept, root, now),
+            Err(X509Error::OcspStatus(OcspResponseStatus::TryLater))
+        ));
+
+        let other = CertificateAuthority::new_root(
+            "CN=Other",
+            generate_key_pair_for(Algorithm::Falcon512),
+            DAY,
+        )
+        .unwrap();
+        let foreign = ocsp_request(&kept, other.certificate()).unwrap();
+        let response = ca.ocsp_response(&foreign, &crl).unwrap();
+        assert!(matches!(
+            verify_ocsp_response(&response, &foreign, &kept, root, now),
+            Err(X509Error::RevocationUnknown)
+        ));
+        let Some(bytes) = &response.response_bytes else {
+            panic!("successful response without body")
+        };
+        let basic = BasicOcspResponse::from_der(bytes.response.as_bytes()).unwrap();
+        assert_eq!(
+            basic.tbs_response_data.responses[0].cert_status,
+            CertStatus::unknown()
+        );
+    }
+}
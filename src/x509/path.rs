@@ -0,0 +1,290 @@
+//! Certification path validation against a trust store
+//!
+//! A reduced RFC 5280 section 6 check: names and signatures chain to a
+//! trusted root, every certificate is valid at the given time, issuers are
+//! CAs allowed to sign certificates, path length constraints hold, and no
+//! certificate carries a critical extension this module does not understand.
+//! Name constraints and certificate policies are not supported.
+
+use super::cert::{find_extension, is_ca, verify_issued_by};
+use super::X509Error;
+use std::time::SystemTime;
+use x509_cert::der::oid::AssociatedOid;
+use x509_cert::ext::pkix::{
+    AuthorityKeyIdentifier, BasicConstraints, ExtendedKeyUsage, KeyUsage, SubjectAltName,
+    SubjectKeyIdentifier,
+};
+use x509_cert::Certificate;
+
+/// Most certificates accepted below the trusted root, leaf included
+pub const MAX_PATH_DEPTH: usize = 8;
+
+/// Root certificates trusted as path anchors
+#[derive(Debug, Clone, Default)]
+pub struct TrustStore {
+    roots: Vec<Certificate>,
+}
+
+impl TrustStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Trust `root`, which must be a CA certificate
+    pub fn add(&mut self, root: Certificate) -> Result<(), X509Error> {
+        if !is_ca(&root)? {
+            return Err(X509Error::NotCa);
+        }
+        self.roots.push(root);
+        Ok(())
+    }
+
+    pub fn roots(&self) -> &[Certificate] {
+        &self.roots
+    }
+
+    /// Validate `leaf` at `time`, building the path through `intermediates`
+    ///
+    /// Returns the path from the leaf up to and including the trusted root.
+    pub fn verify(
+        &self,
+        leaf: &Certificate,
+        intermediates: &[Certificate],
+        time: SystemTime,
+    ) -> Result<Vec<Certificate>, X509Error> {
+        check_certificate(leaf, time)?;
+        let mut path = vec![leaf.clone()];
+        loop {
+            let current = path.last().expect("path starts with the leaf");
+            let below = path.len() - 1;
+            let root = find_issuer(current, &self.roots);
+            if let Ok(Some(root)) = root {
+                check_issuer(root, below, time)?;
+                path.push(root.clone());
+                return Ok(path);
+            }
+            if path.len() >= MAX_PATH_DEPTH {
+                return Err(X509Error::PathLenExceeded);
+            }
+            let unused = intermediates.iter().filter(|c| !path.contains(c));
+            let Some(issuer) = find_issuer(current, unused)? else {
+                return Err(root.err().unwrap_or(X509Error::UnknownIssuer));
+            };
+            check_issuer(issuer, below, time)?;
+            path.push(issuer.clone());
+        }
+    }
+}
+
+/// First candidate that named and signed `certificate`
+///
+/// A candidate with the right name but a bad signature is reported only
+/// when no other candidate matches.
+fn find_issuer<'c>(
+    certificate: &Certificate,
+    candidates: impl IntoIterator<Item = &'c Certificate>,
+) -> Result<Option<&'c Certificate>, X509Error> {
+    let mut error = None;
+    for candidate in candidates {
+        if candidate.tbs_certificate.subject != certificate.tbs_certificate.issuer {
+            continue;
+        }
+        match verify_issued_by(certificate, candidate) {
+            Ok(()) => return Ok(Some(candidate)),
+            Err(e) => error = Some(e),
+        }
+    }
+    error.map_or(Ok(None), Err)
+}
+
+/// Checks applying to every certificate on the path
+fn check_certificate(certificate: &Certificate, time: SystemTime) -> Result<(), X509Error> {
+    let validity = &certificate.tbs_certificate.validity;
+    if time < validity.not_before.to_system_time() || time > validity.not_after.to_system_time() {
+        return Err(X509Error::NotValidAt);
+    }
+    let understood = [
+        BasicConstraints::OID,
+        KeyUsage::OID,
+        ExtendedKeyUsage::OID,
+        SubjectAltName::OID,
+        SubjectKeyIdentifier::OID,
+        AuthorityKeyIdentifier::OID,
+    ];
+    for extension in certificate.tbs_certificate.extensions.iter().flatten() {
+        if extension.critical && !understood.contains(&extension.extn_id) {
+            return Err(X509Error::UnsupportedCriticalExtension(extension.extn_id));
+        }
+    }
+    Ok(())
+}
+
+/// Checks on a certificate that issued `below` intermediates plus the leaf
+fn check_issuer(issuer: &Certificate, below: usize, time: SystemTime) -> Result<(), X509Error> {
+    check_certificate(issuer, time)?;
+    let constraints = find_extension::<BasicConstraints>(issuer)?.ok_or(X509Error::NotCa)?;
+    if !constraints.ca {
+        return Err(X509Error::NotCa);
+    }
+    if let Some(usage) = find_extension::<KeyUsage>(issuer)? {
+        if !usage.key_cert_sign() {
+            return Err(X509Error::NotCa);
+        }
+    }
+    match constraints.path_len_constraint {
+        Some(limit) if below > usize::from(limit) => Err(X509Error::PathLenExceeded),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, Algorithm, KeyPair};
+    use crate::x509::cert::{issue_certificate, subject_public_key_info};
+    use crate::x509::{create_csr, CertificateAuthority, IssueOptions};
+    use std::str::FromStr;
+    use std::time::Duration;
+    use x509_cert::name::Name;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    fn key() -> KeyPair {
+        generate_key_pair_for(Algorithm::Falcon512)
+    }
+
+    fn sub_ca(
+        parent: &CertificateAuthority,
+        name: &str,
+        path_len: Option<u8>,
+    ) -> CertificateAuthority {
+        let key = key();
+        let csr = create_csr(name, &key).unwrap();
+        let certificate = parent
+            .issue(&csr, &IssueOptions::ca(DAY, path_len))
+            .unwrap();
+        CertificateAuthority::from_parts(certificate, key).unwrap()
+    }
+
+    fn leaf(ca: &CertificateAuthority, name: &str) -> Certificate {
+        let csr = create_csr(name, &key()).unwrap();
+        ca.issue(&csr, &IssueOptions::leaf(DAY)).unwrap()
+    }
+
+    fn trust(ca: &CertificateAuthority) -> TrustStore {
+        let mut store = TrustStore::new();
+        store.add(ca.certificate().clone()).unwrap();
+        store
+    }
+
+    #[test]
+    fn test_builds_path_through_intermediates() {
+        let root = CertificateAuthority::new_root("CN=Root", key(), DAY).unwrap();
+        let policy = sub_ca(&root, "CN=Policy CA", Some(1));
+        let issuing = sub_ca(&policy, "CN=Issuing CA", None);
+        let unrelated = sub_ca(&root, "CN=Unrelated CA", None);
+        let cert = leaf(&issuing, "CN=service");
+        let now = SystemTime::now();
+
+        let intermediates = [
+            unrelated.certificate().clone(),
+            issuing.certificate().clone(),
+            policy.certificate().clone(),
+        ];
+        let path = trust(&root).verify(&cert, &intermediates, now).unwrap();
+        assert_eq!(path.len(), 4);
+        assert_eq!(&path[1], issuing.certificate());
+        assert_eq!(&path[3], root.certificate());
+
+        assert!(matches!(
+            trust(&root).verify(&cert, &intermediates[..2], now),
+            Err(X509Error::UnknownIssuer)
+        ));
+        assert!(matches!(
+            trust(&root).verify(&cert, &intermediates, now + DAY * 2),
+            Err(X509Error::NotValidAt)
+        ));
+    }
+
+    #[test]
+    fn test_rejects_untrusted_and_forged() {
+        let root = CertificateAuthority::new_root("CN=Root", key(), DAY).unwrap();
+        let impostor = CertificateAuthority::new_root("CN=Root", key(), DAY).unwrap();
+        let cert = leaf(&impostor, "CN=service");
+        assert!(matches!(
+            trust(&root).verify(&cert, &[], SystemTime::now()),
+            Err(X509Error::BadSignature)
+        ));
+
+        let other = CertificateAuthority::new_root("CN=Other", key(), DAY).unwrap();
+        assert!(matches!(
+            trust(&other).verify(&cert, &[], SystemTime::now()),
+            Err(X509Error::UnknownIssuer)
+        ));
+    }
+
+    #[test]
+    fn test_enforces_ca_constraints() {
+        let root = CertificateAuthority::new_root("CN=Root", key(), DAY).unwrap();
+        let now = SystemTime::now();
+        let spki = |key: &KeyPair| subject_public_key_info(key.algorithm, &key.public_key).unwrap();
+        let name = |name: &str| Name::from_str(name).unwrap();
+
+        // A leaf is neither a trust anchor nor an issuer, even if its key signs
+        let leaf_key = key();
+        let csr = create_csr("CN=leaf", &leaf_key).unwrap();
+        let leaf_cert = root.issue(&csr, &IssueOptions::leaf(DAY)).unwrap();
+        assert!(matches!(
+            TrustStore::new().add(leaf_cert.clone()),
+            Err(X509Error::NotCa)
+        ));
+        let options = IssueOptions::leaf(DAY);
+        let victim = spki(&key());
+        let below_leaf = issue_certificate(
+            name("CN=victim"),
+            victim,
+            Some(&leaf_cert),
+            &leaf_key,
+            &options,
+        )
+        .unwrap();
+        assert!(matches!(
+            trust(&root).verify(&below_leaf, &[leaf_cert], now),
+            Err(X509Error::NotCa)
+        ));
+
+        // pathLen 0 allows leaves but no CA below the limited one
+        let limited_key = key();
+        let csr = create_csr("CN=Limited CA", &limited_key).unwrap();
+        let limited = root.issue(&csr, &IssueOptions::ca(DAY, Some(0))).unwrap();
+        let direct = spki(&key());
+        let direct = issue_certificate(
+            name("CN=direct"),
+            direct,
+            Some(&limited),
+            &limited_key,
+            &options,
+        )
+        .unwrap();
+        trust(&root)
+            .verify(&direct, std::slice::from_ref(&limited), now)
+            .unwrap();
+
+        let nested_key = key();
+        let nested = issue_certificate(
+            name("CN=Nested CA"),
+            spki(&nested_key),
+            Some(&limited),
+            &limited_key,
+            &IssueOptions::ca(DAY, None),
+        )
+        .unwrap();
+        let deep = spki(&key());
+        let deep =
+            issue_certificate(name("CN=deep"), deep, Some(&nested), &nested_key, &options).unwrap();
+        assert!(matches!(
+            trust(&root).verify(&deep, &[nested, limited], now),
+            Err(X509Error::PathLenExceeded)
+        ));
+    }
+}
@@ -0,0 +1,610 @@
+//! HTTP OCSP responder behind the `qsf-ocsp` binary
+//!
+//! Requests arrive as DER in a POST body or base64 in a GET path (RFC 6960
+//! appendix A) and are answered from the CA's current CRL, signed with the
+//! CA key. The CRL file is checked on every request and reloaded when it
+//! changes, so publishing a new CRL revokes a certificate immediately; a
+//! replacement must carry a higher CRL number than the one being served.
+//! Each connection carries one request, which must arrive in full within
+//! `request_timeout_secs`, so a client trickling its request holds a worker
+//! for at most that long. Failures are reported through the `log` facade
+//! under the `qsf_ocsp` target.
+//!
+//! ```toml
+//! listen = "0.0.0.0:8080"
+//! issuer_certificate = "/etc/qsf/ca.pem"
+//! issuer_key = "/etc/qsf/ca-key.json"
+//! crl = "/etc/qsf/ca.crl"
+//! workers = 4
+//! request_timeout_secs = 10
+//! ```
+
+use super::crl::{crl_number, decode_crl, verify_crl};
+use super::{CertificateAuthority, X509Error};
+use crate::crypto::key_pair_from_json;
+use base64::{engine::general_purpose, Engine as _};
+use log::{error, warn};
+use percent_encoding::percent_decode_str;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use x509_cert::crl::CertificateList;
+use x509_cert::der::{Decode, DecodePem, Encode};
+use x509_cert::Certificate;
+use x509_ocsp::{OcspRequest, OcspResponse};
+
+const LOG_TARGET: &str = "qsf_ocsp";
+
+/// Largest request body accepted
+const MAX_REQUEST_LEN: usize = 16 * 1024;
+/// Largest request line and headers accepted
+const MAX_HEAD_LEN: usize = 8 * 1024;
+
+/// Errors raised while configuring or running the responder
+#[derive(Debug)]
+pub enum ResponderError {
+    Io(io::Error),
+    Config(String),
+    X509(X509Error),
+    /// A changed CRL file does not carry a higher CRL number
+    StaleCrl,
+}
+
+impl fmt::Display for ResponderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResponderError::Io(e) => write!(f, "I/O error: {}", e),
+            ResponderError::Config(msg) => write!(f, "Invalid configuration: {}", msg),
+            ResponderError::X509(e) => write!(f, "X.509 error: {}", e),
+            ResponderError::StaleCrl => write!(f, "CRL number did not increase"),
+        }
+    }
+}
+
+impl std::error::Error for ResponderError {}
+
+impl From<io::Error> for ResponderError {
+    fn from(e: io::Error) -> Self {
+        ResponderError::Io(e)
+    }
+}
+
+impl From<X509Error> for ResponderError {
+    fn from(e: X509Error) -> Self {
+        ResponderError::X509(e)
+    }
+}
+
+/// Responder settings, read from a TOML file
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ResponderConfig {
+    /// Address accepting HTTP requests
+    pub listen: String,
+    /// PEM certificate of the CA whose certificates are answered for
+    pub issuer_certificate: PathBuf,
+    /// The CA's key pair as written by `key_pair_to_json`
+    pub issuer_key: PathBuf,
+    /// The CA's latest CRL, PEM or DER
+    pub crl: PathBuf,
+    /// Threads answering requests, so a slow client holds up only one
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    /// Seconds a client has to send its whole request
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+fn default_workers() -> usize {
+    4
+}
+
+fn default_request_timeout_secs() -> u64 {
+    10
+}
+
+impl ResponderConfig {
+    /// Parse a configuration from TOML text
+    pub fn from_toml(text: &str) -> Result<Self, ResponderError> {
+        let config: ResponderConfig =
+            toml::from_str(text).map_err(|e| ResponderError::Config(e.to_string()))?;
+        if config.workers == 0 {
+            return Err(ResponderError::Config(
+                "workers must be positive".to_string(),
+            ));
+        }
+        if config.request_timeout_secs == 0 {
+            return Err(ResponderError::Config(
+                "request_timeout_secs must be positive".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+
+    /// Read and parse a configuration file
+    pub fn load(path: &Path) -> Result<Self, ResponderError> {
+        Self::from_toml(&fs::read_to_string(path)?)
+    }
+}
+
+/// A bound responder, ready to serve
+pub struct OcspServer {
+    listener: TcpListener,
+    authority: CertificateAuthority,
+    crl_path: PathBuf,
+    crl: RwLock<(Arc<CertificateList>, Option<SystemTime>)>,
+    workers: usize,
+    request_timeout: Duration,
+}
+
+impl OcspServer {
+    /// Load the CA and its CRL and bind the listening socket
+    pub fn bind(config: ResponderConfig) -> Result<Self, ResponderError> {
+        let pem = fs::read(&config.issuer_certificate)?;
+        let certificate = Certificate::from_pem(&pem).map_err(X509Error::from)?;
+        let key = key_pair_from_json(&fs::read_to_string(&config.issuer_key)?).map_err(|e| {
+            ResponderError::Config(format!("{}: {}", config.issuer_key.display(), e))
+        })?;
+        let authority = CertificateAuthority::from_parts(certificate, key)?;
+        let modified = modified(&config.crl);
+        let crl = load_crl(&config.crl, &authority)?;
+        let listener = TcpListener::bind(&config.listen)?;
+        Ok(OcspServer {
+            listener,
+            authority,
+            crl_path: config.crl,
+            crl: RwLock::new((Arc::new(crl), modified)),
+            workers: config.workers,
+            request_timeout: Duration::from_secs(config.request_timeout_secs),
+        })
+    }
+
+    /// Address accepting requests
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.listener.local_addr().ok()
+    }
+
+    /// Reload the CRL if its file changed
+    ///
+    /// A failed reload, or a CRL whose number is not above the served one's,
+    /// keeps the old CRL.
+    pub fn reload_if_changed(&self) -> Result<bool, ResponderError> {
+        let modified = modified(&self.crl_path);
+        if modified == self.crl.read().expect("CRL lock poisoned").1 {
+            return Ok(false);
+        }
+        let crl = load_crl(&self.crl_path, &self.authority)?;
+        let mut current = self.crl.write().expect("CRL lock poisoned");
+        if crl_number(&crl)? <= crl_number(&current.0)? {
+            return Err(ResponderError::StaleCrl);
+        }
+        *current = (Arc::new(crl), modified);
+        Ok(true)
+    }
+
+    /// Response to a DER-encoded request
+    pub fn answer(&self, request: &[u8]) -> OcspResponse {
+        let Ok(request) = OcspRequest::from_der(request) else {
+            return OcspResponse::malformed_request();
+        };
+        if let Err(e) = self.reload_if_changed() {
+            warn!(target: LOG_TARGET, "CRL reload failed: {}", e);
+        }
+        let crl = self.crl.read().expect("CRL lock poisoned").0.clone();
+        self.authority
+            .ocsp_response(&request, &crl)
+            .unwrap_or_else(|e| {
+                error!(target: LOG_TARGET, "cannot answer request: {}", e);
+                OcspResponse::internal_error()
+            })
+    }
+
+    /// Serve requests on the configured number of worker threads
+    ///
+    /// Failed accepts are logged and do not stop the responder.
+    pub fn run(self) -> Result<(), ResponderError> {
+        let server = Arc::new(self);
+        for _ in 1..server.workers {
+            let server = server.clone();
+            thread::spawn(move || server.serve());
+        }
+        server.serve()
+    }
+
+    fn serve(&self) -> ! {
+        loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    if let Err(e) = self.handle(&stream) {
+                        warn!(target: LOG_TARGET, "request failed: {}", e);
+                    }
+                }
+                Err(e) => warn!(target: LOG_TARGET, "accept failed: {}", e),
+            }
+        }
+    }
+
+    fn handle(&self, stream: &TcpStream) -> io::Result<()> {
+        stream.set_write_timeout(Some(self.request_timeout))?;
+        let request = match read_request(stream, Instant::now() + self.request_timeout) {
+            Ok(request) => request,
+            Err(e) => {
+                let status = match e.kind() {
+                    io::ErrorKind::TimedOut => "408 Request Timeout",
+                    io::ErrorKind::InvalidData => "400 Bad Request",
+                    _ => return Err(e),
+                };
+                // Best effort: the client may already be gone
+                let _ = respond(stream, status, &[]);
+                return Err(e);
+            }
+        };
+        let der = match request.method.as_str() {
+            "POST" => (request.body.len() <= MAX_REQUEST_LEN).then_some(request.body),
+            "GET" => decode_get_path(&request.target),
+            _ => return respond(stream, "405 Method Not Allowed", &[]),
+        };
+        let response = match der {
+            Some(der) => self.answer(&der),
+            None => OcspResponse::malformed_request(),
+        };
+        let body = response
+            .to_der()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        respond(stream, "200 OK", &body)
+    }
+}
+
+/// Method, target and body of an HTTP/1.x request
+struct HttpRequest {
+    method: String,
+    target: String,
+    body: Vec<u8>,
+}
+
+/// Read one request, failing with `TimedOut` once `deadline` passes
+///
+/// Bodies longer than `MAX_REQUEST_LEN` are read only up to one byte past the
+/// limit, enough to reject them.
+fn read_request(stream: &TcpStream, deadline: Instant) -> io::Result<HttpRequest> {
+    let mut data = Vec::new();
+    let head_len = loop {
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if data.len() > MAX_HEAD_LEN {
+            return Err(invalid_request("request head too long"));
+        }
+        read_some(stream, &mut data, deadline)?;
+    };
+    let head = std::str::from_utf8(&data[..head_len])
+        .map_err(|_| invalid_request("request head is not UTF-8"))?;
+    let mut lines = head.split("\r\n").filter(|line| !line.is_empty());
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let (Some(method), Some(target), Some(version)) = (
+        request_line.next(),
+        request_line.next(),
+        request_line.next(),
+    ) else {
+        return Err(invalid_request("malformed request line"));
+    };
+    if !version.starts_with("HTTP/1.") {
+        return Err(invalid_request("unsupported HTTP version"));
+    }
+    let mut content_length = 0;
+    for line in lines {
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| invalid_request("malformed header"))?;
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value
+                .trim()
+                .parse()
+                .map_err(|_| invalid_request("malformed Content-Length"))?;
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            return Err(invalid_request("chunked bodies are not supported"));
+        }
+    }
+    let (method, target) = (method.to_string(), target.to_string());
+
+    let body_len = content_length.min(MAX_REQUEST_LEN + 1);
+    let mut body = data.split_off(head_len);
+    while body.len() < body_len {
+        read_some(stream, &mut body, deadline)?;
+    }
+    body.truncate(body_len);
+    Ok(HttpRequest {
+        method,
+        target,
+        body,
+    })
+}
+
+/// Append what `stream` has to `buffer`, waiting no later than `deadline`
+fn read_some(mut stream: &TcpStream, buffer: &mut Vec<u8>, deadline: Instant) -> io::Result<()> {
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    if remaining.is_zero() {
+        return Err(io::ErrorKind::TimedOut.into());
+    }
+    stream.set_read_timeout(Some(remaining))?;
+    let mut chunk = [0u8; 4096];
+    match stream.read(&mut chunk) {
+        Ok(0) => Err(io::ErrorKind::UnexpectedEof.into()),
+        Ok(n) => {
+            buffer.extend_from_slice(&chunk[..n]);
+            Ok(())
+        }
+        // Unix reports an expired socket timeout as `WouldBlock`
+        Err(e) if e.kind() == io::ErrorKind::WouldBlock => Err(io::ErrorKind::TimedOut.into()),
+        Err(e) => Err(e),
+    }
+}
+
+fn invalid_request(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Write a response and close the connection; non-empty bodies are OCSP
+fn respond(mut stream: &TcpStream, status: &str, body: &[u8]) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        body.len()
+    );
+    if !body.is_empty() {
+        head.push_str("Content-Type: application/ocsp-response\r\n");
+    }
+    head.push_str("\r\n");
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()
+}
+
+/// DER request from a GET path: URL-encoded base64 after the last `/`
+///
+/// Base64 itself may contain `/`, so clients must percent-encode it.
+fn decode_get_path(url: &str) -> Option<Vec<u8>> {
+    let encoded = url.rsplit('/').next()?;
+    let encoded = percent_decode_str(encoded).decode_utf8().ok()?;
+    general_purpose::STANDARD.decode(encoded.as_bytes()).ok()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Read a PEM or DER CRL and check that `authority` signed it
+fn load_crl(
+    path: &Path,
+    authority: &CertificateAuthority,
+) -> Result<CertificateList, ResponderError> {
+    let crl = decode_crl(&fs::read(path)?)?;
+    verify_crl(&crl, authority.certificate())?;
+    Ok(crl)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{generate_key_pair_for, key_pair_to_json, Algorithm};
+    use crate::x509::{
+        create_csr, crl_to_pem, ocsp_request, verify_ocsp_response, CertStatus, IssueOptions,
+        Revocation,
+    };
+    use std::fs::File;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::Duration;
+    use x509_cert::der::pem::LineEnding;
+    use x509_cert::der::EncodePem;
+    use x509_ocsp::OcspResponseStatus;
+
+    const DAY: Duration = Duration::from_secs(86_400);
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("qsf-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Publish a CRL, stamping it with a distinct mtime
+    fn publish(path: &Path, crl: &CertificateList, generation: u64) {
+        fs::write(path, crl_to_pem(crl).unwrap()).unwrap();
+        let stamp = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + generation);
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(stamp).unwrap();
+    }
+
+    /// Send one HTTP request and return the status code and body
+    fn http(addr: SocketAddr, head: &str, body: &[u8]) -> (u16, Vec<u8>) {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{}\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            head,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).unwrap();
+        let split = reply.windows(4).position(|w| w == b"\r\n\r\n").unwrap();
+        let status = std::str::from_utf8(&reply[9..12]).unwrap().parse().unwrap();
+        (status, reply[split + 4..].to_vec())
+    }
+
+    #[test]
+    fn test_config() {
+        let text = "listen = \"127.0.0.1:8080\"\nissuer_certificate = \"ca.pem\"\n\
+                    issuer_key = \"ca-key.json\"\ncrl = \"ca.crl\"\n";
+        let config = ResponderConfig::from_toml(text).unwrap();
+        assert_eq!(config.crl, PathBuf::from("ca.crl"));
+        assert_eq!(config.workers, 4);
+        assert_eq!(config.request_timeout_secs, 10);
+        for invalid in [
+            format!("{}unknown = 1\n", text),
+            format!("{}workers = 0\n", text),
+            format!("{}request_timeout_secs = 0\n", text),
+            "listen = \"x\"\n".to_string(),
+        ] {
+            assert!(matches!(
+                ResponderConfig::from_toml(&invalid),
+                Err(ResponderError::Config(_))
+            ));
+        }
+    }
+
+    /// Responder for a new CA whose CRL number 1 is empty, with `settings`
+    /// appended to its configuration, and a leaf the CA issued
+    fn responder(
+        name: &str,
+        settings: &str,
+    ) -> (PathBuf, CertificateAuthority, Certificate, OcspServer) {
+        let dir = temp_dir(name);
+        let key = generate_key_pair_for(Algorithm::Falcon512);
+        fs::write(dir.join("ca-key.json"), key_pair_to_json(&key)).unwrap();
+        let ca = CertificateAuthority::new_root("CN=Root", key, DAY).unwrap();
+        let pem = ca.certificate().to_pem(LineEnding::LF).unwrap();
+        fs::write(dir.join("ca.pem"), pem).unwrap();
+        let csr = create_csr("CN=service", &generate_key_pair_for(Algorithm::Falcon512)).unwrap();
+        let leaf = ca.issue(&csr, &IssueOptions::leaf(DAY)).unwrap();
+        publish(&dir.join("ca.crl"), &ca.issue_crl(&[], 1, DAY).unwrap(), 1);
+
+        let server = OcspServer::bind(
+            ResponderConfig::from_toml(&format!(
+                "listen = \"127.0.0.1:0\"\nissuer_certificate = {:?}\nissuer_key = {:?}\ncrl = {:?}\n{}",
+                dir.join("ca.pem"),
+                dir.join("ca-key.json"),
+                dir.join("ca.crl"),
+                settings
+            ))
+            .unwrap(),
+        )
+        .unwrap();
+        (dir, ca, leaf, server)
+    }
+
+    #[test]
+    fn test_serves_post_and_get() {
+        let (dir, ca, leaf, server) = responder("ocsp", "");
+        let root = ca.certificate().clone();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        let request = ocsp_request(&leaf, &root).unwrap();
+        let (status, body) = http(addr, "POST / HTTP/1.1", &request.to_der().unwrap());
+        assert_eq!(status, 200);
+        let response = OcspResponse::from_der(&body).unwrap();
+        let now = SystemTime::now();
+        let status = verify_ocsp_response(&response, &request, &leaf, &root, now).unwrap();
+        assert_eq!(status, CertStatus::good());
+
+        let revoked = ca
+            .issue_crl(&[Revocation::new(&leaf, now)], 2, DAY)
+            .unwrap();
+        publish(&dir.join("ca.crl"), &revoked, 2);
+        let request = ocsp_request(&leaf, &root).unwrap();
+        let encoded = general_purpose::STANDARD.encode(request.to_der().unwrap());
+        let path =
+            percent_encoding::utf8_percent_encode(&encoded, percent_encoding::NON_ALPHANUMERIC);
+        let (status, body) = http(addr, &format!("GET /ocsp/{} HTTP/1.1", path), &[]);
+        assert_eq!(status, 200);
+        let response = OcspResponse::from_der(&body).unwrap();
+        let status = verify_ocsp_response(&response, &request, &leaf, &root, now).unwrap();
+        assert!(matches!(status, CertStatus::Revoked(_)));
+
+        let (_, body) = http(addr, "POST / HTTP/1.1", b"not an ocsp request");
+        let response = OcspResponse::from_der(&body).unwrap();
+        assert_eq!(
+            response.response_status,
+            OcspResponseStatus::MalformedRequest
+        );
+        assert_eq!(http(addr, "PUT / HTTP/1.1", &[]).0, 405);
+
+        // A client that never sends its body holds up only its own worker
+        let mut stalled = TcpStream::connect(addr).unwrap();
+        write!(
+            stalled,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10000\r\n\r\n"
+        )
+        .unwrap();
+        thread::sleep(Duration::from_millis(100));
+        let (status, _) = http(addr, "POST / HTTP/1.1", &request.to_der().unwrap());
+        assert_eq!(status, 200);
+        drop(stalled);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_request_deadline() {
+        let (dir, ca, leaf, server) =
+            responder("ocsp-deadline", "workers = 1\nrequest_timeout_secs = 1\n");
+        let request = ocsp_request(&leaf, ca.certificate()).unwrap();
+        let addr = server.local_addr().unwrap();
+        thread::spawn(move || server.run());
+
+        // The only worker is held by a client trickling its body, each byte
+        // well within a per-read timeout
+        let mut trickling = TcpStream::connect(addr).unwrap();
+        write!(
+            trickling,
+            "POST / HTTP/1.1\r\nHost: localhost\r\nContent-Length: 10000\r\n\r\n"
+        )
+        .unwrap();
+        let closed = thread::spawn(move || {
+            for _ in 0..100 {
+                if trickling.write_all(&[0]).is_err() {
+                    return true;
+                }
+                thread::sleep(Duration::from_millis(50));
+            }
+            false
+        });
+        thread::sleep(Duration::from_millis(100));
+
+        let started = Instant::now();
+        let (status, _) = http(addr, "POST / HTTP/1.1", &request.to_der().unwrap());
+        assert_eq!(status, 200);
+        assert!(started.elapsed() < Duration::from_secs(3));
+        assert!(closed.join().unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_reload_requires_newer_crl() {
+        let (dir, ca, leaf, server) = responder("ocsp-reload", "");
+        let path = dir.join("ca.crl");
+        let now = SystemTime::now();
+        let revoked = ca
+            .issue_crl(&[Revocation::new(&leaf, now)], 2, DAY)
+            .unwrap();
+        publish(&path, &revoked, 2);
+        assert!(server.reload_if_changed().unwrap());
+        assert!(!server.reload_if_changed().unwrap());
+
+        // Republishing an older or equally numbered CRL cannot unrevoke
+        for (number, generation) in [(1, 3), (2, 4)] {
+            publish(&path, &ca.issue_crl(&[], number, DAY).unwrap(), generation);
+            assert!(matches!(
+                server.reload_if_changed(),
+                Err(ResponderError::StaleCrl)
+            ));
+        }
+        let request = ocsp_request(&leaf, ca.certificate()).unwrap();
+        let response = server.answer(&request.to_der().unwrap());
+        let status =
+            verify_ocsp_response(&response, &request, &leaf, ca.certificate(), now).unwrap();
+        assert!(matches!(status, CertStatus::Revoked(_)));
+
+        publish(&path, &ca.issue_crl(&[], 3, DAY).unwrap(), 5);
+        assert!(server.reload_if_changed().unwrap());
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
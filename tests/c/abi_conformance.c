@@ -0,0 +1,387 @@
+/*
+ * ABI conformance tests for the C API in include/quantumsafe.h.
+ *
+ * Every exported function is called through the header and the cdylib,
+ * including its error paths. Run with `make` (AddressSanitizer and UBSan)
+ * or `make valgrind`; see the Makefile in this directory.
+ */
+#include <limits.h>
+#include <pthread.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "quantumsafe.h"
+
+static int checks;
+static int failures;
+
+#define CHECK(cond)                                                                \
+    do {                                                                           \
+        checks++;                                                                  \
+        if (!(cond)) {                                                             \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+            failures++;                                                            \
+        }                                                                          \
+    } while (0)
+
+#define CHECK_STATUS(call, expected)                                              \
+    do {                                                                          \
+        QsfStatus got_ = (call);                                                  \
+        checks++;                                                                 \
+        if (got_ != (expected)) {                                                 \
+            fprintf(stderr, "%s:%d: %s returned %d (%s), expected %s\n", __FILE__, \
+                    __LINE__, #call, got_, qsf_status_message(got_), #expected);  \
+            failures++;                                                           \
+        }                                                                         \
+    } while (0)
+
+/* Out pointers start as garbage to check that failures clear them */
+#define GARBAGE ((void *)(uintptr_t)0xdeadbeef)
+
+static const uint8_t MESSAGE[] = "authorise 42.00 EUR";
+static const size_t MESSAGE_LEN = sizeof(MESSAGE) - 1;
+
+static const QsfAlgorithm ALGORITHMS[] = {
+    QSF_ALGORITHM_HMAC_SHA3_256,
+    QSF_ALGORITHM_FALCON512,
+    QSF_ALGORITHM_FALCON1024,
+    QSF_ALGORITHM_ML_DSA_44,
+    QSF_ALGORITHM_SLH_DSA_SHAKE_128F,
+    QSF_ALGORITHM_ML_DSA_44_ED25519,
+};
+
+static QsfKeyPair *generate(QsfAlgorithm algorithm) {
+    QsfKeyPair *key_pair = NULL;
+    CHECK_STATUS(qsf_key_pair_generate(algorithm, &key_pair), QSF_OK);
+    CHECK(key_pair != NULL);
+    return key_pair;
+}
+
+static void test_layout(void) {
+    /* QsfBytes is passed by pointer and read field by field from C */
+    CHECK(sizeof(QsfBytes) == sizeof(uint8_t *) + sizeof(size_t));
+    CHECK(offsetof(QsfBytes, data) == 0);
+    CHECK(offsetof(QsfBytes, len) == sizeof(uint8_t *));
+    CHECK(sizeof(QsfStatus) == sizeof(int));
+    CHECK(sizeof(QsfAlgorithm) == 4);
+}
+
+static void test_status_messages(void) {
+    const QsfStatus statuses[] = {
+        QSF_OK,
+        QSF_ERR_NULL_POINTER,
+        QSF_ERR_INVALID_LENGTH,
+        QSF_ERR_INVALID_ALGORITHM,
+        QSF_ERR_INVALID_KEY,
+        QSF_ERR_INVALID_ENCODING,
+        QSF_ERR_BAD_SIGNATURE,
+        QSF_ERR_INTERNAL,
+    };
+    const size_t count = sizeof(statuses) / sizeof(statuses[0]);
+    const char *unknown = qsf_status_message(INT_MIN);
+    CHECK(unknown != NULL && strlen(unknown) > 0);
+    for (size_t i = 0; i < count; i++) {
+        const char *message = qsf_status_message(statuses[i]);
+        CHECK(message != NULL && strlen(message) > 0);
+        CHECK(strcmp(message, unknown) != 0);
+        for (size_t j = 0; j < i; j++)
+            CHECK(strcmp(message, qsf_status_message(statuses[j])) != 0);
+    }
+}
+
+static void test_sign_and_verify(void) {
+    for (size_t i = 0; i < sizeof(ALGORITHMS) / sizeof(ALGORITHMS[0]); i++) {
+        QsfKeyPair *key_pair = generate(ALGORITHMS[i]);
+        QsfAlgorithm algorithm = UINT32_MAX;
+        CHECK_STATUS(qsf_key_pair_algorithm(key_pair, &algorithm), QSF_OK);
+        CHECK(algorithm == ALGORITHMS[i]);
+
+        QsfBytes signature = {0};
+        CHECK_STATUS(qsf_sign(key_pair, MESSAGE, MESSAGE_LEN, &signature), QSF_OK);
+        CHECK(signature.data != NULL && signature.len > 0);
+        CHECK_STATUS(qsf_verify(key_pair, MESSAGE, MESSAGE_LEN, signature.data, signature.len),
+                     QSF_OK);
+        CHECK_STATUS(qsf_verify(key_pair, MESSAGE, MESSAGE_LEN - 1, signature.data, signature.len),
+                     QSF_ERR_BAD_SIGNATURE);
+        signature.data[0] ^= 1;
+        CHECK_STATUS(qsf_verify(key_pair, MESSAGE, MESSAGE_LEN, signature.data, signature.len),
+                     QSF_ERR_BAD_SIGNATURE);
+
+        /* Empty messages may be passed as NULL */
+        QsfBytes empty = {0};
+        CHECK_STATUS(qsf_sign(key_pair, NULL, 0, &empty), QSF_OK);
+        CHECK_STATUS(qsf_verify(key_pair, NULL, 0, empty.data, empty.len), QSF_OK);
+
+        qsf_bytes_free(&empty);
+        qsf_bytes_free(&signature);
+        qsf_key_pair_free(&key_pair);
+    }
+}
+
+static void test_json_round_trip(void) {
+    QsfKeyPair *key_pair = generate(QSF_ALGORITHM_FALCON512);
+    QsfBytes json = {0};
+    CHECK_STATUS(qsf_key_pair_to_json(key_pair, &json), QSF_OK);
+    CHECK(json.len > 0 && json.data[0] == '{');
+
+    QsfKeyPair *imported = NULL;
+    CHECK_STATUS(qsf_key_pair_from_json(json.data, json.len, &imported), QSF_OK);
+    QsfBytes original = {0}, copy = {0};
+    CHECK_STATUS(qsf_key_pair_public_key(key_pair, &original), QSF_OK);
+    CHECK_STATUS(qsf_key_pair_public_key(imported, &copy), QSF_OK);
+    CHECK(original.len == 897 && copy.len == original.len);
+    CHECK(memcmp(original.data, copy.data, original.len) == 0);
+
+    /* A signature from the import verifies with the original */
+    QsfBytes signature = {0};
+    CHECK_STATUS(qsf_sign(imported, MESSAGE, MESSAGE_LEN, &signature), QSF_OK);
+    CHECK_STATUS(qsf_verify(key_pair, MESSAGE, MESSAGE_LEN, signature.data, signature.len),
+                 QSF_OK);
+
+    QsfKeyPair *out = GARBAGE;
+    CHECK_STATUS(qsf_key_pair_from_json(json.data, json.len - 1, &out), QSF_ERR_INVALID_ENCODING);
+    CHECK(out == NULL);
+    const uint8_t not_utf8[] = {'{', 0xff, '}'};
+    CHECK_STATUS(qsf_key_pair_from_json(not_utf8, sizeof(not_utf8), &out),
+                 QSF_ERR_INVALID_ENCODING);
+    const char *mismatched = "{\"public_key\":[1],\"private_key\":[2],\"algorithm\":\"Falcon512\"}";
+    CHECK_STATUS(qsf_key_pair_from_json((const uint8_t *)mismatched, strlen(mismatched), &out),
+                 QSF_ERR_INVALID_KEY);
+    CHECK(out == NULL);
+
+    qsf_bytes_free(&signature);
+    qsf_bytes_free(&copy);
+    qsf_bytes_free(&original);
+    qsf_bytes_free(&json);
+    qsf_key_pair_free(&imported);
+    qsf_key_pair_free(&key_pair);
+}
+
+static void test_public_key_verification(void) {
+    QsfKeyPair *key_pair = generate(QSF_ALGORITHM_FALCON1024);
+    QsfBytes signature = {0};
+    CHECK_STATUS(qsf_sign(key_pair, MESSAGE, MESSAGE_LEN, &signature), QSF_OK);
+
+    QsfPublicKey *exported = NULL;
+    QsfBytes encoded = {0};
+    CHECK_STATUS(qsf_public_key_from_key_pair(key_pair, &exported), QSF_OK);
+    CHECK_STATUS(qsf_public_key_to_bytes(exported, &encoded), QSF_OK);
+    qsf_public_key_free(&exported);
+    qsf_key_pair_free(&key_pair);
+
+    QsfPublicKey *public_key = NULL;
+    CHECK_STATUS(qsf_public_key_from_bytes(encoded.data, encoded.len, &public_key), QSF_OK);
+    QsfAlgorithm algorithm = UINT32_MAX;
+    CHECK_STATUS(qsf_public_key_algorithm(public_key, &algorithm), QSF_OK);
+    CHECK(algorithm == QSF_ALGORITHM_FALCON1024);
+    CHECK_STATUS(qsf_public_key_verify(public_key, MESSAGE, MESSAGE_LEN, signature.data,
+                                       signature.len),
+                 QSF_OK);
+    CHECK_STATUS(qsf_public_key_verify(public_key, MESSAGE, 1, signature.data, signature.len),
+                 QSF_ERR_BAD_SIGNATURE);
+
+    /* The verifier keeps working after the public key handle is freed */
+    QsfVerifier *verifier = NULL;
+    CHECK_STATUS(qsf_verifier_new(public_key, &verifier), QSF_OK);
+    qsf_public_key_free(&public_key);
+    for (int i = 0; i < 3; i++)
+        CHECK_STATUS(qsf_verifier_verify(verifier, MESSAGE, MESSAGE_LEN, signature.data,
+                                         signature.len),
+                     QSF_OK);
+    CHECK_STATUS(qsf_verifier_verify(verifier, MESSAGE, MESSAGE_LEN, signature.data, 10),
+                 QSF_ERR_BAD_SIGNATURE);
+
+    /* Corrupt encodings and keys that cannot verify on their own */
+    QsfPublicKey *out = GARBAGE;
+    CHECK_STATUS(qsf_public_key_from_bytes(encoded.data, encoded.len - 1, &out),
+                 QSF_ERR_INVALID_KEY);
+    CHECK(out == NULL);
+    CHECK_STATUS(qsf_public_key_from_bytes(encoded.data, 5, &out), QSF_ERR_INVALID_ENCODING);
+    CHECK_STATUS(qsf_public_key_from_bytes(NULL, 0, &out), QSF_ERR_INVALID_ENCODING);
+    QsfKeyPair *hmac = generate(QSF_ALGORITHM_HMAC_SHA3_256);
+    CHECK_STATUS(qsf_public_key_from_key_pair(hmac, &out), QSF_ERR_INVALID_ALGORITHM);
+    CHECK(out == NULL);
+    qsf_key_pair_free(&hmac);
+
+    qsf_verifier_free(&verifier);
+    qsf_bytes_free(&encoded);
+    qsf_bytes_free(&signature);
+}
+
+static void test_null_and_length_checks(void) {
+    QsfKeyPair *key_pair = generate(QSF_ALGORITHM_FALCON512);
+    QsfPublicKey *public_key = NULL;
+    QsfVerifier *verifier = NULL;
+    CHECK_STATUS(qsf_public_key_from_key_pair(key_pair, &public_key), QSF_OK);
+    CHECK_STATUS(qsf_verifier_new(public_key, &verifier), QSF_OK);
+    QsfBytes bytes = {0};
+    QsfAlgorithm algorithm = 0;
+
+    /* Null handles */
+    CHECK_STATUS(qsf_key_pair_to_json(NULL, &bytes), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_key_pair_algorithm(NULL, &algorithm), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_key_pair_public_key(NULL, &bytes), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_sign(NULL, MESSAGE, MESSAGE_LEN, &bytes), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_verify(NULL, MESSAGE, MESSAGE_LEN, MESSAGE, 1), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_public_key_to_bytes(NULL, &bytes), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_public_key_algorithm(NULL, &algorithm), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_public_key_verify(NULL, MESSAGE, MESSAGE_LEN, MESSAGE, 1),
+                 QSF_ERR_NULL_POINTER);
+    QsfVerifier *no_verifier = GARBAGE;
+    CHECK_STATUS(qsf_verifier_new(NULL, &no_verifier), QSF_ERR_NULL_POINTER);
+    CHECK(no_verifier == NULL);
+    CHECK_STATUS(qsf_verifier_verify(NULL, MESSAGE, MESSAGE_LEN, MESSAGE, 1),
+                 QSF_ERR_NULL_POINTER);
+
+    /* Null out pointers */
+    CHECK_STATUS(qsf_key_pair_generate(QSF_ALGORITHM_FALCON512, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_key_pair_from_json((const uint8_t *)"{}", 2, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_key_pair_to_json(key_pair, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_key_pair_algorithm(key_pair, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_key_pair_public_key(key_pair, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_sign(key_pair, MESSAGE, MESSAGE_LEN, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_public_key_from_bytes(MESSAGE, MESSAGE_LEN, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_public_key_from_key_pair(key_pair, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_public_key_to_bytes(public_key, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_public_key_algorithm(public_key, NULL), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_verifier_new(public_key, NULL), QSF_ERR_NULL_POINTER);
+
+    /* Null data with a non-zero length, and lengths no buffer can have */
+    CHECK_STATUS(qsf_sign(key_pair, NULL, 1, &bytes), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_verify(key_pair, MESSAGE, MESSAGE_LEN, NULL, 666), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_public_key_verify(public_key, NULL, 1, MESSAGE, 1), QSF_ERR_NULL_POINTER);
+    CHECK_STATUS(qsf_verifier_verify(verifier, MESSAGE, MESSAGE_LEN, NULL, 1),
+                 QSF_ERR_NULL_POINTER);
+    QsfKeyPair *imported = GARBAGE;
+    CHECK_STATUS(qsf_key_pair_from_json(NULL, 1, &imported), QSF_ERR_NULL_POINTER);
+    CHECK(imported == NULL);
+    CHECK_STATUS(qsf_sign(key_pair, MESSAGE, SIZE_MAX, &bytes), QSF_ERR_INVALID_LENGTH);
+    CHECK_STATUS(qsf_verify(key_pair, MESSAGE, SIZE_MAX, MESSAGE, 1), QSF_ERR_INVALID_LENGTH);
+    CHECK_STATUS(qsf_verifier_verify(verifier, MESSAGE, MESSAGE_LEN, MESSAGE, SIZE_MAX),
+                 QSF_ERR_INVALID_LENGTH);
+    QsfPublicKey *no_public_key = GARBAGE;
+    CHECK_STATUS(qsf_public_key_from_bytes(MESSAGE, SIZE_MAX, &no_public_key),
+                 QSF_ERR_INVALID_LENGTH);
+    CHECK(no_public_key == NULL);
+
+    /* Unknown algorithms */
+    QsfKeyPair *out = GARBAGE;
+    CHECK_STATUS(qsf_key_pair_generate(14, &out), QSF_ERR_INVALID_ALGORITHM);
+    CHECK(out == NULL);
+    CHECK_STATUS(qsf_key_pair_generate(UINT32_MAX, &out), QSF_ERR_INVALID_ALGORITHM);
+
+    qsf_verifier_free(&verifier);
+    qsf_public_key_free(&public_key);
+    qsf_key_pair_free(&key_pair);
+}
+
+static void test_outputs_cleared_on_failure(void) {
+    QsfBytes bytes = {GARBAGE, 12345};
+    CHECK_STATUS(qsf_sign(NULL, MESSAGE, MESSAGE_LEN, &bytes), QSF_ERR_NULL_POINTER);
+    CHECK(bytes.data == NULL && bytes.len == 0);
+
+    QsfAlgorithm algorithm = 99;
+    CHECK_STATUS(qsf_public_key_algorithm(NULL, &algorithm), QSF_ERR_NULL_POINTER);
+    CHECK(algorithm == 0);
+
+    QsfVerifier *verifier = GARBAGE;
+    CHECK_STATUS(qsf_verifier_new(NULL, &verifier), QSF_ERR_NULL_POINTER);
+    CHECK(verifier == NULL);
+
+    /* A cleared buffer is safe to free */
+    qsf_bytes_free(&bytes);
+}
+
+static void test_double_free_protection(void) {
+    QsfKeyPair *key_pair = generate(QSF_ALGORITHM_FALCON512);
+    QsfPublicKey *public_key = NULL;
+    QsfVerifier *verifier = NULL;
+    QsfBytes bytes = {0};
+    CHECK_STATUS(qsf_public_key_from_key_pair(key_pair, &public_key), QSF_OK);
+    CHECK_STATUS(qsf_verifier_new(public_key, &verifier), QSF_OK);
+    CHECK_STATUS(qsf_public_key_to_bytes(public_key, &bytes), QSF_OK);
+
+    /* Free functions null the caller's handle, so a second call is a no-op */
+    qsf_bytes_free(&bytes);
+    CHECK(bytes.data == NULL && bytes.len == 0);
+    qsf_bytes_free(&bytes);
+    qsf_verifier_free(&verifier);
+    CHECK(verifier == NULL);
+    qsf_verifier_free(&verifier);
+    qsf_public_key_free(&public_key);
+    CHECK(public_key == NULL);
+    qsf_public_key_free(&public_key);
+    qsf_key_pair_free(&key_pair);
+    CHECK(key_pair == NULL);
+    qsf_key_pair_free(&key_pair);
+
+    /* Null handle addresses are ignored */
+    qsf_bytes_free(NULL);
+    qsf_verifier_free(NULL);
+    qsf_public_key_free(NULL);
+    qsf_key_pair_free(NULL);
+
+    /* A status from a freed handle is a null pointer error, not a crash */
+    CHECK_STATUS(qsf_sign(key_pair, MESSAGE, MESSAGE_LEN, &bytes), QSF_ERR_NULL_POINTER);
+}
+
+#define THREADS 4
+
+struct shared_verifier {
+    const QsfVerifier *verifier;
+    const QsfBytes *signature;
+    int failures;
+};
+
+static void *verify_repeatedly(void *arg) {
+    struct shared_verifier *shared = arg;
+    for (int i = 0; i < 25; i++) {
+        if (qsf_verifier_verify(shared->verifier, MESSAGE, MESSAGE_LEN, shared->signature->data,
+                                shared->signature->len) != QSF_OK)
+            shared->failures++;
+    }
+    return NULL;
+}
+
+static void test_verifier_shared_between_threads(void) {
+    QsfKeyPair *key_pair = generate(QSF_ALGORITHM_FALCON512);
+    QsfPublicKey *public_key = NULL;
+    QsfVerifier *verifier = NULL;
+    QsfBytes signature = {0};
+    CHECK_STATUS(qsf_sign(key_pair, MESSAGE, MESSAGE_LEN, &signature), QSF_OK);
+    CHECK_STATUS(qsf_public_key_from_key_pair(key_pair, &public_key), QSF_OK);
+    CHECK_STATUS(qsf_verifier_new(public_key, &verifier), QSF_OK);
+
+    pthread_t threads[THREADS];
+    struct shared_verifier shared[THREADS];
+    for (int i = 0; i < THREADS; i++) {
+        shared[i] = (struct shared_verifier){verifier, &signature, 0};
+        CHECK(pthread_create(&threads[i], NULL, verify_repeatedly, &shared[i]) == 0);
+    }
+    for (int i = 0; i < THREADS; i++) {
+        CHECK(pthread_join(threads[i], NULL) == 0);
+        CHECK(shared[i].failures == 0);
+    }
+
+    qsf_verifier_free(&verifier);
+    qsf_public_key_free(&public_key);
+    qsf_bytes_free(&signature);
+    qsf_key_pair_free(&key_pair);
+}
+
+int main(void) {
+    test_layout();
+    test_status_messages();
+    test_sign_and_verify();
+    test_json_round_trip();
+    test_public_key_verification();
+    test_null_and_length_checks();
+    test_outputs_cleared_on_failure();
+    test_double_free_protection();
+    test_verifier_shared_between_threads();
+
+    printf("%d checks, %d failures\n", checks, failures);
+    return failures == 0 ? 0 : 1;
+}
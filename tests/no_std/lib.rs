@@ -0,0 +1,145 @@
+//! Builds the `crypto` core the way firmware for a payment terminal would
+//!
+//! `cargo build --target thumbv7em-none-eabihf` checks the `no_std` build,
+//! which signs and encapsulates with the fixed-size types, and
+//! `--features alloc` adds `KeyPair` and JSON. The target has no `std`, so
+//! the build fails if any dependency needs it.
+
+#![no_std]
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+use core::panic::PanicInfo;
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use quantumsafe_finance::crypto::{
+    self, Algorithm, Ciphertext, CryptoError, Falcon512, KeyId, MlKem768, PublicKey,
+    PublicKeyVerifier, SecretKey, Signature, SHARED_SECRET_LEN,
+};
+use rand_core::{CryptoRng, Error, RngCore};
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    loop {}
+}
+
+/// Verify a Falcon-512 signature with only stack memory
+pub fn verify_falcon(public_key: &[u8], message: &[u8], signature: &[u8]) -> bool {
+    PublicKeyVerifier::new(Algorithm::Falcon512, public_key)
+        .is_ok_and(|verifier| verifier.verify(message, signature))
+}
+
+pub fn verify_hmac(message: &[u8], signature: &[u8], key: &[u8]) -> bool {
+    crypto::verify_signature(message, signature, key)
+}
+
+pub fn key_id(encoded_public_key: &[u8]) -> Option<KeyId> {
+    let (algorithm, public_key) = crypto::decode_public_key(encoded_public_key)?;
+    Some(crypto::key_id(algorithm, public_key))
+}
+
+extern "C" {
+    /// Provided by the terminal firmware: fill `len` bytes from the TRNG
+    fn trng_fill(buf: *mut u8, len: usize);
+}
+
+/// The terminal's hardware random number generator
+pub struct HardwareRng;
+
+impl RngCore for HardwareRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        unsafe { trng_fill(dest.as_mut_ptr(), dest.len()) }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for HardwareRng {}
+
+/// Sign with a key held in static memory, without touching the heap
+pub fn sign_falcon(
+    secret_key: &SecretKey<Falcon512>,
+    message: &[u8],
+) -> Result<Signature<Falcon512>, CryptoError> {
+    secret_key.sign(message, &mut HardwareRng)
+}
+
+pub fn encapsulate(
+    public_key: &PublicKey<MlKem768>,
+) -> (Ciphertext<MlKem768>, [u8; SHARED_SECRET_LEN]) {
+    public_key.encapsulate(&mut HardwareRng)
+}
+
+const HEAP_SIZE: usize = 64 * 1024;
+
+/// Bump allocator standing in for the firmware's heap
+struct Heap {
+    memory: UnsafeCell<[u8; HEAP_SIZE]>,
+    next: AtomicUsize,
+}
+
+unsafe impl Sync for Heap {}
+
+unsafe impl GlobalAlloc for Heap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let base = self.memory.get() as usize;
+        let mut next = self.next.load(Ordering::Relaxed);
+        loop {
+            let start = (base + next).next_multiple_of(layout.align()) - base;
+            let end = start + layout.size();
+            if end > HEAP_SIZE {
+                return ptr::null_mut();
+            }
+            match self
+                .next
+                .compare_exchange_weak(next, end, Ordering::Relaxed, Ordering::Relaxed)
+            {
+                Ok(_) => return (base + start) as *mut u8,
+                Err(current) => next = current,
+            }
+        }
+    }
+
+    unsafe fn dealloc(&self, _: *mut u8, _: Layout) {}
+}
+
+#[global_allocator]
+static HEAP: Heap = Heap {
+    memory: UnsafeCell::new([0; HEAP_SIZE]),
+    next: AtomicUsize::new(0),
+};
+
+#[cfg(feature = "alloc")]
+pub use self::signing::*;
+
+#[cfg(feature = "alloc")]
+mod signing {
+    extern crate alloc;
+
+    use super::HardwareRng;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+    use quantumsafe_finance::crypto::{self, Algorithm, CryptoError};
+
+    /// Generate a key pair and return it as JSON together with a signature
+    pub fn enrol(message: &[u8]) -> Result<(String, Vec<u8>), CryptoError> {
+        let key_pair = crypto::generate_key_pair_with_rng(Algorithm::Falcon512, &mut HardwareRng);
+        let signature = key_pair.sign_with_rng(message, &mut HardwareRng)?;
+        Ok((crypto::key_pair_to_json(&key_pair), signature))
+    }
+}
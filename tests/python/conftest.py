@@ -0,0 +1,17 @@
+import json
+from pathlib import Path
+
+import pytest
+
+VECTORS = Path(__file__).resolve().parents[1] / "vectors" / "signatures.json"
+
+
+def load_vectors():
+    return json.loads(VECTORS.read_text())["vectors"]
+
+
+@pytest.fixture(scope="session")
+def falcon_key_pair():
+    from quantumsafe_finance import KeyPairWrapper
+
+    return KeyPairWrapper.generate("falcon-512")
@@ -0,0 +1,81 @@
+import asyncio
+import threading
+
+import pytest
+
+from quantumsafe_finance import KeyPairWrapper, PublicKey
+
+
+def batch(key_pair, count):
+    messages = [b"payment %d" % i for i in range(count)]
+    signatures = [key_pair.sign(m).to_bytes() for m in messages]
+    signatures[1] = signatures[0]
+    expected = [i != 1 for i in range(count)]
+    return messages, signatures, expected
+
+
+def test_verify_many(falcon_key_pair):
+    messages, signatures, expected = batch(falcon_key_pair, 200)
+    public_key = PublicKey.from_key_pair(falcon_key_pair)
+    assert falcon_key_pair.verify_many(messages, signatures) == expected
+    assert public_key.verify_many(tuple(messages), iter(signatures)) == expected
+    assert public_key.verify_many([], []) == []
+
+
+def test_verify_many_numpy(falcon_key_pair):
+    np = pytest.importorskip("numpy")
+    messages, signatures, expected = batch(falcon_key_pair, 8)
+    public_key = PublicKey.from_key_pair(falcon_key_pair)
+
+    rows = np.frombuffer(b"".join(signatures), dtype=np.uint8).reshape(8, -1)
+    assert public_key.verify_many(np.array(messages), rows) == expected
+    assert public_key.verify_many(np.array(messages, dtype=object), list(rows)) == expected
+
+
+def test_threads_share_key_pair(falcon_key_pair):
+    results = []
+
+    def worker():
+        signature = falcon_key_pair.sign(b"threaded")
+        results.append(falcon_key_pair.verify(b"threaded", signature))
+
+    threads = [threading.Thread(target=worker) for _ in range(4)]
+    for thread in threads:
+        thread.start()
+    for thread in threads:
+        thread.join()
+    assert results == [True] * 4
+
+
+def test_async_variants():
+    async def main():
+        key_pair = await KeyPairWrapper.generate_async("falcon-512")
+        public_key = PublicKey.from_key_pair(key_pair)
+        signatures = await asyncio.gather(*(key_pair.sign_async(b"m%d" % i) for i in range(8)))
+        messages = [b"m%d" % i for i in range(8)]
+        return (
+            await key_pair.verify_async(b"m0", signatures[0]),
+            await public_key.verify_async(b"m1", signatures[0]),
+            await public_key.verify_many_async(messages, signatures),
+            await key_pair.verify_many_async(messages, signatures),
+        )
+
+    one, other, many, many_key_pair = asyncio.run(main())
+    assert one and not other
+    assert many == many_key_pair == [True] * 8
+
+
+def test_async_errors_and_cancellation(falcon_key_pair):
+    async def main():
+        with pytest.raises(ValueError):
+            await falcon_key_pair.verify_many_async([b"a"], [])
+        cancelled = falcon_key_pair.sign_async(b"cancelled")
+        cancelled.cancel()
+        return await falcon_key_pair.sign_async(b"after")
+
+    assert falcon_key_pair.verify(b"after", asyncio.run(main()))
+
+
+def test_async_needs_running_loop(falcon_key_pair):
+    with pytest.raises(RuntimeError):
+        falcon_key_pair.sign_async(b"no loop")
@@ -0,0 +1,65 @@
+import json
+
+import pytest
+
+from conftest import load_vectors
+from quantumsafe_finance import (
+    EncodingError,
+    InvalidAlgorithmError,
+    InvalidKeyError,
+    KeyPairWrapper,
+    PublicKey,
+    QuantumSafeError,
+)
+
+
+@pytest.mark.parametrize("error", [InvalidKeyError, InvalidAlgorithmError, EncodingError])
+def test_hierarchy(error):
+    assert issubclass(error, QuantumSafeError)
+    assert issubclass(QuantumSafeError, Exception)
+
+
+def test_unknown_algorithm():
+    with pytest.raises(InvalidAlgorithmError, match="rsa-2048"):
+        KeyPairWrapper.generate("rsa-2048")
+
+
+@pytest.mark.parametrize("text", ["", "{", "not json"])
+def test_malformed_json(text):
+    with pytest.raises(EncodingError):
+        KeyPairWrapper.from_json(text)
+
+
+def test_invalid_key_pair_json(falcon_key_pair):
+    with pytest.raises(InvalidKeyError):
+        KeyPairWrapper.from_json("{}")
+
+    falcon = next(v for v in load_vectors() if v["algorithm"] == "falcon-512")
+    forged = json.loads(falcon["key_pair"])
+    forged["public_key"] = list(falcon_key_pair.public_key)
+    with pytest.raises(InvalidKeyError):
+        KeyPairWrapper.from_json(json.dumps(forged))
+
+
+def test_public_key_errors(falcon_key_pair):
+    with pytest.raises(EncodingError):
+        PublicKey.from_bytes(b"")
+    with pytest.raises(InvalidAlgorithmError):
+        PublicKey.from_key_pair(KeyPairWrapper.generate("hmac-sha3-256"))
+
+    encoded = PublicKey.from_key_pair(falcon_key_pair).to_bytes()
+    with pytest.raises(InvalidKeyError):
+        PublicKey.from_bytes(encoded[:-1])
+
+
+@pytest.mark.parametrize("message", [42, None, [1, 2, 3]])
+def test_non_bytes_arguments(falcon_key_pair, message):
+    with pytest.raises(TypeError):
+        falcon_key_pair.sign(message)
+
+
+def test_verify_many_arguments(falcon_key_pair):
+    with pytest.raises(ValueError):
+        falcon_key_pair.verify_many([b"a", b"b"], [b"a"])
+    with pytest.raises(TypeError):
+        falcon_key_pair.verify_many(b"ab", [b"a", b"b"])
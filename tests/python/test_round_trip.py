@@ -0,0 +1,95 @@
+import json
+
+import pytest
+
+from quantumsafe_finance import (
+    ALGORITHMS,
+    KeyPairWrapper,
+    PublicKey,
+    Signature,
+    sign_message,
+    verify_signature,
+)
+
+MESSAGE = b"Transfer 100.00 EUR to DE89370400440532013000"
+
+
+def test_algorithms():
+    assert ALGORITHMS == (
+        "hmac-sha3-256",
+        "falcon-512",
+        "falcon-1024",
+        "ml-dsa-44",
+        "ml-dsa-65",
+        "ml-dsa-87",
+        "slh-dsa-shake-128s",
+        "slh-dsa-shake-128f",
+        "slh-dsa-shake-192s",
+        "slh-dsa-shake-192f",
+        "slh-dsa-shake-256s",
+        "slh-dsa-shake-256f",
+        "ml-dsa-44-ed25519",
+        "ml-dsa-65-ed25519",
+    )
+
+
+@pytest.mark.parametrize("algorithm", ALGORITHMS)
+def test_sign_and_verify(algorithm):
+    key_pair = KeyPairWrapper.generate(algorithm)
+    assert key_pair.algorithm == algorithm
+
+    signature = key_pair.sign(MESSAGE)
+    assert key_pair.verify(MESSAGE, signature)
+    assert key_pair.verify(MESSAGE, signature.to_bytes())
+    assert not key_pair.verify(MESSAGE + b".", signature)
+
+
+@pytest.mark.parametrize(
+    "message",
+    [MESSAGE, bytearray(MESSAGE), memoryview(MESSAGE), MESSAGE.decode()],
+    ids=["bytes", "bytearray", "memoryview", "str"],
+)
+def test_bytes_like_messages(falcon_key_pair, message):
+    signature = falcon_key_pair.sign(message)
+    assert falcon_key_pair.verify(MESSAGE, signature)
+
+
+@pytest.mark.parametrize("algorithm", ALGORITHMS)
+def test_json_round_trip(algorithm):
+    key_pair = KeyPairWrapper.generate(algorithm)
+    restored = KeyPairWrapper.from_json(key_pair.to_json())
+    assert restored.algorithm == algorithm
+    assert restored.public_key == key_pair.public_key
+    assert restored.private_key == key_pair.private_key
+    assert restored.key_id == key_pair.key_id
+    assert restored.verify(MESSAGE, key_pair.sign(MESSAGE))
+
+    data = json.loads(key_pair.to_json())
+    assert bytes(data["public_key"]) == key_pair.public_key
+
+
+def test_signature_round_trip(falcon_key_pair):
+    signature = falcon_key_pair.sign(MESSAGE)
+    copy = Signature.from_bytes(signature.to_bytes())
+    assert copy == signature
+    assert copy != Signature(b"other")
+    assert bytes(copy) == signature.signature
+    assert len(copy) == 666
+
+
+def test_public_key_round_trip(falcon_key_pair):
+    public_key = PublicKey.from_key_pair(falcon_key_pair)
+    decoded = PublicKey.from_bytes(public_key.to_bytes())
+    assert decoded.algorithm == "falcon-512"
+    assert decoded.to_bytes() == public_key.to_bytes()
+
+    signature = falcon_key_pair.sign(MESSAGE)
+    assert decoded.verify(MESSAGE, signature)
+    assert not decoded.verify(b"other", signature)
+
+
+def test_hmac_functions():
+    key_pair = KeyPairWrapper()
+    signature = sign_message("Hello, quantum world!", key_pair.private_key)
+    assert verify_signature("Hello, quantum world!", signature, key_pair.private_key)
+    assert not verify_signature("Hello, quantum world?", signature, key_pair.private_key)
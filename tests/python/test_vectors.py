@@ -0,0 +1,29 @@
+import pytest
+
+from conftest import load_vectors
+from quantumsafe_finance import KeyPairWrapper, PublicKey, Signature
+
+VECTORS = load_vectors()
+
+
+@pytest.mark.parametrize("vector", VECTORS, ids=[v["algorithm"] for v in VECTORS])
+def test_verify_rust_signatures(vector):
+    key_pair = KeyPairWrapper.from_json(vector["key_pair"])
+    message = bytes.fromhex(vector["message"])
+    signature = Signature.from_bytes(bytes.fromhex(vector["signature"]))
+
+    assert key_pair.algorithm == vector["algorithm"]
+    assert key_pair.verify(message, signature)
+    assert not key_pair.verify(message[:-1], signature)
+
+    if vector["public_key"] is None:
+        return
+    public_key = PublicKey.from_bytes(bytes.fromhex(vector["public_key"]))
+    assert public_key.algorithm == vector["algorithm"]
+    assert public_key.to_bytes().hex() == vector["public_key"]
+    assert public_key.verify(message, signature)
+
+
+@pytest.mark.parametrize("vector", VECTORS, ids=[v["algorithm"] for v in VECTORS])
+def test_json_matches_rust(vector):
+    assert KeyPairWrapper.from_json(vector["key_pair"]).to_json() == vector["key_pair"]
@@ -0,0 +1,50 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const { loadVectors } = require('./helpers');
+const { KeyPairWrapper, PublicKey, Signature, signatureFromBase64 } = require('./pkg');
+
+function throwsNamed(fn, name) {
+    assert.throws(fn, (error) => error instanceof Error && error.name === name);
+}
+
+test('unknown algorithm', () => {
+    throwsNamed(() => KeyPairWrapper.generate('rsa-2048'), 'InvalidAlgorithmError');
+    throwsNamed(() => new KeyPairWrapper('rsa-2048'), 'InvalidAlgorithmError');
+});
+
+test('malformed JSON', () => {
+    for (const text of ['', '{', 'not json']) {
+        throwsNamed(() => KeyPairWrapper.fromJson(text), 'EncodingError');
+    }
+});
+
+test('invalid key pair JSON', () => {
+    throwsNamed(() => KeyPairWrapper.fromJson('{}'), 'InvalidKeyError');
+
+    const falcon = loadVectors().find((v) => v.algorithm === 'falcon-512');
+    const forged = JSON.parse(falcon.key_pair);
+    forged.public_key = Array.from(KeyPairWrapper.generate('falcon-512').publicKey);
+    throwsNamed(() => KeyPairWrapper.fromJson(JSON.stringify(forged)), 'InvalidKeyError');
+});
+
+test('public key errors', () => {
+    throwsNamed(() => PublicKey.fromBytes(new Uint8Array()), 'EncodingError');
+    throwsNamed(() => PublicKey.fromKeyPair(new KeyPairWrapper()), 'InvalidAlgorithmError');
+
+    const encoded = PublicKey.fromKeyPair(KeyPairWrapper.generate('falcon-512')).toBytes();
+    throwsNamed(() => PublicKey.fromBytes(encoded.subarray(0, -1)), 'InvalidKeyError');
+});
+
+test('invalid base64', () => {
+    throwsNamed(() => Signature.fromBase64('!'), 'EncodingError');
+    throwsNamed(() => signatureFromBase64('!'), 'EncodingError');
+});
+
+test('arguments of the wrong type', () => {
+    const keyPair = KeyPairWrapper.generate('falcon-512');
+    for (const message of [42, null, [1, 2, 3]]) {
+        assert.throws(() => keyPair.sign(message), TypeError);
+    }
+    assert.throws(() => keyPair.verify('m', 'not a signature'), TypeError);
+    assert.throws(() => keyPair.verify('m', {}), TypeError);
+});
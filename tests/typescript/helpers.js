@@ -0,0 +1,8 @@
+const fs = require('node:fs');
+const path = require('node:path');
+
+const VECTORS = path.join(__dirname, '..', 'vectors', 'signatures.json');
+
+exports.loadVectors = () => JSON.parse(fs.readFileSync(VECTORS, 'utf8')).vectors;
+
+exports.hex = (text) => Uint8Array.from(Buffer.from(text, 'hex'));
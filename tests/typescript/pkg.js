@@ -0,0 +1,2 @@
+// The package built by `npm run build`
+module.exports = require('../../target/pkg-node');
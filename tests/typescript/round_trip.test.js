@@ -0,0 +1,95 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const {
+    KeyPairWrapper,
+    PublicKey,
+    Signature,
+    algorithms,
+    signMessage,
+    verifySignature,
+    signatureFromBase64,
+    signatureToBase64,
+} = require('./pkg');
+
+const MESSAGE = 'Transfer 100.00 EUR to DE89370400440532013000';
+const MESSAGE_BYTES = new TextEncoder().encode(MESSAGE);
+
+test('algorithms', () => {
+    assert.deepEqual(algorithms(), [
+        'hmac-sha3-256',
+        'falcon-512',
+        'falcon-1024',
+        'ml-dsa-44',
+        'ml-dsa-65',
+        'ml-dsa-87',
+        'slh-dsa-shake-128s',
+        'slh-dsa-shake-128f',
+        'slh-dsa-shake-192s',
+        'slh-dsa-shake-192f',
+        'slh-dsa-shake-256s',
+        'slh-dsa-shake-256f',
+        'ml-dsa-44-ed25519',
+        'ml-dsa-65-ed25519',
+    ]);
+});
+
+for (const algorithm of algorithms()) {
+    test(`sign and verify with ${algorithm}`, () => {
+        const keyPair = KeyPairWrapper.generate(algorithm);
+        assert.equal(keyPair.algorithm, algorithm);
+
+        const signature = keyPair.sign(MESSAGE);
+        assert.ok(signature instanceof Signature);
+        assert.ok(keyPair.verify(MESSAGE, signature));
+        assert.ok(keyPair.verify(MESSAGE_BYTES, signature.signature));
+        assert.ok(!keyPair.verify(MESSAGE + '.', signature));
+    });
+
+    test(`JSON round trip with ${algorithm}`, () => {
+        const keyPair = KeyPairWrapper.generate(algorithm);
+        const restored = KeyPairWrapper.fromJson(keyPair.toJson());
+        assert.equal(restored.algorithm, algorithm);
+        assert.deepEqual(restored.publicKey, keyPair.publicKey);
+        assert.deepEqual(restored.privateKey, keyPair.privateKey);
+        assert.equal(restored.keyId, keyPair.keyId);
+        assert.ok(restored.verify(MESSAGE, keyPair.sign(MESSAGE)));
+
+        const data = JSON.parse(keyPair.toJson());
+        assert.deepEqual(Uint8Array.from(data.public_key), keyPair.publicKey);
+    });
+}
+
+test('default constructor makes an HMAC key pair', () => {
+    assert.equal(new KeyPairWrapper().algorithm, 'hmac-sha3-256');
+});
+
+test('public key round trip', () => {
+    const keyPair = KeyPairWrapper.generate('falcon-512');
+    const publicKey = PublicKey.fromKeyPair(keyPair);
+    const decoded = PublicKey.fromBytes(publicKey.toBytes());
+    assert.equal(decoded.algorithm, 'falcon-512');
+    assert.equal(decoded.keyId, keyPair.keyId);
+    assert.deepEqual(decoded.toBytes(), publicKey.toBytes());
+
+    const signature = keyPair.sign(MESSAGE_BYTES);
+    assert.ok(decoded.verify(MESSAGE, signature));
+    assert.ok(!decoded.verify('other', signature));
+});
+
+test('signature encodings', () => {
+    const signature = KeyPairWrapper.generate('falcon-512').sign(MESSAGE);
+    assert.equal(signature.length, 666);
+
+    const copy = Signature.fromBase64(signature.toBase64());
+    assert.ok(copy.equals(signature));
+    assert.ok(!copy.equals(new Signature(new Uint8Array([1, 2, 3]))));
+    assert.deepEqual(signatureFromBase64(signatureToBase64(signature.signature)), signature.signature);
+});
+
+test('HMAC functions verify with the private key', () => {
+    const keyPair = new KeyPairWrapper();
+    const signature = signMessage('Hello, quantum world!', keyPair.privateKey);
+    assert.ok(verifySignature('Hello, quantum world!', signature, keyPair.privateKey));
+    assert.ok(!verifySignature('Hello, quantum world?', signature, keyPair.privateKey));
+    assert.ok(!verifySignature('Hello, quantum world!', signature, keyPair.publicKey));
+});
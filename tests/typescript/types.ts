@@ -0,0 +1,28 @@
+// Compiled by `npm run typecheck` against the generated typings; never run
+import {
+    KeyPairWrapper,
+    PublicKey,
+    QuantumSafeError,
+    QuantumSafeErrorName,
+    Signature,
+    algorithms,
+} from 'quantumsafe-finance-wasm';
+
+const tags: string[] = algorithms();
+const keyPair: KeyPairWrapper = KeyPairWrapper.generate(tags[1]);
+const publicKeyBytes: Uint8Array = keyPair.publicKey;
+const signature: Signature = keyPair.sign('message');
+const valid: boolean =
+    keyPair.verify(new Uint8Array([1]), signature) &&
+    PublicKey.fromKeyPair(keyPair).verify('message', signature.signature);
+
+// @ts-expect-error messages are bytes or strings
+keyPair.sign(42);
+// @ts-expect-error signatures are bytes or Signature objects
+keyPair.verify('message', 'signature');
+
+function errorName(error: unknown): QuantumSafeErrorName | undefined {
+    return error instanceof Error ? (error as QuantumSafeError).name : undefined;
+}
+
+export { errorName, publicKeyBytes, valid };
@@ -0,0 +1,25 @@
+const test = require('node:test');
+const assert = require('node:assert/strict');
+const { hex, loadVectors } = require('./helpers');
+const { KeyPairWrapper, PublicKey } = require('./pkg');
+
+for (const vector of loadVectors()) {
+    test(`verify Rust signatures: ${vector.algorithm}`, () => {
+        const keyPair = KeyPairWrapper.fromJson(vector.key_pair);
+        const message = hex(vector.message);
+        const signature = hex(vector.signature);
+
+        assert.equal(keyPair.algorithm, vector.algorithm);
+        assert.ok(keyPair.verify(message, signature));
+        assert.ok(!keyPair.verify(message.subarray(0, -1), signature));
+        assert.equal(keyPair.toJson(), vector.key_pair);
+
+        if (vector.public_key === null) {
+            return;
+        }
+        const publicKey = PublicKey.fromBytes(hex(vector.public_key));
+        assert.equal(publicKey.algorithm, vector.algorithm);
+        assert.deepEqual(publicKey.toBytes(), hex(vector.public_key));
+        assert.ok(publicKey.verify(message, signature));
+    });
+}
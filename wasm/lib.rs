@@ -0,0 +1,9 @@
+//! The wasm-pack package for QuantumSafe Finance
+//!
+//! wasm-pack only builds crates whose manifest asks for a `cdylib`, and the
+//! main crate is an `rlib` so that `no_std` consumers can link it. This crate
+//! links the TypeScript bindings into the `cdylib`:
+//!
+//! `wasm-pack build wasm --target nodejs`
+
+pub use quantumsafe_finance::bindings::typescript::*;